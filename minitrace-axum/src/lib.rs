@@ -0,0 +1,152 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+#![doc = include_str!("../README.md")]
+
+use std::convert::Infallible;
+
+use axum::extract::FromRequestParts;
+use axum::extract::MatchedPath;
+use axum::http::header::HeaderName;
+use axum::http::request::Parts;
+use axum::http::HeaderValue;
+use axum::response::IntoResponseParts;
+use axum::response::ResponseParts;
+use minitrace::local::LocalParentGuard;
+use minitrace::prelude::*;
+pub use minitrace_tower::extract_span_context;
+
+/// A [`Layer`] that traces each request of a [`Router`], see [`minitrace_tower::TraceLayer`].
+///
+/// [`Layer`]: axum::routing::Router::layer
+/// [`Router`]: axum::Router
+pub type TraceLayer = minitrace_tower::TraceLayer<axum::body::Body>;
+
+/// An extractor that starts a [`Span`] for the request handler.
+///
+/// If the request is traced by [`TraceLayer`], the span is a child of the span of the request.
+/// Otherwise, it is a [`SpanKind::Server`] root span continuing the trace of the upstream
+/// [`SpanContext`] extracted from the request headers by [`extract_span_context()`], or
+/// starting a new trace if there is none. The span is named after the method and the matched
+/// route of the request, e.g. `GET /users/{id}`, and ends when the `TraceContext` is dropped,
+/// which is usually at the end of the handler.
+///
+/// The [`SpanContext`] of the span, which keeps the upstream `tracestate`, is also inserted into
+/// the request extensions.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::prelude::*;
+/// use minitrace_axum::TraceContext;
+/// use minitrace_axum::TraceContextExtension;
+///
+/// async fn handler(trace: TraceContext) -> (TraceContextExtension, &'static str) {
+///     {
+///         let _guard = trace.set_local_parent();
+///         let _span = LocalSpan::enter_with_local_parent("compute");
+///     }
+///
+///     fetch()
+///         .in_span(Span::enter_with_parent("fetch", trace.span()))
+///         .await;
+///
+///     (trace.extension(), "hello")
+/// }
+/// # async fn fetch() {}
+/// ```
+pub struct TraceContext {
+    span: Span,
+    span_context: SpanContext,
+}
+
+impl TraceContext {
+    /// Returns the span of the handler.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// Returns the [`SpanContext`] of the span of the handler.
+    pub fn span_context(&self) -> &SpanContext {
+        &self.span_context
+    }
+
+    /// Sets the span of the handler as the local parent of the current thread.
+    ///
+    /// The returned guard is not `Send`, so it has to be dropped before the next `.await` of the
+    /// handler. Use [`FutureExt::in_span()`] to trace a future instead.
+    ///
+    /// [`FutureExt::in_span()`]: minitrace::future::FutureExt::in_span
+    pub fn set_local_parent(&self) -> LocalParentGuard {
+        self.span.set_local_parent()
+    }
+
+    /// Returns a [`TraceContextExtension`] to propagate the span context back to the caller.
+    pub fn extension(&self) -> TraceContextExtension {
+        TraceContextExtension(self.span_context.clone())
+    }
+
+    /// Returns the span of the handler, which ends when it is dropped.
+    pub fn into_span(self) -> Span {
+        self.span
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for TraceContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let route = parts
+            .extensions
+            .get::<MatchedPath>()
+            .map(|path| path.as_str())
+            .unwrap_or_else(|| parts.uri.path());
+        let name = format!("{} {}", parts.method, route);
+
+        let (span, tracestate) = if SpanContext::current_local_parent().is_some() {
+            (Span::enter_with_local_parent(name), None)
+        } else {
+            let parent = extract_span_context(&parts.headers).unwrap_or_else(SpanContext::random);
+            let tracestate = parent.tracestate().map(str::to_string);
+            (
+                Span::root(name, parent).with_kind(SpanKind::Server),
+                tracestate,
+            )
+        };
+        let mut span_context = SpanContext::from_span(&span).unwrap_or_default();
+        if let Some(tracestate) = tracestate {
+            span_context = span_context.with_tracestate(tracestate);
+        }
+        parts.extensions.insert(span_context.clone());
+
+        Ok(TraceContext { span, span_context })
+    }
+}
+
+/// A response part that propagates a [`SpanContext`] back to the caller in the W3C `traceparent`
+/// and `tracestate` response headers.
+///
+/// Nothing is added to the response if the span context is not valid, e.g. because it is the
+/// context of a noop span.
+#[derive(Clone, Debug)]
+pub struct TraceContextExtension(pub SpanContext);
+
+impl IntoResponseParts for TraceContextExtension {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        if self.0.trace_id.0 == 0 {
+            return Ok(res);
+        }
+
+        for (name, value) in self.0.encode_w3c_headers() {
+            if value.is_empty() {
+                continue;
+            }
+            if let Ok(value) = HeaderValue::try_from(value) {
+                res.headers_mut()
+                    .insert(HeaderName::from_static(name), value);
+            }
+        }
+        Ok(res)
+    }
+}
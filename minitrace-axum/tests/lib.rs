@@ -0,0 +1,120 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use axum::routing::get;
+use axum::Router;
+use axum_test::TestServer;
+use minitrace::collector::Config;
+use minitrace::collector::SpanRecord;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+use minitrace::util::tree::tree_str_from_span_records;
+use minitrace_axum::TraceContext;
+use minitrace_axum::TraceContextExtension;
+use minitrace_axum::TraceLayer;
+use serial_test::serial;
+
+async fn handler(trace: TraceContext) -> (TraceContextExtension, &'static str) {
+    {
+        let _guard = trace.set_local_parent();
+        let _span = LocalSpan::enter_with_local_parent("greet");
+    }
+    (trace.extension(), "hello")
+}
+
+fn find<'a>(spans: &'a [SpanRecord], name: &str) -> &'a SpanRecord {
+    spans.iter().find(|span| span.name == name).unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn extractor_without_upstream() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let server = TestServer::new(Router::new().route("/users/{id}", get(handler))).unwrap();
+    let response = server.get("/users/1").await;
+    response.assert_text("hello");
+
+    minitrace::flush();
+
+    let spans = collected_spans.lock().clone();
+    let expected_graph = r#"
+GET /users/{id} []
+    greet []
+"#;
+    assert_eq!(tree_str_from_span_records(spans.clone()), expected_graph);
+
+    let root = find(&spans, "GET /users/{id}");
+    assert_eq!(root.parent_id, SpanId::default());
+    assert_eq!(root.kind, SpanKind::Server);
+
+    let traceparent = response.header("traceparent");
+    let span_context = SpanContext::decode_w3c_traceparent(traceparent.to_str().unwrap()).unwrap();
+    assert_eq!(span_context.trace_id, root.trace_id);
+    assert_eq!(span_context.span_id, root.span_id);
+}
+
+#[tokio::test]
+#[serial]
+async fn extractor_with_upstream() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let server = TestServer::new(Router::new().route("/", get(handler))).unwrap();
+    let response = server
+        .get("/")
+        .add_header(
+            "traceparent",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+        )
+        .add_header("tracestate", "rojo=00f067aa0ba902b7")
+        .await;
+    response.assert_header("tracestate", "rojo=00f067aa0ba902b7");
+
+    minitrace::flush();
+
+    let spans = collected_spans.lock().clone();
+    let root = find(&spans, "GET /");
+    assert_eq!(root.trace_id, TraceId(0x0af7651916cd43dd8448eb211c80319c));
+    assert_eq!(root.parent_id, SpanId(0xb7ad6b7169203331));
+}
+
+#[tokio::test]
+#[serial]
+async fn layer() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let app = Router::new()
+        .route("/users/{id}", get(handler))
+        .layer(TraceLayer::new("test-service"));
+    let server = TestServer::new(app).unwrap();
+    server
+        .get("/users/1")
+        .add_header("b3", "0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-1")
+        .await
+        .assert_text("hello");
+
+    minitrace::flush();
+
+    let spans = collected_spans.lock().clone();
+    assert_eq!(spans.len(), 3);
+
+    let root = find(&spans, "GET /users/1");
+    assert_eq!(root.trace_id, TraceId(0x0af7651916cd43dd8448eb211c80319c));
+    assert_eq!(root.parent_id, SpanId(0xb7ad6b7169203331));
+    assert_eq!(root.properties, vec![
+        ("service.name".into(), "test-service".into()),
+        ("http.method".into(), "GET".into()),
+        ("http.target".into(), "/users/1".into()),
+        ("http.status_code".into(), "200".into()),
+    ]);
+
+    let handler = find(&spans, "GET /users/{id}");
+    assert_eq!(handler.trace_id, root.trace_id);
+    assert_eq!(handler.parent_id, root.span_id);
+    assert_eq!(handler.kind, SpanKind::Internal);
+
+    let greet = find(&spans, "greet");
+    assert_eq!(greet.parent_id, handler.span_id);
+}
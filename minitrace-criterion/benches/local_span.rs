@@ -0,0 +1,25 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use minitrace::prelude::*;
+use minitrace_criterion::bench_with_tracing;
+use minitrace_criterion::MinitracePlugin;
+
+fn local_span(c: &mut Criterion) {
+    for n in [1, 10, 100] {
+        bench_with_tracing(c, &format!("local_span/{n}"), || {
+            for _ in 0..n {
+                let _span = LocalSpan::enter_with_local_parent("child");
+            }
+        });
+    }
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().with_profiler(MinitracePlugin::new());
+    targets = local_span
+}
+criterion_main!(benches);
@@ -0,0 +1,160 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+#![doc = include_str!("../README.md")]
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::path::Path;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use criterion::profiler::Profiler;
+use criterion::Criterion;
+use minitrace::collector::Config;
+use minitrace::collector::Reporter;
+use minitrace::collector::ReporterGuard;
+use minitrace::prelude::*;
+
+/// A reporter that only counts the spans, so that the spans of a long benchmark are not kept in
+/// memory.
+struct CountingReporter(Arc<AtomicU64>);
+
+impl Reporter for CountingReporter {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        self.0.fetch_add(spans.len() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Flushes the spans recorded so far to the current reporter, then sets a `CountingReporter`
+/// until the returned guard is dropped, which restores the current reporter.
+fn count_spans(span_count: Arc<AtomicU64>) -> ReporterGuard {
+    minitrace::flush();
+    minitrace::scoped_reporter(CountingReporter(span_count), Config::default())
+}
+
+/// The spans collected while a benchmark was profiled by [`MinitracePlugin`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TracingStats {
+    /// The number of spans reported.
+    pub span_count: u64,
+    /// The time taken to collect the spans still pending when the profiling stopped.
+    pub flush_time: Duration,
+}
+
+impl Display for TracingStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "spans: {}, flush time: {:?}",
+            self.span_count, self.flush_time
+        )
+    }
+}
+
+/// A criterion [`Profiler`] that counts the spans reported while a benchmark is profiled.
+///
+/// The profiler runs when the benchmarks are started with `--profile-time <seconds>`. While a
+/// benchmark is profiled, the global reporter is replaced with one that only counts the spans,
+/// and the reporter set before is restored when the profiling stops. The resulting
+/// [`TracingStats`] are printed and written to `minitrace.txt` in the profile directory of the
+/// benchmark.
+///
+/// The spans are only recorded if the `enable` feature of `minitrace` is enabled by the
+/// benchmark.
+///
+/// # Examples
+///
+/// ```no_run
+/// use criterion::criterion_group;
+/// use criterion::criterion_main;
+/// use criterion::Criterion;
+/// use minitrace_criterion::MinitracePlugin;
+///
+/// fn bench(c: &mut Criterion) {
+///     // ...
+/// }
+///
+/// criterion_group! {
+///     name = benches;
+///     config = Criterion::default().with_profiler(MinitracePlugin::new());
+///     targets = bench
+/// }
+/// criterion_main!(benches);
+/// ```
+#[derive(Default)]
+pub struct MinitracePlugin {
+    // The spans counted and the guard of the counting reporter while a benchmark is profiled.
+    profiling: Option<(Arc<AtomicU64>, ReporterGuard)>,
+}
+
+impl MinitracePlugin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Profiler for MinitracePlugin {
+    fn start_profiling(&mut self, _benchmark_id: &str, _benchmark_dir: &Path) {
+        let span_count = Arc::new(AtomicU64::new(0));
+        let guard = count_spans(span_count.clone());
+        self.profiling = Some((span_count, guard));
+    }
+
+    fn stop_profiling(&mut self, benchmark_id: &str, benchmark_dir: &Path) {
+        let Some((span_count, guard)) = self.profiling.take() else {
+            return;
+        };
+
+        // Dropping the guard flushes the pending spans to the counting reporter.
+        let flush_start = Instant::now();
+        drop(guard);
+        let stats = TracingStats {
+            span_count: span_count.load(Ordering::Relaxed),
+            flush_time: flush_start.elapsed(),
+        };
+
+        println!("{benchmark_id}: {stats}");
+        let result = std::fs::create_dir_all(benchmark_dir).and_then(|()| {
+            std::fs::write(benchmark_dir.join("minitrace.txt"), format!("{stats}\n"))
+        });
+        if let Err(err) = result {
+            eprintln!("failed to write the tracing stats of {benchmark_id}: {err}");
+        }
+    }
+}
+
+/// Benchmarks `f` twice in a group named `name`: as `untraced`, and as `traced`, where each
+/// iteration runs in a new root span set as the local parent, so that the [`LocalSpan`]s started
+/// by `f` are collected.
+///
+/// While the group runs, the global reporter is replaced with one that only counts the spans, as
+/// with [`MinitracePlugin`], and the reporter set before is restored afterwards.
+///
+/// # Examples
+///
+/// ```no_run
+/// use criterion::Criterion;
+/// use minitrace::prelude::*;
+///
+/// let mut c = Criterion::default();
+/// minitrace_criterion::bench_with_tracing(&mut c, "compute", || {
+///     let _span = LocalSpan::enter_with_local_parent("compute");
+/// });
+/// ```
+pub fn bench_with_tracing(c: &mut Criterion, name: &str, f: impl Fn()) {
+    let _guard = count_spans(Arc::new(AtomicU64::new(0)));
+
+    let mut group = c.benchmark_group(name);
+    group.bench_function("untraced", |b| b.iter(&f));
+    group.bench_function("traced", |b| {
+        b.iter(|| {
+            let root = Span::root("iteration", SpanContext::random());
+            let _guard = root.set_local_parent();
+            f();
+        })
+    });
+    group.finish();
+}
@@ -0,0 +1,42 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use criterion::profiler::Profiler;
+use minitrace::collector::Config;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+use minitrace_criterion::MinitracePlugin;
+
+#[test]
+fn minitrace_plugin() {
+    let benchmark_dir = std::env::temp_dir().join(format!(
+        "minitrace-criterion-{}/local_span",
+        std::process::id()
+    ));
+
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let mut plugin = MinitracePlugin::new();
+    plugin.start_profiling("local_span", &benchmark_dir);
+    for _ in 0..10 {
+        let root = Span::root("root", SpanContext::random());
+        let _guard = root.set_local_parent();
+        let _span = LocalSpan::enter_with_local_parent("child");
+    }
+    plugin.stop_profiling("local_span", &benchmark_dir);
+
+    let stats = std::fs::read_to_string(benchmark_dir.join("minitrace.txt")).unwrap();
+    assert!(stats.starts_with("spans: 20, flush time: "), "{stats}");
+
+    // The reporter set before the profiling is restored.
+    drop(Span::root("after", SpanContext::random()));
+    minitrace::flush();
+    let names = collected_spans
+        .lock()
+        .iter()
+        .map(|span| span.name.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["after"]);
+
+    std::fs::remove_dir_all(benchmark_dir.parent().unwrap()).unwrap();
+}
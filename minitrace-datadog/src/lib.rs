@@ -16,6 +16,7 @@ pub struct DatadogReporter {
     service_name: String,
     resource: String,
     trace_type: String,
+    client: Option<reqwest::blocking::Client>,
 }
 
 impl DatadogReporter {
@@ -30,6 +31,7 @@ impl DatadogReporter {
             service_name: service_name.into(),
             resource: resource.into(),
             trace_type: trace_type.into(),
+            client: None,
         }
     }
 
@@ -70,7 +72,10 @@ impl DatadogReporter {
     fn try_report(&self, spans: &[SpanRecord]) -> Result<(), Box<dyn std::error::Error>> {
         let datadog_spans = self.convert(spans);
         let bytes = self.serialize(datadog_spans)?;
-        let client = reqwest::blocking::Client::new();
+        let client = match &self.client {
+            Some(client) => client.clone(),
+            None => reqwest::blocking::Client::new(),
+        };
         let _rep = client
             .post(format!("http://{}/v0.4/traces", self.agent_addr))
             .header("Datadog-Meta-Tracer-Version", "v1.27.0")
@@ -91,6 +96,14 @@ impl Reporter for DatadogReporter {
             log::error!("report to datadog failed: {}", err);
         }
     }
+
+    fn start(&mut self) {
+        self.client = Some(reqwest::blocking::Client::new());
+    }
+
+    fn stop(&mut self) {
+        self.client = None;
+    }
 }
 
 #[derive(Serialize)]
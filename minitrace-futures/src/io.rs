@@ -0,0 +1,207 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::io::IoSlice;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+
+use minitrace::Span;
+use pin_project_lite::pin_project;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio::io::ReadBuf;
+
+/// An extension trait for [`tokio::io::AsyncRead`] that provides tracing instrument adapters.
+pub trait ReadExt: AsyncRead + Sized {
+    /// Binds a [`Span`] to the reader that continues to record until the reader is dropped.
+    ///
+    /// The span is set as the local parent at every poll, and the total number of bytes read is
+    /// recorded as the `bytes_read` property of the span when the reader is dropped.
+    ///
+    /// For a type that is both a reader and a writer, such as a `TcpStream`, call
+    /// `ReadExt::traced(stream, span)` to pick this adapter.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// use minitrace::prelude::*;
+    /// use minitrace_futures::ReadExt;
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let mut reader = "hello"
+    ///     .as_bytes()
+    ///     .traced(Span::enter_with_parent("read", &root));
+    ///
+    /// let mut buf = String::new();
+    /// reader.read_to_string(&mut buf).await.unwrap();
+    /// assert_eq!(buf, "hello");
+    /// // span ends here.
+    /// # }
+    /// ```
+    fn traced(self, span: Span) -> TracedReader<Self> {
+        TracedReader {
+            inner: self,
+            span,
+            bytes_read: 0,
+        }
+    }
+}
+
+impl<T> ReadExt for T where T: AsyncRead {}
+
+/// An extension trait for [`tokio::io::AsyncWrite`] that provides tracing instrument adapters.
+pub trait WriteExt: AsyncWrite + Sized {
+    /// Binds a [`Span`] to the writer that continues to record until the writer is dropped.
+    ///
+    /// The span is set as the local parent at every poll, and the total number of bytes written
+    /// is recorded as the `bytes_written` property of the span when the writer is dropped.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// use minitrace::prelude::*;
+    /// use minitrace_futures::WriteExt;
+    /// use tokio::io::AsyncWriteExt;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let mut writer = Vec::new().traced(Span::enter_with_parent("write", &root));
+    ///
+    /// writer.write_all(b"hello").await.unwrap();
+    /// // span ends here.
+    /// # }
+    /// ```
+    fn traced(self, span: Span) -> TracedWriter<Self> {
+        TracedWriter {
+            inner: self,
+            span,
+            bytes_written: 0,
+        }
+    }
+}
+
+impl<T> WriteExt for T where T: AsyncWrite {}
+
+pin_project! {
+    /// Adapter for [`ReadExt::traced()`](ReadExt::traced).
+    pub struct TracedReader<R> {
+        #[pin]
+        inner: R,
+        span: Span,
+        bytes_read: u64,
+    }
+
+    impl<R> PinnedDrop for TracedReader<R> {
+        fn drop(this: Pin<&mut Self>) {
+            let this = this.project();
+            let bytes_read = *this.bytes_read;
+            this.span
+                .add_property(|| ("bytes_read", bytes_read.to_string()));
+        }
+    }
+}
+
+impl<R> TracedReader<R> {
+    /// Returns the total number of bytes read so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+}
+
+impl<R> AsyncRead for TracedReader<R>
+where R: AsyncRead
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.project();
+        let _guard = this.span.set_local_parent();
+
+        let filled = buf.filled().len();
+        let res = this.inner.poll_read(cx, buf);
+        *this.bytes_read += (buf.filled().len() - filled) as u64;
+        res
+    }
+}
+
+pin_project! {
+    /// Adapter for [`WriteExt::traced()`](WriteExt::traced).
+    pub struct TracedWriter<W> {
+        #[pin]
+        inner: W,
+        span: Span,
+        bytes_written: u64,
+    }
+
+    impl<W> PinnedDrop for TracedWriter<W> {
+        fn drop(this: Pin<&mut Self>) {
+            let this = this.project();
+            let bytes_written = *this.bytes_written;
+            this.span
+                .add_property(|| ("bytes_written", bytes_written.to_string()));
+        }
+    }
+}
+
+impl<W> TracedWriter<W> {
+    /// Returns the total number of bytes written so far.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+}
+
+impl<W> AsyncWrite for TracedWriter<W>
+where W: AsyncWrite
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.project();
+        let _guard = this.span.set_local_parent();
+
+        let res = this.inner.poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = res {
+            *this.bytes_written += n as u64;
+        }
+        res
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.project();
+        let _guard = this.span.set_local_parent();
+
+        let res = this.inner.poll_write_vectored(cx, bufs);
+        if let Poll::Ready(Ok(n)) = res {
+            *this.bytes_written += n as u64;
+        }
+        res
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        let this = self.project();
+        let _guard = this.span.set_local_parent();
+        this.inner.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        let this = self.project();
+        let _guard = this.span.set_local_parent();
+        this.inner.poll_shutdown(cx)
+    }
+}
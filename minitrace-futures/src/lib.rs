@@ -2,15 +2,22 @@
 
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "tokio")]
+mod io;
+
 use std::pin::Pin;
 use std::task::Context;
 use std::task::Poll;
 
+use futures::ready;
 use futures::Sink;
 use futures::Stream;
 use minitrace::Span;
 use pin_project_lite::pin_project;
 
+#[cfg(feature = "tokio")]
+pub use crate::io::*;
+
 /// An extension trait for [`futures::Stream`] that provides tracing instrument adapters.
 pub trait StreamExt: futures::Stream + Sized {
     /// Binds a [`Span`] to the [`Stream`] that continues to record until the stream is
@@ -52,6 +59,47 @@ pub trait StreamExt: futures::Stream + Sized {
             span: Some(span),
         }
     }
+
+    /// Maps each item of the [`Stream`] to a sub-stream with `f` and flattens the sub-streams,
+    /// like [`futures::StreamExt::flat_map`], with a [`Span`] covering the processing of each
+    /// item.
+    ///
+    /// `make_span` is called once for every item yielded by the stream. The span is set as the
+    /// local parent while `f` is called and while the sub-stream is polled, and ends when the
+    /// sub-stream is **finished**.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// use futures::stream;
+    /// use futures::StreamExt;
+    /// use minitrace::prelude::*;
+    /// use minitrace_futures::StreamExt as _;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let s = stream::iter(0..2).flat_map_in_span(
+    ///     |i| stream::iter([i, i]),
+    ///     || Span::enter_with_parent("item", &root),
+    /// );
+    ///
+    /// assert_eq!(s.collect::<Vec<_>>().await, [0, 0, 1, 1]);
+    /// # }
+    /// ```
+    fn flat_map_in_span<U, F, M>(self, f: F, make_span: M) -> FlatMapInSpan<Self, U, F, M>
+    where
+        U: Stream,
+        F: FnMut(Self::Item) -> U,
+        M: FnMut() -> Span,
+    {
+        FlatMapInSpan {
+            stream: self,
+            next: None,
+            f,
+            make_span,
+        }
+    }
 }
 
 impl<T> StreamExt for T where T: futures::Stream {}
@@ -126,6 +174,53 @@ where T: Stream
     }
 }
 
+pin_project! {
+    /// Adapter for [`StreamExt::flat_map_in_span()`](StreamExt::flat_map_in_span).
+    pub struct FlatMapInSpan<St, U, F, M> {
+        #[pin]
+        stream: St,
+        #[pin]
+        next: Option<InSpan<U>>,
+        f: F,
+        make_span: M,
+    }
+}
+
+impl<St, U, F, M> Stream for FlatMapInSpan<St, U, F, M>
+where
+    St: Stream,
+    U: Stream,
+    F: FnMut(St::Item) -> U,
+    M: FnMut() -> Span,
+{
+    type Item = U::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        loop {
+            if let Some(next) = this.next.as_mut().as_pin_mut() {
+                match ready!(next.poll_next(cx)) {
+                    Some(item) => return Poll::Ready(Some(item)),
+                    None => this.next.set(None),
+                }
+            }
+
+            match ready!(this.stream.as_mut().poll_next(cx)) {
+                Some(item) => {
+                    let span = (this.make_span)();
+                    let next = {
+                        let _guard = span.set_local_parent();
+                        (this.f)(item)
+                    };
+                    this.next.set(Some(next.in_span(span)));
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
 impl<T, I> Sink<I> for InSpan<T>
 where T: Sink<I>
 {
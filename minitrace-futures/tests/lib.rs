@@ -0,0 +1,97 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::cell::Cell;
+
+use futures::stream;
+use futures::StreamExt;
+use minitrace::collector::Config;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+use minitrace::util::tree::tree_str_from_span_records;
+use minitrace_futures::ReadExt;
+use minitrace_futures::StreamExt as _;
+use minitrace_futures::WriteExt;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+
+#[tokio::test]
+async fn flat_map_in_span() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let spans_made = Cell::new(0);
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let items = stream::iter(0..5)
+            .flat_map_in_span(
+                |i| {
+                    let _span = LocalSpan::enter_with_local_parent(format!("map-{i}"));
+                    stream::iter([i * 10, i * 10 + 1])
+                },
+                || {
+                    spans_made.set(spans_made.get() + 1);
+                    Span::enter_with_parent(format!("item-{}", spans_made.get()), &root)
+                },
+            )
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(items, [0, 1, 10, 11, 20, 21, 30, 31, 40, 41]);
+    }
+
+    minitrace::flush();
+
+    assert_eq!(spans_made.get(), 5);
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        r#"
+root []
+    item-1 []
+        map-0 []
+    item-2 []
+        map-1 []
+    item-3 []
+        map-2 []
+    item-4 []
+        map-3 []
+    item-5 []
+        map-4 []
+"#
+    );
+}
+
+#[tokio::test]
+async fn traced_reader_and_writer() {
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+
+        let mock = tokio_test::io::Builder::new()
+            .read(b"hello ")
+            .read(b"world")
+            .build();
+        let mut reader = ReadExt::traced(mock, Span::enter_with_parent("read", &root));
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"hello world");
+        assert_eq!(reader.bytes_read(), 11);
+
+        let mock = tokio_test::io::Builder::new().write(b"hello").build();
+        let mut writer = WriteExt::traced(mock, Span::enter_with_parent("write", &root));
+        writer.write_all(b"hello").await.unwrap();
+        assert_eq!(writer.bytes_written(), 5);
+    }
+
+    minitrace::flush();
+
+    reporter.assert_tree(
+        r#"
+root []
+    read [("bytes_read", "11")]
+    write [("bytes_written", "5")]
+"#,
+    );
+}
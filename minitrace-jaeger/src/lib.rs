@@ -11,7 +11,11 @@ use std::net::UdpSocket;
 use minitrace::collector::Reporter;
 use minitrace::prelude::*;
 use thrift::Log;
+#[cfg(feature = "http")]
+use thrift_codec::data::Struct;
 use thrift_codec::message::Message;
+#[cfg(feature = "http")]
+use thrift_codec::BinaryEncode;
 use thrift_codec::CompactEncode;
 
 use crate::thrift::Batch;
@@ -21,10 +25,14 @@ use crate::thrift::Process;
 use crate::thrift::Tag;
 
 /// [Jaeger](https://www.jaegertracing.io/) reporter for `minitrace` via UDP endpoint.
+///
+/// The UDP socket is bound by [`JaegerReporter::new()`] and closed when the reporter is stopped
+/// by [`minitrace::shutdown()`]. A stopped reporter binds a new socket when it's started again or
+/// has spans to report.
 pub struct JaegerReporter {
     agent_addr: SocketAddr,
     service_name: String,
-    socket: UdpSocket,
+    socket: Option<UdpSocket>,
 }
 
 impl JaegerReporter {
@@ -32,60 +40,24 @@ impl JaegerReporter {
         agent_addr: SocketAddr,
         service_name: impl Into<String>,
     ) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
-        let local_addr: SocketAddr = if agent_addr.is_ipv4() {
-            "0.0.0.0:0"
-        } else {
-            "[::]:0"
-        }
-        .parse()
-        .unwrap();
-        let socket = std::net::UdpSocket::bind(local_addr)?;
+        let socket = Self::bind(agent_addr)?;
 
         Ok(Self {
             agent_addr,
             service_name: service_name.into(),
-            socket,
+            socket: Some(socket),
         })
     }
 
-    fn convert(&self, spans: &[SpanRecord]) -> Vec<JaegerSpan> {
-        spans
-            .iter()
-            .map(move |s| JaegerSpan {
-                trace_id_high: (s.trace_id.0 >> 64) as i64,
-                trace_id_low: s.trace_id.0 as i64,
-                span_id: s.span_id.0 as i64,
-                parent_span_id: s.parent_id.0 as i64,
-                operation_name: s.name.to_string(),
-                references: vec![],
-                flags: 1,
-                start_time: (s.begin_time_unix_ns / 1_000) as i64,
-                duration: (s.duration_ns / 1_000) as i64,
-                tags: s
-                    .properties
-                    .iter()
-                    .map(|(k, v)| Tag::String {
-                        key: k.to_string(),
-                        value: v.to_string(),
-                    })
-                    .collect(),
-                logs: s
-                    .events
-                    .iter()
-                    .map(|event| Log {
-                        timestamp: (event.timestamp_unix_ns / 1_000) as i64,
-                        fields: [("name".into(), event.name.clone())]
-                            .iter()
-                            .chain(&event.properties)
-                            .map(|(k, v)| Tag::String {
-                                key: k.to_string(),
-                                value: v.to_string(),
-                            })
-                            .collect(),
-                    })
-                    .collect(),
-            })
-            .collect()
+    fn bind(agent_addr: SocketAddr) -> std::io::Result<UdpSocket> {
+        let local_addr: SocketAddr = if agent_addr.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        }
+        .parse()
+        .unwrap();
+        UdpSocket::bind(local_addr)
     }
 
     fn serialize(&self, spans: Vec<JaegerSpan>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
@@ -106,15 +78,20 @@ impl JaegerReporter {
         Ok(bytes)
     }
 
-    fn try_report(&self, spans: &[SpanRecord]) -> Result<(), Box<dyn std::error::Error>> {
+    fn try_report(&mut self, spans: &[SpanRecord]) -> Result<(), Box<dyn std::error::Error>> {
         const MAX_UDP_PACKAGE_SIZE: usize = 8000;
 
+        if self.socket.is_none() {
+            self.socket = Some(Self::bind(self.agent_addr)?);
+        }
+        let socket = self.socket.as_ref().unwrap();
+
         let mut spans_per_batch = spans.len();
         let mut sent_spans = 0;
 
         while sent_spans < spans.len() {
             let batch_size = spans_per_batch.min(spans.len() - sent_spans);
-            let jaeger_spans = self.convert(&spans[sent_spans..sent_spans + batch_size]);
+            let jaeger_spans = convert(&spans[sent_spans..sent_spans + batch_size]);
             let bytes = self.serialize(jaeger_spans)?;
             if bytes.len() >= MAX_UDP_PACKAGE_SIZE {
                 if batch_size <= 1 {
@@ -124,7 +101,7 @@ impl JaegerReporter {
                 }
                 continue;
             }
-            self.socket.send_to(&bytes, self.agent_addr)?;
+            socket.send_to(&bytes, self.agent_addr)?;
             sent_spans += batch_size;
         }
 
@@ -142,4 +119,146 @@ impl Reporter for JaegerReporter {
             log::error!("report to jaeger failed: {}", err);
         }
     }
+
+    fn start(&mut self) {
+        if self.socket.is_none() {
+            match Self::bind(self.agent_addr) {
+                Ok(socket) => self.socket = Some(socket),
+                Err(err) => log::error!("failed to open socket for jaeger: {}", err),
+            }
+        }
+    }
+
+    fn stop(&mut self) {
+        self.socket = None;
+    }
+}
+
+/// [Jaeger](https://www.jaegertracing.io/) reporter for `minitrace` via the HTTP endpoint of the
+/// Jaeger collector.
+///
+/// Spans are sent to `{collector_url}/api/traces` as a thrift `Batch` in the binary protocol,
+/// which is what the collector expects for `Content-Type: application/x-thrift`.
+///
+/// Requires the `http` feature.
+#[cfg(feature = "http")]
+pub struct JaegerHttpReporter {
+    collector_url: String,
+    service_name: String,
+    client: Option<reqwest::blocking::Client>,
+}
+
+#[cfg(feature = "http")]
+impl JaegerHttpReporter {
+    pub fn new(collector_url: impl Into<String>, service_name: impl Into<String>) -> Self {
+        JaegerHttpReporter {
+            collector_url: collector_url.into(),
+            service_name: service_name.into(),
+            client: None,
+        }
+    }
+
+    fn serialize(&self, spans: Vec<JaegerSpan>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let batch = Batch {
+            process: Process {
+                service_name: self.service_name.clone(),
+                tags: vec![],
+            },
+            spans,
+        };
+
+        let mut bytes = Vec::new();
+        Struct::from(batch).binary_encode(&mut bytes)?;
+
+        Ok(bytes)
+    }
+
+    fn try_report(&self, spans: &[SpanRecord]) -> Result<(), Box<dyn std::error::Error>> {
+        let bytes = self.serialize(convert(spans))?;
+        let client = match &self.client {
+            Some(client) => client.clone(),
+            None => reqwest::blocking::Client::new(),
+        };
+        let url = format!("{}/api/traces", self.collector_url.trim_end_matches('/'));
+        let send = || {
+            client
+                .post(&url)
+                .header("Content-Type", "application/x-thrift")
+                .body(bytes.clone())
+                .send()
+        };
+
+        // Retry once in case of a transient network error.
+        let rep = match send() {
+            Ok(rep) => rep,
+            Err(_) => send()?,
+        };
+        rep.error_for_status()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "http")]
+impl Reporter for JaegerHttpReporter {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        if spans.is_empty() {
+            return;
+        }
+
+        if let Err(err) = self.try_report(spans) {
+            log::error!("report to jaeger collector failed: {}", err);
+        }
+    }
+
+    fn start(&mut self) {
+        self.client = Some(reqwest::blocking::Client::new());
+    }
+
+    fn stop(&mut self) {
+        self.client = None;
+    }
+}
+
+fn convert(spans: &[SpanRecord]) -> Vec<JaegerSpan> {
+    spans
+        .iter()
+        .map(move |s| JaegerSpan {
+            trace_id_high: (s.trace_id.0 >> 64) as i64,
+            trace_id_low: s.trace_id.0 as i64,
+            span_id: s.span_id.0 as i64,
+            parent_span_id: s.parent_id.0 as i64,
+            operation_name: s.name.to_string(),
+            references: vec![],
+            flags: s.sampled as i32,
+            start_time: (s.begin_time_unix_ns / 1_000) as i64,
+            duration: (s.duration_ns / 1_000) as i64,
+            tags: s
+                .properties
+                .iter()
+                .map(|(k, v)| Tag::String {
+                    key: k.to_string(),
+                    value: v.to_string(),
+                })
+                .collect(),
+            logs: s
+                .events
+                .iter()
+                .map(|event| Log {
+                    timestamp: (event.timestamp_unix_ns / 1_000) as i64,
+                    fields: [
+                        ("name".into(), event.name.clone()),
+                        ("level".into(), event.level.as_str().into()),
+                    ]
+                    .iter()
+                    .chain(&event.properties)
+                    .map(|(k, v)| Tag::String {
+                        key: k.to_string(),
+                        value: v.to_string(),
+                    })
+                    .collect(),
+                })
+                .collect(),
+        })
+        .collect()
 }
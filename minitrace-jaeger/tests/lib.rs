@@ -0,0 +1,49 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use minitrace::collector::Reporter;
+use minitrace::prelude::*;
+use minitrace_jaeger::JaegerHttpReporter;
+use thrift_codec::data::Data;
+use thrift_codec::data::Struct;
+use thrift_codec::BinaryDecode;
+
+#[test]
+fn jaeger_http_reporter() {
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("POST", "/api/traces")
+        .match_header("content-type", "application/x-thrift")
+        .match_request(|req| {
+            let body = req.body().unwrap();
+            let batch = Struct::binary_decode(&mut body.as_slice()).unwrap();
+            match (batch.fields()[0].data(), batch.fields()[1].data()) {
+                (Data::Struct(process), Data::List(spans)) => {
+                    process.fields()[0].data() == &Data::Binary(b"test-service".to_vec())
+                        && spans.len() == 2
+                }
+                _ => false,
+            }
+        })
+        .with_status(202)
+        .create();
+
+    let mut reporter = JaegerHttpReporter::new(server.url(), "test-service");
+    reporter.report(&[
+        SpanRecord {
+            trace_id: TraceId(1),
+            span_id: SpanId(1),
+            name: "root".into(),
+            ..SpanRecord::default()
+        },
+        SpanRecord {
+            trace_id: TraceId(1),
+            span_id: SpanId(2),
+            parent_id: SpanId(1),
+            name: "child".into(),
+            properties: vec![("k".into(), "v".into())],
+            ..SpanRecord::default()
+        },
+    ]);
+
+    mock.assert();
+}
@@ -0,0 +1,64 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+#![doc = include_str!("../README.md")]
+
+use log::Level;
+use log::LevelFilter;
+use log::Log;
+use log::Metadata;
+use log::Record;
+use log::SetLoggerError;
+use minitrace::prelude::*;
+
+/// A [`log::Log`] implementation that records each log record as an [`Event`] of the current
+/// local parent.
+///
+/// The event is named after the formatted message, and has the properties `message`, `level` and
+/// `target`. Log records are dropped without being formatted if there is no local parent.
+pub struct MinitraceLogger {
+    max_level: LevelFilter,
+}
+
+impl MinitraceLogger {
+    pub fn new(max_level: LevelFilter) -> Self {
+        MinitraceLogger { max_level }
+    }
+
+    /// Sets a `MinitraceLogger` as the global logger of the `log` crate.
+    ///
+    /// This fails if a global logger has been set.
+    pub fn install(max_level: LevelFilter) -> Result<(), SetLoggerError> {
+        log::set_boxed_logger(Box::new(Self::new(max_level)))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+}
+
+impl Log for MinitraceLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= self.max_level
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) || SpanContext::current_local_parent().is_none() {
+            return;
+        }
+
+        let message = record.args().to_string();
+        let level = match record.level() {
+            Level::Error => EventLevel::Error,
+            Level::Warn => EventLevel::Warn,
+            Level::Info => EventLevel::Info,
+            Level::Debug | Level::Trace => EventLevel::Debug,
+        };
+        Event::add_to_local_parent_with_level(message.clone(), level, || {
+            [
+                ("message".into(), message.into()),
+                ("level".into(), record.level().as_str().into()),
+                ("target".into(), record.target().to_string().into()),
+            ]
+        });
+    }
+
+    fn flush(&self) {}
+}
@@ -0,0 +1,72 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::sync::Once;
+
+use minitrace::collector::Config;
+use minitrace::collector::EventRecord;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+use minitrace_log::MinitraceLogger;
+use serial_test::serial;
+
+fn install_logger() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| MinitraceLogger::install(log::LevelFilter::Info).unwrap());
+}
+
+#[test]
+#[serial]
+fn log_in_root() {
+    install_logger();
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        log::info!("hello");
+        log::debug!("filtered out");
+    }
+
+    minitrace::flush();
+
+    let collected_spans = collected_spans.lock();
+    assert_eq!(collected_spans.len(), 1);
+    let events = collected_spans[0]
+        .events
+        .iter()
+        .map(|event| EventRecord {
+            timestamp_unix_ns: 0,
+            ..event.clone()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(events, vec![EventRecord {
+        name: "hello".into(),
+        timestamp_unix_ns: 0,
+        level: EventLevel::Info,
+        properties: vec![
+            ("message".into(), "hello".into()),
+            ("level".into(), "INFO".into()),
+            ("target".into(), "lib".into()),
+        ],
+    }]);
+}
+
+#[test]
+#[serial]
+fn log_without_local_parent() {
+    install_logger();
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let _root = Span::root("root", SpanContext::random());
+        log::info!("hello");
+    }
+
+    minitrace::flush();
+
+    let collected_spans = collected_spans.lock();
+    assert_eq!(collected_spans.len(), 1);
+    assert!(collected_spans[0].events.is_empty());
+}
@@ -16,42 +16,127 @@ use std::collections::HashMap;
 
 use proc_macro2::Span;
 use quote::quote_spanned;
+use syn::ext::IdentExt;
 use syn::parse::Parse;
 use syn::parse::ParseStream;
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+use syn::visit_mut::{self};
 use syn::*;
 
+#[derive(Clone)]
 struct Args {
     name: Option<String>,
+    name_expr: Option<Expr>,
+    target: Option<String>,
     short_name: bool,
     enter_on_poll: bool,
-    properties: Vec<(String, String)>,
+    force_async: bool,
+    properties: Vec<(String, PropertyValue)>,
+    ret: Option<String>,
+    error_if: Option<Expr>,
+    kind: Option<Ident>,
+    skip: Vec<Ident>,
+    max_depth: Option<usize>,
 }
 
 struct Property {
     key: String,
-    value: String,
+    value: PropertyValue,
+}
+
+#[derive(Clone)]
+enum PropertyValue {
+    /// A format string, e.g. `"argument a is {a:?}"`.
+    Format(String),
+    /// `display("{expr}")`, which formats the expression with `Display`.
+    Display(Expr),
+    /// `raw("{expr}")`, which converts the expression, a `String` or a `&'static str`, into the
+    /// property value without formatting it.
+    Raw(Expr),
+}
+
+impl PropertyValue {
+    /// Returns whether the value refers to the variable `name`.
+    fn refers_to(&self, name: &str) -> bool {
+        match self {
+            PropertyValue::Format(value) => format_args_idents(value).any(|ident| ident == name),
+            PropertyValue::Display(expr) | PropertyValue::Raw(expr) => {
+                token_stream_refers_to(quote::quote!(#expr), name)
+            }
+        }
+    }
 }
 
 impl Parse for Property {
     fn parse(input: ParseStream) -> Result<Self> {
         let key: LitStr = input.parse()?;
         input.parse::<Token![:]>()?;
-        let value: LitStr = input.parse()?;
+        if input.peek(LitStr) {
+            let value: LitStr = input.parse()?;
+            return Ok(Property {
+                key: key.value(),
+                value: PropertyValue::Format(value.value()),
+            });
+        }
+
+        let modifier: Ident = input.parse()?;
+        let content;
+        let _paren_token = syn::parenthesized!(content in input);
+        let value: LitStr = content.parse()?;
+        let expr = parse_braced_expr(&value).ok_or_else(|| {
+            syn::Error::new(
+                value.span(),
+                format!("expected a single `{{expression}}` in `{modifier}`"),
+            )
+        })?;
+        let value = match modifier.to_string().as_str() {
+            "display" => PropertyValue::Display(expr),
+            "raw" => PropertyValue::Raw(expr),
+            _ => {
+                return Err(syn::Error::new(
+                    modifier.span(),
+                    "unknown property modifier, expected `display` or `raw`",
+                ));
+            }
+        };
         Ok(Property {
             key: key.value(),
-            value: value.value(),
+            value,
         })
     }
 }
 
+/// Parses the expression of a string in the form of `"{expr}"`.
+fn parse_braced_expr(value: &LitStr) -> Option<Expr> {
+    let value_str = value.value();
+    let expr = value_str.trim().strip_prefix('{')?.strip_suffix('}')?;
+    LitStr::new(expr, value.span()).parse().ok()
+}
+
+fn token_stream_refers_to(tokens: proc_macro2::TokenStream, name: &str) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => ident.unraw() == name,
+        proc_macro2::TokenTree::Group(group) => token_stream_refers_to(group.stream(), name),
+        _ => false,
+    })
+}
+
 impl Parse for Args {
     fn parse(input: ParseStream) -> Result<Self> {
         let mut name = None;
+        let mut name_expr = None;
+        let mut target = None;
         let mut short_name = false;
         let mut enter_on_poll = false;
+        let mut force_async = false;
         let mut properties = Vec::new();
+        let mut ret = None;
+        let mut error_if = None;
+        let mut kind = None;
+        let mut skip = Vec::new();
+        let mut max_depth = None;
         let mut seen = HashMap::new();
 
         while !input.is_empty() {
@@ -66,6 +151,20 @@ impl Parse for Args {
                     let parsed_name: LitStr = input.parse()?;
                     name = Some(parsed_name.value());
                 }
+                "name_expr" => {
+                    let parsed_name_expr: LitStr = input.parse()?;
+                    let expr = parsed_name_expr.parse::<Expr>().map_err(|_| {
+                        syn::Error::new(
+                            parsed_name_expr.span(),
+                            "invalid expression in `name_expr`",
+                        )
+                    })?;
+                    name_expr = Some(expr);
+                }
+                "target" => {
+                    let parsed_target: LitStr = input.parse()?;
+                    target = Some(parsed_target.value());
+                }
                 "short_name" => {
                     let parsed_short_name: LitBool = input.parse()?;
                     short_name = parsed_short_name.value;
@@ -74,6 +173,10 @@ impl Parse for Args {
                     let parsed_enter_on_poll: LitBool = input.parse()?;
                     enter_on_poll = parsed_enter_on_poll.value;
                 }
+                "force_async" => {
+                    let parsed_force_async: LitBool = input.parse()?;
+                    force_async = parsed_force_async.value;
+                }
                 "properties" => {
                     let content;
                     let _brace_token = syn::braced!(content in input);
@@ -89,6 +192,53 @@ impl Parse for Args {
                         properties.push((property.key, property.value));
                     }
                 }
+                "ret" => {
+                    let parsed_ret: LitStr = input.parse()?;
+                    ret = Some(parsed_ret.value());
+                }
+                "error_if" => {
+                    let parsed_error_if: LitStr = input.parse()?;
+                    let expr = parsed_error_if.parse::<Expr>().map_err(|_| {
+                        syn::Error::new(parsed_error_if.span(), "invalid expression in `error_if`")
+                    })?;
+                    error_if = Some(expr);
+                }
+                "kind" => {
+                    let parsed_kind: LitStr = input.parse()?;
+                    let variant = match parsed_kind.value().as_str() {
+                        "internal" => "Internal",
+                        "server" => "Server",
+                        "client" => "Client",
+                        "producer" => "Producer",
+                        "consumer" => "Consumer",
+                        _ => {
+                            return Err(syn::Error::new(
+                                parsed_kind.span(),
+                                "unknown span kind, expected one of `internal`, `server`, \
+                                 `client`, `producer` or `consumer`",
+                            ));
+                        }
+                    };
+                    kind = Some(Ident::new(variant, parsed_kind.span()));
+                }
+                "skip" => {
+                    let content;
+                    let _bracket_token = syn::bracketed!(content in input);
+                    let parsed_skip: Punctuated<Ident, Token![,]> =
+                        content.parse_terminated(Ident::parse_any)?;
+                    skip.extend(parsed_skip);
+                }
+                "max_depth" => {
+                    let parsed_max_depth: LitInt = input.parse()?;
+                    let value = parsed_max_depth.base10_parse::<usize>()?;
+                    if value == 0 {
+                        return Err(syn::Error::new(
+                            parsed_max_depth.span(),
+                            "`max_depth` must be greater than zero",
+                        ));
+                    }
+                    max_depth = Some(value);
+                }
                 _ => return Err(syn::Error::new(Span::call_site(), "unexpected identifier")),
             }
             if !input.is_empty() {
@@ -98,9 +248,17 @@ impl Parse for Args {
 
         Ok(Args {
             name,
+            name_expr,
+            target,
             short_name,
             enter_on_poll,
+            force_async,
             properties,
+            ret,
+            error_if,
+            kind,
+            skip,
+            max_depth,
         })
     }
 }
@@ -118,12 +276,55 @@ impl Parse for Args {
 /// ## Arguments
 ///
 /// * `name` - The name of the span. Defaults to the full path of the function.
+/// * `name_expr` - An expression evaluated into the name of the span when the function is called,
+///   where the function arguments are accessible, e.g. `"format!(\"query:{}\", table)"`. It must
+///   evaluate to an `impl Into<Cow<'static, str>>`. Can not be used with `name`, `short_name`,
+///   `target` or `enter_on_poll`.
 /// * `short_name` - Whether to use the function name without path as the span name. Defaults to
 ///   `false`.
+/// * `target` - A prefix prepended to the span name with `::`, e.g. `"my_module::function_name"`.
+///   Spans can then be filtered by target in the reporter, see
+///   `minitrace::collector::report_only_target()`. Defaults to no prefix.
 /// * `enter_on_poll` - Whether to enter the span on poll. If set to `false`, `in_span` will be
 ///   used. Only available for `async fn`. Defaults to `false`.
+/// * `force_async` - Whether to instrument the future returned by a non-async function, for
+///   return types that are not recognized as futures. Functions returning `impl Future<...>` or
+///   `Pin<Box<dyn Future<...>>>` are recognized without it. The future is boxed by `Box::pin()`
+///   unless the return type is `impl Trait`. Defaults to `false`.
 /// * `properties` - A list of key-value pairs to be added as properties to the span. The value can
-///   be a format string, where the function arguments are accessible. Defaults to `{}`.
+///   be a format string, where the function arguments are accessible. A value can also be
+///   `display("{expr}")` to format an expression, such as `self.id`, with `Display`, or
+///   `raw("{expr}")` to use an expression that is already a `String` or a `&'static str` as is,
+///   without the allocation of `format!`. Defaults to `{}`.
+/// * `ret` - A format string to record the return value as the property `return`, e.g. `"{:?}"`.
+///   Omitted for functions returning `()`. Can not be used with `enter_on_poll`. Defaults to not
+///   recording the return value.
+/// * `error_if` - A boolean expression evaluated when the function returns, where the return value
+///   is accessible by reference as `result`, e.g. `"result.is_err()"`. The span status is set to
+///   `SpanStatus::Error` if it is `true`, otherwise `SpanStatus::Ok`. Can not be used with
+///   `enter_on_poll`. Defaults to leaving the status unset.
+/// * `kind` - The kind of the span, one of `"internal"`, `"server"`, `"client"`, `"producer"` or
+///   `"consumer"`. Can not be used with `enter_on_poll`. Defaults to `"internal"`.
+/// * `skip` - A list of function arguments to keep out of the span, e.g. `[password]`, which is
+///   useful for arguments that are sensitive or expensive to format. Referring to a skipped
+///   argument in `properties` is a compile error. Defaults to `[]`.
+/// * `max_depth` - The maximum number of nested calls of the function traced on a thread, which is
+///   useful for recursive functions. The deeper calls run without a span. Only available for
+///   non-async functions not returning a future. Defaults to no limit.
+///
+/// With `ret` or `error_if`, the early exits of a non-async function are captured by rewriting
+/// `return` into a break out of the function body, and `?` as well if the return type is spelled
+/// `Result<...>` or `Option<...>`. A `?` in a function returning another type, or a `return` or
+/// `?` within a macro invocation, leaves the function without recording the return value.
+///
+/// A non-async function returning a future is instrumented like an `async fn`: its body is
+/// evaluated with the span as the local parent, and the returned future is polled within the span.
+///
+/// `#[trace]` can also be applied on an `impl` block to instrument all of its methods, except
+/// `const fn`, as if each of them were annotated with `#[trace]` with the same arguments, which
+/// can not include `name`, `name_expr` or `skip`. A method annotated with `#[trace(skip)]` is
+/// left uninstrumented, and a method annotated with its own `#[trace(...)]` uses its own
+/// arguments.
 ///
 /// # Examples
 ///
@@ -145,10 +346,79 @@ impl Parse for Args {
 ///     // ...
 /// }
 ///
+/// #[trace(target = "my_module", short_name = true)]
+/// fn targeted() {
+///     // ...
+/// }
+///
+/// #[trace(name_expr = "format!(\"query:{}\", table)")]
+/// fn query(table: &str) {
+///     // ...
+/// }
+///
 /// #[trace(properties = { "k1": "v1", "a": "argument `a` is {a:?}" })]
 /// async fn properties(a: u64) {
 ///     // ...
 /// }
+///
+/// #[trace(ret = "{:?}")]
+/// fn ret(a: u64) -> u64 {
+///     a + 1
+/// }
+///
+/// #[trace(error_if = "result.is_err()")]
+/// fn fallible(a: &str) -> Result<u64, std::num::ParseIntError> {
+///     a.parse()
+/// }
+///
+/// #[trace(kind = "server")]
+/// async fn handle_request() {
+///     // ...
+/// }
+///
+/// #[trace(properties = { "user": "{user}" }, skip = [password])]
+/// fn login(user: &str, password: &str) {
+///     // ...
+/// }
+///
+/// struct Request {
+///     id: u64,
+///     path: String,
+/// }
+///
+/// impl Request {
+///     #[trace(properties = { "id": display("{self.id}"), "path": raw("{self.path.clone()}") })]
+///     fn handle(&self) {
+///         // ...
+///     }
+/// }
+///
+/// #[trace(max_depth = 3)]
+/// fn factorial(n: u64) -> u64 {
+///     if n <= 1 {
+///         1
+///     } else {
+///         n * factorial(n - 1)
+///     }
+/// }
+///
+/// struct Storage;
+///
+/// #[trace(short_name = true)]
+/// impl Storage {
+///     fn get(&self, key: &str) -> Option<String> {
+///         None
+///     }
+///
+///     async fn put(&self, key: &str, value: String) {
+///         // ...
+///     }
+///
+///     #[trace(skip)]
+///     fn len(&self) -> usize {
+///         0
+///     }
+/// }
 /// ```
 ///
 /// The code snippets above will be expanded to:
@@ -178,6 +448,16 @@ impl Parse for Args {
 ///     .await
 /// }
 ///
+/// fn targeted() {
+///     let __guard__ = LocalSpan::enter_with_local_parent("my_module::targeted");
+///     // ...
+/// }
+///
+/// fn query(table: &str) {
+///     let __guard__ = LocalSpan::enter_with_local_parent(format!("query:{}", table));
+///     // ...
+/// }
+///
 /// async fn properties(a: u64) {
 ///     let __span__ = Span::enter_with_local_parent("example::properties").with_properties(|| {
 ///         [
@@ -194,6 +474,13 @@ impl Parse for Args {
 ///     .in_span(__span__)
 ///     .await
 /// }
+///
+/// fn ret(a: u64) -> u64 {
+///     let __guard__ = LocalSpan::enter_with_local_parent("example::ret");
+///     let __ret__: u64 = '__trace__: { a + 1 };
+///     let __guard__ = __guard__.with_property(|| ("return", format!("{:?}", __ret__)));
+///     __ret__
+/// }
 /// ```
 #[proc_macro_attribute]
 #[proc_macro_error]
@@ -202,7 +489,100 @@ pub fn trace(
     item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
     let args = parse_macro_input!(args as Args);
-    let input = syn::parse_macro_input!(item as ItemFn);
+    let item = syn::parse_macro_input!(item as Item);
+
+    match item {
+        Item::Fn(input) => trace_fn(args, input),
+        Item::Impl(input) => trace_impl(&args, input),
+        _ => abort_call_site!("`#[trace]` can only be applied on a function or an impl block"),
+    }
+    .into()
+}
+
+/// Instruments every method of an impl block as if it were annotated with `#[trace]` with the
+/// same arguments. The methods annotated with `#[trace(skip)]` are left as is, and the methods
+/// annotated with `#[trace(...)]` are instrumented by their own attribute.
+fn trace_impl(args: &Args, mut input: ItemImpl) -> proc_macro2::TokenStream {
+    if args.name.is_some() {
+        abort_call_site!("`name` can not be used on an impl block")
+    }
+    if args.name_expr.is_some() {
+        abort_call_site!("`name_expr` can not be used on an impl block")
+    }
+    if !args.skip.is_empty() {
+        abort_call_site!("`skip` can not be used on an impl block")
+    }
+
+    for item in &mut input.items {
+        let ImplItem::Method(method) = item else {
+            continue;
+        };
+
+        let mut skipped = false;
+        let mut traced = false;
+        method.attrs.retain(|attr| {
+            if !is_trace_attr(attr) {
+                return true;
+            }
+            if attr
+                .parse_args::<Ident>()
+                .is_ok_and(|ident| ident == "skip")
+            {
+                skipped = true;
+                return false;
+            }
+            traced = true;
+            true
+        });
+        if skipped || traced || method.sig.constness.is_some() || method.defaultness.is_some() {
+            continue;
+        }
+
+        let ImplItemMethod {
+            attrs,
+            vis,
+            sig,
+            block,
+            ..
+        } = method.clone();
+        let method_fn = trace_fn(args.clone(), ItemFn {
+            attrs,
+            vis,
+            sig,
+            block: Box::new(block),
+        });
+        *method = parse_quote!(#method_fn);
+    }
+
+    quote::quote!(#input)
+}
+
+/// Returns whether the attribute is `#[trace(...)]` or `#[minitrace::trace(...)]`.
+fn is_trace_attr(attr: &Attribute) -> bool {
+    attr.path.is_ident("trace")
+        || (attr.path.segments.len() == 2
+            && attr.path.segments[0].ident == "minitrace"
+            && attr.path.segments[1].ident == "trace")
+}
+
+fn trace_fn(mut args: Args, input: ItemFn) -> proc_macro2::TokenStream {
+    if args.ret.is_some() && args.enter_on_poll {
+        abort_call_site!("`enter_on_poll` can not be used with `ret`")
+    }
+    if args.error_if.is_some() && args.enter_on_poll {
+        abort_call_site!("`enter_on_poll` can not be used with `error_if`")
+    }
+    // The name would be evaluated on the first poll, after the arguments have been moved into the
+    // future.
+    if args.name_expr.is_some() && args.enter_on_poll {
+        abort_call_site!("`enter_on_poll` can not be used with `name_expr`")
+    }
+    let returns_future = input.sig.asyncness.is_none()
+        && (args.force_async || returns_future(&input.sig.output));
+    if args.max_depth.is_some() && (input.sig.asyncness.is_some() || returns_future) {
+        abort_call_site!("`max_depth` can not be applied on async function")
+    }
+    check_skip(&input.sig, &args);
 
     let func_name = input.sig.ident.to_string();
     // check for async_trait-like patterns in the block, and instrument
@@ -222,8 +602,14 @@ pub fn trace(
             AsyncTraitKind::Async(async_expr) => {
                 // fallback if we couldn't find the '__async_trait' binding, might be
                 // useful for crates exhibiting the same behaviors as async-trait
-                let instrumented_block =
-                    gen_block(&func_name, &async_expr.block, true, false, &args);
+                let instrumented_block = gen_block(
+                    &func_name,
+                    &async_expr.block,
+                    &input.sig.output,
+                    true,
+                    false,
+                    &args,
+                );
                 let async_attrs = &async_expr.attrs;
                 quote::quote! {
                     Box::pin(#(#async_attrs) * #instrumented_block)
@@ -231,13 +617,23 @@ pub fn trace(
             }
         }
     } else {
-        gen_block(
-            &func_name,
-            &input.block,
-            input.sig.asyncness.is_some(),
-            input.sig.asyncness.is_some(),
-            &args,
-        )
+        if returns_unit(&input.sig.output) {
+            args.ret = None;
+        }
+
+        if returns_future {
+            let boxed = !returns_impl_trait(&input.sig.output);
+            gen_future_block(&func_name, &input.block, boxed, &args)
+        } else {
+            gen_block(
+                &func_name,
+                &input.block,
+                &input.sig.output,
+                input.sig.asyncness.is_some(),
+                input.sig.asyncness.is_some(),
+                &args,
+            )
+        }
     };
 
     let ItemFn {
@@ -269,10 +665,105 @@ pub fn trace(
             #func_body
         }
     )
+}
+
+/// Derives `minitrace::SpanFields` for a struct, exposing every `pub` field as a span property.
+///
+/// The property key is the name of the field, and the property value is formatted by `Display`,
+/// so every `pub` field is required to implement `Display`. Private fields are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::prelude::*;
+/// use minitrace::SpanFields;
+///
+/// #[derive(SpanFields)]
+/// struct Request {
+///     pub method: String,
+///     pub path: String,
+///     pub status: u16,
+///     token: String,
+/// }
+///
+/// let request = Request {
+///     method: "GET".to_string(),
+///     path: "/".to_string(),
+///     status: 200,
+///     token: "secret".to_string(),
+/// };
+/// let root = Span::root("root", SpanContext::random()).with_properties_from_struct(&request);
+/// ```
+#[proc_macro_derive(SpanFields)]
+#[proc_macro_error]
+pub fn span_fields(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(item as DeriveInput);
+
+    let fields = match &input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => fields.named.iter().collect(),
+        Data::Struct(DataStruct {
+            fields: Fields::Unit,
+            ..
+        }) => vec![],
+        _ => abort_call_site!("`SpanFields` can only be derived for structs with named fields"),
+    };
+
+    let properties = fields
+        .into_iter()
+        .filter(|field| matches!(field.vis, Visibility::Public(_)))
+        .map(|field| {
+            let ident = field.ident.as_ref().unwrap();
+            let key = ident.unraw().to_string();
+            quote::quote_spanned!(field.span()=>
+                (
+                    std::borrow::Cow::Borrowed(#key),
+                    std::borrow::Cow::Owned(std::string::ToString::to_string(&self.#ident)),
+                )
+            )
+        });
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    quote::quote!(
+        impl #impl_generics minitrace::SpanFields for #ident #ty_generics #where_clause {
+            fn span_fields(
+                &self,
+            ) -> std::vec::Vec<(std::borrow::Cow<'static, str>, std::borrow::Cow<'static, str>)> {
+                std::vec![#(#properties),*]
+            }
+        }
+    )
     .into()
 }
 
 fn gen_name(span: proc_macro2::Span, func_name: &str, args: &Args) -> proc_macro2::TokenStream {
+    let target = match &args.target {
+        Some(target) if target.is_empty() => {
+            abort_call_site!("`target` can not be empty")
+        }
+        Some(target) => Some(target.as_str()),
+        None => None,
+    };
+
+    if let Some(name_expr) = &args.name_expr {
+        if args.name.is_some() {
+            abort_call_site!("`name` and `name_expr` can not be used together")
+        }
+        if args.short_name {
+            abort_call_site!("`name_expr` and `short_name` can not be used together")
+        }
+        if target.is_some() {
+            abort_call_site!("`name_expr` and `target` can not be used together")
+        }
+        return quote_spanned!(span=>
+            #name_expr
+        );
+    }
+
     match &args.name {
         Some(name) if name.is_empty() => {
             abort_call_site!("`name` can not be empty")
@@ -281,20 +772,44 @@ fn gen_name(span: proc_macro2::Span, func_name: &str, args: &Args) -> proc_macro
             abort_call_site!("`name` and `short_name` can not be used together")
         }
         Some(name) => {
+            let name = with_target(target, name);
             quote_spanned!(span=>
                 #name
             )
         }
         None if args.short_name => {
+            let name = with_target(target, func_name);
             quote_spanned!(span=>
-                #func_name
-            )
-        }
-        None => {
-            quote_spanned!(span=>
-                minitrace::full_name!()
+                #name
             )
         }
+        None => match target {
+            Some(target) => {
+                // Formats the name only once to keep the hot path free of allocation.
+                quote_spanned!(span=>
+                    {
+                        static NAME: std::sync::OnceLock<std::string::String> =
+                            std::sync::OnceLock::new();
+                        NAME.get_or_init(|| {
+                            format!("{}::{}", #target, minitrace::full_name!())
+                        })
+                        .as_str()
+                    }
+                )
+            }
+            None => {
+                quote_spanned!(span=>
+                    minitrace::full_name!()
+                )
+            }
+        },
+    }
+}
+
+fn with_target(target: Option<&str>, name: &str) -> String {
+    match target {
+        Some(target) => format!("{target}::{name}"),
+        None => name.to_string(),
     }
 }
 
@@ -309,18 +824,27 @@ fn gen_properties(span: proc_macro2::Span, args: &Args) -> proc_macro2::TokenStr
 
     let properties = args.properties.iter().map(|(k, v)| {
         let k = k.as_str();
-        let v = v.as_str();
 
-        let (v, need_format) = unescape_format_string(v);
+        match v {
+            PropertyValue::Format(v) => {
+                let (v, need_format) = unescape_format_string(v);
 
-        if need_format {
-            quote_spanned!(span=>
-                (std::borrow::Cow::from(#k), std::borrow::Cow::from(format!(#v)))
-            )
-        } else {
-            quote_spanned!(span=>
-                (std::borrow::Cow::from(#k), std::borrow::Cow::from(#v))
-            )
+                if need_format {
+                    quote_spanned!(span=>
+                        (std::borrow::Cow::from(#k), std::borrow::Cow::from(format!(#v)))
+                    )
+                } else {
+                    quote_spanned!(span=>
+                        (std::borrow::Cow::from(#k), std::borrow::Cow::from(#v))
+                    )
+                }
+            }
+            PropertyValue::Display(expr) => quote_spanned!(span=>
+                (std::borrow::Cow::from(#k), std::borrow::Cow::from(format!("{}", #expr)))
+            ),
+            PropertyValue::Raw(expr) => quote_spanned!(span=>
+                (std::borrow::Cow::from(#k), std::borrow::Cow::from(#expr))
+            ),
         }
     });
     let properties = Punctuated::<_, Token![,]>::from_iter(properties);
@@ -329,6 +853,84 @@ fn gen_properties(span: proc_macro2::Span, args: &Args) -> proc_macro2::TokenStr
     )
 }
 
+/// Checks that the skipped arguments are parameters of the function and are not referred to by
+/// the properties.
+fn check_skip(sig: &Signature, args: &Args) {
+    let params = sig
+        .inputs
+        .iter()
+        .filter_map(|param| match param {
+            FnArg::Receiver(_) => Some("self".to_string()),
+            FnArg::Typed(PatType { pat, .. }) => match &**pat {
+                Pat::Ident(PatIdent { ident, .. }) => Some(ident.unraw().to_string()),
+                _ => None,
+            },
+        })
+        .collect::<Vec<_>>();
+
+    for skipped in &args.skip {
+        let name = skipped.unraw().to_string();
+        if !params.contains(&name) {
+            abort!(
+                skipped.span(),
+                "`{}` is not an argument of the function",
+                name
+            );
+        }
+        if let Some((key, _)) = args
+            .properties
+            .iter()
+            .find(|(_, value)| value.refers_to(&name))
+        {
+            abort!(
+                skipped.span(),
+                "`{}` is skipped but referred to by the property `{}`",
+                name,
+                key
+            );
+        }
+        if let Some(name_expr) = &args.name_expr {
+            if token_stream_refers_to(quote::quote!(#name_expr), &name) {
+                abort!(
+                    skipped.span(),
+                    "`{}` is skipped but referred to by `name_expr`",
+                    name
+                );
+            }
+        }
+    }
+}
+
+/// Returns the names of the arguments captured by a format string, such as `a` in `"{a:?}"`.
+fn format_args_idents(s: &str) -> impl Iterator<Item = &str> {
+    s.split("{{")
+        .flat_map(|s| s.split('{').skip(1))
+        .filter_map(|arg| {
+            let arg = arg.split('}').next()?;
+            let name = arg.split(':').next()?.trim();
+            let name = name.strip_prefix("r#").unwrap_or(name);
+            let mut chars = name.chars();
+            let first = chars.next()?;
+            ((first.is_alphabetic() || first == '_')
+                && chars.all(|c| c.is_alphanumeric() || c == '_'))
+            .then_some(name)
+        })
+}
+
+fn gen_kind(span: proc_macro2::Span, args: &Args) -> proc_macro2::TokenStream {
+    let Some(kind) = &args.kind else {
+        return quote::quote!();
+    };
+
+    if args.enter_on_poll {
+        abort_call_site!("`enter_on_poll` can not be used with `kind`")
+    }
+
+    quote_spanned!(span=>
+        .with_kind(minitrace::collector::SpanKind::#kind)
+    )
+}
+
 fn unescape_format_string(s: &str) -> (String, bool) {
     let unescaped_delete = s.replace("{{", "").replace("}}", "");
     let contains_valid_format_string =
@@ -341,16 +943,80 @@ fn unescape_format_string(s: &str) -> (String, bool) {
     }
 }
 
+fn returns_unit(output: &ReturnType) -> bool {
+    match output {
+        ReturnType::Default => true,
+        ReturnType::Type(_, ty) => matches!(&**ty, Type::Tuple(tuple) if tuple.elems.is_empty()),
+    }
+}
+
+fn returns_impl_trait(output: &ReturnType) -> bool {
+    matches!(output, ReturnType::Type(_, ty) if matches!(&**ty, Type::ImplTrait(_)))
+}
+
+/// Returns whether the return type is `impl Future<...>` or `Pin<Box<dyn Future<...>>>`.
+fn returns_future(output: &ReturnType) -> bool {
+    let ReturnType::Type(_, ty) = output else {
+        return false;
+    };
+    match &**ty {
+        Type::ImplTrait(TypeImplTrait { bounds, .. }) => bounds_contain_future(bounds),
+        Type::Path(TypePath { path, .. }) => {
+            let Some(pinned) = single_generic_type(path, "Pin") else {
+                return false;
+            };
+            let Type::Path(TypePath { path, .. }) = pinned else {
+                return false;
+            };
+            matches!(
+                single_generic_type(path, "Box"),
+                Some(Type::TraitObject(TypeTraitObject { bounds, .. })) if bounds_contain_future(bounds)
+            )
+        }
+        _ => false,
+    }
+}
+
+/// Returns the type argument of a path like `Pin<T>`, if the last segment is named `ident` and
+/// has a single type argument.
+fn single_generic_type<'a>(path: &'a Path, ident: &str) -> Option<&'a Type> {
+    let segment = path.segments.last()?;
+    if segment.ident != ident {
+        return None;
+    }
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    let mut types = arguments.args.iter().filter_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    });
+    let ty = types.next()?;
+    types.next().is_none().then_some(ty)
+}
+
+fn bounds_contain_future(bounds: &Punctuated<TypeParamBound, Token![+]>) -> bool {
+    bounds.iter().any(|bound| match bound {
+        TypeParamBound::Trait(TraitBound { path, .. }) => path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Future"),
+        _ => false,
+    })
+}
+
 /// Instrument a block
 fn gen_block(
     func_name: &str,
     block: &Block,
+    output: &ReturnType,
     async_context: bool,
     async_keyword: bool,
     args: &Args,
 ) -> proc_macro2::TokenStream {
     let name = gen_name(block.span(), func_name, args);
     let properties = gen_properties(block.span(), args);
+    let kind = gen_kind(block.span(), args);
 
     // Generate the instrumented function body.
     // If the function is an `async fn`, this will wrap it in an async block.
@@ -359,16 +1025,28 @@ fn gen_block(
         let block = if args.enter_on_poll {
             quote_spanned!(block.span()=>
                 minitrace::future::FutureExt::enter_on_poll(
-                    async move { #block },
+                    async move #block,
                     #name
                 )
             )
+        } else if args.ret.is_some() || args.error_if.is_some() {
+            let in_span = gen_in_span_with_ret(
+                block.span(),
+                quote_spanned!(block.span()=> async move #block),
+                args,
+            );
+            quote_spanned!(block.span()=>
+                {
+                    let __span__ = minitrace::Span::enter_with_local_parent( #name ) #properties #kind;
+                    #in_span
+                }
+            )
         } else {
             quote_spanned!(block.span()=>
                 {
-                    let __span__ = minitrace::Span::enter_with_local_parent( #name ) #properties;
+                    let __span__ = minitrace::Span::enter_with_local_parent( #name ) #properties #kind;
                     minitrace::future::FutureExt::in_span(
-                        async move { #block },
+                        async move #block,
                         __span__,
                     )
                 }
@@ -387,11 +1065,291 @@ fn gen_block(
             abort_call_site!("`enter_on_poll` can not be applied on non-async function");
         }
 
+        let depth = gen_depth(block.span(), args);
+        let enter = quote_spanned!(block.span()=>
+            minitrace::local::LocalSpan::enter_with_local_parent( #name ) #properties #kind
+        );
+        let enter = if depth.is_some() {
+            quote_spanned!(block.span()=>
+                match __traced__ {
+                    true => #enter,
+                    false => minitrace::local::LocalSpan::default(),
+                }
+            )
+        } else {
+            enter
+        };
+
+        if args.ret.is_some() || args.error_if.is_some() {
+            let ret = gen_ret(block.span(), args, quote::quote!(__guard__));
+            let status = gen_status(block.span(), args).map(|status| {
+                let set_status = quote_spanned!(block.span()=>
+                    minitrace::local::LocalSpan::set_status(#status);
+                );
+                // Without a span, the status would be set on the local parent.
+                if depth.is_some() {
+                    quote_spanned!(block.span()=>
+                        if __traced__ { #set_status }
+                    )
+                } else {
+                    set_status
+                }
+            });
+            // Runs the body in a labeled block, with the early exits turned into breaks out of
+            // it, so that they are captured as well.
+            let ret_type = gen_ret_type(output);
+            let mut block = block.clone();
+            EarlyExitRewriter::new(output).visit_block_mut(&mut block);
+            quote_spanned!(block.span()=>
+                #depth
+                let __guard__ = #enter;
+                #[allow(unused_labels, clippy::question_mark, clippy::useless_conversion)]
+                let __ret__ #ret_type = '__trace__: #block;
+                #ret
+                #status
+                __ret__
+            )
+        } else {
+            quote_spanned!(block.span()=>
+                #depth
+                let __guard__ = #enter;
+                #block
+            )
+        }
+    }
+}
+
+/// Annotates `__ret__` with the return type, which helps inferring the error type converted by a
+/// rewritten `?`. Omitted for `impl Trait`, which can not annotate a binding.
+fn gen_ret_type(output: &ReturnType) -> Option<proc_macro2::TokenStream> {
+    let ReturnType::Type(_, ty) = output else {
+        return None;
+    };
+    if contains_impl_trait(quote::quote!(#ty)) {
+        return None;
+    }
+    Some(quote_spanned!(ty.span()=> : #ty))
+}
+
+fn contains_impl_trait(tokens: proc_macro2::TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => ident == "impl",
+        proc_macro2::TokenTree::Group(group) => contains_impl_trait(group.stream()),
+        _ => false,
+    })
+}
+
+/// The return types whose `?` can be rewritten into a break out of the labeled block.
+#[derive(Clone, Copy)]
+enum TryKind {
+    Result,
+    Option,
+}
+
+/// Rewrites the `return`s of a function body into `break '__trace__`, and the `?`s into a `match`
+/// breaking out with the residual, if the return type is spelled `Result<...>` or `Option<...>`.
+///
+/// The closures, async blocks and nested items are left untouched, as their early exits don't
+/// leave the function. So are the macro invocations, whose tokens can not be inspected.
+struct EarlyExitRewriter {
+    try_kind: Option<TryKind>,
+}
+
+impl EarlyExitRewriter {
+    fn new(output: &ReturnType) -> Self {
+        let try_kind = match output {
+            ReturnType::Type(_, ty) => match &**ty {
+                Type::Path(TypePath { path, .. }) => {
+                    path.segments
+                        .last()
+                        .and_then(|segment| match segment.ident.to_string().as_str() {
+                            "Result" => Some(TryKind::Result),
+                            "Option" => Some(TryKind::Option),
+                            _ => None,
+                        })
+                }
+                _ => None,
+            },
+            ReturnType::Default => None,
+        };
+        EarlyExitRewriter { try_kind }
+    }
+}
+
+impl VisitMut for EarlyExitRewriter {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Closure(_) | Expr::Async(_) => {}
+            Expr::Return(ExprReturn {
+                expr: value,
+                return_token,
+                ..
+            }) => {
+                if let Some(value) = value {
+                    self.visit_expr_mut(value);
+                }
+                *expr = parse_quote_spanned!(return_token.span=> break '__trace__ #value);
+            }
+            Expr::Try(ExprTry {
+                expr: value,
+                question_token,
+                ..
+            }) if self.try_kind.is_some() => {
+                self.visit_expr_mut(value);
+                let span = question_token.span;
+                *expr = match self.try_kind.unwrap() {
+                    TryKind::Result => parse_quote_spanned!(span=>
+                        match #value {
+                            ::core::result::Result::Ok(__value__) => __value__,
+                            ::core::result::Result::Err(__err__) => {
+                                break '__trace__ ::core::result::Result::Err(
+                                    ::core::convert::From::from(__err__),
+                                )
+                            }
+                        }
+                    ),
+                    TryKind::Option => parse_quote_spanned!(span=>
+                        match #value {
+                            ::core::option::Option::Some(__value__) => __value__,
+                            ::core::option::Option::None => {
+                                break '__trace__ ::core::option::Option::None
+                            }
+                        }
+                    ),
+                };
+            }
+            _ => visit_mut::visit_expr_mut(self, expr),
+        }
+    }
+
+    fn visit_item_mut(&mut self, _item: &mut Item) {}
+}
+
+/// Instrument the block of a non-async function returning a future. The block is evaluated with the
+/// span as the local parent, and the returned future is polled within the span.
+fn gen_future_block(
+    func_name: &str,
+    block: &Block,
+    boxed: bool,
+    args: &Args,
+) -> proc_macro2::TokenStream {
+    let name = gen_name(block.span(), func_name, args);
+    let properties = gen_properties(block.span(), args);
+    let kind = gen_kind(block.span(), args);
+
+    let box_pin = |future: proc_macro2::TokenStream| {
+        if boxed {
+            quote_spanned!(block.span()=>
+                std::boxed::Box::pin(#future)
+            )
+        } else {
+            future
+        }
+    };
+
+    if args.enter_on_poll {
+        return box_pin(quote_spanned!(block.span()=>
+            minitrace::future::FutureExt::enter_on_poll(#block, #name)
+        ));
+    }
+
+    let in_span = box_pin(if args.ret.is_some() || args.error_if.is_some() {
+        gen_in_span_with_ret(block.span(), quote::quote!(__future__), args)
+    } else {
         quote_spanned!(block.span()=>
-            let __guard__ = minitrace::local::LocalSpan::enter_with_local_parent( #name ) #properties;
+            minitrace::future::FutureExt::in_span(__future__, __span__)
+        )
+    });
+    quote_spanned!(block.span()=>
+        let __span__ = minitrace::Span::enter_with_local_parent( #name ) #properties #kind;
+        let __future__ = {
+            let __guard__ = __span__.set_local_parent();
             #block
+        };
+        #in_span
+    )
+}
+
+/// Polls `future` within the span `__span__` like `in_span`, but keeps the span alive until the
+/// return value is inspected.
+fn gen_in_span_with_ret(
+    span: proc_macro2::Span,
+    future: proc_macro2::TokenStream,
+    args: &Args,
+) -> proc_macro2::TokenStream {
+    let ret = gen_ret(span, args, quote::quote!(__span__));
+    let status = gen_status(span, args).map(|status| {
+        quote_spanned!(span=>
+            let mut __span__ = __span__;
+            __span__.set_status(#status);
         )
-    }
+    });
+    quote_spanned!(span=>
+        async move {
+            let mut __future__ = std::pin::pin!(#future);
+            let __ret__ = std::future::poll_fn(|cx| {
+                let __guard__ = __span__.set_local_parent();
+                std::future::Future::poll(__future__.as_mut(), cx)
+            })
+            .await;
+            #ret
+            #status
+            drop(__span__);
+            __ret__
+        }
+    )
+}
+
+/// Counts the nested calls of the function on the current thread, binding `__traced__` to whether
+/// the call is within `max_depth`. The count is decremented when the function returns or unwinds.
+fn gen_depth(span: proc_macro2::Span, args: &Args) -> Option<proc_macro2::TokenStream> {
+    let max_depth = args.max_depth?;
+    Some(quote_spanned!(span=>
+        std::thread_local! {
+            static __DEPTH__: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+        }
+        struct __DepthGuard__;
+        impl Drop for __DepthGuard__ {
+            fn drop(&mut self) {
+                __DEPTH__.with(|depth| depth.set(depth.get() - 1));
+            }
+        }
+        let __traced__ = __DEPTH__.with(|depth| {
+            depth.set(depth.get() + 1);
+            depth.get() <= #max_depth
+        });
+        let __depth_guard__ = __DepthGuard__;
+    ))
+}
+
+/// Rebinds `span` with the return value `__ret__` recorded as the property `return`.
+fn gen_ret(
+    span: proc_macro2::Span,
+    args: &Args,
+    binding: proc_macro2::TokenStream,
+) -> Option<proc_macro2::TokenStream> {
+    let ret = args.ret.as_ref()?;
+    Some(quote_spanned!(span=>
+        let #binding = #binding.with_property(|| {
+            (std::borrow::Cow::from("return"), std::borrow::Cow::from(format!(#ret, __ret__)))
+        });
+    ))
+}
+
+/// Evaluates `error_if` against the return value `__ret__` into a `SpanStatus`.
+fn gen_status(span: proc_macro2::Span, args: &Args) -> Option<proc_macro2::TokenStream> {
+    let error_if = args.error_if.as_ref()?;
+    Some(quote_spanned!(span=>
+        {
+            #[allow(unused_variables)]
+            let result = &__ret__;
+            if #error_if {
+                minitrace::collector::SpanStatus::Error(std::borrow::Cow::Borrowed(""))
+            } else {
+                minitrace::collector::SpanStatus::Ok
+            }
+        }
+    ))
 }
 
 enum AsyncTraitKind<'a> {
@@ -0,0 +1,9 @@
+use minitrace::SpanFields;
+
+#[derive(SpanFields)]
+enum Method {
+    Get,
+    Post,
+}
+
+fn main() {}
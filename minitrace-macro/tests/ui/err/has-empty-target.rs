@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(target = "")]
+fn f() {}
+
+fn main() {}
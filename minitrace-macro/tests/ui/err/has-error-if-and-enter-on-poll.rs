@@ -0,0 +1,8 @@
+use minitrace::trace;
+
+#[trace(enter_on_poll = true, error_if = "result.is_err()")]
+async fn f() -> Result<(), ()> {
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use minitrace::trace;
+
+#[trace(error_if = "result.is_err(")]
+fn f() -> Result<(), ()> {
+    Ok(())
+}
+
+fn main() {}
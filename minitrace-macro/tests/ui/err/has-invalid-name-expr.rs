@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(name_expr = "format!(\"query:{}\", table")]
+fn f(table: &str) {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(max_depth = 3)]
+async fn f() {}
+
+fn main() {}
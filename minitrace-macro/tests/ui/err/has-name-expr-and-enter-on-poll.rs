@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(name_expr = "format!(\"query:{}\", table)", enter_on_poll = true)]
+async fn f(table: String) {}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use minitrace::trace;
+
+#[trace(enter_on_poll = true, ret = "{:?}")]
+async fn f() -> i64 {
+    1
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(properties = { "a": "{a}", "b": "b is {b:?}" }, skip = [b])]
+fn f(a: u8, b: u8) {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(skip = [b])]
+fn f(a: u8) {}
+
+fn main() {}
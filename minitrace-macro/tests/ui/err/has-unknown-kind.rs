@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(kind = "remote")]
+fn f() {}
+
+fn main() {}
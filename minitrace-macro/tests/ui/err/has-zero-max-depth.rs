@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(max_depth = 0)]
+fn f() {}
+
+fn main() {}
@@ -0,0 +1,27 @@
+use std::fmt::Display;
+
+use minitrace::SpanFields;
+
+#[derive(SpanFields)]
+pub struct Request<T: Display> {
+    pub method: String,
+    pub r#type: T,
+    #[allow(dead_code)]
+    token: Vec<u8>,
+}
+
+#[derive(SpanFields)]
+struct Empty;
+
+fn main() {
+    let request = Request {
+        method: "GET".to_string(),
+        r#type: 1,
+        token: vec![],
+    };
+    let fields = request.span_fields();
+    assert_eq!(fields[0], ("method".into(), "GET".into()));
+    assert_eq!(fields[1], ("type".into(), "1".into()));
+    assert_eq!(fields.len(), 2);
+    assert!(Empty.span_fields().is_empty());
+}
@@ -0,0 +1,31 @@
+use minitrace::trace;
+
+#[trace(error_if = "result.is_err()")]
+fn f(a: &str) -> Result<i64, std::num::ParseIntError> {
+    let a = a.parse::<i64>()?;
+    Ok(a)
+}
+
+#[trace(error_if = "*result < 0", ret = "{}")]
+fn g(a: i64) -> i64 {
+    if a < 0 {
+        return -1;
+    }
+    a
+}
+
+#[trace(error_if = "result.is_none()")]
+async fn h(a: Option<String>) -> Option<String> {
+    a
+}
+
+#[trace(error_if = "true")]
+fn i() {}
+
+#[tokio::main]
+async fn main() {
+    f("1").ok();
+    g(1);
+    h(None).await;
+    i();
+}
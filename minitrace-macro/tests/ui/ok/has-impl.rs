@@ -0,0 +1,35 @@
+use minitrace::trace;
+
+trait Foo {
+    fn f(&self, a: u32) -> u32;
+    fn g(&self);
+}
+
+struct Bar;
+
+#[trace(short_name = true)]
+impl Foo for Bar {
+    fn f(&self, a: u32) -> u32 {
+        a
+    }
+
+    #[trace(skip)]
+    fn g(&self) {}
+}
+
+#[trace]
+impl Bar {
+    async fn h(&self) {}
+
+    const fn i(&self) -> u32 {
+        1
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    Bar.f(1);
+    Bar.g();
+    Bar.h().await;
+    Bar.i();
+}
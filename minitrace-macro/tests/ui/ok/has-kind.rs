@@ -0,0 +1,21 @@
+use minitrace::trace;
+
+#[trace(kind = "server")]
+fn f() {}
+
+#[trace(kind = "client", properties = { "a": "{a}" })]
+async fn g(a: u64) -> u64 {
+    a
+}
+
+#[trace(kind = "producer", ret = "{}")]
+fn h() -> u64 {
+    1
+}
+
+#[tokio::main]
+async fn main() {
+    f();
+    g(1).await;
+    h();
+}
@@ -0,0 +1,20 @@
+use minitrace::trace;
+
+#[trace(max_depth = 3)]
+fn f(n: u64) -> u64 {
+    if n <= 1 {
+        1
+    } else {
+        n * f(n - 1)
+    }
+}
+
+#[trace(max_depth = 1, ret = "{}", error_if = "*result > 1")]
+fn g<T: Into<u64>>(n: T) -> u64 {
+    n.into()
+}
+
+fn main() {
+    f(20);
+    g(1u32);
+}
@@ -0,0 +1,21 @@
+use minitrace::trace;
+
+#[trace(name_expr = "format!(\"query:{}\", table)")]
+fn f(table: &str) {}
+
+#[trace(name_expr = "method.to_string()", properties = { "path": "{path}" })]
+async fn g(method: &str, path: String) -> String {
+    path
+}
+
+#[trace(name_expr = "if a > 1 { \"large\" } else { \"small\" }", ret = "{}")]
+fn h(a: u64) -> u64 {
+    a
+}
+
+#[tokio::main]
+async fn main() {
+    f("users");
+    g("GET", "/".to_string()).await;
+    h(1);
+}
@@ -14,8 +14,24 @@ async fn g(a: u32) -> u32 {
     a
 }
 
+struct Baz {
+    id: u64,
+    name: String,
+}
+
+impl Baz {
+    #[trace(short_name = true, properties = { "id": display("{self.id}"), "name": raw("{self.name.clone()}") })]
+    async fn h(&self) {}
+}
+
 #[tokio::main]
 async fn main() {
     f(1, &Bar, Bar).await;
     g(1).await;
+    Baz {
+        id: 1,
+        name: "baz".to_string(),
+    }
+    .h()
+    .await;
 }
@@ -0,0 +1,39 @@
+use minitrace::trace;
+
+#[derive(Debug)]
+struct Empty;
+
+#[trace(error_if = "result.is_err()")]
+fn f(v: &mut Vec<u8>) -> Result<&u8, Empty> {
+    v.push(1);
+    v.first().ok_or(Empty)
+}
+
+#[trace(ret = "{:?}")]
+fn g(v: &mut Vec<u8>) -> Option<&mut u8> {
+    if v.is_empty() {
+        return None;
+    }
+    let last = v.last_mut()?;
+    *last += 1;
+    Some(last)
+}
+
+struct Buffer {
+    data: Vec<u8>,
+}
+
+impl Buffer {
+    #[trace(ret = "{:?}", error_if = "result.is_empty()")]
+    fn data(&mut self) -> &[u8] {
+        self.data.push(1);
+        &self.data
+    }
+}
+
+fn main() {
+    let mut v = Vec::new();
+    f(&mut v).ok();
+    g(&mut v);
+    Buffer { data: Vec::new() }.data();
+}
@@ -0,0 +1,35 @@
+use minitrace::trace;
+
+#[trace(ret = "{:?}")]
+fn f(a: i64) -> i64 {
+    if a < 0 {
+        return 0;
+    }
+    a
+}
+
+#[trace(ret = "{:?}")]
+fn g(a: &str) -> Result<&str, std::num::ParseIntError> {
+    a.parse::<i64>()?;
+    Ok(a)
+}
+
+#[trace(ret = "{:?}")]
+fn h() {}
+
+#[trace(ret = "returned {}")]
+async fn i(a: String) -> String {
+    a
+}
+
+#[trace(ret = "{:?}")]
+async fn j() {}
+
+#[tokio::main]
+async fn main() {
+    f(1);
+    g("1").ok();
+    h();
+    i("a".to_string()).await;
+    j().await;
+}
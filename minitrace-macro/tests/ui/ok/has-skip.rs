@@ -0,0 +1,24 @@
+use minitrace::trace;
+
+struct Secret(String);
+
+#[trace(short_name = true, properties = { "user": "{user}", "escaped": "{{password}}" }, skip = [password])]
+fn f(user: &str, password: Secret) -> usize {
+    password.0.len() + user.len()
+}
+
+#[trace(short_name = true, properties = { "user": "{user}" }, skip = [password, r#type])]
+async fn g(user: String, password: Secret, r#type: Secret) -> usize {
+    password.0.len() + r#type.0.len() + user.len()
+}
+
+#[tokio::main]
+async fn main() {
+    f("user", Secret("password".to_string()));
+    g(
+        "user".to_string(),
+        Secret("password".to_string()),
+        Secret("type".to_string()),
+    )
+    .await;
+}
@@ -0,0 +1,20 @@
+use minitrace::trace;
+
+#[trace(target = "my_module")]
+fn f(a: u32) -> u32 {
+    a
+}
+
+#[trace(target = "my_module", short_name = true)]
+async fn g(a: u32) -> u32 {
+    a
+}
+
+#[trace(target = "my_module", name = "h")]
+fn h() {}
+
+fn main() {
+    f(1);
+    let _ = g(1);
+    h();
+}
@@ -0,0 +1,39 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use minitrace::trace;
+
+#[trace]
+fn f(a: u32) -> impl Future<Output = u32> {
+    async move { a }
+}
+
+#[trace(short_name = true, ret = "{:?}")]
+fn g<'a>(a: &'a str) -> Pin<Box<dyn Future<Output = usize> + Send + 'a>> {
+    Box::pin(async move { a.len() })
+}
+
+#[trace(enter_on_poll = true)]
+fn h() -> impl Future<Output = ()> {
+    async {}
+}
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+#[trace(force_async = true)]
+fn i() -> BoxFuture {
+    Box::pin(async {})
+}
+
+struct Foo;
+
+#[trace]
+impl Foo {
+    fn j(&self) -> impl Future<Output = ()> + '_ {
+        async move {
+            let _ = self;
+        }
+    }
+}
+
+fn main() {}
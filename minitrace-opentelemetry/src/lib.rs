@@ -3,6 +3,8 @@
 #![doc = include_str!("../README.md")]
 
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 use std::time::UNIX_EPOCH;
 
@@ -10,8 +12,8 @@ use minitrace::collector::EventRecord;
 use minitrace::collector::Reporter;
 use minitrace::prelude::*;
 use opentelemetry::trace::Event;
+use opentelemetry::trace::Link;
 use opentelemetry::trace::SpanContext;
-use opentelemetry::trace::SpanKind;
 use opentelemetry::trace::Status;
 use opentelemetry::trace::TraceFlags;
 use opentelemetry::trace::TraceState;
@@ -30,32 +32,79 @@ use opentelemetry_sdk::Resource;
 ///
 /// `OpenTelemetryReporter` exports trace records to remote agents that OpenTelemetry
 /// supports, which includes Jaeger, Datadog, Zipkin, and OpenTelemetry Collector.
+///
+/// With the `tokio` feature, the spans are exported on a Tokio runtime so that exporters
+/// depending on Tokio, such as the gRPC exporter of `opentelemetry-otlp`, can work. If the
+/// reporter is created inside a multi-thread runtime, the spans are exported on that runtime.
+/// Otherwise, including inside a current-thread runtime such as the one of `#[tokio::test]`, the
+/// reporter owns a dedicated current-thread runtime, because a current-thread runtime can't
+/// drive IO and timers while its thread is blocked waiting for `minitrace::flush()`. Without the
+/// `tokio` feature, the export is driven by `futures::executor::block_on()`.
 pub struct OpenTelemetryReporter {
     opentelemetry_exporter: Box<dyn SpanExporter>,
-    span_kind: SpanKind,
     resource: Cow<'static, Resource>,
     instrumentation_lib: InstrumentationLibrary,
+    #[cfg(feature = "tokio")]
+    runtime: Option<ExportRuntime>,
+}
+
+/// The Tokio runtime on which the spans are exported.
+#[cfg(feature = "tokio")]
+enum ExportRuntime {
+    /// The multi-thread runtime in which the reporter is created, whose worker threads keep
+    /// driving IO and timers while the reporter blocks on it.
+    Shared(tokio::runtime::Handle),
+    /// A current-thread runtime owned by the reporter, driven by the thread blocking on it.
+    Owned(tokio::runtime::Runtime),
+}
+
+#[cfg(feature = "tokio")]
+impl ExportRuntime {
+    fn new() -> Option<Self> {
+        use tokio::runtime::RuntimeFlavor;
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                Some(ExportRuntime::Shared(handle))
+            }
+            _ => tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map(ExportRuntime::Owned)
+                .map_err(|err| log::error!("failed to build the runtime to export spans: {}", err))
+                .ok(),
+        }
+    }
+
+    fn block_on<F: std::future::Future>(&self, future: F) -> F::Output {
+        match self {
+            ExportRuntime::Shared(handle) => handle.block_on(future),
+            ExportRuntime::Owned(runtime) => runtime.block_on(future),
+        }
+    }
 }
 
 impl OpenTelemetryReporter {
     pub fn new(
         opentelemetry_exporter: impl SpanExporter + 'static,
-        span_kind: SpanKind,
         resource: Cow<'static, Resource>,
         instrumentation_lib: InstrumentationLibrary,
     ) -> Self {
         OpenTelemetryReporter {
             opentelemetry_exporter: Box::new(opentelemetry_exporter),
-            span_kind,
             resource,
             instrumentation_lib,
+            #[cfg(feature = "tokio")]
+            runtime: ExportRuntime::new(),
         }
     }
 
     fn convert(&self, spans: &[SpanRecord]) -> Vec<SpanData> {
+        // The resources of the traces, converted once for each batch.
+        let mut resources = HashMap::<_, Cow<'static, Resource>>::new();
         spans
             .iter()
-            .map(move |span| SpanData {
+            .map(|span| SpanData {
                 span_context: SpanContext::new(
                     span.trace_id.0.into(),
                     span.span_id.0.into(),
@@ -71,15 +120,34 @@ impl OpenTelemetryReporter {
                     + Duration::from_nanos(span.begin_time_unix_ns + span.duration_ns),
                 attributes: Self::convert_properties(&span.properties),
                 events: Self::convert_events(&span.events),
-                links: SpanLinks::default(),
-                status: Status::default(),
-                span_kind: self.span_kind.clone(),
-                resource: self.resource.clone(),
+                links: Self::convert_links(&span.links),
+                status: Self::convert_status(&span.status),
+                span_kind: Self::convert_kind(span.kind),
+                resource: match &span.resource {
+                    Some(resource) => resources
+                        .entry(Arc::as_ptr(resource))
+                        .or_insert_with(|| Cow::Owned(self.convert_resource(resource)))
+                        .clone(),
+                    None => self.resource.clone(),
+                },
                 instrumentation_lib: self.instrumentation_lib.clone(),
             })
             .collect()
     }
 
+    /// Merges the resource of a trace, given to `Span::root_with_resource()` or set by
+    /// `minitrace::set_resource()`, into the resource of the reporter, overriding the attributes
+    /// with the same keys.
+    fn convert_resource(
+        &self,
+        resource: &HashMap<Cow<'static, str>, Cow<'static, str>>,
+    ) -> Resource {
+        self.resource
+            .merge(&Resource::new(resource.iter().map(|(k, v)| {
+                KeyValue::new(cow_to_otel_key(k.clone()), cow_to_otel_value(v.clone()))
+            })))
+    }
+
     fn convert_properties(properties: &[(Cow<'static, str>, Cow<'static, str>)]) -> Vec<KeyValue> {
         let mut map = Vec::new();
         for (k, v) in properties {
@@ -91,18 +159,36 @@ impl OpenTelemetryReporter {
         map
     }
 
+    fn convert_status(status: &SpanStatus) -> Status {
+        match status {
+            SpanStatus::Unset => Status::Unset,
+            SpanStatus::Ok => Status::Ok,
+            SpanStatus::Error(description) => Status::Error {
+                description: description.clone(),
+            },
+        }
+    }
+
+    fn convert_kind(kind: SpanKind) -> opentelemetry::trace::SpanKind {
+        match kind {
+            SpanKind::Internal => opentelemetry::trace::SpanKind::Internal,
+            SpanKind::Server => opentelemetry::trace::SpanKind::Server,
+            SpanKind::Client => opentelemetry::trace::SpanKind::Client,
+            SpanKind::Producer => opentelemetry::trace::SpanKind::Producer,
+            SpanKind::Consumer => opentelemetry::trace::SpanKind::Consumer,
+        }
+    }
+
     fn convert_events(events: &[EventRecord]) -> SpanEvents {
         let mut queue = SpanEvents::default();
         queue.events.extend(events.iter().map(|event| {
             Event::new(
                 event.name.clone(),
                 UNIX_EPOCH + Duration::from_nanos(event.timestamp_unix_ns),
-                event
-                    .properties
-                    .iter()
-                    .map(|(k, v)| {
+                std::iter::once(KeyValue::new("level", event.level.as_str()))
+                    .chain(event.properties.iter().map(|(k, v)| {
                         KeyValue::new(cow_to_otel_key(k.clone()), cow_to_otel_value(v.clone()))
-                    })
+                    }))
                     .collect(),
                 0,
             )
@@ -110,13 +196,51 @@ impl OpenTelemetryReporter {
         queue
     }
 
+    fn convert_links(links: &[(TraceId, SpanId)]) -> SpanLinks {
+        let mut queue = SpanLinks::default();
+        queue.links.extend(links.iter().map(|(trace_id, span_id)| {
+            Link::new(
+                SpanContext::new(
+                    trace_id.0.into(),
+                    span_id.0.into(),
+                    TraceFlags::default(),
+                    false,
+                    TraceState::default(),
+                ),
+                Vec::new(),
+                0,
+            )
+        }));
+        queue
+    }
+
     fn try_report(&mut self, spans: &[SpanRecord]) -> Result<(), Box<dyn std::error::Error>> {
         let opentelemetry_spans = self.convert(spans);
-        futures::executor::block_on(self.opentelemetry_exporter.export(opentelemetry_spans))?;
+        let export = self.opentelemetry_exporter.export(opentelemetry_spans);
+
+        // The reporter is always called outside the runtime, by the minitrace threads, so it is
+        // safe to block on the runtime here.
+        #[cfg(feature = "tokio")]
+        if let Some(runtime) = &self.runtime {
+            return Ok(runtime.block_on(export)?);
+        }
+
+        futures::executor::block_on(export)?;
         Ok(())
     }
 }
 
+#[cfg(feature = "tokio")]
+impl Drop for OpenTelemetryReporter {
+    fn drop(&mut self) {
+        // Dropping a runtime panics inside an async context, where the reporter may be dropped
+        // if it's never handed over to minitrace.
+        if let Some(ExportRuntime::Owned(runtime)) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
 impl Reporter for OpenTelemetryReporter {
     fn report(&mut self, spans: &[SpanRecord]) {
         if spans.is_empty() {
@@ -142,3 +266,187 @@ fn cow_to_otel_value(cow: Cow<'static, str>) -> Value {
         Cow::Owned(s) => Value::String(StringValue::from(s)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::future::BoxFuture;
+    use opentelemetry_sdk::export::trace::ExportResult;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct NoopExporter;
+
+    impl SpanExporter for NoopExporter {
+        fn export(&mut self, _batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[test]
+    fn span_kind() {
+        let reporter = OpenTelemetryReporter::new(
+            NoopExporter,
+            Cow::Owned(Resource::empty()),
+            InstrumentationLibrary::default(),
+        );
+        let spans = [
+            SpanRecord {
+                name: "server".into(),
+                kind: SpanKind::Server,
+                ..SpanRecord::default()
+            },
+            SpanRecord {
+                name: "internal".into(),
+                ..SpanRecord::default()
+            },
+        ];
+
+        let span_data = reporter.convert(&spans);
+        assert_eq!(span_data[0].name, "server");
+        assert_eq!(
+            span_data[0].span_kind,
+            opentelemetry::trace::SpanKind::Server
+        );
+        assert_eq!(span_data[1].name, "internal");
+        assert_eq!(
+            span_data[1].span_kind,
+            opentelemetry::trace::SpanKind::Internal
+        );
+    }
+
+    #[test]
+    fn span_links() {
+        let reporter = OpenTelemetryReporter::new(
+            NoopExporter,
+            Cow::Owned(Resource::empty()),
+            InstrumentationLibrary::default(),
+        );
+        let spans = [SpanRecord {
+            name: "batch".into(),
+            links: vec![(TraceId(1), SpanId(2)), (TraceId(3), SpanId(4))],
+            ..SpanRecord::default()
+        }];
+
+        let span_data = reporter.convert(&spans);
+        let links = span_data[0]
+            .links
+            .iter()
+            .map(|link| (link.span_context.trace_id(), link.span_context.span_id()))
+            .collect::<Vec<_>>();
+        assert_eq!(links, vec![(1.into(), 2.into()), (3.into(), 4.into())]);
+    }
+
+    #[test]
+    fn span_resource() {
+        let reporter = OpenTelemetryReporter::new(
+            NoopExporter,
+            Cow::Owned(Resource::new([
+                KeyValue::new("service.name", "default"),
+                KeyValue::new("host.name", "localhost"),
+            ])),
+            InstrumentationLibrary::default(),
+        );
+        let resource = Arc::new(HashMap::from([("service.name".into(), "billing".into())]));
+        let spans = [
+            SpanRecord {
+                resource: Some(resource),
+                ..SpanRecord::default()
+            },
+            SpanRecord::default(),
+        ];
+
+        let span_data = reporter.convert(&spans);
+        let service_name = |span_data: &SpanData| {
+            span_data
+                .resource
+                .get(Key::from_static_str("service.name"))
+                .map(|value| value.to_string())
+        };
+        assert_eq!(service_name(&span_data[0]).as_deref(), Some("billing"));
+        assert_eq!(
+            span_data[0]
+                .resource
+                .get(Key::from_static_str("host.name"))
+                .map(|value| value.to_string())
+                .as_deref(),
+            Some("localhost")
+        );
+        assert_eq!(service_name(&span_data[1]).as_deref(), Some("default"));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn export_on_tokio_runtime() {
+        #[derive(Debug)]
+        struct TokioExporter;
+
+        impl SpanExporter for TokioExporter {
+            fn export(&mut self, _batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+                Box::pin(async {
+                    tokio::runtime::Handle::try_current()
+                        .map(|_| ())
+                        .map_err(|err| err.to_string().into())
+                })
+            }
+        }
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let mut reporter = runtime.block_on(async {
+            OpenTelemetryReporter::new(
+                TokioExporter,
+                Cow::Owned(Resource::empty()),
+                InstrumentationLibrary::default(),
+            )
+        });
+
+        // Reports outside the runtime, like the minitrace threads.
+        let result = std::thread::spawn(move || {
+            reporter
+                .try_report(&[SpanRecord::default()])
+                .map_err(|err| err.to_string())
+        })
+        .join()
+        .unwrap();
+        assert_eq!(result, Ok(()));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn export_on_current_thread_runtime() {
+        #[derive(Debug)]
+        struct TimerExporter;
+
+        impl SpanExporter for TimerExporter {
+            fn export(&mut self, _batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+                Box::pin(async {
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                    Ok(())
+                })
+            }
+        }
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let result = runtime.block_on(async {
+            let mut reporter = OpenTelemetryReporter::new(
+                TimerExporter,
+                Cow::Owned(Resource::empty()),
+                InstrumentationLibrary::default(),
+            );
+
+            // Reports on another thread while the runtime thread is blocked joining it, like
+            // `minitrace::flush()` called inside `#[tokio::test]`.
+            std::thread::spawn(move || {
+                reporter
+                    .try_report(&[SpanRecord::default()])
+                    .map_err(|err| err.to_string())
+            })
+            .join()
+            .unwrap()
+        });
+        assert_eq!(result, Ok(()));
+    }
+}
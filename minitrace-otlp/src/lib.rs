@@ -0,0 +1,226 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+#![doc = include_str!("../README.md")]
+
+pub mod proto;
+
+use std::borrow::Cow;
+
+use minitrace::collector::EventRecord;
+use minitrace::collector::Reporter;
+use minitrace::prelude::*;
+use prost::Message;
+
+/// [OTLP/HTTP](https://opentelemetry.io/docs/specs/otlp/#otlphttp) reporter for `minitrace` in
+/// the binary protobuf encoding.
+///
+/// Spans are sent to `endpoint`, which is usually `http://{collector}:4318/v1/traces`, as the
+/// spans of a resource with the `service.name` attribute set to `service_name`.
+pub struct OtlpReporter {
+    endpoint: String,
+    service_name: String,
+    client: Option<reqwest::blocking::Client>,
+}
+
+impl OtlpReporter {
+    pub fn new(endpoint: impl Into<String>, service_name: impl Into<String>) -> OtlpReporter {
+        OtlpReporter {
+            endpoint: endpoint.into(),
+            service_name: service_name.into(),
+            client: None,
+        }
+    }
+
+    fn convert(&self, spans: &[SpanRecord]) -> proto::ExportTraceServiceRequest {
+        proto::ExportTraceServiceRequest {
+            resource_spans: vec![proto::ResourceSpans {
+                resource: Some(proto::Resource {
+                    attributes: vec![key_value("service.name", &self.service_name)],
+                    dropped_attributes_count: 0,
+                }),
+                scope_spans: vec![proto::ScopeSpans {
+                    scope: Some(proto::InstrumentationScope {
+                        name: env!("CARGO_PKG_NAME").to_string(),
+                        version: env!("CARGO_PKG_VERSION").to_string(),
+                        ..Default::default()
+                    }),
+                    spans: spans.iter().map(convert_span).collect(),
+                    schema_url: String::new(),
+                }],
+                schema_url: String::new(),
+            }],
+        }
+    }
+
+    fn try_report(&self, spans: &[SpanRecord]) -> Result<(), Box<dyn std::error::Error>> {
+        let bytes = self.convert(spans).encode_to_vec();
+        let client = match &self.client {
+            Some(client) => client.clone(),
+            None => reqwest::blocking::Client::new(),
+        };
+        client
+            .post(&self.endpoint)
+            .header("Content-Type", "application/x-protobuf")
+            .body(bytes)
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+impl Reporter for OtlpReporter {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        if spans.is_empty() {
+            return;
+        }
+
+        if let Err(err) = self.try_report(spans) {
+            log::error!("report to otlp endpoint failed: {}", err);
+        }
+    }
+
+    fn start(&mut self) {
+        self.client = Some(reqwest::blocking::Client::new());
+    }
+
+    fn stop(&mut self) {
+        self.client = None;
+    }
+}
+
+fn convert_span(span: &SpanRecord) -> proto::Span {
+    proto::Span {
+        trace_id: span.trace_id.0.to_be_bytes().to_vec(),
+        span_id: span.span_id.0.to_be_bytes().to_vec(),
+        parent_span_id: if span.parent_id.0 == 0 {
+            vec![]
+        } else {
+            span.parent_id.0.to_be_bytes().to_vec()
+        },
+        name: span.name.to_string(),
+        kind: convert_kind(span.kind) as i32,
+        start_time_unix_nano: span.begin_time_unix_ns,
+        end_time_unix_nano: span.begin_time_unix_ns + span.duration_ns,
+        attributes: convert_properties(&span.properties),
+        events: span.events.iter().map(convert_event).collect(),
+        links: span
+            .links
+            .iter()
+            .map(|(trace_id, span_id)| proto::span::Link {
+                trace_id: trace_id.0.to_be_bytes().to_vec(),
+                span_id: span_id.0.to_be_bytes().to_vec(),
+                ..Default::default()
+            })
+            .collect(),
+        status: Some(convert_status(&span.status)),
+        ..Default::default()
+    }
+}
+
+fn convert_kind(kind: SpanKind) -> proto::span::SpanKind {
+    match kind {
+        SpanKind::Internal => proto::span::SpanKind::Internal,
+        SpanKind::Server => proto::span::SpanKind::Server,
+        SpanKind::Client => proto::span::SpanKind::Client,
+        SpanKind::Producer => proto::span::SpanKind::Producer,
+        SpanKind::Consumer => proto::span::SpanKind::Consumer,
+    }
+}
+
+fn convert_status(status: &SpanStatus) -> proto::Status {
+    let (code, message) = match status {
+        SpanStatus::Unset => (proto::status::StatusCode::Unset, String::new()),
+        SpanStatus::Ok => (proto::status::StatusCode::Ok, String::new()),
+        SpanStatus::Error(description) => {
+            (proto::status::StatusCode::Error, description.to_string())
+        }
+    };
+    proto::Status {
+        message,
+        code: code as i32,
+    }
+}
+
+fn convert_event(event: &EventRecord) -> proto::span::Event {
+    proto::span::Event {
+        time_unix_nano: event.timestamp_unix_ns,
+        name: event.name.to_string(),
+        attributes: std::iter::once(key_value("level", event.level.as_str()))
+            .chain(convert_properties(&event.properties))
+            .collect(),
+        dropped_attributes_count: 0,
+    }
+}
+
+fn convert_properties(
+    properties: &[(Cow<'static, str>, Cow<'static, str>)],
+) -> Vec<proto::KeyValue> {
+    properties.iter().map(|(k, v)| key_value(k, v)).collect()
+}
+
+fn key_value(key: &str, value: &str) -> proto::KeyValue {
+    proto::KeyValue {
+        key: key.to_string(),
+        value: Some(proto::AnyValue {
+            value: Some(proto::any_value::Value::StringValue(value.to_string())),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use minitrace::collector::EventLevel;
+
+    use super::*;
+
+    #[test]
+    fn span_protobuf() {
+        let reporter = OtlpReporter::new("http://127.0.0.1:4318/v1/traces", "test-service");
+        let spans = [SpanRecord {
+            trace_id: TraceId(0x1234),
+            span_id: SpanId(0x56),
+            parent_id: SpanId(0x78),
+            begin_time_unix_ns: 1_000_000,
+            duration_ns: 2_000,
+            name: "span".into(),
+            properties: vec![("k".into(), "v".into())],
+            events: vec![EventRecord {
+                name: "event".into(),
+                timestamp_unix_ns: 1_500_000,
+                level: EventLevel::Warn,
+                properties: vec![],
+            }],
+            status: SpanStatus::Error("failed".into()),
+            kind: SpanKind::Server,
+            links: vec![(TraceId(0x9a), SpanId(0xbc))],
+            sampled: true,
+            resource: None,
+        }];
+
+        let bytes = reporter.convert(&spans).encode_to_vec();
+        let request = proto::ExportTraceServiceRequest::decode(bytes.as_slice()).unwrap();
+        let resource_spans = &request.resource_spans[0];
+        assert_eq!(resource_spans.resource.as_ref().unwrap().attributes, vec![
+            key_value("service.name", "test-service")
+        ]);
+
+        let span = &resource_spans.scope_spans[0].spans[0];
+        assert_eq!(span.trace_id, 0x1234u128.to_be_bytes());
+        assert_eq!(span.span_id, 0x56u64.to_be_bytes());
+        assert_eq!(span.parent_span_id, 0x78u64.to_be_bytes());
+        assert_eq!(span.name, "span");
+        assert_eq!(span.kind(), proto::span::SpanKind::Server);
+        assert_eq!(span.start_time_unix_nano, 1_000_000);
+        assert_eq!(span.end_time_unix_nano, 1_002_000);
+        assert_eq!(span.attributes, vec![key_value("k", "v")]);
+        assert_eq!(span.events[0].name, "event");
+        assert_eq!(span.events[0].time_unix_nano, 1_500_000);
+        assert_eq!(span.events[0].attributes, vec![key_value("level", "WARN")]);
+        assert_eq!(span.links[0].trace_id, 0x9au128.to_be_bytes());
+        assert_eq!(span.links[0].span_id, 0xbcu64.to_be_bytes());
+
+        let status = span.status.as_ref().unwrap();
+        assert_eq!(status.code(), proto::status::StatusCode::Error);
+        assert_eq!(status.message, "failed");
+    }
+}
@@ -0,0 +1,64 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use minitrace::collector::Reporter;
+use minitrace::prelude::*;
+use minitrace_otlp::proto::ExportTraceServiceRequest;
+use minitrace_otlp::OtlpReporter;
+use prost::Message;
+
+#[test]
+fn otlp_reporter() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("POST", "/v1/traces")
+        .match_header("content-type", "application/x-protobuf")
+        .with_status(200)
+        .with_body_from_request({
+            let requests = requests.clone();
+            move |request| {
+                let body = request.body().unwrap();
+                requests
+                    .lock()
+                    .unwrap()
+                    .push(ExportTraceServiceRequest::decode(body.as_slice()).unwrap());
+                vec![]
+            }
+        })
+        .create();
+
+    let mut reporter = OtlpReporter::new(format!("{}/v1/traces", server.url()), "test-service");
+    reporter.report(&[
+        SpanRecord {
+            trace_id: TraceId(1),
+            span_id: SpanId(1),
+            name: "root".into(),
+            ..SpanRecord::default()
+        },
+        SpanRecord {
+            trace_id: TraceId(1),
+            span_id: SpanId(2),
+            parent_id: SpanId(1),
+            name: "child".into(),
+            properties: vec![("k".into(), "v".into())],
+            ..SpanRecord::default()
+        },
+    ]);
+
+    mock.assert();
+
+    let requests = requests.lock().unwrap();
+    let spans = &requests[0].resource_spans[0].scope_spans[0].spans;
+    let spans = spans
+        .iter()
+        .map(|span| (span.name.as_str(), span.parent_span_id.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(spans, vec![
+        ("root", vec![]),
+        ("child", 1u64.to_be_bytes().to_vec())
+    ]);
+}
@@ -0,0 +1,80 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+#![doc = include_str!("../README.md")]
+
+use minitrace::Span;
+use rayon::iter::ParallelIterator;
+
+/// An extension trait for [`ParallelIterator`] that processes each item in a [`Span`].
+///
+/// A span named `span_name`, which is a child of the current local parent, covers the whole
+/// iteration. Each item is processed in a child span of it with the same name, which is set as
+/// the local parent of the worker thread while the item is processed, so that
+/// [`LocalSpan`]s can be started by the closure. Nothing is traced if there is no local parent
+/// when the method is called.
+///
+/// [`LocalSpan`]: minitrace::local::LocalSpan
+pub trait ParIterExt: ParallelIterator {
+    /// Calls `f` on each item in parallel, like [`ParallelIterator::for_each()`], in a span
+    /// for each item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    /// use minitrace_rayon::ParIterExt;
+    /// use rayon::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let _guard = root.set_local_parent();
+    ///
+    /// (0..100).into_par_iter().in_span_par_each("process", |i| {
+    ///     let _span = LocalSpan::enter_with_local_parent("compute");
+    /// });
+    /// ```
+    fn in_span_par_each<F>(self, span_name: &'static str, f: F)
+    where F: Fn(Self::Item) + Sync + Send {
+        let parent = Span::enter_with_local_parent(span_name);
+        self.for_each(|item| {
+            let span = Span::enter_with_parent(span_name, &parent);
+            let _guard = span.set_local_parent();
+            f(item)
+        });
+    }
+
+    /// Maps each item with `f` in parallel, like [`ParallelIterator::map()`], in a span for each
+    /// item.
+    ///
+    /// The span covering the whole iteration ends when the returned iterator is dropped, which
+    /// is usually when it has been consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    /// use minitrace_rayon::ParIterExt;
+    /// use rayon::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let _guard = root.set_local_parent();
+    ///
+    /// let sum: u64 = (0..100u64)
+    ///     .into_par_iter()
+    ///     .traced_par_map("square", |i| i * i)
+    ///     .sum();
+    /// ```
+    fn traced_par_map<F, R>(self, span_name: &'static str, f: F) -> impl ParallelIterator<Item = R>
+    where
+        F: Fn(Self::Item) -> R + Sync + Send,
+        R: Send,
+    {
+        let parent = Span::enter_with_local_parent(span_name);
+        self.map(move |item| {
+            let span = Span::enter_with_parent(span_name, &parent);
+            let _guard = span.set_local_parent();
+            f(item)
+        })
+    }
+}
+
+impl<I: ParallelIterator> ParIterExt for I {}
@@ -0,0 +1,98 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use minitrace::collector::Config;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+use minitrace::util::tree::tree_str_from_span_records;
+use minitrace_rayon::ParIterExt;
+use rayon::prelude::*;
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn traced_par_map() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _guard = root.set_local_parent();
+
+        let squares: Vec<u64> = (0..4u64)
+            .into_par_iter()
+            .traced_par_map("square", |i| {
+                let _span = LocalSpan::enter_with_local_parent(format!("compute {i}"));
+                i * i
+            })
+            .collect();
+        assert_eq!(squares, [0, 1, 4, 9]);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    square []
+        square []
+            compute 0 []
+        square []
+            compute 1 []
+        square []
+            compute 2 []
+        square []
+            compute 3 []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn in_span_par_each() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _guard = root.set_local_parent();
+
+        (0..3).into_par_iter().in_span_par_each("process", |i| {
+            let _span = LocalSpan::enter_with_local_parent(format!("item {i}"));
+        });
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    process []
+        process []
+            item 0 []
+        process []
+            item 1 []
+        process []
+            item 2 []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn without_local_parent() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let sum: u64 = (0..10u64)
+        .into_par_iter()
+        .traced_par_map("square", |i| i * i)
+        .sum();
+    assert_eq!(sum, 285);
+
+    minitrace::flush();
+    assert!(collected_spans.lock().is_empty());
+}
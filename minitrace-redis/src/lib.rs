@@ -0,0 +1,119 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+#![doc = include_str!("../README.md")]
+
+use std::borrow::Cow;
+
+use minitrace::prelude::*;
+use redis::aio::ConnectionLike;
+use redis::Arg;
+use redis::Cmd;
+use redis::Pipeline;
+use redis::RedisFuture;
+use redis::RedisResult;
+use redis::Value;
+
+/// An async Redis connection that traces each command it sends.
+///
+/// Each command gets a [`SpanKind::Client`] child span of the parent span, named after the
+/// command in uppercase, e.g. `redis::GET`, and a pipeline gets a single span named
+/// `redis::pipeline`, with the properties:
+///
+/// - `db.system`: `redis`.
+/// - `db.operation`: the command, or the commands of the pipeline separated by spaces.
+/// - `net.peer.addr`: the address of the server, if set by
+///   [`TracedConnection::with_peer_addr()`].
+///
+/// The status of the span is set to [`SpanStatus::Error`] if the command fails.
+pub struct TracedConnection<C> {
+    inner: C,
+    parent_span: Span,
+    peer_addr: Option<Cow<'static, str>>,
+}
+
+impl<C: ConnectionLike> TracedConnection<C> {
+    pub fn new(inner: C, parent_span: Span) -> Self {
+        TracedConnection {
+            inner,
+            parent_span,
+            peer_addr: None,
+        }
+    }
+
+    /// Sets the address of the server, recorded in the `net.peer.addr` property.
+    pub fn with_peer_addr(self, peer_addr: impl Into<Cow<'static, str>>) -> Self {
+        TracedConnection {
+            peer_addr: Some(peer_addr.into()),
+            ..self
+        }
+    }
+
+    /// Returns the wrapped connection. The commands sent by the wrapped connection directly are
+    /// not traced.
+    pub fn inner(&mut self) -> &mut C {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped connection.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    fn command_span(&self, name: String, operation: String) -> Span {
+        let span = Span::enter_with_parent(name, &self.parent_span)
+            .with_kind(SpanKind::Client)
+            .with_properties(|| [("db.system", "redis".into()), ("db.operation", operation)]);
+        match &self.peer_addr {
+            Some(peer_addr) => span.with_property(|| ("net.peer.addr", peer_addr.clone())),
+            None => span,
+        }
+    }
+}
+
+impl<C: ConnectionLike + Send> ConnectionLike for TracedConnection<C> {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        let command = command_name(cmd);
+        let span = self.command_span(format!("redis::{command}"), command);
+        traced_future(self.inner.req_packed_command(cmd), span)
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        let operation = cmd
+            .cmd_iter()
+            .map(command_name)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let span = self.command_span("redis::pipeline".to_string(), operation);
+        traced_future(self.inner.req_packed_commands(cmd, offset, count), span)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.inner.get_db()
+    }
+}
+
+/// Returns the name of the command, which is the first argument of the command, in uppercase.
+fn command_name(cmd: &Cmd) -> String {
+    match cmd.args_iter().next() {
+        Some(Arg::Simple(name)) => String::from_utf8_lossy(name).to_uppercase(),
+        _ => "UNKNOWN".to_string(),
+    }
+}
+
+fn traced_future<'a, T: Send + 'a>(
+    future: RedisFuture<'a, T>,
+    mut span: Span,
+) -> RedisFuture<'a, T> {
+    Box::pin(async move {
+        let result: RedisResult<T> = future.await;
+        if let Err(err) = &result {
+            span.set_status(SpanStatus::Error(err.to_string().into()));
+        }
+        result
+    })
+}
@@ -0,0 +1,141 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use futures::executor::block_on;
+use minitrace::collector::Config;
+use minitrace::collector::SpanRecord;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+use minitrace::util::tree::tree_str_from_span_records;
+use minitrace_redis::TracedConnection;
+use redis::aio::ConnectionLike;
+use redis::Cmd;
+use redis::ErrorKind;
+use redis::Pipeline;
+use redis::RedisError;
+use redis::RedisFuture;
+use redis::Value;
+use serial_test::serial;
+
+/// A connection replying `OK` to every command, except `GET` which replies `value`, and `FAIL`
+/// which fails.
+struct MockConnection;
+
+impl MockConnection {
+    fn reply(cmd: &Cmd) -> Result<Value, RedisError> {
+        match cmd.args_iter().next() {
+            Some(redis::Arg::Simple(b"GET")) => Ok(Value::BulkString(b"value".to_vec())),
+            Some(redis::Arg::Simple(b"FAIL")) => {
+                Err(RedisError::from((ErrorKind::ResponseError, "mock error")))
+            }
+            _ => Ok(Value::Okay),
+        }
+    }
+}
+
+impl ConnectionLike for MockConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        Box::pin(async move { Self::reply(cmd) })
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        _offset: usize,
+        _count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        Box::pin(async move { cmd.cmd_iter().map(Self::reply).collect() })
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+}
+
+fn property<'a>(span: &'a SpanRecord, key: &str) -> Option<&'a str> {
+    span.properties
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_ref())
+}
+
+#[test]
+#[serial]
+fn commands() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let mut connection =
+            TracedConnection::new(MockConnection, root).with_peer_addr("127.0.0.1:6379");
+        block_on(async {
+            let _: () = redis::cmd("set")
+                .arg("key")
+                .arg("value")
+                .query_async(&mut connection)
+                .await
+                .unwrap();
+            let value: String = redis::cmd("GET")
+                .arg("key")
+                .query_async(&mut connection)
+                .await
+                .unwrap();
+            assert_eq!(value, "value");
+
+            let (): () = redis::pipe()
+                .cmd("SET")
+                .arg("key")
+                .arg("value")
+                .ignore()
+                .cmd("DEL")
+                .arg("key")
+                .ignore()
+                .query_async(&mut connection)
+                .await
+                .unwrap();
+        });
+    }
+
+    minitrace::flush();
+
+    let spans = collected_spans.lock().clone();
+    assert_eq!(
+        tree_str_from_span_records(spans.clone()),
+        r#"
+root []
+    redis::GET [("db.system", "redis"), ("db.operation", "GET"), ("net.peer.addr", "127.0.0.1:6379")]
+    redis::SET [("db.system", "redis"), ("db.operation", "SET"), ("net.peer.addr", "127.0.0.1:6379")]
+    redis::pipeline [("db.system", "redis"), ("db.operation", "SET DEL"), ("net.peer.addr", "127.0.0.1:6379")]
+"#
+    );
+    assert!(spans
+        .iter()
+        .filter(|span| span.name != "root")
+        .all(|span| span.kind == SpanKind::Client && span.status == SpanStatus::Unset));
+}
+
+#[test]
+#[serial]
+fn command_error() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let mut connection = TracedConnection::new(MockConnection, root);
+        let result: redis::RedisResult<()> =
+            block_on(redis::cmd("FAIL").query_async(&mut connection));
+        assert!(result.is_err());
+    }
+
+    minitrace::flush();
+
+    let spans = collected_spans.lock().clone();
+    let span = spans
+        .iter()
+        .find(|span| span.name == "redis::FAIL")
+        .unwrap();
+    assert_eq!(property(span, "db.operation"), Some("FAIL"));
+    assert_eq!(property(span, "net.peer.addr"), None);
+    assert!(matches!(&span.status, SpanStatus::Error(message) if message.contains("mock error")));
+}
@@ -0,0 +1,221 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+#![doc = include_str!("../README.md")]
+
+use minitrace::prelude::*;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderName;
+use reqwest::header::HeaderValue;
+use reqwest::Method;
+use reqwest::StatusCode;
+use reqwest::Url;
+
+/// The format in which the [`SpanContext`] of a request is injected into its headers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HeaderInjector {
+    /// The W3C `traceparent` and `tracestate` headers, see
+    /// [`SpanContext::encode_w3c_headers()`].
+    #[default]
+    W3C,
+    /// The B3 single `b3` header, see [`SpanContext::encode_b3_single_header()`].
+    B3Single,
+    /// The B3 multiple `X-B3-*` headers, see [`SpanContext::encode_b3_multi_headers()`].
+    B3Multi,
+    /// The Datadog `x-datadog-*` headers, see [`SpanContext::encode_datadog_headers()`].
+    Datadog,
+}
+
+impl HeaderInjector {
+    /// Inserts the headers encoding `span_context` into `headers`, replacing the existing ones.
+    pub fn inject(self, span_context: &SpanContext, headers: &mut HeaderMap) {
+        let encoded = match self {
+            HeaderInjector::W3C => span_context.encode_w3c_headers().to_vec(),
+            HeaderInjector::B3Single => {
+                vec![("b3", span_context.encode_b3_single_header(span_context.sampled()))]
+            }
+            HeaderInjector::B3Multi => span_context
+                .encode_b3_multi_headers(span_context.sampled())
+                .to_vec(),
+            HeaderInjector::Datadog => span_context
+                .encode_datadog_headers(span_context.sampled())
+                .to_vec(),
+        };
+
+        for (name, value) in encoded {
+            if value.is_empty() {
+                continue;
+            }
+            if let (Ok(name), Ok(value)) =
+                (HeaderName::try_from(name), HeaderValue::try_from(value))
+            {
+                headers.insert(name, value);
+            }
+        }
+    }
+}
+
+/// A [`reqwest::blocking::Client`] that traces each request it executes.
+///
+/// Each request gets a [`SpanKind::Client`] child span of the current local parent, named after
+/// the method and the path of the URL, e.g. `GET /api/users`, and the span context is injected
+/// into the request headers by the [`HeaderInjector`]. The span has the properties
+/// `http.method`, `http.url` and, once the response is received, `http.status_code`. Its status is
+/// set to [`SpanStatus::Error`] if the request fails or the response status is not 2xx.
+///
+/// Nothing is traced or injected if there is no local parent.
+///
+/// # Examples
+///
+/// ```no_run
+/// use minitrace::prelude::*;
+/// use minitrace_reqwest::TracedClient;
+///
+/// let client = TracedClient::new(reqwest::blocking::Client::new());
+///
+/// let root = Span::root("root", SpanContext::random());
+/// let _guard = root.set_local_parent();
+///
+/// let request = client
+///     .inner()
+///     .get("http://localhost:8080/api/users")
+///     .build()
+///     .unwrap();
+/// let response = client.execute(request).unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TracedClient {
+    client: reqwest::blocking::Client,
+    injector: HeaderInjector,
+}
+
+impl TracedClient {
+    /// Wraps `client`, injecting the span context in the W3C format.
+    pub fn new(client: reqwest::blocking::Client) -> Self {
+        TracedClient {
+            client,
+            injector: HeaderInjector::default(),
+        }
+    }
+
+    /// Sets the format in which the span context is injected into the request headers.
+    pub fn with_injector(self, injector: HeaderInjector) -> Self {
+        TracedClient { injector, ..self }
+    }
+
+    /// Returns the wrapped client, e.g. to build a request with [`Client::get()`].
+    ///
+    /// The requests sent with the wrapped client directly are not traced.
+    ///
+    /// [`Client::get()`]: reqwest::blocking::Client::get
+    pub fn inner(&self) -> &reqwest::blocking::Client {
+        &self.client
+    }
+
+    /// Executes the request in a child span of the current local parent.
+    pub fn execute(
+        &self,
+        mut request: reqwest::blocking::Request,
+    ) -> reqwest::Result<reqwest::blocking::Response> {
+        let span = start_span(request.method(), request.url());
+        if let Some(span_context) = SpanContext::from_span(&span) {
+            self.injector.inject(&span_context, request.headers_mut());
+        }
+        let result = self.client.execute(request);
+        finish_span(span, result.as_ref().map(|response| response.status()));
+        result
+    }
+}
+
+/// A [`reqwest::Client`] that traces each request it executes.
+///
+/// Each request gets a child span of the current local parent, see [`TracedClient`] for the
+/// details of the span.
+///
+/// # Examples
+///
+/// ```no_run
+/// use minitrace::prelude::*;
+/// use minitrace_reqwest::TracedClientAsync;
+///
+/// # async fn run() {
+/// let client = TracedClientAsync::new(reqwest::Client::new());
+///
+/// let root = Span::root("root", SpanContext::random());
+/// async {
+///     let request = client
+///         .inner()
+///         .get("http://localhost:8080/api/users")
+///         .build()
+///         .unwrap();
+///     let response = client.execute(request).await.unwrap();
+/// }
+/// .in_span(root)
+/// .await;
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TracedClientAsync {
+    client: reqwest::Client,
+    injector: HeaderInjector,
+}
+
+impl TracedClientAsync {
+    /// Wraps `client`, injecting the span context in the W3C format.
+    pub fn new(client: reqwest::Client) -> Self {
+        TracedClientAsync {
+            client,
+            injector: HeaderInjector::default(),
+        }
+    }
+
+    /// Sets the format in which the span context is injected into the request headers.
+    pub fn with_injector(self, injector: HeaderInjector) -> Self {
+        TracedClientAsync { injector, ..self }
+    }
+
+    /// Returns the wrapped client, e.g. to build a request with [`Client::get()`].
+    ///
+    /// The requests sent with the wrapped client directly are not traced.
+    ///
+    /// [`Client::get()`]: reqwest::Client::get
+    pub fn inner(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Executes the request in a child span of the local parent of the task when it is called.
+    pub async fn execute(
+        &self,
+        mut request: reqwest::Request,
+    ) -> reqwest::Result<reqwest::Response> {
+        let span = start_span(request.method(), request.url());
+        if let Some(span_context) = SpanContext::from_span(&span) {
+            self.injector.inject(&span_context, request.headers_mut());
+        }
+        let result = self.client.execute(request).await;
+        finish_span(span, result.as_ref().map(|response| response.status()));
+        result
+    }
+}
+
+fn start_span(method: &Method, url: &Url) -> Span {
+    Span::enter_with_local_parent(format!("{} {}", method, url.path()))
+        .with_kind(SpanKind::Client)
+        .with_properties(|| {
+            [
+                ("http.method", method.to_string()),
+                ("http.url", url.to_string()),
+            ]
+        })
+}
+
+fn finish_span(mut span: Span, result: Result<StatusCode, &reqwest::Error>) {
+    match result {
+        Ok(status) => {
+            if !status.is_success() {
+                span.set_status(SpanStatus::Error(status.to_string().into()));
+            }
+            drop(span.with_property(|| ("http.status_code", status.as_str().to_string())));
+        }
+        Err(err) => span.set_status(SpanStatus::Error(err.to_string().into())),
+    }
+}
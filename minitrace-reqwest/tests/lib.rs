@@ -0,0 +1,168 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use minitrace::collector::Config;
+use minitrace::collector::SpanRecord;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+use minitrace_reqwest::HeaderInjector;
+use minitrace_reqwest::TracedClient;
+use minitrace_reqwest::TracedClientAsync;
+use mockito::Matcher;
+use serial_test::serial;
+
+const TRACE_ID: TraceId = TraceId(0x0af7651916cd43dd8448eb211c80319c);
+
+fn find<'a>(spans: &'a [SpanRecord], name: &str) -> &'a SpanRecord {
+    spans.iter().find(|span| span.name == name).unwrap()
+}
+
+fn property<'a>(span: &'a SpanRecord, key: &str) -> Option<&'a str> {
+    span.properties
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_ref())
+}
+
+#[test]
+#[serial]
+fn blocking_w3c() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/api/users")
+        .match_query(Matcher::Any)
+        .match_header(
+            "traceparent",
+            Matcher::Regex(format!("^00-{:032x}-[0-9a-f]{{16}}-01$", TRACE_ID.0)),
+        )
+        .with_body("[]")
+        .create();
+
+    let client = TracedClient::new(reqwest::blocking::Client::new());
+    {
+        let root = Span::root("root", SpanContext::new(TRACE_ID, SpanId(1)));
+        let _guard = root.set_local_parent();
+        let request = client
+            .inner()
+            .get(format!("{}/api/users?page=2", server.url()))
+            .build()
+            .unwrap();
+        let response = client.execute(request).unwrap();
+        assert_eq!(response.text().unwrap(), "[]");
+    }
+    mock.assert();
+
+    minitrace::flush();
+
+    let spans = collected_spans.lock().clone();
+    let root = find(&spans, "root");
+    let span = find(&spans, "GET /api/users");
+    assert_eq!(span.trace_id, TRACE_ID);
+    assert_eq!(span.parent_id, root.span_id);
+    assert_eq!(span.kind, SpanKind::Client);
+    assert_eq!(span.status, SpanStatus::Unset);
+    assert_eq!(property(span, "http.method"), Some("GET"));
+    assert_eq!(
+        property(span, "http.url"),
+        Some(format!("{}/api/users?page=2", server.url()).as_str())
+    );
+    assert_eq!(property(span, "http.status_code"), Some("200"));
+}
+
+#[test]
+#[serial]
+fn blocking_without_local_parent() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/")
+        .match_header("traceparent", Matcher::Missing)
+        .create();
+
+    let client = TracedClient::new(reqwest::blocking::Client::new());
+    let request = client.inner().get(server.url()).build().unwrap();
+    client.execute(request).unwrap();
+    mock.assert();
+
+    minitrace::flush();
+    assert!(collected_spans.lock().is_empty());
+}
+
+#[tokio::test]
+#[serial]
+async fn async_b3_multi_error() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/api/users")
+        .match_header("x-b3-traceid", format!("{:032x}", TRACE_ID.0).as_str())
+        .match_header("x-b3-spanid", Matcher::Regex("^[0-9a-f]{16}$".into()))
+        .match_header("x-b3-sampled", "1")
+        .match_header("traceparent", Matcher::Missing)
+        .with_status(404)
+        .create_async()
+        .await;
+
+    let client =
+        TracedClientAsync::new(reqwest::Client::new()).with_injector(HeaderInjector::B3Multi);
+    let root = Span::root("root", SpanContext::new(TRACE_ID, SpanId(1)));
+    async {
+        let request = client
+            .inner()
+            .post(format!("{}/api/users", server.url()))
+            .build()
+            .unwrap();
+        let response = client.execute(request).await.unwrap();
+        assert_eq!(response.status(), 404);
+    }
+    .in_span(root)
+    .await;
+    mock.assert_async().await;
+
+    minitrace::flush();
+
+    let spans = collected_spans.lock().clone();
+    let root = find(&spans, "root");
+    let span = find(&spans, "POST /api/users");
+    assert_eq!(span.parent_id, root.span_id);
+    assert_eq!(span.status, SpanStatus::Error("404 Not Found".into()));
+    assert_eq!(property(span, "http.status_code"), Some("404"));
+}
+
+#[test]
+fn header_injector() {
+    let span_context = SpanContext::new(TraceId(12), SpanId(34));
+    let inject = |injector: HeaderInjector| {
+        let mut headers = reqwest::header::HeaderMap::new();
+        injector.inject(&span_context, &mut headers);
+        let mut headers = headers
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value.to_str().unwrap()))
+            .collect::<Vec<_>>();
+        headers.sort();
+        headers
+    };
+
+    assert_eq!(inject(HeaderInjector::W3C), [
+        "traceparent: 00-0000000000000000000000000000000c-0000000000000022-01"
+    ]);
+    assert_eq!(inject(HeaderInjector::B3Single), [
+        "b3: 0000000000000000000000000000000c-0000000000000022-1"
+    ]);
+    assert_eq!(inject(HeaderInjector::B3Multi), [
+        "x-b3-sampled: 1",
+        "x-b3-spanid: 0000000000000022",
+        "x-b3-traceid: 0000000000000000000000000000000c",
+    ]);
+    assert_eq!(inject(HeaderInjector::Datadog), [
+        "x-datadog-parent-id: 34",
+        "x-datadog-sampling-priority: 1",
+        "x-datadog-trace-id: 12",
+    ]);
+}
@@ -0,0 +1,234 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+#![doc = include_str!("../README.md")]
+
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::FutureExt as _;
+use futures::StreamExt as _;
+use minitrace::prelude::*;
+use sqlx::database::Database;
+use sqlx::Describe;
+use sqlx::Either;
+use sqlx::Error;
+use sqlx::Execute;
+use sqlx::Executor;
+use sqlx::Pool;
+
+/// A [`Database`] whose queries can be traced by [`TracedPool`].
+///
+/// It's implemented for the databases enabled by the `sqlite`, `postgres` and `mysql` features.
+pub trait TracedDatabase: Database {
+    /// Returns the number of rows affected by a query.
+    fn rows_affected(result: &Self::QueryResult) -> u64;
+}
+
+#[cfg(feature = "sqlite")]
+impl TracedDatabase for sqlx::Sqlite {
+    fn rows_affected(result: &Self::QueryResult) -> u64 {
+        result.rows_affected()
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl TracedDatabase for sqlx::Postgres {
+    fn rows_affected(result: &Self::QueryResult) -> u64 {
+        result.rows_affected()
+    }
+}
+
+#[cfg(feature = "mysql")]
+impl TracedDatabase for sqlx::MySql {
+    fn rows_affected(result: &Self::QueryResult) -> u64 {
+        result.rows_affected()
+    }
+}
+
+/// A [`Pool`] that traces each query it executes.
+///
+/// `&TracedPool` implements [`Executor`] like `&Pool`. Each query gets a [`SpanKind::Client`]
+/// child span of the current local parent, named after the method of `Executor` that runs it,
+/// e.g. `sqlx::execute` or `sqlx::fetch_one`, with the properties:
+///
+/// - `db.statement`: the SQL statement.
+/// - `db.operation`: the first keyword of the statement in lowercase, e.g. `select`.
+/// - `db.rows_affected`: the number of rows affected, added by `execute()` once the query succeeds.
+///
+/// The status of the span is set to [`SpanStatus::Error`] if the query fails. The span of a
+/// future ends when the future completes, and the span of a stream ends when the stream is
+/// dropped. Nothing is traced if there is no local parent.
+#[derive(Clone, Debug)]
+pub struct TracedPool<DB: Database> {
+    pool: Pool<DB>,
+}
+
+impl<DB: Database> TracedPool<DB> {
+    pub fn new(pool: Pool<DB>) -> Self {
+        TracedPool { pool }
+    }
+
+    /// Returns the wrapped pool. The queries executed by the wrapped pool directly are not
+    /// traced.
+    pub fn inner(&self) -> &Pool<DB> {
+        &self.pool
+    }
+
+    /// Returns the wrapped pool.
+    pub fn into_inner(self) -> Pool<DB> {
+        self.pool
+    }
+}
+
+impl<DB: Database> From<Pool<DB>> for TracedPool<DB> {
+    fn from(pool: Pool<DB>) -> Self {
+        TracedPool::new(pool)
+    }
+}
+
+impl<'p, DB: TracedDatabase> Executor<'p> for &'_ TracedPool<DB>
+where for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>
+{
+    type Database = DB;
+
+    fn execute<'e, 'q: 'e, E>(self, query: E) -> BoxFuture<'e, Result<DB::QueryResult, Error>>
+    where
+        'p: 'e,
+        E: 'q + Execute<'q, DB>,
+    {
+        let mut span = query_span("sqlx::execute", query.sql());
+        let future = self.pool.execute(query);
+        async move {
+            let result = future.await;
+            match &result {
+                Ok(result) => {
+                    drop(span.with_property(|| {
+                        ("db.rows_affected", DB::rows_affected(result).to_string())
+                    }))
+                }
+                Err(err) => span.set_status(SpanStatus::Error(err.to_string().into())),
+            }
+            result
+        }
+        .boxed()
+    }
+
+    fn execute_many<'e, 'q: 'e, E>(self, query: E) -> BoxStream<'e, Result<DB::QueryResult, Error>>
+    where
+        'p: 'e,
+        E: 'q + Execute<'q, DB>,
+    {
+        let span = query_span("sqlx::execute_many", query.sql());
+        traced_stream(self.pool.execute_many(query), span)
+    }
+
+    fn fetch<'e, 'q: 'e, E>(self, query: E) -> BoxStream<'e, Result<DB::Row, Error>>
+    where
+        'p: 'e,
+        E: 'q + Execute<'q, DB>,
+    {
+        let span = query_span("sqlx::fetch", query.sql());
+        traced_stream(self.pool.fetch(query), span)
+    }
+
+    fn fetch_many<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> BoxStream<'e, Result<Either<DB::QueryResult, DB::Row>, Error>>
+    where
+        'p: 'e,
+        E: 'q + Execute<'q, DB>,
+    {
+        let span = query_span("sqlx::fetch_many", query.sql());
+        traced_stream(self.pool.fetch_many(query), span)
+    }
+
+    fn fetch_all<'e, 'q: 'e, E>(self, query: E) -> BoxFuture<'e, Result<Vec<DB::Row>, Error>>
+    where
+        'p: 'e,
+        E: 'q + Execute<'q, DB>,
+    {
+        let span = query_span("sqlx::fetch_all", query.sql());
+        traced_future(self.pool.fetch_all(query), span)
+    }
+
+    fn fetch_one<'e, 'q: 'e, E>(self, query: E) -> BoxFuture<'e, Result<DB::Row, Error>>
+    where
+        'p: 'e,
+        E: 'q + Execute<'q, DB>,
+    {
+        let span = query_span("sqlx::fetch_one", query.sql());
+        traced_future(self.pool.fetch_one(query), span)
+    }
+
+    fn fetch_optional<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> BoxFuture<'e, Result<Option<DB::Row>, Error>>
+    where
+        'p: 'e,
+        E: 'q + Execute<'q, DB>,
+    {
+        let span = query_span("sqlx::fetch_optional", query.sql());
+        traced_future(self.pool.fetch_optional(query), span)
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [DB::TypeInfo],
+    ) -> BoxFuture<'e, Result<DB::Statement<'q>, Error>>
+    where
+        'p: 'e,
+    {
+        self.pool.prepare_with(sql, parameters)
+    }
+
+    #[doc(hidden)]
+    fn describe<'e, 'q: 'e>(self, sql: &'q str) -> BoxFuture<'e, Result<Describe<DB>, Error>>
+    where 'p: 'e {
+        self.pool.describe(sql)
+    }
+}
+
+fn query_span(name: &'static str, sql: &str) -> Span {
+    Span::enter_with_local_parent(name)
+        .with_kind(SpanKind::Client)
+        .with_properties(|| {
+            let operation = sql
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_lowercase();
+            [
+                ("db.statement", sql.to_string()),
+                ("db.operation", operation),
+            ]
+        })
+}
+
+fn traced_future<'e, T: Send + 'e>(
+    future: BoxFuture<'e, Result<T, Error>>,
+    mut span: Span,
+) -> BoxFuture<'e, Result<T, Error>> {
+    async move {
+        let result = future.await;
+        if let Err(err) = &result {
+            span.set_status(SpanStatus::Error(err.to_string().into()));
+        }
+        result
+    }
+    .boxed()
+}
+
+fn traced_stream<'e, T: Send + 'e>(
+    stream: BoxStream<'e, Result<T, Error>>,
+    mut span: Span,
+) -> BoxStream<'e, Result<T, Error>> {
+    stream
+        .inspect(move |item| {
+            if let Err(err) = item {
+                span.set_status(SpanStatus::Error(err.to_string().into()));
+            }
+        })
+        .boxed()
+}
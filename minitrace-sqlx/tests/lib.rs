@@ -0,0 +1,141 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use futures::TryStreamExt;
+use minitrace::collector::Config;
+use minitrace::collector::SpanRecord;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+use minitrace::util::tree::tree_str_from_span_records;
+use minitrace_sqlx::TracedPool;
+use serial_test::serial;
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::Row;
+use sqlx::Sqlite;
+
+async fn connect() -> TracedPool<Sqlite> {
+    // Each connection to an in-memory database has its own database.
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    TracedPool::new(pool)
+}
+
+fn property<'a>(span: &'a SpanRecord, key: &str) -> Option<&'a str> {
+    span.properties
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_ref())
+}
+
+#[tokio::test]
+#[serial]
+async fn queries() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let pool = connect().await;
+    let root = Span::root("root", SpanContext::random());
+    async {
+        sqlx::query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let result = sqlx::query("INSERT INTO users (name) VALUES (?), (?)")
+            .bind("alice")
+            .bind("bob")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(result.rows_affected(), 2);
+
+        let row = sqlx::query("SELECT name FROM users WHERE id = ?")
+            .bind(1)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<String, _>("name"), "alice");
+
+        let rows = sqlx::query("SELECT name FROM users")
+            .fetch(&pool)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+    }
+    .in_span(root)
+    .await;
+
+    minitrace::flush();
+
+    let spans = collected_spans.lock().clone();
+    let expected_graph = r#"
+root []
+    sqlx::execute [("db.statement", "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)"), ("db.operation", "create"), ("db.rows_affected", "0")]
+    sqlx::execute [("db.statement", "INSERT INTO users (name) VALUES (?), (?)"), ("db.operation", "insert"), ("db.rows_affected", "2")]
+    sqlx::fetch [("db.statement", "SELECT name FROM users"), ("db.operation", "select")]
+    sqlx::fetch_one [("db.statement", "SELECT name FROM users WHERE id = ?"), ("db.operation", "select")]
+"#;
+    assert_eq!(tree_str_from_span_records(spans.clone()), expected_graph);
+    for span in spans.iter().filter(|span| span.name != "root") {
+        assert_eq!(span.kind, SpanKind::Client);
+        assert_eq!(span.status, SpanStatus::Unset);
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn query_error() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let pool = connect().await;
+    let root = Span::root("root", SpanContext::random());
+    async {
+        assert!(sqlx::query("SELECT * FROM missing")
+            .fetch_all(&pool)
+            .await
+            .is_err());
+        assert!(sqlx::query("UPDATE missing SET id = 1")
+            .execute(&pool)
+            .await
+            .is_err());
+    }
+    .in_span(root)
+    .await;
+
+    minitrace::flush();
+
+    let spans = collected_spans.lock().clone();
+    let fetch_all = spans
+        .iter()
+        .find(|span| span.name == "sqlx::fetch_all")
+        .unwrap();
+    assert_eq!(property(fetch_all, "db.operation"), Some("select"));
+    assert!(
+        matches!(&fetch_all.status, SpanStatus::Error(message) if message.contains("no such table"))
+    );
+
+    let execute = spans
+        .iter()
+        .find(|span| span.name == "sqlx::execute")
+        .unwrap();
+    assert_eq!(property(execute, "db.operation"), Some("update"));
+    assert_eq!(property(execute, "db.rows_affected"), None);
+    assert!(matches!(&execute.status, SpanStatus::Error(_)));
+}
+
+#[tokio::test]
+#[serial]
+async fn without_local_parent() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let pool = connect().await;
+    let row = sqlx::query("SELECT 1").fetch_one(&pool).await.unwrap();
+    assert_eq!(row.get::<i64, _>(0), 1);
+
+    minitrace::flush();
+    assert!(collected_spans.lock().is_empty());
+}
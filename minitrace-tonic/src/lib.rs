@@ -0,0 +1,49 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+#![doc = include_str!("../README.md")]
+
+use minitrace::prelude::*;
+use tonic::service::Interceptor;
+use tonic::Request;
+use tonic::Status;
+
+/// A tonic [`Interceptor`] that propagates the trace context of the requests.
+///
+/// - The [client](TonicTraceInterceptor::client) interceptor injects the span context of the
+///   current local parent into the metadata of the outgoing requests, with
+///   [`SpanContext::inject_grpc_metadata()`]. The requests are left untouched if there is no local
+///   parent.
+/// - The [server](TonicTraceInterceptor::server) interceptor extracts the span context from the
+///   metadata of the incoming requests, with [`SpanContext::extract_grpc_metadata()`], and
+///   inserts it into the request extensions. The handler is expected to start its span from it,
+///   e.g. with [`Span::root()`].
+#[derive(Clone, Copy, Debug)]
+pub struct TonicTraceInterceptor {
+    is_client: bool,
+}
+
+impl TonicTraceInterceptor {
+    /// Creates an interceptor for the outgoing requests of a client.
+    pub fn client() -> Self {
+        TonicTraceInterceptor { is_client: true }
+    }
+
+    /// Creates an interceptor for the incoming requests of a server.
+    pub fn server() -> Self {
+        TonicTraceInterceptor { is_client: false }
+    }
+}
+
+impl Interceptor for TonicTraceInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if self.is_client {
+            if let Some(span_context) = SpanContext::current_local_parent() {
+                span_context.inject_grpc_metadata(request.metadata_mut());
+            }
+        } else if let Some(span_context) = SpanContext::extract_grpc_metadata(request.metadata()) {
+            request.extensions_mut().insert(span_context);
+        }
+
+        Ok(request)
+    }
+}
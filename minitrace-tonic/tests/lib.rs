@@ -0,0 +1,81 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use minitrace::collector::Config;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+use minitrace_tonic::TonicTraceInterceptor;
+use serial_test::serial;
+use tonic::service::Interceptor;
+use tonic::Request;
+
+#[test]
+#[serial]
+fn client_interceptor() {
+    let (reporter, _collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let mut interceptor = TonicTraceInterceptor::client();
+
+    let request = interceptor.call(Request::new(())).unwrap();
+    assert!(request.metadata().get("traceparent").is_none());
+
+    let root = Span::root("root", SpanContext::random());
+    let _guard = root.set_local_parent();
+    let request = interceptor.call(Request::new(())).unwrap();
+
+    let extracted = SpanContext::extract_grpc_metadata(request.metadata()).unwrap();
+    let expected = SpanContext::from_span(&root).unwrap();
+    assert_eq!(extracted.trace_id, expected.trace_id);
+    assert_eq!(extracted.span_id, expected.span_id);
+}
+
+#[test]
+fn server_interceptor() {
+    let mut interceptor = TonicTraceInterceptor::server();
+
+    let request = interceptor.call(Request::new(())).unwrap();
+    assert!(request.extensions().get::<SpanContext>().is_none());
+
+    let mut request = Request::new(());
+    request.metadata_mut().insert(
+        "traceparent",
+        "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"
+            .parse()
+            .unwrap(),
+    );
+    let request = interceptor.call(request).unwrap();
+
+    let span_context = request.extensions().get::<SpanContext>().unwrap();
+    assert_eq!(
+        span_context.trace_id,
+        TraceId(0x0af7651916cd43dd8448eb211c80319c)
+    );
+    assert_eq!(span_context.span_id, SpanId(0xb7ad6b7169203331));
+}
+
+#[test]
+#[serial]
+fn interceptor_round_trip() {
+    let (reporter, _collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let parent = SpanContext::random().with_tracestate("rojo=00f067aa0ba902b7");
+    let root = Span::root("root", parent);
+    let _guard = root.set_local_parent();
+    let client_request = TonicTraceInterceptor::client()
+        .call(Request::new(()))
+        .unwrap();
+
+    let mut request = Request::new(());
+    *request.metadata_mut() = client_request.metadata().clone();
+    let request = TonicTraceInterceptor::server().call(request).unwrap();
+
+    let span_context = request.extensions().get::<SpanContext>().unwrap();
+    let expected = SpanContext::from_span(&root).unwrap();
+    assert_eq!(span_context.trace_id, expected.trace_id);
+    assert_eq!(span_context.span_id, expected.span_id);
+    assert_eq!(
+        span_context.tracestate(),
+        Some("rojo=00f067aa0ba902b7")
+    );
+}
@@ -0,0 +1,197 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+#![doc = include_str!("../README.md")]
+
+use std::borrow::Cow;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+
+use http::HeaderMap;
+use http::Request;
+use http::Response;
+use minitrace::prelude::*;
+use pin_project_lite::pin_project;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// A [`Layer`] that traces each HTTP request with a [`TraceService`].
+pub struct TraceLayer<B> {
+    service_name: String,
+    span_name_fn: fn(&Request<B>) -> Cow<'static, str>,
+    header_extractor: fn(&HeaderMap) -> Option<SpanContext>,
+}
+
+impl<B> TraceLayer<B> {
+    /// Creates a `TraceLayer` that records `service_name` as the property `service.name` of the
+    /// span of each request.
+    pub fn new(service_name: impl Into<String>) -> Self {
+        TraceLayer {
+            service_name: service_name.into(),
+            span_name_fn: default_span_name,
+            header_extractor: extract_span_context,
+        }
+    }
+
+    /// Sets the function to name the span of a request. Defaults to [`default_span_name()`].
+    pub fn span_name_fn(self, span_name_fn: fn(&Request<B>) -> Cow<'static, str>) -> Self {
+        TraceLayer {
+            span_name_fn,
+            ..self
+        }
+    }
+
+    /// Sets the function to extract the upstream [`SpanContext`] from the request headers.
+    /// Defaults to [`extract_span_context()`].
+    pub fn header_extractor(self, header_extractor: fn(&HeaderMap) -> Option<SpanContext>) -> Self {
+        TraceLayer {
+            header_extractor,
+            ..self
+        }
+    }
+}
+
+impl<B> Clone for TraceLayer<B> {
+    fn clone(&self) -> Self {
+        TraceLayer {
+            service_name: self.service_name.clone(),
+            span_name_fn: self.span_name_fn,
+            header_extractor: self.header_extractor,
+        }
+    }
+}
+
+impl<S, B> Layer<S> for TraceLayer<B> {
+    type Service = TraceService<S, B>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TraceService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// A [`Service`] that starts a root [`Span`] for each HTTP request.
+///
+/// The span continues the trace of the upstream [`SpanContext`] extracted from the request
+/// headers, or starts a new trace if there is none. It is set as the local parent while the inner
+/// service is called and while the response future is polled, and ends when the response is
+/// ready.
+///
+/// The span is a [`SpanKind::Server`] span with the properties `service.name`, `http.method`,
+/// `http.target` and `http.status_code`. Its status is set to [`SpanStatus::Error`] if the inner
+/// service fails or responds with a server error.
+pub struct TraceService<S, B> {
+    inner: S,
+    layer: TraceLayer<B>,
+}
+
+impl<S: Clone, B> Clone for TraceService<S, B> {
+    fn clone(&self) -> Self {
+        TraceService {
+            inner: self.inner.clone(),
+            layer: self.layer.clone(),
+        }
+    }
+}
+
+impl<S, B, ResBody> Service<Request<B>> for TraceService<S, B>
+where S: Service<Request<B>, Response = Response<ResBody>>
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let parent =
+            (self.layer.header_extractor)(request.headers()).unwrap_or_else(SpanContext::random);
+        let span = Span::root((self.layer.span_name_fn)(&request), parent)
+            .with_kind(SpanKind::Server)
+            .with_properties(|| {
+                [
+                    ("service.name", self.layer.service_name.clone()),
+                    ("http.method", request.method().to_string()),
+                    ("http.target", request.uri().path().to_string()),
+                ]
+            });
+
+        let inner = {
+            let _guard = span.set_local_parent();
+            self.inner.call(request)
+        };
+
+        ResponseFuture {
+            inner,
+            span: Some(span),
+        }
+    }
+}
+
+pin_project! {
+    /// The response future of [`TraceService`].
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: F,
+        span: Option<Span>,
+    }
+}
+
+impl<F, ResBody, E> Future for ResponseFuture<F>
+where F: Future<Output = Result<Response<ResBody>, E>>
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        let guard = this.span.as_ref().map(|span| span.set_local_parent());
+        let result = match this.inner.poll(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Poll::Pending,
+        };
+        drop(guard);
+
+        if let Some(mut span) = this.span.take() {
+            match &result {
+                Ok(response) => {
+                    let status = response.status();
+                    if status.is_server_error() {
+                        span.set_status(SpanStatus::Error(status.to_string().into()));
+                    }
+                    drop(span.with_property(|| ("http.status_code", status.as_str().to_string())));
+                }
+                Err(_) => span.set_status(SpanStatus::Error("service error".into())),
+            }
+        }
+
+        Poll::Ready(result)
+    }
+}
+
+/// Names the span of a request after its method and path, e.g. `GET /users`.
+pub fn default_span_name<B>(request: &Request<B>) -> Cow<'static, str> {
+    format!("{} {}", request.method(), request.uri().path()).into()
+}
+
+/// Extracts the upstream [`SpanContext`] from the first of the W3C `traceparent` and `tracestate`
+/// headers, the B3 single `b3` header, and the B3 multiple `X-B3-*` headers that can be decoded.
+pub fn extract_span_context(headers: &HeaderMap) -> Option<SpanContext> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+    header("traceparent")
+        .and_then(|traceparent| SpanContext::decode_w3c_headers(traceparent, header("tracestate")))
+        .or_else(|| header("b3").and_then(SpanContext::decode_b3_single_header))
+        .or_else(|| {
+            SpanContext::decode_b3_multi_headers(
+                header("x-b3-traceid")?,
+                header("x-b3-spanid")?,
+                header("x-b3-sampled"),
+            )
+        })
+}
@@ -0,0 +1,119 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::borrow::Cow;
+use std::convert::Infallible;
+
+use http::Request;
+use http::Response;
+use http::StatusCode;
+use minitrace::collector::Config;
+use minitrace::collector::SpanRecord;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+use minitrace::util::tree::tree_str_from_span_records;
+use minitrace_tower::TraceLayer;
+use serial_test::serial;
+use tower::ServiceBuilder;
+use tower::ServiceExt;
+
+async fn handle(request: Request<()>) -> Result<Response<()>, Infallible> {
+    let _span = LocalSpan::enter_with_local_parent("handler");
+    let status = match request.uri().path() {
+        "/error" => StatusCode::INTERNAL_SERVER_ERROR,
+        _ => StatusCode::OK,
+    };
+    Ok(Response::builder().status(status).body(()).unwrap())
+}
+
+async fn call(layer: TraceLayer<()>, request: Request<()>) -> Vec<SpanRecord> {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let service = ServiceBuilder::new().layer(layer).service_fn(handle);
+    service.oneshot(request).await.unwrap();
+
+    minitrace::flush();
+    let spans = collected_spans.lock().clone();
+    spans
+}
+
+#[tokio::test]
+#[serial]
+async fn new_trace() {
+    let request = Request::get("/hello?a=1").body(()).unwrap();
+    let spans = call(TraceLayer::new("test-service"), request).await;
+
+    let expected_graph = r#"
+GET /hello [("service.name", "test-service"), ("http.method", "GET"), ("http.target", "/hello"), ("http.status_code", "200")]
+    handler []
+"#;
+    assert_eq!(tree_str_from_span_records(spans.clone()), expected_graph);
+
+    let root = spans.iter().find(|span| span.name == "GET /hello").unwrap();
+    assert_eq!(root.parent_id, SpanId::default());
+    assert_eq!(root.kind, SpanKind::Server);
+    assert_eq!(root.status, SpanStatus::Unset);
+}
+
+#[tokio::test]
+#[serial]
+async fn propagation() {
+    let requests = [
+        Request::get("/")
+            .header(
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            )
+            .body(())
+            .unwrap(),
+        Request::get("/")
+            .header("traceparent", "invalid")
+            .header("b3", "0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-1")
+            .body(())
+            .unwrap(),
+        Request::get("/")
+            .header("X-B3-TraceId", "0af7651916cd43dd8448eb211c80319c")
+            .header("X-B3-SpanId", "b7ad6b7169203331")
+            .header("X-B3-Sampled", "1")
+            .body(())
+            .unwrap(),
+    ];
+
+    for request in requests {
+        let spans = call(TraceLayer::new("test-service"), request).await;
+        let root = spans.iter().find(|span| span.name == "GET /").unwrap();
+        assert_eq!(root.trace_id, TraceId(0x0af7651916cd43dd8448eb211c80319c));
+        assert_eq!(root.parent_id, SpanId(0xb7ad6b7169203331));
+
+        let handler = spans.iter().find(|span| span.name == "handler").unwrap();
+        assert_eq!(handler.trace_id, root.trace_id);
+        assert_eq!(handler.parent_id, root.span_id);
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn custom_span_name_and_server_error() {
+    fn span_name(request: &Request<()>) -> Cow<'static, str> {
+        format!("handle {}", request.uri().path()).into()
+    }
+
+    let request = Request::post("/error").body(()).unwrap();
+    let spans = call(
+        TraceLayer::new("test-service").span_name_fn(span_name),
+        request,
+    )
+    .await;
+
+    let root = spans
+        .iter()
+        .find(|span| span.name == "handle /error")
+        .unwrap();
+    assert!(root
+        .properties
+        .contains(&("http.status_code".into(), "500".into())));
+    assert_eq!(
+        root.status,
+        SpanStatus::Error("500 Internal Server Error".into())
+    );
+}
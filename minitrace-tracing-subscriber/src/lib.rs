@@ -0,0 +1,142 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+#![doc = include_str!("../README.md")]
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::fmt::Debug;
+
+use minitrace::local::LocalParentGuard;
+use minitrace::prelude::*;
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing::span;
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+type Properties = Vec<(Cow<'static, str>, Cow<'static, str>)>;
+
+thread_local! {
+    static ENTERED_SPANS: RefCell<Vec<(span::Id, LocalParentGuard)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A [`tracing_subscriber::Layer`] that records the spans and events of `tracing` with
+/// `minitrace`.
+///
+/// Each `tracing` span is recorded as a [`Span`], which is a child of its explicit parent or
+/// otherwise of the current local parent, and is submitted when the `tracing` span is closed.
+/// Entering a `tracing` span sets its `Span` as the local parent of the current thread, so that
+/// `tracing` events, [`LocalSpan`]s and functions annotated with `#[trace]` are recorded as its
+/// children.
+///
+/// The fields of spans and events are recorded as properties. Events are named after their
+/// `target`.
+///
+/// Like other spans of `minitrace`, `tracing` spans are only recorded within a trace started by
+/// [`Span::root()`]. A `tracing` span with an explicit `parent: None` is not recorded.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MinitraceLayer;
+
+/// The [`Span`] of a `tracing` span, stored in the extensions of the span.
+struct MinitraceSpan(Span);
+
+impl<S> Layer<S> for MinitraceLayer
+where S: Subscriber + for<'a> LookupSpan<'a>
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let name = attrs.metadata().name();
+        let span = match attrs.parent() {
+            Some(parent) => with_span(&ctx, parent, |parent| Span::enter_with_parent(name, parent))
+                .unwrap_or_else(Span::noop),
+            None if attrs.is_contextual() => Span::enter_with_local_parent(name),
+            None => Span::noop(),
+        };
+        let span = span.with_properties(|| {
+            let mut properties = Vec::new();
+            attrs.record(&mut PropertyVisitor(&mut properties));
+            properties
+        });
+
+        if let Some(span_ref) = ctx.span(id) {
+            span_ref.extensions_mut().insert(MinitraceSpan(span));
+        }
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        with_span(&ctx, id, |span| {
+            let recorded = std::mem::replace(span, Span::noop());
+            *span = recorded.with_properties(|| {
+                let mut properties = Vec::new();
+                values.record(&mut PropertyVisitor(&mut properties));
+                properties
+            });
+        });
+    }
+
+    fn on_event(&self, event: &tracing::Event<'_>, ctx: Context<'_, S>) {
+        let target = event.metadata().target();
+        let properties = || {
+            let mut properties = Vec::new();
+            event.record(&mut PropertyVisitor(&mut properties));
+            properties
+        };
+        match event.parent() {
+            Some(parent) => {
+                with_span(&ctx, parent, |parent| {
+                    Event::add_to_parent(target, parent, properties)
+                });
+            }
+            None if event.is_contextual() => Event::add_to_local_parent(target, properties),
+            None => {}
+        }
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(guard) = with_span(&ctx, id, |span| span.set_local_parent()) {
+            ENTERED_SPANS.with(|entered| entered.borrow_mut().push((id.clone(), guard)));
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, _ctx: Context<'_, S>) {
+        // Spans are usually exited in the reverse order of entering, but `tracing` doesn't
+        // guarantee it.
+        let guard = ENTERED_SPANS.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            let index = entered
+                .iter()
+                .rposition(|(entered_id, _)| entered_id == id)?;
+            Some(entered.remove(index))
+        });
+        drop(guard);
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let span = ctx
+            .span(&id)
+            .and_then(|span_ref| span_ref.extensions_mut().remove::<MinitraceSpan>());
+        drop(span);
+    }
+}
+
+fn with_span<S, T>(ctx: &Context<'_, S>, id: &span::Id, f: impl FnOnce(&mut Span) -> T) -> Option<T>
+where S: Subscriber + for<'a> LookupSpan<'a> {
+    let span_ref = ctx.span(id)?;
+    let mut extensions = span_ref.extensions_mut();
+    let MinitraceSpan(span) = extensions.get_mut::<MinitraceSpan>()?;
+    Some(f(span))
+}
+
+struct PropertyVisitor<'a>(&'a mut Properties);
+
+impl Visit for PropertyVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name().into(), value.to_string().into()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .push((field.name().into(), format!("{value:?}").into()));
+    }
+}
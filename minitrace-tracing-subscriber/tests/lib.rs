@@ -0,0 +1,87 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use minitrace::collector::Config;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+use minitrace_tracing_subscriber::MinitraceLayer;
+use serial_test::serial;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Registry;
+
+#[tracing::instrument]
+fn add(a: u64, b: u64) -> u64 {
+    tracing::info!(sum = a + b, "adding");
+    a + b
+}
+
+#[tracing::instrument]
+fn add_twice(a: u64) -> u64 {
+    let _span = LocalSpan::enter_with_local_parent("local");
+    add(add(a, 1), 1)
+}
+
+#[test]
+#[serial]
+fn instrument() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    tracing::subscriber::with_default(Registry::default().with(MinitraceLayer), || {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        add(1, 2);
+        add_twice(3);
+    });
+
+    minitrace::flush();
+
+    reporter.assert_tree(
+        r#"
+root []
+    add [("a", "1"), ("b", "2")]
+    add_twice [("a", "3")]
+        local []
+            add [("a", "3"), ("b", "1")]
+            add [("a", "4"), ("b", "1")]
+"#,
+    );
+
+    let collected_spans = collected_spans.lock();
+    let add_span = collected_spans
+        .iter()
+        .find(|span| span.name == "add" && span.properties.contains(&("a".into(), "1".into())))
+        .unwrap();
+    assert_eq!(add_span.events.len(), 1);
+    assert_eq!(add_span.events[0].name, "lib");
+    assert_eq!(add_span.events[0].properties, vec![
+        ("message".into(), "adding".into()),
+        ("sum".into(), "3".into()),
+    ]);
+}
+
+#[test]
+#[serial]
+fn record_and_explicit_parent() {
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    tracing::subscriber::with_default(Registry::default().with(MinitraceLayer), || {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        let parent = tracing::info_span!("parent", status = tracing::field::Empty);
+        parent.record("status", "done");
+        let _child = tracing::info_span!(parent: &parent, "child").entered();
+        let _orphan = tracing::info_span!(parent: None, "orphan").entered();
+    });
+
+    minitrace::flush();
+
+    reporter.assert_tree(
+        r#"
+root []
+    parent [("status", "done")]
+        child []
+"#,
+    );
+}
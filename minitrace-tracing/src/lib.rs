@@ -0,0 +1,200 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+#![doc = include_str!("../README.md")]
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::fmt::Debug;
+
+use minitrace::local::LocalParentGuard;
+use minitrace::prelude::*;
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing::span;
+use tracing::subscriber::Interest;
+use tracing::Metadata;
+use tracing::Subscriber;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Registry;
+
+type Properties = Vec<(Cow<'static, str>, Cow<'static, str>)>;
+
+thread_local! {
+    static ENTERED_SPANS: RefCell<Vec<(span::Id, LocalParentGuard)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A [`tracing::Subscriber`] that records the spans and events of `tracing` with `minitrace`.
+///
+/// Each `tracing` span is recorded as a [`Span`], which is a child of its explicit parent or
+/// otherwise of the current local parent. Entering a `tracing` span sets its `Span` as the local
+/// parent of the current thread, so that `tracing` events, [`LocalSpan`]s and functions annotated
+/// with `#[trace]` are recorded as its children. This also holds for futures instrumented by
+/// `tracing`, whose span is entered on every poll.
+///
+/// The fields of spans and events, along with the `target`, `file` and `line` of the callsite, are
+/// recorded as properties. Events are named after their level.
+///
+/// Like other spans of `minitrace`, `tracing` spans are only recorded within a trace started by
+/// [`Span::root()`]. A `tracing` span with an explicit `parent: None` is not recorded.
+pub struct MinitraceSubscriber {
+    registry: Registry,
+}
+
+impl MinitraceSubscriber {
+    pub fn new() -> Self {
+        MinitraceSubscriber {
+            registry: Registry::default(),
+        }
+    }
+
+    fn with_span<T>(&self, id: &span::Id, f: impl FnOnce(&mut Span) -> T) -> Option<T> {
+        let span_ref = self.registry.span(id)?;
+        let mut extensions = span_ref.extensions_mut();
+        let MinitraceSpan(span) = extensions.get_mut::<MinitraceSpan>()?;
+        Some(f(span))
+    }
+}
+
+impl Default for MinitraceSubscriber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The [`Span`] of a `tracing` span, stored in the extensions of the span.
+struct MinitraceSpan(Span);
+
+impl Subscriber for MinitraceSubscriber {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        self.registry.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.registry.enabled(metadata)
+    }
+
+    fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+        let id = self.registry.new_span(attrs);
+
+        let metadata = attrs.metadata();
+        let span = match attrs.parent() {
+            Some(parent) => self
+                .with_span(parent, |parent| {
+                    Span::enter_with_parent(metadata.name(), parent)
+                })
+                .unwrap_or_else(Span::noop),
+            None if attrs.is_contextual() => Span::enter_with_local_parent(metadata.name()),
+            None => Span::noop(),
+        };
+        let span = span.with_properties(|| {
+            let mut properties = metadata_properties(metadata);
+            attrs.record(&mut PropertyVisitor(&mut properties));
+            properties
+        });
+
+        if let Some(span_ref) = self.registry.span(&id) {
+            span_ref.extensions_mut().insert(MinitraceSpan(span));
+        }
+
+        id
+    }
+
+    fn record(&self, id: &span::Id, values: &span::Record<'_>) {
+        self.registry.record(id, values);
+
+        self.with_span(id, |span| {
+            let recorded = std::mem::replace(span, Span::noop());
+            *span = recorded.with_properties(|| {
+                let mut properties = Vec::new();
+                values.record(&mut PropertyVisitor(&mut properties));
+                properties
+            });
+        });
+    }
+
+    fn record_follows_from(&self, id: &span::Id, follows: &span::Id) {
+        self.registry.record_follows_from(id, follows);
+    }
+
+    fn event(&self, event: &tracing::Event<'_>) {
+        self.registry.event(event);
+
+        let metadata = event.metadata();
+        let properties = || {
+            let mut properties = metadata_properties(metadata);
+            event.record(&mut PropertyVisitor(&mut properties));
+            properties
+        };
+        match event.parent() {
+            Some(parent) => {
+                self.with_span(parent, |parent| {
+                    Event::add_to_parent(metadata.level().as_str(), parent, properties)
+                });
+            }
+            None if event.is_contextual() => {
+                Event::add_to_local_parent(metadata.level().as_str(), properties)
+            }
+            None => {}
+        }
+    }
+
+    fn enter(&self, id: &span::Id) {
+        self.registry.enter(id);
+
+        if let Some(guard) = self.with_span(id, |span| span.set_local_parent()) {
+            ENTERED_SPANS.with(|entered| entered.borrow_mut().push((id.clone(), guard)));
+        }
+    }
+
+    fn exit(&self, id: &span::Id) {
+        // Spans are usually exited in the reverse order of entering, but `tracing` doesn't
+        // guarantee it.
+        let guard = ENTERED_SPANS.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            let index = entered
+                .iter()
+                .rposition(|(entered_id, _)| entered_id == id)?;
+            Some(entered.remove(index))
+        });
+        drop(guard);
+
+        self.registry.exit(id);
+    }
+
+    fn clone_span(&self, id: &span::Id) -> span::Id {
+        self.registry.clone_span(id)
+    }
+
+    fn try_close(&self, id: span::Id) -> bool {
+        // The `Span` is submitted when the registry drops the extensions of the closed span.
+        self.registry.try_close(id)
+    }
+
+    fn current_span(&self) -> tracing_core::span::Current {
+        self.registry.current_span()
+    }
+}
+
+fn metadata_properties(metadata: &'static Metadata<'static>) -> Properties {
+    let mut properties = vec![("target".into(), metadata.target().into())];
+    if let Some(file) = metadata.file() {
+        properties.push(("file".into(), file.into()));
+    }
+    if let Some(line) = metadata.line() {
+        properties.push(("line".into(), line.to_string().into()));
+    }
+    properties
+}
+
+struct PropertyVisitor<'a>(&'a mut Properties);
+
+impl Visit for PropertyVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name().into(), value.to_string().into()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .push((field.name().into(), format!("{value:?}").into()));
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use futures::executor::block_on;
+use minitrace::collector::Config;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+use minitrace::util::tree::tree_str_from_span_records;
+use minitrace_tracing::MinitraceSubscriber;
+use serial_test::serial;
+
+#[tracing::instrument]
+fn add(a: u64, b: u64) -> u64 {
+    tracing::info!("adding {a} and {b}");
+    a + b
+}
+
+#[tracing::instrument]
+async fn add_async(a: u64) -> u64 {
+    let _span = LocalSpan::enter_with_local_parent("local");
+    add(a, 1)
+}
+
+/// Removes the properties `file` and `line`, which change with the layout of this file.
+fn without_location(mut spans: Vec<SpanRecord>) -> Vec<SpanRecord> {
+    for span in &mut spans {
+        span.properties.retain(|(k, _)| k != "file" && k != "line");
+        for event in &mut span.events {
+            event.properties.retain(|(k, _)| k != "file" && k != "line");
+        }
+    }
+    spans
+}
+
+#[test]
+#[serial]
+fn instrument() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    tracing::subscriber::with_default(MinitraceSubscriber::new(), || {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        add(1, 2);
+        block_on(add_async(3));
+    });
+
+    minitrace::flush();
+
+    let collected_spans = without_location(collected_spans.lock().clone());
+    let expected_graph = r#"
+root []
+    add [("target", "lib"), ("a", "1"), ("b", "2")]
+    add_async [("target", "lib"), ("a", "3")]
+        local []
+            add [("target", "lib"), ("a", "3"), ("b", "1")]
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.clone()),
+        expected_graph
+    );
+
+    let add_span = collected_spans
+        .iter()
+        .find(|span| span.name == "add" && span.properties.contains(&("a".into(), "1".into())))
+        .unwrap();
+    assert_eq!(add_span.events.len(), 1);
+    assert_eq!(add_span.events[0].name, "INFO");
+    assert_eq!(add_span.events[0].properties, vec![
+        ("target".into(), "lib".into()),
+        ("message".into(), "adding 1 and 2".into()),
+    ]);
+}
+
+#[test]
+#[serial]
+fn record_and_explicit_parent() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    tracing::subscriber::with_default(MinitraceSubscriber::new(), || {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        let parent = tracing::info_span!("parent", status = tracing::field::Empty);
+        parent.record("status", "done");
+        let _child = tracing::info_span!(parent: &parent, "child").entered();
+        let _orphan = tracing::info_span!(parent: None, "orphan").entered();
+    });
+
+    minitrace::flush();
+
+    let collected_spans = without_location(collected_spans.lock().clone());
+    let expected_graph = r#"
+root []
+    parent [("target", "lib"), ("status", "done")]
+        child [("target", "lib")]
+"#;
+    assert_eq!(tree_str_from_span_records(collected_spans), expected_graph);
+}
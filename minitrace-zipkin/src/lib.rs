@@ -0,0 +1,180 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+#![doc = include_str!("../README.md")]
+
+use std::collections::HashMap;
+
+use minitrace::collector::Reporter;
+use minitrace::prelude::*;
+use serde::Serialize;
+
+/// [Zipkin](https://zipkin.io/) reporter for `minitrace` in the v2 JSON format.
+///
+/// Spans are sent to `endpoint_url`, which is usually `http://{zipkin}:9411/api/v2/spans`.
+pub struct ZipkinReporter {
+    endpoint_url: String,
+    local_service_name: String,
+    client: Option<reqwest::blocking::Client>,
+}
+
+impl ZipkinReporter {
+    pub fn new(
+        endpoint_url: impl Into<String>,
+        local_service_name: impl Into<String>,
+    ) -> ZipkinReporter {
+        ZipkinReporter {
+            endpoint_url: endpoint_url.into(),
+            local_service_name: local_service_name.into(),
+            client: None,
+        }
+    }
+
+    fn convert<'a>(&'a self, spans: &'a [SpanRecord]) -> Vec<ZipkinSpan<'a>> {
+        spans
+            .iter()
+            .map(move |s| ZipkinSpan {
+                trace_id: format!("{:032x}", s.trace_id.0),
+                id: format!("{:016x}", s.span_id.0),
+                parent_id: if s.parent_id.0 == 0 {
+                    None
+                } else {
+                    Some(format!("{:016x}", s.parent_id.0))
+                },
+                name: &s.name,
+                timestamp: s.begin_time_unix_ns / 1_000,
+                duration: s.duration_ns / 1_000,
+                local_endpoint: Endpoint {
+                    service_name: &self.local_service_name,
+                },
+                tags: s
+                    .properties
+                    .iter()
+                    .map(|(k, v)| (k.as_ref(), v.as_ref()))
+                    .collect(),
+                annotations: s
+                    .events
+                    .iter()
+                    .map(|event| Annotation {
+                        timestamp: event.timestamp_unix_ns / 1_000,
+                        value: &event.name,
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    fn serialize(&self, spans: Vec<ZipkinSpan>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Ok(serde_json::to_vec(&spans)?)
+    }
+
+    fn try_report(&self, spans: &[SpanRecord]) -> Result<(), Box<dyn std::error::Error>> {
+        let zipkin_spans = self.convert(spans);
+        let bytes = self.serialize(zipkin_spans)?;
+        let client = match &self.client {
+            Some(client) => client.clone(),
+            None => reqwest::blocking::Client::new(),
+        };
+        client
+            .post(&self.endpoint_url)
+            .header("Content-Type", "application/json")
+            .body(bytes)
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+impl Reporter for ZipkinReporter {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        if spans.is_empty() {
+            return;
+        }
+
+        if let Err(err) = self.try_report(spans) {
+            log::error!("report to zipkin failed: {}", err);
+        }
+    }
+
+    fn start(&mut self) {
+        self.client = Some(reqwest::blocking::Client::new());
+    }
+
+    fn stop(&mut self) {
+        self.client = None;
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ZipkinSpan<'a> {
+    trace_id: String,
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent_id: Option<String>,
+    name: &'a str,
+    timestamp: u64,
+    duration: u64,
+    local_endpoint: Endpoint<'a>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    tags: HashMap<&'a str, &'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Annotation<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Endpoint<'a> {
+    service_name: &'a str,
+}
+
+#[derive(Serialize)]
+struct Annotation<'a> {
+    timestamp: u64,
+    value: &'a str,
+}
+
+#[cfg(test)]
+mod tests {
+    use minitrace::collector::EventLevel;
+    use minitrace::collector::EventRecord;
+
+    use super::*;
+
+    #[test]
+    fn span_json() {
+        let reporter = ZipkinReporter::new("http://127.0.0.1:9411/api/v2/spans", "test-service");
+        let spans = [SpanRecord {
+            trace_id: TraceId(0x1234),
+            span_id: SpanId(0x56),
+            parent_id: SpanId(0x78),
+            begin_time_unix_ns: 1_000_000,
+            duration_ns: 2_000,
+            name: "span".into(),
+            properties: vec![("k".into(), "v".into())],
+            events: vec![EventRecord {
+                name: "event".into(),
+                timestamp_unix_ns: 1_500_000,
+                level: EventLevel::Info,
+                properties: vec![],
+            }],
+            ..SpanRecord::default()
+        }];
+
+        let bytes = reporter.serialize(reporter.convert(&spans)).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{
+                "traceId": "00000000000000000000000000001234",
+                "id": "0000000000000056",
+                "parentId": "0000000000000078",
+                "name": "span",
+                "timestamp": 1000,
+                "duration": 2,
+                "localEndpoint": { "serviceName": "test-service" },
+                "tags": { "k": "v" },
+                "annotations": [{ "timestamp": 1500, "value": "event" }],
+            }])
+        );
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use minitrace::collector::Reporter;
+use minitrace::prelude::*;
+use minitrace_zipkin::ZipkinReporter;
+
+#[test]
+fn zipkin_reporter() {
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("POST", "/api/v2/spans")
+        .match_header("content-type", "application/json")
+        .match_body(mockito::Matcher::Json(serde_json::json!([
+            {
+                "traceId": "00000000000000000000000000000001",
+                "id": "0000000000000001",
+                "name": "root",
+                "timestamp": 0,
+                "duration": 0,
+                "localEndpoint": { "serviceName": "test-service" },
+            },
+            {
+                "traceId": "00000000000000000000000000000001",
+                "id": "0000000000000002",
+                "parentId": "0000000000000001",
+                "name": "child",
+                "timestamp": 0,
+                "duration": 0,
+                "localEndpoint": { "serviceName": "test-service" },
+                "tags": { "k": "v" },
+            },
+        ])))
+        .with_status(202)
+        .create();
+
+    let mut reporter =
+        ZipkinReporter::new(format!("{}/api/v2/spans", server.url()), "test-service");
+    reporter.report(&[
+        SpanRecord {
+            trace_id: TraceId(1),
+            span_id: SpanId(1),
+            name: "root".into(),
+            ..SpanRecord::default()
+        },
+        SpanRecord {
+            trace_id: TraceId(1),
+            span_id: SpanId(2),
+            parent_id: SpanId(1),
+            name: "child".into(),
+            properties: vec![("k".into(), "v".into())],
+            ..SpanRecord::default()
+        },
+    ]);
+
+    mock.assert();
+}
@@ -0,0 +1,165 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::time::Duration;
+use std::time::Instant;
+
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use criterion::Throughput;
+
+const OPS: u64 = 1_000_000;
+const CAPACITY: usize = 10240;
+
+fn spsc_throughput_single_thread(c: &mut Criterion) {
+    let mut bgroup = c.benchmark_group("spsc throughput single thread");
+    bgroup.throughput(Throughput::Elements(OPS));
+
+    bgroup.bench_function("minitrace", |b| {
+        b.iter_custom(|iters| {
+            let mut total_time = Duration::default();
+            for _ in 0..iters {
+                let (mut tx, mut rx) = minitrace::util::spsc::bounded(CAPACITY);
+
+                let start = Instant::now();
+
+                for i in 0..OPS {
+                    tx.send(i).unwrap();
+                    rx.try_recv().unwrap().unwrap();
+                }
+
+                total_time += start.elapsed();
+            }
+            total_time
+        })
+    });
+    bgroup.bench_function("crossbeam", |b| {
+        b.iter_custom(|iters| {
+            let mut total_time = Duration::default();
+            for _ in 0..iters {
+                let (tx, rx) = crossbeam::channel::bounded(CAPACITY);
+
+                let start = Instant::now();
+
+                for i in 0..OPS {
+                    tx.try_send(i).unwrap();
+                    rx.try_recv().unwrap();
+                }
+
+                total_time += start.elapsed();
+            }
+            total_time
+        })
+    });
+    bgroup.bench_function("std-mpsc", |b| {
+        b.iter_custom(|iters| {
+            let mut total_time = Duration::default();
+            for _ in 0..iters {
+                let (tx, rx) = std::sync::mpsc::channel();
+
+                let start = Instant::now();
+
+                for i in 0..OPS {
+                    tx.send(i).unwrap();
+                    rx.try_recv().unwrap();
+                }
+
+                total_time += start.elapsed();
+            }
+            total_time
+        })
+    });
+
+    bgroup.finish();
+}
+
+fn spsc_throughput_multi_thread(c: &mut Criterion) {
+    let mut bgroup = c.benchmark_group("spsc throughput multi thread");
+    bgroup.throughput(Throughput::Elements(OPS));
+
+    bgroup.bench_function("minitrace", |b| {
+        b.iter_custom(|iters| {
+            let mut total_time = Duration::default();
+            for _ in 0..iters {
+                let (mut tx, mut rx) = minitrace::util::spsc::bounded(CAPACITY);
+
+                let start = Instant::now();
+
+                let handle = std::thread::spawn(move || {
+                    for i in 0..OPS {
+                        while tx.send(i).is_err() {}
+                    }
+                });
+
+                for _ in 0..OPS {
+                    loop {
+                        if let Ok(Some(_)) = rx.try_recv() {
+                            break;
+                        }
+                    }
+                }
+
+                total_time += start.elapsed();
+                handle.join().unwrap();
+            }
+            total_time
+        })
+    });
+    bgroup.bench_function("crossbeam", |b| {
+        b.iter_custom(|iters| {
+            let mut total_time = Duration::default();
+            for _ in 0..iters {
+                let (tx, rx) = crossbeam::channel::bounded(CAPACITY);
+
+                let start = Instant::now();
+
+                let handle = std::thread::spawn(move || {
+                    for i in 0..OPS {
+                        while tx.try_send(i).is_err() {}
+                    }
+                });
+
+                for _ in 0..OPS {
+                    while rx.try_recv().is_err() {}
+                }
+
+                total_time += start.elapsed();
+                handle.join().unwrap();
+            }
+            total_time
+        })
+    });
+    bgroup.bench_function("std-mpsc", |b| {
+        b.iter_custom(|iters| {
+            let mut total_time = Duration::default();
+            for _ in 0..iters {
+                let (tx, rx) = std::sync::mpsc::channel();
+
+                let start = Instant::now();
+
+                let handle = std::thread::spawn(move || {
+                    for i in 0..OPS {
+                        tx.send(i).unwrap();
+                    }
+                });
+
+                for _ in 0..OPS {
+                    while rx.try_recv().is_err() {}
+                }
+
+                total_time += start.elapsed();
+                handle.join().unwrap();
+            }
+            total_time
+        })
+    });
+
+    bgroup.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = spsc_throughput_single_thread, spsc_throughput_multi_thread
+);
+criterion_main!(benches);
@@ -128,12 +128,102 @@ fn bench_trace_future(c: &mut Criterion) {
     minitrace::flush()
 }
 
+fn bench_add_events(c: &mut Criterion) {
+    init_minitrace();
+
+    let mut group = c.benchmark_group("add_events");
+
+    group.bench_function("add_to_local_parent", |b| {
+        b.iter(|| {
+            let root = Span::root("root", SpanContext::new(TraceId(12), SpanId::default()));
+            let _sg = root.set_local_parent();
+            for _ in 0..100 {
+                Event::add_to_local_parent(black_box("event"), || []);
+            }
+        })
+    });
+
+    group.bench_function("add_batch_to_local_parent", |b| {
+        b.iter(|| {
+            let root = Span::root("root", SpanContext::new(TraceId(12), SpanId::default()));
+            let _sg = root.set_local_parent();
+            Event::add_batch_to_local_parent((0..100).map(|i| (black_box("event"), i, [])));
+        })
+    });
+
+    group.finish();
+    minitrace::flush()
+}
+
+fn bench_span_name(c: &mut Criterion) {
+    init_minitrace();
+
+    let mut group = c.benchmark_group("span_name");
+
+    group.bench_function("root_static", |b| {
+        b.iter(|| Span::root_static(black_box("root"), SpanContext::random()))
+    });
+
+    group.bench_function("root_string", |b| {
+        b.iter(|| Span::root(black_box("root").to_string(), SpanContext::random()))
+    });
+
+    group.bench_function("local_span_static", |b| {
+        let root = Span::root("root", SpanContext::random());
+        let _sg = root.set_local_parent();
+        b.iter(|| LocalSpan::enter_with_local_parent(black_box("span")))
+    });
+
+    group.bench_function("local_span_string", |b| {
+        let root = Span::root("root", SpanContext::random());
+        let _sg = root.set_local_parent();
+        b.iter(|| LocalSpan::enter_with_local_parent(black_box("span").to_string()))
+    });
+
+    group.finish();
+    minitrace::flush()
+}
+
+fn bench_attach_local_spans(c: &mut Criterion) {
+    init_minitrace();
+
+    let mut group = c.benchmark_group("attach_local_spans");
+
+    for len in &[1, 10, 100, 1000] {
+        group.bench_function(format!("push_child_spans/{len}"), |b| {
+            b.iter(|| {
+                let local_collector = LocalCollector::start();
+                dummy_iter(*len);
+                let local_spans = local_collector.collect();
+                let root = Span::root("root", SpanContext::new(TraceId(12), SpanId::default()));
+                root.push_child_spans(local_spans);
+            })
+        });
+
+        group.bench_function(format!("adopt_local_spans/{len}"), |b| {
+            b.iter(|| {
+                let local_collector = LocalCollector::start();
+                dummy_iter(*len);
+                let local_spans = local_collector.collect();
+                Span::root("root", SpanContext::new(TraceId(12), SpanId::default()))
+                    .adopt_local_spans(local_spans)
+            })
+        });
+    }
+
+    group.finish();
+    minitrace::flush()
+}
+
 criterion_group!(
     benches,
     bench_trace_wide_raw,
     bench_trace_wide,
     bench_trace_deep_raw,
     bench_trace_deep,
-    bench_trace_future
+    bench_trace_future,
+    bench_add_events,
+    bench_span_name,
+    bench_attach_local_spans
 );
 criterion_main!(benches);
@@ -0,0 +1,9 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+fn main() {
+    // `MINITRACE_MAX_SPANS` hard-codes the default `Config::max_spans_per_trace` at compile time.
+    println!("cargo:rerun-if-env-changed=MINITRACE_MAX_SPANS");
+    if let Ok(max_spans) = std::env::var("MINITRACE_MAX_SPANS") {
+        println!("cargo:rustc-env=MINITRACE_MAX_SPANS={max_spans}");
+    }
+}
@@ -42,7 +42,7 @@ async fn main() {
 
     {
         let parent = SpanContext::random();
-        let span = Span::root("root", parent);
+        let span = Span::root("root", parent).with_kind(SpanKind::Server);
 
         let f = async {
             let jhs = {
@@ -95,7 +95,6 @@ impl ReportAll {
                     ))
                     .build_span_exporter()
                     .expect("initialize oltp exporter"),
-                opentelemetry::trace::SpanKind::Server,
                 Cow::Owned(opentelemetry_sdk::Resource::new([
                     opentelemetry::KeyValue::new("service.name", "asynchronous(opentelemetry)"),
                 ])),
@@ -113,4 +112,16 @@ impl Reporter for ReportAll {
         self.datadog.report(spans);
         self.opentelemetry.report(spans);
     }
+
+    fn start(&mut self) {
+        self.jaeger.start();
+        self.datadog.start();
+        self.opentelemetry.start();
+    }
+
+    fn stop(&mut self) {
+        self.jaeger.stop();
+        self.datadog.stop();
+        self.opentelemetry.stop();
+    }
 }
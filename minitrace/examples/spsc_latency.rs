@@ -0,0 +1,56 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Measures the tail latency of a single send-and-receive round trip through
+//! `minitrace::util::spsc`, `crossbeam::channel::bounded` and `std::sync::mpsc::channel`.
+//!
+//! Criterion only reports the mean and the median of the samples, so the round trips are timed by
+//! hand. Run it in release mode:
+//!
+//! ```shell
+//! cargo run --release --example spsc_latency
+//! ```
+
+use std::time::Duration;
+use std::time::Instant;
+
+const OPS: u64 = 1_000_000;
+const CAPACITY: usize = 10240;
+
+fn measure(name: &str, mut round_trip: impl FnMut(u64)) {
+    let mut samples = Vec::with_capacity(OPS as usize);
+    for i in 0..OPS {
+        let start = Instant::now();
+        round_trip(i);
+        samples.push(start.elapsed());
+    }
+    samples.sort_unstable();
+
+    let mean = samples.iter().sum::<Duration>() / samples.len() as u32;
+    let p95 = samples[samples.len() * 95 / 100];
+    let p99 = samples[samples.len() * 99 / 100];
+    println!("spsc latency/{name:<10} mean: {mean:>10?} p95: {p95:>10?} p99: {p99:>10?}");
+}
+
+fn main() {
+    {
+        let (mut tx, mut rx) = minitrace::util::spsc::bounded(CAPACITY);
+        measure("minitrace", |i| {
+            tx.send(i).unwrap();
+            rx.try_recv().unwrap().unwrap();
+        });
+    }
+    {
+        let (tx, rx) = crossbeam::channel::bounded(CAPACITY);
+        measure("crossbeam", |i| {
+            tx.try_send(i).unwrap();
+            rx.try_recv().unwrap();
+        });
+    }
+    {
+        let (tx, rx) = std::sync::mpsc::channel();
+        measure("std-mpsc", |i| {
+            tx.send(i).unwrap();
+            rx.try_recv().unwrap();
+        });
+    }
+}
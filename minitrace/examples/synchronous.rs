@@ -27,7 +27,7 @@ async fn main() {
 
     {
         let parent = SpanContext::random();
-        let root = Span::root("root", parent);
+        let root = Span::root("root", parent).with_kind(SpanKind::Server);
 
         let _g = root.set_local_parent();
         let _span = LocalSpan::enter_with_local_parent("a span")
@@ -71,7 +71,6 @@ impl ReportAll {
                     ))
                     .build_span_exporter()
                     .expect("initialize oltp exporter"),
-                opentelemetry::trace::SpanKind::Server,
                 Cow::Owned(opentelemetry_sdk::Resource::new([
                     opentelemetry::KeyValue::new("service.name", "synchronous(opentelemetry)"),
                 ])),
@@ -89,4 +88,16 @@ impl Reporter for ReportAll {
         self.datadog.report(spans);
         self.opentelemetry.report(spans);
     }
+
+    fn start(&mut self) {
+        self.jaeger.start();
+        self.datadog.start();
+        self.opentelemetry.start();
+    }
+
+    fn stop(&mut self) {
+        self.jaeger.stop();
+        self.datadog.stop();
+        self.opentelemetry.stop();
+    }
 }
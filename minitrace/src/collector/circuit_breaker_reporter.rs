@@ -0,0 +1,180 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::time::Duration;
+use std::time::Instant;
+
+use super::global_collector::Reporter;
+use super::FallibleReporter;
+use super::SpanRecord;
+
+/// The state of a [`CircuitBreakerReporter`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CircuitState {
+    /// The spans are reported to the inner reporter.
+    Closed,
+    /// The inner reporter has failed too many times in a row, so the spans are dropped without
+    /// calling it until `reset_after` has elapsed since the last failure.
+    Open,
+    /// `reset_after` has elapsed since the circuit opened, so the next batch is reported to the
+    /// inner reporter to probe whether it has recovered.
+    HalfOpen,
+}
+
+/// A reporter that stops calling the inner reporter for a while after it fails `threshold` times
+/// in a row, so that an unavailable backend is not hammered every report interval.
+///
+/// Once the circuit is open, the spans are dropped for `reset_after`. The next batch is then
+/// reported to the inner reporter, which closes the circuit if it succeeds, or reopens it
+/// otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use std::error::Error;
+/// use std::time::Duration;
+///
+/// use minitrace::collector::CircuitBreakerReporter;
+/// use minitrace::collector::Config;
+/// use minitrace::collector::FallibleReporter;
+/// use minitrace::collector::SpanRecord;
+///
+/// struct Backend;
+///
+/// impl FallibleReporter for Backend {
+///     fn try_report(&mut self, _spans: &[SpanRecord]) -> Result<(), Box<dyn Error>> {
+///         Err("connection refused".into())
+///     }
+/// }
+///
+/// minitrace::set_reporter(
+///     CircuitBreakerReporter::new(Backend, 3, Duration::from_secs(30)),
+///     Config::default(),
+/// );
+/// ```
+pub struct CircuitBreakerReporter<R> {
+    inner: R,
+    failure_count: u32,
+    threshold: u32,
+    reset_after: Duration,
+    state: CircuitState,
+    last_failure: Instant,
+}
+
+impl<R: FallibleReporter> CircuitBreakerReporter<R> {
+    /// Creates a reporter opening the circuit after `threshold` consecutive failures, at least 1,
+    /// for `reset_after`.
+    pub fn new(inner: R, threshold: u32, reset_after: Duration) -> Self {
+        CircuitBreakerReporter {
+            inner,
+            failure_count: 0,
+            threshold: threshold.max(1),
+            reset_after,
+            state: CircuitState::Closed,
+            last_failure: Instant::now(),
+        }
+    }
+
+    /// Returns the current state of the circuit.
+    pub fn state(&self) -> CircuitState {
+        self.state
+    }
+}
+
+impl<R: FallibleReporter> Reporter for CircuitBreakerReporter<R> {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        let now = Instant::now();
+        if self.state == CircuitState::Open {
+            if now.duration_since(self.last_failure) < self.reset_after {
+                return;
+            }
+            self.state = CircuitState::HalfOpen;
+        }
+
+        match self.inner.try_report(spans) {
+            Ok(()) => {
+                self.failure_count = 0;
+                self.state = CircuitState::Closed;
+            }
+            Err(_) => {
+                self.failure_count = self.failure_count.saturating_add(1);
+                self.last_failure = now;
+                if self.state == CircuitState::HalfOpen || self.failure_count >= self.threshold {
+                    self.state = CircuitState::Open;
+                }
+            }
+        }
+    }
+
+    fn start(&mut self) {
+        self.inner.start();
+    }
+
+    fn stop(&mut self) {
+        self.inner.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use super::*;
+
+    /// A reporter failing the first `failures` calls.
+    struct FlakyReporter {
+        failures: usize,
+        calls: usize,
+        reported: usize,
+    }
+
+    impl FallibleReporter for FlakyReporter {
+        fn try_report(&mut self, spans: &[SpanRecord]) -> Result<(), Box<dyn Error>> {
+            self.calls += 1;
+            if self.calls <= self.failures {
+                return Err("unreachable".into());
+            }
+            self.reported += spans.len();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn circuit_breaker_reporter() {
+        let inner = FlakyReporter {
+            failures: 3,
+            calls: 0,
+            reported: 0,
+        };
+        let reset_after = Duration::from_secs(30);
+        let mut reporter = CircuitBreakerReporter::new(inner, 2, reset_after);
+        let spans = vec![SpanRecord::default(); 2];
+
+        reporter.report(&spans);
+        assert_eq!(reporter.state(), CircuitState::Closed);
+        reporter.report(&spans);
+        assert_eq!(reporter.state(), CircuitState::Open);
+        assert_eq!(reporter.inner.calls, 2);
+
+        // The spans are dropped while the circuit is open.
+        reporter.report(&spans);
+        assert_eq!(reporter.state(), CircuitState::Open);
+        assert_eq!(reporter.inner.calls, 2);
+
+        // The probe fails and reopens the circuit.
+        reporter.last_failure -= reset_after;
+        reporter.report(&spans);
+        assert_eq!(reporter.state(), CircuitState::Open);
+        assert_eq!(reporter.inner.calls, 3);
+        reporter.report(&spans);
+        assert_eq!(reporter.inner.calls, 3);
+
+        // The probe succeeds and closes the circuit.
+        reporter.last_failure -= reset_after;
+        reporter.report(&spans);
+        assert_eq!(reporter.state(), CircuitState::Closed);
+        assert_eq!(reporter.inner.calls, 4);
+        reporter.report(&spans);
+        assert_eq!(reporter.inner.calls, 5);
+        assert_eq!(reporter.inner.reported, 4);
+    }
+}
@@ -1,19 +1,28 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::sync::Arc;
+
+use crate::collector::Resource;
 use crate::collector::SpanSet;
+use crate::util::oneshot;
 use crate::util::CollectToken;
 
+// `SubmitSpans` is by far the most frequent command, so boxing it to shrink the others would only
+// add an allocation per submission.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum CollectCommand {
     StartCollect(StartCollect),
     DropCollect(DropCollect),
     CommitCollect(CommitCollect),
     SubmitSpans(SubmitSpans),
+    Flush(oneshot::Sender<()>),
 }
 
 #[derive(Debug)]
 pub struct StartCollect {
     pub collect_id: usize,
+    pub resource: Option<Arc<Resource>>,
 }
 
 #[derive(Debug)]
@@ -1,7 +1,13 @@
 // Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::io::IsTerminal;
+use std::time::Duration;
+
 use super::global_collector::Reporter;
 use super::SpanRecord;
+use crate::util::tree::tree_str_with_timing_by;
+
+const DEFAULT_SLOW_THRESHOLD: Duration = Duration::from_millis(100);
 
 /// A console reporter that prints span records to the stderr.
 pub struct ConsoleReporter;
@@ -13,3 +19,142 @@ impl Reporter for ConsoleReporter {
         }
     }
 }
+
+/// A console reporter that prints each batch of span records to the stderr as trees annotated
+/// with the duration of the spans, see [`tree_str_with_timing()`].
+///
+/// If color is enabled, the spans lasting at least the [slow threshold] are printed in red, and
+/// the others in green.
+///
+/// [`tree_str_with_timing()`]: crate::util::tree::tree_str_with_timing
+/// [slow threshold]: PrettyConsoleReporter::slow_threshold
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use minitrace::collector::Config;
+/// use minitrace::collector::PrettyConsoleReporter;
+///
+/// minitrace::set_reporter(
+///     PrettyConsoleReporter::with_color().slow_threshold(Duration::from_millis(10)),
+///     Config::default(),
+/// );
+/// ```
+pub struct PrettyConsoleReporter {
+    color: bool,
+    min_duration_ns: u64,
+}
+
+impl PrettyConsoleReporter {
+    /// Creates a reporter printing without color.
+    pub fn new() -> Self {
+        PrettyConsoleReporter {
+            color: false,
+            min_duration_ns: DEFAULT_SLOW_THRESHOLD.as_nanos() as u64,
+        }
+    }
+
+    /// Creates a reporter printing with color if the stderr is a terminal.
+    pub fn with_color() -> Self {
+        PrettyConsoleReporter {
+            color: std::io::stderr().is_terminal(),
+            ..Self::new()
+        }
+    }
+
+    /// Sets the minimum duration of the spans printed in red. Defaults to 100 milliseconds.
+    pub fn slow_threshold(self, threshold: Duration) -> Self {
+        PrettyConsoleReporter {
+            min_duration_ns: threshold.as_nanos().try_into().unwrap_or(u64::MAX),
+            ..self
+        }
+    }
+
+    fn render(&self, spans: &[SpanRecord]) -> String {
+        if !self.color {
+            return tree_str_with_timing_by(spans, &|_, line| line);
+        }
+
+        tree_str_with_timing_by(spans, &|span, line| {
+            let color = if span.duration_ns >= self.min_duration_ns {
+                "31"
+            } else {
+                "32"
+            };
+            format!("\x1b[{color}m{line}\x1b[0m")
+        })
+    }
+}
+
+impl Default for PrettyConsoleReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reporter for PrettyConsoleReporter {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        if spans.is_empty() {
+            return;
+        }
+
+        eprintln!("{}", self.render(spans));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::SpanId;
+    use crate::collector::TraceId;
+
+    fn span_record(
+        span_id: u64,
+        parent_id: u64,
+        name: &'static str,
+        duration_ms: u64,
+    ) -> SpanRecord {
+        SpanRecord {
+            trace_id: TraceId(1),
+            span_id: SpanId(span_id),
+            parent_id: SpanId(parent_id),
+            begin_time_unix_ns: 1_000_000_000 + span_id * 1_000_000,
+            duration_ns: duration_ms * 1_000_000,
+            name: name.into(),
+            ..SpanRecord::default()
+        }
+    }
+
+    #[test]
+    fn pretty_console_reporter() {
+        let spans = [
+            span_record(2, 1, "child", 20),
+            span_record(1, 0, "root", 200),
+        ];
+
+        assert_eq!(
+            PrettyConsoleReporter::new().render(&spans),
+            r#"
+root [200ms] 100.0%
+└── child [20ms] 10.0%
+"#
+        );
+
+        let reporter = PrettyConsoleReporter {
+            color: true,
+            ..PrettyConsoleReporter::new()
+        };
+        assert_eq!(
+            reporter.render(&spans),
+            "\n\x1b[31mroot [200ms] 100.0%\x1b[0m\n\x1b[32m└── child [20ms] 10.0%\x1b[0m\n"
+        );
+
+        let reporter = reporter.slow_threshold(Duration::from_millis(10));
+        assert_eq!(
+            reporter.render(&spans),
+            "\n\x1b[31mroot [200ms] 100.0%\x1b[0m\n\x1b[31m└── child [20ms] 10.0%\x1b[0m\n"
+        );
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::error::Error;
+
+use super::global_collector::Reporter;
+use super::SpanRecord;
+
+/// A reporter that tells whether a batch of spans has been reported successfully.
+///
+/// It's used by [`FallbackReporter`] to decide when to fall back to another reporter.
+pub trait FallibleReporter: Send + 'static {
+    /// Reports a batch of spans, returning an error if they could not be reported.
+    fn try_report(&mut self, spans: &[SpanRecord]) -> Result<(), Box<dyn Error>>;
+
+    /// See [`Reporter::start()`].
+    fn start(&mut self) {}
+
+    /// See [`Reporter::stop()`].
+    fn stop(&mut self) {}
+}
+
+/// A reporter that reports the spans to the primary reporter, and to the fallback reporter if the
+/// primary reporter fails.
+///
+/// # Examples
+///
+/// ```
+/// use std::error::Error;
+///
+/// use minitrace::collector::Config;
+/// use minitrace::collector::ConsoleReporter;
+/// use minitrace::collector::FallbackReporter;
+/// use minitrace::collector::FallibleReporter;
+/// use minitrace::collector::SpanRecord;
+///
+/// struct UnreachableBackend;
+///
+/// impl FallibleReporter for UnreachableBackend {
+///     fn try_report(&mut self, _spans: &[SpanRecord]) -> Result<(), Box<dyn Error>> {
+///         Err("connection refused".into())
+///     }
+/// }
+///
+/// minitrace::set_reporter(
+///     FallbackReporter::new(UnreachableBackend, ConsoleReporter),
+///     Config::default(),
+/// );
+/// ```
+pub struct FallbackReporter<P, F> {
+    primary: P,
+    fallback: F,
+}
+
+impl<P: FallibleReporter, F: Reporter> FallbackReporter<P, F> {
+    pub fn new(primary: P, fallback: F) -> Self {
+        FallbackReporter { primary, fallback }
+    }
+}
+
+impl<P: FallibleReporter, F: Reporter> Reporter for FallbackReporter<P, F> {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        if self.primary.try_report(spans).is_err() {
+            self.fallback.report(spans);
+        }
+    }
+
+    fn start(&mut self) {
+        self.primary.start();
+        self.fallback.start();
+    }
+
+    fn stop(&mut self) {
+        self.primary.stop();
+        self.fallback.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::TestReporter;
+
+    struct FlakyReporter {
+        fail: bool,
+        reported: usize,
+    }
+
+    impl FallibleReporter for FlakyReporter {
+        fn try_report(&mut self, spans: &[SpanRecord]) -> Result<(), Box<dyn Error>> {
+            if self.fail {
+                return Err("unreachable".into());
+            }
+            self.reported += spans.len();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn fallback_reporter() {
+        let primary = FlakyReporter {
+            fail: false,
+            reported: 0,
+        };
+        let (fallback, collected_spans) = TestReporter::new();
+        let mut reporter = FallbackReporter::new(primary, fallback);
+
+        let spans = vec![SpanRecord::default(); 2];
+        reporter.report(&spans);
+        assert_eq!(reporter.primary.reported, 2);
+        assert!(collected_spans.lock().is_empty());
+
+        reporter.primary.fail = true;
+        reporter.report(&spans);
+        assert_eq!(reporter.primary.reported, 2);
+        assert_eq!(collected_spans.lock().len(), 2);
+    }
+}
@@ -0,0 +1,170 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::error::Error;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use super::fallback_reporter::FallibleReporter;
+use super::global_collector::Reporter;
+use super::SpanRecord;
+
+/// A reporter that appends the spans to a file as newline-delimited JSON, one
+/// [`SpanRecord::to_json()`] per line.
+///
+/// It's typically used as the fallback of a [`FallbackReporter`] to keep the spans on disk when
+/// the tracing backend is unreachable. The spans can be read back with
+/// [`SpanRecord::from_json()`].
+///
+/// [`FallbackReporter`]: crate::collector::FallbackReporter
+///
+/// # Examples
+///
+/// ```no_run
+/// use minitrace::collector::Config;
+/// use minitrace::collector::FileReporter;
+///
+/// let reporter = FileReporter::new("/var/log/spans.jsonl").unwrap();
+/// minitrace::set_reporter(reporter, Config::default());
+/// ```
+pub struct FileReporter {
+    path: PathBuf,
+    file: BufWriter<File>,
+}
+
+impl FileReporter {
+    /// Opens the file at `path` in append mode, creating it if it does not exist.
+    pub fn new(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let file = open(&path)?;
+        Ok(FileReporter { path, file })
+    }
+
+    /// Returns the path of the file the spans are written to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Switches to the file at `new_path`, e.g. after the current file has been moved away.
+    ///
+    /// The new file is opened before the current one is flushed and closed, so the reporter keeps
+    /// writing to the current file if an error is returned.
+    pub fn rotate(&mut self, new_path: PathBuf) -> io::Result<()> {
+        let file = open(&new_path)?;
+        self.file.flush()?;
+        self.file = file;
+        self.path = new_path;
+        Ok(())
+    }
+}
+
+fn open(path: &Path) -> io::Result<BufWriter<File>> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    Ok(BufWriter::new(file))
+}
+
+impl FallibleReporter for FileReporter {
+    fn try_report(&mut self, spans: &[SpanRecord]) -> Result<(), Box<dyn Error>> {
+        for span in spans {
+            self.file.write_all(span.to_json().as_bytes())?;
+            self.file.write_all(b"\n")?;
+        }
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        self.file.flush().ok();
+    }
+}
+
+impl Reporter for FileReporter {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        if let Err(err) = self.try_report(spans) {
+            eprintln!("failed to report spans to {}: {}", self.path.display(), err);
+        }
+    }
+
+    fn stop(&mut self) {
+        FallibleReporter::stop(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::EventLevel;
+    use crate::collector::EventRecord;
+    use crate::collector::SpanId;
+    use crate::collector::SpanStatus;
+    use crate::collector::TraceId;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "minitrace-{}-{}-{}.jsonl",
+            name,
+            std::process::id(),
+            rand::random::<u32>()
+        ))
+    }
+
+    fn read_spans(path: &Path) -> Vec<SpanRecord> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| SpanRecord::from_json(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn file_reporter() {
+        let path = temp_path("file-reporter");
+        let spans = vec![
+            SpanRecord {
+                trace_id: TraceId(1),
+                span_id: SpanId(2),
+                name: "root".into(),
+                properties: vec![("k".into(), "multi\nline".into())],
+                events: vec![EventRecord {
+                    name: "event".into(),
+                    timestamp_unix_ns: 3,
+                    level: EventLevel::Warn,
+                    properties: vec![],
+                }],
+                status: SpanStatus::Error("failed".into()),
+                ..SpanRecord::default()
+            },
+            SpanRecord {
+                trace_id: TraceId(1),
+                span_id: SpanId(4),
+                parent_id: SpanId(2),
+                name: "child".into(),
+                ..SpanRecord::default()
+            },
+        ];
+
+        let mut reporter = FileReporter::new(&path).unwrap();
+        Reporter::report(&mut reporter, &spans[..1]);
+        assert_eq!(read_spans(&path), &spans[..1]);
+
+        // The file is appended to, even after being reopened.
+        drop(reporter);
+        let mut reporter = FileReporter::new(&path).unwrap();
+        Reporter::report(&mut reporter, &spans[1..]);
+        assert_eq!(read_spans(&path), spans);
+
+        let new_path = temp_path("file-reporter-rotated");
+        reporter.rotate(new_path.clone()).unwrap();
+        assert_eq!(reporter.path(), new_path);
+        Reporter::report(&mut reporter, &spans[..1]);
+        assert_eq!(read_spans(&path), spans);
+        assert_eq!(read_spans(&new_path), &spans[..1]);
+
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(new_path).unwrap();
+    }
+}
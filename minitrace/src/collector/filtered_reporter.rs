@@ -0,0 +1,59 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use super::global_collector::Reporter;
+use super::SpanRecord;
+
+/// A reporter that only forwards the spans of a specific target to the inner reporter.
+///
+/// Created by [`report_only_target()`].
+pub struct FilteredReporter<R> {
+    inner: R,
+    prefix: String,
+    spans: Vec<SpanRecord>,
+}
+
+/// Wraps a reporter so that it only receives the spans whose name is prefixed with `target::`,
+/// such as the spans created by `#[trace(target = "...")]`.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::report_only_target;
+/// use minitrace::collector::Config;
+/// use minitrace::collector::ConsoleReporter;
+///
+/// minitrace::set_reporter(
+///     report_only_target(ConsoleReporter, "my_module"),
+///     Config::default(),
+/// );
+/// ```
+pub fn report_only_target<R: Reporter>(reporter: R, target: &'static str) -> FilteredReporter<R> {
+    FilteredReporter {
+        inner: reporter,
+        prefix: format!("{target}::"),
+        spans: Vec::new(),
+    }
+}
+
+impl<R: Reporter> Reporter for FilteredReporter<R> {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        self.spans.extend(
+            spans
+                .iter()
+                .filter(|span| span.name.starts_with(&self.prefix))
+                .cloned(),
+        );
+        if !self.spans.is_empty() {
+            self.inner.report(&self.spans);
+            self.spans.clear();
+        }
+    }
+
+    fn start(&mut self) {
+        self.inner.start();
+    }
+
+    fn stop(&mut self) {
+        self.inner.stop();
+    }
+}
@@ -1,11 +1,20 @@
 // Copyright 2021 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::borrow::Cow;
 use std::cell::UnsafeCell;
 use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::future::Future;
 use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicU8;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+#[cfg(not(target_family = "wasm"))]
+use std::sync::OnceLock;
+#[cfg(not(target_family = "wasm"))]
+use std::thread::Thread;
 
 use minstant::Anchor;
 use minstant::Instant;
@@ -17,7 +26,10 @@ use crate::collector::command::DropCollect;
 use crate::collector::command::StartCollect;
 use crate::collector::command::SubmitSpans;
 use crate::collector::Config;
+use crate::collector::EventLevel;
 use crate::collector::EventRecord;
+use crate::collector::EvictionPolicy;
+use crate::collector::Resource;
 use crate::collector::SpanContext;
 use crate::collector::SpanId;
 use crate::collector::SpanRecord;
@@ -26,6 +38,7 @@ use crate::collector::TraceId;
 use crate::local::local_collector::LocalSpansInner;
 use crate::local::raw_span::RawSpan;
 use crate::util::object_pool;
+use crate::util::oneshot;
 use crate::util::spsc::Receiver;
 use crate::util::spsc::Sender;
 use crate::util::spsc::{self};
@@ -35,10 +48,27 @@ static NEXT_COLLECT_ID: AtomicUsize = AtomicUsize::new(0);
 static GLOBAL_COLLECTOR: Mutex<Option<GlobalCollector>> = Mutex::new(None);
 static SPSC_RXS: Mutex<Vec<Receiver<CollectCommand>>> = Mutex::new(Vec::new());
 static REPORTER_READY: AtomicBool = AtomicBool::new(false);
+#[cfg(not(target_family = "wasm"))]
+static COLLECTOR_THREAD: OnceLock<Thread> = OnceLock::new();
+static EVICTION_POLICY: AtomicU8 = AtomicU8::new(EvictionPolicy::DropNewest as u8);
+// `usize::MAX` stands for no limit.
+static MAX_SPANS_PER_THREAD: AtomicUsize = AtomicUsize::new(usize::MAX);
+static REPORTER_FACTORY: Mutex<Option<ReporterFactory>> = Mutex::new(None);
+static REPORTER_FACTORY_PENDING: AtomicBool = AtomicBool::new(false);
+static RECEIVED_COMMANDS: AtomicU64 = AtomicU64::new(0);
+static DROPPED_OVERFLOW_COMMANDS: AtomicU64 = AtomicU64::new(0);
+static FLUSHED_OVERFLOW_COMMANDS: AtomicU64 = AtomicU64::new(0);
+static DEFAULT_RESOURCE: Mutex<Option<Arc<Resource>>> = Mutex::new(None);
+
+const COMMAND_CHANNEL_CAPACITY: usize = 10240;
+// The maximum number of commands kept by a thread when its command channel is full.
+const COMMAND_OVERFLOW_CAPACITY: usize = 1024;
+
+type ReporterFactory = Box<dyn FnOnce() -> (Box<dyn Reporter>, Config) + Send>;
 
 thread_local! {
     static COMMAND_SENDER: UnsafeCell<Sender<CollectCommand>> = {
-        let (tx, rx) = spsc::bounded(10240);
+        let (tx, rx) = spsc::bounded(COMMAND_CHANNEL_CAPACITY);
         register_receiver(rx);
         UnsafeCell::new(tx)
     };
@@ -50,16 +80,82 @@ fn register_receiver(rx: Receiver<CollectCommand>) {
 
 fn send_command(cmd: CollectCommand) {
     COMMAND_SENDER
-        .try_with(|sender| unsafe { (*sender.get()).send(cmd).ok() })
+        .try_with(|sender| {
+            let outcome = unsafe { (*sender.get()).send_or_buffer(cmd, COMMAND_OVERFLOW_CAPACITY) };
+            if outcome.flushed > 0 {
+                FLUSHED_OVERFLOW_COMMANDS.fetch_add(outcome.flushed as u64, Ordering::Relaxed);
+            }
+            if outcome.dropped {
+                DROPPED_OVERFLOW_COMMANDS.fetch_add(1, Ordering::Relaxed);
+            }
+        })
         .ok();
 }
 
 fn force_send_command(cmd: CollectCommand) {
     COMMAND_SENDER
-        .try_with(|sender| unsafe { (*sender.get()).force_send(cmd) })
+        .try_with(|sender| {
+            unsafe { (*sender.get()).force_send(cmd) };
+        })
         .ok();
 }
 
+/// Statistics of the commands sent by the threads to the global collector, returned by
+/// [`send_command_stats()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SpscStats {
+    /// The number of commands received by the global collector, either directly or through the
+    /// overflow buffer. The commands still waiting in the channels are not counted yet.
+    pub sent: u64,
+    /// The number of commands dropped because both the command channel and the overflow buffer
+    /// of the thread were full.
+    pub dropped_overflow: u64,
+    /// The number of commands that were kept in the overflow buffer and sent later.
+    pub flushed_overflow: u64,
+}
+
+/// Returns the statistics of the commands sent by all threads to the global collector.
+///
+/// Each thread sends the commands, such as the spans to submit, through a bounded channel of
+/// 10240 commands that the global collector drains every `report_interval`. When a burst of spans
+/// fills the channel, up to 1024 more commands are kept in an overflow buffer of the thread and
+/// sent before its next command, trading the memory of the buffer and the delay of the buffered
+/// commands for not losing them. The commands beyond the overflow buffer are dropped and counted
+/// in `dropped_overflow`.
+///
+/// The commands are counted when the global collector drains the channels, and the overflow
+/// events when they happen, so that sending a command does not touch any shared counter.
+///
+/// # Examples
+///
+/// ```
+/// let stats = minitrace::collector::send_command_stats();
+/// assert_eq!(stats.dropped_overflow, 0);
+/// ```
+pub fn send_command_stats() -> SpscStats {
+    SpscStats {
+        sent: RECEIVED_COMMANDS.load(Ordering::Relaxed),
+        dropped_overflow: DROPPED_OVERFLOW_COMMANDS.load(Ordering::Relaxed),
+        flushed_overflow: FLUSHED_OVERFLOW_COMMANDS.load(Ordering::Relaxed),
+    }
+}
+
+/// Returns the number of command channels registered to the global collector.
+///
+/// A thread registers a channel when it sends its first command. The channel of a thread that has
+/// exited is removed the next time the global collector drains the channels, which happens every
+/// `report_interval` and on [`flush()`], so the count stays close to the number of live threads
+/// that have recorded spans, even when short-lived threads are spawned repeatedly.
+///
+/// # Examples
+///
+/// ```
+/// let channels = minitrace::collector::registered_channel_count();
+/// ```
+pub fn registered_channel_count() -> usize {
+    SPSC_RXS.lock().len()
+}
+
 /// Sets the reporter and its configuration for the current application.
 ///
 /// # Examples
@@ -73,8 +169,234 @@ fn force_send_command(cmd: CollectCommand) {
 pub fn set_reporter(reporter: impl Reporter, config: Config) {
     #[cfg(feature = "enable")]
     {
-        GlobalCollector::start(reporter, config);
+        REPORTER_FACTORY_PENDING.store(false, Ordering::Relaxed);
+        REPORTER_FACTORY.lock().take();
+    }
+    replace_reporter(reporter, config);
+}
+
+/// Replaces the reporter and its configuration, under the lock of the global collector so that no
+/// span is handled in between.
+///
+/// The new reporter is started, and the previous one is stopped once it is replaced. If
+/// [`Config::report_on_replace()`] was set for the previous reporter, the pending spans are
+/// reported to it before the swap, otherwise they are left to the new one.
+///
+/// This is the lower-level API behind [`set_reporter()`], which additionally discards the factory
+/// set by [`set_reporter_factory()`].
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::ConsoleReporter;
+///
+/// let config = Config::default().report_on_replace(true);
+/// minitrace::collector::replace_reporter(ConsoleReporter, config);
+/// ```
+pub fn replace_reporter(reporter: impl Reporter, config: Config) {
+    #[cfg(feature = "enable")]
+    {
+        GlobalCollector::replace_reporter(reporter, config);
+        store_thread_local_config(&config);
+        REPORTER_READY.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Sets the resource attributes, such as `service.name`, of the traces whose root span is not
+/// created by [`Span::root_with_resource()`].
+///
+/// The resource is attached to the [`SpanRecord`]s as they are reported, so it applies to the
+/// spans of unfinished traces as well. An empty resource removes the previous one.
+///
+/// [`Span::root_with_resource()`]: crate::Span::root_with_resource
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// minitrace::set_resource(HashMap::from([
+///     ("service.name".into(), "my-service".into()),
+///     ("service.version".into(), "1.0.0".into()),
+/// ]));
+/// ```
+pub fn set_resource(resource: HashMap<Cow<'static, str>, Cow<'static, str>>) {
+    #[cfg(feature = "enable")]
+    {
+        *DEFAULT_RESOURCE.lock() = (!resource.is_empty()).then(|| Arc::new(resource));
+    }
+}
+
+/// Sets a factory creating the reporter and its configuration when the first root span is
+/// created, for applications that only know how to report spans after parsing their flags or
+/// loading their configuration.
+///
+/// The factory is called on a background thread, so the spans recorded in the meantime are
+/// buffered, keeping the most recent `max_buffered_spans` spans, and reported once the reporter
+/// is created. Until then, the spans are collected with [`Config::default()`]. Setting a reporter
+/// by [`set_reporter()`] before the factory is called discards the factory.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::ConsoleReporter;
+/// use minitrace::collector::Reporter;
+///
+/// minitrace::set_reporter_factory(
+///     || {
+///         // Load the configuration of the reporter...
+///         let reporter: Box<dyn Reporter> = Box::new(ConsoleReporter);
+///         (reporter, Config::default())
+///     },
+///     10_000,
+/// );
+/// ```
+pub fn set_reporter_factory(
+    factory: impl FnOnce() -> (Box<dyn Reporter>, Config) + Send + 'static,
+    max_buffered_spans: usize,
+) {
+    #[cfg(feature = "enable")]
+    {
+        *REPORTER_FACTORY.lock() = Some(Box::new(factory));
+        REPORTER_FACTORY_PENDING.store(true, Ordering::Relaxed);
+        GlobalCollector::start_buffering(max_buffered_spans);
+        store_thread_local_config(&Config::default());
+        REPORTER_READY.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Calls the factory set by [`set_reporter_factory()`] if it has not been called yet.
+pub(crate) fn run_reporter_factory() {
+    if !REPORTER_FACTORY_PENDING.load(Ordering::Relaxed)
+        || !REPORTER_FACTORY_PENDING.swap(false, Ordering::Relaxed)
+    {
+        return;
+    }
+    let Some(factory) = REPORTER_FACTORY.lock().take() else {
+        return;
+    };
+
+    let run = move || {
+        let (mut reporter, config) = factory();
+
+        let mut global_collector = GLOBAL_COLLECTOR.lock();
+        // The factory is discarded if another reporter has been set in the meantime.
+        let Some(global_collector) = global_collector
+            .as_mut()
+            .filter(|global_collector| global_collector.buffered_records.is_some())
+        else {
+            return;
+        };
+
+        reporter.start();
+        if let Some(mut buffered_records) = global_collector.buffered_records.take() {
+            if !buffered_records.records.is_empty() {
+                reporter.report(buffered_records.records.make_contiguous());
+            }
+        }
+        global_collector.config = config;
+        global_collector.reporter = Some(reporter);
+        store_thread_local_config(&config);
+    };
+
+    #[cfg(target_family = "wasm")]
+    {
+        run();
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    {
+        std::thread::Builder::new()
+            .name("minitrace-reporter-factory".to_string())
+            .spawn(run)
+            .unwrap();
+    }
+}
+
+/// Sets the reporter and its configuration until the returned [`ReporterGuard`] is dropped.
+///
+/// This is mostly useful in tests, where every test case can install its own reporter and have
+/// it torn down automatically. When the guard is dropped, the pending span records are flushed
+/// to the reporter, which is then stopped, and the reporter and configuration that were set
+/// before are restored. If no reporter was set before, spans are no longer recorded, as after
+/// [`shutdown()`].
+///
+/// The guards are expected to be dropped in the reverse order of their creation.
+///
+/// The reporter is still global: it receives the spans of every thread, not only those of the
+/// test that installed it. Tests that record spans while sharing the global collector must
+/// therefore still run one at a time, e.g. with `#[serial]` from the `serial_test` crate.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::ConsoleReporter;
+///
+/// let _guard = minitrace::scoped_reporter(ConsoleReporter, Config::default());
+/// ```
+pub fn scoped_reporter(reporter: impl Reporter, config: Config) -> ReporterGuard {
+    #[cfg(not(feature = "enable"))]
+    {
+        ReporterGuard { _private: () }
+    }
+
+    #[cfg(feature = "enable")]
+    {
+        let mut reporter = reporter;
+        REPORTER_FACTORY_PENDING.store(false, Ordering::Relaxed);
+        REPORTER_FACTORY.lock().take();
+        let prev = GlobalCollector::run_reporters(move || {
+            reporter.start();
+            GlobalCollector::swap_reporter(Some((Box::new(reporter), config)), false)
+        });
+        GlobalCollector::wake_collector_thread();
+        store_thread_local_config(&config);
         REPORTER_READY.store(true, Ordering::Relaxed);
+
+        ReporterGuard {
+            prev,
+            _private: (),
+        }
+    }
+}
+
+/// A guard returned by [`scoped_reporter()`].
+///
+/// On drop, the pending span records are flushed to the reporter, the reporter is stopped, and
+/// the previous reporter is restored.
+#[must_use]
+pub struct ReporterGuard {
+    // The reporter and its configuration replaced by the scoped reporter.
+    #[cfg(feature = "enable")]
+    prev: Option<(Box<dyn Reporter>, Config)>,
+    _private: (),
+}
+
+impl Drop for ReporterGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "enable")]
+        {
+            let prev = self.prev.take();
+            let prev_config = prev.as_ref().map(|(_, config)| *config);
+            if prev_config.is_none() {
+                REPORTER_READY.store(false, Ordering::Relaxed);
+            }
+
+            GlobalCollector::run_reporters(move || {
+                if let Some((mut reporter, _)) = GlobalCollector::swap_reporter(prev, true) {
+                    reporter.stop();
+                }
+            });
+
+            if let Some(config) = prev_config {
+                GlobalCollector::wake_collector_thread();
+                store_thread_local_config(&config);
+                REPORTER_READY.store(true, Ordering::Relaxed);
+            }
+        }
     }
 }
 
@@ -82,14 +404,45 @@ pub(crate) fn reporter_ready() -> bool {
     REPORTER_READY.load(Ordering::Relaxed)
 }
 
+/// The default `max_spans_per_trace`, hard-coded at compile time by the `MINITRACE_MAX_SPANS`
+/// environment variable.
+pub(crate) fn default_max_spans_per_trace() -> Option<usize> {
+    option_env!("MINITRACE_MAX_SPANS").and_then(|max_spans| max_spans.parse::<usize>().ok())
+}
+
+/// Stores the configuration read by the thread-local span stacks when they record spans.
+fn store_thread_local_config(config: &Config) {
+    EVICTION_POLICY.store(config.eviction_policy as u8, Ordering::Relaxed);
+    MAX_SPANS_PER_THREAD.store(
+        config.max_spans_per_thread.unwrap_or(usize::MAX),
+        Ordering::Relaxed,
+    );
+}
+
+pub(crate) fn max_spans_per_thread() -> Option<usize> {
+    match MAX_SPANS_PER_THREAD.load(Ordering::Relaxed) {
+        usize::MAX => None,
+        max_spans_per_thread => Some(max_spans_per_thread),
+    }
+}
+
+pub(crate) fn eviction_policy() -> EvictionPolicy {
+    match EVICTION_POLICY.load(Ordering::Relaxed) {
+        policy if policy == EvictionPolicy::DropOldest as u8 => EvictionPolicy::DropOldest,
+        policy if policy == EvictionPolicy::InsertMarker as u8 => EvictionPolicy::InsertMarker,
+        _ => EvictionPolicy::DropNewest,
+    }
+}
+
 /// Flushes all pending span records to the reporter immediately.
 pub fn flush() {
     #[cfg(feature = "enable")]
     {
         #[cfg(target_family = "wasm")]
         {
-            let mut global_collector = GLOBAL_COLLECTOR.lock();
-            global_collector.handle_commands(true);
+            if let Some(global_collector) = GLOBAL_COLLECTOR.lock().as_mut() {
+                global_collector.handle_commands();
+            }
         }
 
         #[cfg(not(target_family = "wasm"))]
@@ -110,12 +463,132 @@ pub fn flush() {
     }
 }
 
+/// Flushes all pending span records to the reporter without blocking the current thread.
+///
+/// The returned future resolves once the background collector has reported the spans submitted
+/// before the call. Unlike [`flush()`], no thread is spawned, so it can be awaited in any async
+/// runtime, such as `tokio`, `async-std` or `smol`.
+///
+/// The returned future must be polled by an async runtime. Do not call `flush_async()` in a
+/// `#[tokio::test]` without an explicit runtime context, e.g. in a `Drop` implementation that
+/// runs after the runtime is shut down; use [`flush()`] there instead.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::ConsoleReporter;
+///
+/// minitrace::set_reporter(ConsoleReporter, Config::default());
+///
+/// # futures::executor::block_on(async {
+/// minitrace::flush_async().await;
+/// # });
+/// ```
+pub fn flush_async() -> impl Future<Output = ()> {
+    #[cfg(not(feature = "enable"))]
+    {
+        std::future::ready(())
+    }
+
+    #[cfg(all(feature = "enable", target_family = "wasm"))]
+    {
+        flush();
+        std::future::ready(())
+    }
+
+    #[cfg(all(feature = "enable", not(target_family = "wasm")))]
+    {
+        let (tx, rx) = oneshot::channel();
+        match COLLECTOR_THREAD.get() {
+            Some(collector_thread) => {
+                force_send_command(CollectCommand::Flush(tx));
+                collector_thread.unpark();
+            }
+            // No reporter has ever been set, so there is nothing to flush.
+            None => tx.send(()),
+        }
+        async move {
+            rx.await;
+        }
+    }
+}
+
+/// Flushes all pending span records to the reporter, and then stops the reporter.
+///
+/// Spans created after the shutdown are not recorded until a reporter is set again by
+/// [`set_reporter()`].
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::ConsoleReporter;
+///
+/// minitrace::set_reporter(ConsoleReporter, Config::default());
+///
+/// // ...
+///
+/// minitrace::shutdown();
+/// ```
+pub fn shutdown() {
+    #[cfg(feature = "enable")]
+    {
+        REPORTER_READY.store(false, Ordering::Relaxed);
+        REPORTER_FACTORY_PENDING.store(false, Ordering::Relaxed);
+        REPORTER_FACTORY.lock().take();
+
+        let shutdown = || {
+            if let Some(global_collector) = GLOBAL_COLLECTOR.lock().as_mut() {
+                global_collector.handle_commands();
+                if let Some(mut reporter) = global_collector.reporter.take() {
+                    reporter.stop();
+                }
+            }
+        };
+
+        #[cfg(target_family = "wasm")]
+        {
+            shutdown();
+        }
+
+        #[cfg(not(target_family = "wasm"))]
+        {
+            // Spawns a new thread to ensure the reporter operates outside the tokio runtime to
+            // prevent panic.
+            std::thread::Builder::new()
+                .name("minitrace-shutdown".to_string())
+                .spawn(shutdown)
+                .unwrap()
+                .join()
+                .unwrap();
+        }
+    }
+}
+
 /// A trait defining the behavior of a reporter. A reporter is responsible for
 /// handling span records, typically by sending them to a remote service for
 /// further processing and analysis.
 pub trait Reporter: Send + 'static {
     /// Reports a batch of spans to a remote service.
     fn report(&mut self, spans: &[SpanRecord]);
+
+    /// Called once by [`set_reporter()`] before any span is reported. Resources such as
+    /// connections can be set up here. It's called on a background thread, so it may block.
+    ///
+    /// Reporters wrapping other reporters should forward the call to the inner reporters.
+    ///
+    /// The default implementation does nothing.
+    fn start(&mut self) {}
+
+    /// Called once by [`shutdown()`] after the pending spans are reported, or when the reporter
+    /// is replaced by another call to [`set_reporter()`]. Resources such as connections can be
+    /// released here.
+    ///
+    /// Reporters wrapping other reporters should forward the call to the inner reporters.
+    ///
+    /// The default implementation does nothing.
+    fn stop(&mut self) {}
 }
 
 #[derive(Default, Clone)]
@@ -123,9 +596,12 @@ pub(crate) struct GlobalCollect;
 
 #[cfg_attr(test, mockall::automock)]
 impl GlobalCollect {
-    pub fn start_collect(&self) -> usize {
+    pub fn start_collect(&self, resource: Option<Arc<Resource>>) -> usize {
         let collect_id = NEXT_COLLECT_ID.fetch_add(1, Ordering::Relaxed);
-        send_command(CollectCommand::StartCollect(StartCollect { collect_id }));
+        send_command(CollectCommand::StartCollect(StartCollect {
+            collect_id,
+            resource,
+        }));
         collect_id
     }
 
@@ -178,24 +654,92 @@ enum SpanCollection {
         spans: SpanSet,
         trace_id: TraceId,
         parent_id: SpanId,
+        sampled: bool,
     },
     Shared {
         spans: Arc<SpanSet>,
         trace_id: TraceId,
         parent_id: SpanId,
+        sampled: bool,
     },
 }
 
-#[derive(Default)]
 struct ActiveCollector {
     span_collections: Vec<SpanCollection>,
     span_count: usize,
-    dangling_events: HashMap<SpanId, Vec<EventRecord>>,
+    dangling_events: DanglingEvents,
+    start_instant: Instant,
+    resource: Option<Arc<Resource>>,
+    // The number of span collections that have been reported by
+    // `Config::report_long_running_threshold` before the root span finishes.
+    long_running_reported: usize,
+}
+
+impl ActiveCollector {
+    fn new(
+        start_instant: Instant,
+        max_events_per_span: Option<usize>,
+        resource: Option<Arc<Resource>>,
+    ) -> Self {
+        ActiveCollector {
+            span_collections: Vec::new(),
+            span_count: 0,
+            dangling_events: DanglingEvents::new(max_events_per_span),
+            start_instant,
+            resource,
+            long_running_reported: 0,
+        }
+    }
+}
+
+impl SpanCollection {
+    /// Moves the owned spans into an `Arc` so that the collection can be cheaply cloned.
+    fn into_shared(self) -> Self {
+        match self {
+            SpanCollection::Owned {
+                spans,
+                trace_id,
+                parent_id,
+                sampled,
+            } => SpanCollection::Shared {
+                spans: Arc::new(spans),
+                trace_id,
+                parent_id,
+                sampled,
+            },
+            shared @ SpanCollection::Shared { .. } => shared,
+        }
+    }
+
+    fn clone_shared(&self) -> Option<Self> {
+        match self {
+            SpanCollection::Owned { .. } => None,
+            SpanCollection::Shared {
+                spans,
+                trace_id,
+                parent_id,
+                sampled,
+            } => Some(SpanCollection::Shared {
+                spans: spans.clone(),
+                trace_id: *trace_id,
+                parent_id: *parent_id,
+                sampled: *sampled,
+            }),
+        }
+    }
+}
+
+/// The spans collected while the reporter is created by the factory set by
+/// [`set_reporter_factory()`].
+struct BufferedRecords {
+    records: VecDeque<SpanRecord>,
+    max_records: usize,
 }
 
 pub(crate) struct GlobalCollector {
     config: Config,
     reporter: Option<Box<dyn Reporter>>,
+    buffered_records: Option<BufferedRecords>,
 
     active_collectors: HashMap<usize, ActiveCollector>,
 
@@ -205,15 +749,104 @@ pub(crate) struct GlobalCollector {
     drop_collects: Vec<DropCollect>,
     commit_collects: Vec<CommitCollect>,
     submit_spans: Vec<SubmitSpans>,
+    flushes: Vec<oneshot::Sender<()>>,
     committed_records: Vec<SpanRecord>,
 }
 
 impl GlobalCollector {
-    #[allow(unreachable_code)]
-    fn start(reporter: impl Reporter, config: Config) {
-        let global_collector = GlobalCollector {
+    /// Replaces the global collector with a new one reporting to `reporter`, and stops the
+    /// previous reporter.
+    ///
+    /// The new reporter is started and the previous one is stopped on the same thread as the
+    /// swap, so that neither runs inside the caller's async runtime.
+    fn replace_reporter(mut reporter: impl Reporter, config: Config) {
+        Self::run_reporters(move || {
+            reporter.start();
+            if let Some((mut prev_reporter, _)) =
+                Self::swap_reporter(Some((Box::new(reporter), config)), false)
+            {
+                prev_reporter.stop();
+            }
+        });
+        Self::wake_collector_thread();
+    }
+
+    /// Replaces the global collector with a new one reporting to `reporter`, under the lock of
+    /// the global collector so that no command is handled in between, or only removes the
+    /// reporter of the global collector if `reporter` is `None`. Returns the previous reporter
+    /// and its configuration, neither started nor stopped.
+    ///
+    /// If `flush` or [`Config::report_on_replace`] is set for the previous reporter, the pending
+    /// commands are handled with the previous reporter before it is replaced, otherwise they are
+    /// left to the new one.
+    fn swap_reporter(
+        reporter: Option<(Box<dyn Reporter>, Config)>,
+        flush: bool,
+    ) -> Option<(Box<dyn Reporter>, Config)> {
+        let mut global_collector = GLOBAL_COLLECTOR.lock();
+        if let Some(prev) = global_collector
+            .as_mut()
+            .filter(|prev| flush || prev.config.report_on_replace)
+        {
+            prev.handle_commands();
+        }
+        match reporter {
+            Some((reporter, config)) => {
+                let prev = global_collector.replace(Self::new(Some(reporter), None, config))?;
+                prev.reporter.map(|reporter| (reporter, prev.config))
+            }
+            None => {
+                let prev = global_collector.as_mut()?;
+                prev.reporter.take().map(|reporter| (reporter, prev.config))
+            }
+        }
+    }
+
+    /// Runs `f`, which starts or stops reporters, on a new thread to ensure the reporters operate
+    /// outside the tokio runtime to prevent panic.
+    fn run_reporters<R: Send + 'static>(f: impl FnOnce() -> R + Send + 'static) -> R {
+        #[cfg(target_family = "wasm")]
+        {
+            f()
+        }
+
+        #[cfg(not(target_family = "wasm"))]
+        {
+            std::thread::Builder::new()
+                .name("minitrace-replace-reporter".to_string())
+                .spawn(f)
+                .unwrap()
+                .join()
+                .unwrap()
+        }
+    }
+
+    fn start_buffering(max_buffered_spans: usize) {
+        let buffered_records = BufferedRecords {
+            records: VecDeque::new(),
+            max_records: max_buffered_spans,
+        };
+        Self::start_with(None, Some(buffered_records), Config::default());
+    }
+
+    fn start_with(
+        reporter: Option<Box<dyn Reporter>>,
+        buffered_records: Option<BufferedRecords>,
+        config: Config,
+    ) {
+        *GLOBAL_COLLECTOR.lock() = Some(Self::new(reporter, buffered_records, config));
+        Self::wake_collector_thread();
+    }
+
+    fn new(
+        reporter: Option<Box<dyn Reporter>>,
+        buffered_records: Option<BufferedRecords>,
+        config: Config,
+    ) -> Self {
+        GlobalCollector {
             config,
-            reporter: Some(Box::new(reporter)),
+            reporter,
+            buffered_records,
 
             active_collectors: HashMap::new(),
             committed_records: Vec::new(),
@@ -222,27 +855,17 @@ impl GlobalCollector {
             drop_collects: Vec::new(),
             commit_collects: Vec::new(),
             submit_spans: Vec::new(),
-        };
-
-        *GLOBAL_COLLECTOR.lock() = Some(global_collector);
+            flushes: Vec::new(),
+        }
+    }
 
+    fn wake_collector_thread() {
+        // The background thread is shared by all the reporters that are set in turn, such as
+        // the ones installed by `scoped_reporter()`.
         #[cfg(not(target_family = "wasm"))]
-        {
-            std::thread::Builder::new()
-                .name("minitrace-global-collector".to_string())
-                .spawn(move || {
-                    loop {
-                        let begin_instant = Instant::now();
-                        GLOBAL_COLLECTOR.lock().as_mut().unwrap().handle_commands();
-                        std::thread::sleep(
-                            config
-                                .report_interval
-                                .saturating_sub(begin_instant.elapsed()),
-                        );
-                    }
-                })
-                .unwrap();
-        }
+        COLLECTOR_THREAD
+            .get_or_init(spawn_collector_thread)
+            .unpark();
     }
 
     fn handle_commands(&mut self) {
@@ -252,22 +875,31 @@ impl GlobalCollector {
         debug_assert!(self.drop_collects.is_empty());
         debug_assert!(self.commit_collects.is_empty());
         debug_assert!(self.submit_spans.is_empty());
+        debug_assert!(self.flushes.is_empty());
         debug_assert!(self.committed_records.is_empty());
 
         let start_collects = &mut self.start_collects;
         let drop_collects = &mut self.drop_collects;
         let commit_collects = &mut self.commit_collects;
         let submit_spans = &mut self.submit_spans;
+        let flushes = &mut self.flushes;
         let committed_records = &mut self.committed_records;
 
         {
+            let mut received = 0;
             SPSC_RXS.lock().retain_mut(|rx| {
                 loop {
                     match rx.try_recv() {
-                        Ok(Some(CollectCommand::StartCollect(cmd))) => start_collects.push(cmd),
-                        Ok(Some(CollectCommand::DropCollect(cmd))) => drop_collects.push(cmd),
-                        Ok(Some(CollectCommand::CommitCollect(cmd))) => commit_collects.push(cmd),
-                        Ok(Some(CollectCommand::SubmitSpans(cmd))) => submit_spans.push(cmd),
+                        Ok(Some(cmd)) => {
+                            received += 1;
+                            match cmd {
+                                CollectCommand::StartCollect(cmd) => start_collects.push(cmd),
+                                CollectCommand::DropCollect(cmd) => drop_collects.push(cmd),
+                                CollectCommand::CommitCollect(cmd) => commit_collects.push(cmd),
+                                CollectCommand::SubmitSpans(cmd) => submit_spans.push(cmd),
+                                CollectCommand::Flush(tx) => flushes.push(tx),
+                            }
+                        }
                         Ok(None) => {
                             // Channel is empty.
                             return true;
@@ -279,21 +911,34 @@ impl GlobalCollector {
                     }
                 }
             });
+            if received > 0 {
+                RECEIVED_COMMANDS.fetch_add(received, Ordering::Relaxed);
+            }
         }
 
         // If the reporter is not set, global collectior only clears the channel and then dismiss
         // all messages.
-        if self.reporter.is_none() {
+        if self.reporter.is_none() && self.buffered_records.is_none() {
             start_collects.clear();
             drop_collects.clear();
             commit_collects.clear();
             submit_spans.clear();
+            for tx in flushes.drain(..) {
+                tx.send(());
+            }
             return;
         }
 
-        for StartCollect { collect_id } in self.start_collects.drain(..) {
-            self.active_collectors
-                .insert(collect_id, ActiveCollector::default());
+        let now = Instant::now();
+        for StartCollect {
+            collect_id,
+            resource,
+        } in self.start_collects.drain(..)
+        {
+            self.active_collectors.insert(
+                collect_id,
+                ActiveCollector::new(now, self.config.max_events_per_span, resource),
+            );
         }
 
         for DropCollect { collect_id } in self.drop_collects.drain(..) {
@@ -321,6 +966,7 @@ impl GlobalCollector {
                                 spans,
                                 trace_id: item.trace_id,
                                 parent_id: item.parent_id,
+                                sampled: item.sampled,
                             });
                     }
                 }
@@ -342,6 +988,7 @@ impl GlobalCollector {
                                     spans: spans.clone(),
                                     trace_id: item.trace_id,
                                     parent_id: item.parent_id,
+                                    sampled: item.sampled,
                                 });
                         }
                     }
@@ -350,6 +997,7 @@ impl GlobalCollector {
         }
 
         let anchor = Anchor::new();
+        let default_resource = DEFAULT_RESOURCE.lock().clone();
 
         for CommitCollect { collect_id } in commit_collects.drain(..) {
             if let Some(mut active_collector) = self.active_collectors.remove(&collect_id) {
@@ -358,6 +1006,10 @@ impl GlobalCollector {
                     &anchor,
                     committed_records,
                     &mut active_collector.dangling_events,
+                    active_collector
+                        .resource
+                        .as_ref()
+                        .or(default_resource.as_ref()),
                 );
             }
         }
@@ -369,29 +1021,108 @@ impl GlobalCollector {
                     &anchor,
                     committed_records,
                     &mut active_collector.dangling_events,
+                    active_collector
+                        .resource
+                        .as_ref()
+                        .or(default_resource.as_ref()),
+                );
+            }
+        } else if let Some(threshold) = self.config.report_long_running_threshold {
+            for active_collector in self.active_collectors.values_mut() {
+                if now.duration_since(active_collector.start_instant) < threshold
+                    || active_collector.long_running_reported
+                        == active_collector.span_collections.len()
+                {
+                    continue;
+                }
+
+                // The spans are kept to be reported again when the root span finishes, so the
+                // owned ones are shared to avoid copying them, and the dangling events are kept
+                // for the spans that are not submitted yet.
+                let span_collections = std::mem::take(&mut active_collector.span_collections);
+                active_collector.span_collections = span_collections
+                    .into_iter()
+                    .map(SpanCollection::into_shared)
+                    .collect();
+                postprocess_span_collection(
+                    active_collector.span_collections[active_collector.long_running_reported..]
+                        .iter()
+                        .filter_map(SpanCollection::clone_shared),
+                    &anchor,
+                    committed_records,
+                    &mut active_collector.dangling_events.clone(),
+                    active_collector
+                        .resource
+                        .as_ref()
+                        .or(default_resource.as_ref()),
                 );
+                active_collector.long_running_reported = active_collector.span_collections.len();
             }
         }
 
-        self.reporter.as_mut().unwrap().report(committed_records);
+        if self.config.min_event_level > EventLevel::Debug {
+            for record in committed_records.iter_mut() {
+                record
+                    .events
+                    .retain(|event| event.level >= self.config.min_event_level);
+            }
+        }
+
+        match (&mut self.reporter, &mut self.buffered_records) {
+            (Some(reporter), _) => reporter.report(committed_records),
+            (None, Some(buffered_records)) => {
+                buffered_records.records.extend(committed_records.drain(..));
+                let excess = buffered_records
+                    .records
+                    .len()
+                    .saturating_sub(buffered_records.max_records);
+                buffered_records.records.drain(..excess);
+            }
+            (None, None) => unreachable!(),
+        }
         committed_records.clear();
+
+        for tx in self.flushes.drain(..) {
+            tx.send(());
+        }
     }
 }
 
+#[cfg(not(target_family = "wasm"))]
+fn spawn_collector_thread() -> Thread {
+    std::thread::Builder::new()
+        .name("minitrace-global-collector".to_string())
+        .spawn(|| loop {
+            let begin_instant = Instant::now();
+            let report_interval = {
+                let mut global_collector = GLOBAL_COLLECTOR.lock();
+                let global_collector = global_collector.as_mut().unwrap();
+                global_collector.handle_commands();
+                global_collector.config.report_interval
+            };
+            // Woken up early by `flush_async()` or when a new reporter is set.
+            std::thread::park_timeout(report_interval.saturating_sub(begin_instant.elapsed()));
+        })
+        .unwrap()
+        .thread()
+        .clone()
+}
+
 impl LocalSpansInner {
     pub fn to_span_records(&self, parent: SpanContext) -> Vec<SpanRecord> {
         let anchor: Anchor = Anchor::new();
-        let mut dangling_events = HashMap::new();
+        let mut dangling_events = DanglingEvents::new(None);
         let mut records = Vec::new();
         amend_local_span(
             self,
             parent.trace_id,
             parent.span_id,
+            parent.sampled,
             &mut records,
             &mut dangling_events,
             &anchor,
         );
-        mount_events(&mut records, &mut dangling_events);
+        dangling_events.mount(&mut records);
         records
     }
 }
@@ -400,7 +1131,8 @@ fn postprocess_span_collection(
     span_collections: impl IntoIterator<Item = SpanCollection>,
     anchor: &Anchor,
     committed_records: &mut Vec<SpanRecord>,
-    dangling_events: &mut HashMap<SpanId, Vec<EventRecord>>,
+    dangling_events: &mut DanglingEvents,
+    resource: Option<&Arc<Resource>>,
 ) {
     let committed_len = committed_records.len();
 
@@ -410,11 +1142,13 @@ fn postprocess_span_collection(
                 spans,
                 trace_id,
                 parent_id,
+                sampled,
             } => match spans {
                 SpanSet::Span(raw_span) => amend_span(
                     &raw_span,
                     trace_id,
                     parent_id,
+                    sampled,
                     committed_records,
                     dangling_events,
                     anchor,
@@ -423,6 +1157,7 @@ fn postprocess_span_collection(
                     &local_spans,
                     trace_id,
                     parent_id,
+                    sampled,
                     committed_records,
                     dangling_events,
                     anchor,
@@ -431,6 +1166,7 @@ fn postprocess_span_collection(
                     &local_spans,
                     trace_id,
                     parent_id,
+                    sampled,
                     committed_records,
                     dangling_events,
                     anchor,
@@ -440,11 +1176,13 @@ fn postprocess_span_collection(
                 spans,
                 trace_id,
                 parent_id,
+                sampled,
             } => match &*spans {
                 SpanSet::Span(raw_span) => amend_span(
                     raw_span,
                     trace_id,
                     parent_id,
+                    sampled,
                     committed_records,
                     dangling_events,
                     anchor,
@@ -453,6 +1191,7 @@ fn postprocess_span_collection(
                     local_spans,
                     trace_id,
                     parent_id,
+                    sampled,
                     committed_records,
                     dangling_events,
                     anchor,
@@ -461,6 +1200,7 @@ fn postprocess_span_collection(
                     local_spans,
                     trace_id,
                     parent_id,
+                    sampled,
                     committed_records,
                     dangling_events,
                     anchor,
@@ -469,19 +1209,28 @@ fn postprocess_span_collection(
         }
     }
 
-    mount_events(&mut committed_records[committed_len..], dangling_events);
+    dangling_events.mount(&mut committed_records[committed_len..]);
+
+    if let Some(resource) = resource {
+        for record in &mut committed_records[committed_len..] {
+            record.resource = Some(resource.clone());
+        }
+    }
 }
 
 fn amend_local_span(
     local_spans: &LocalSpansInner,
     trace_id: TraceId,
     parent_id: SpanId,
+    sampled: bool,
     spans: &mut Vec<SpanRecord>,
-    events: &mut HashMap<SpanId, Vec<EventRecord>>,
+    events: &mut DanglingEvents,
     anchor: &Anchor,
 ) {
     for span in local_spans.spans.iter() {
-        let begin_time_unix_ns = span.begin_instant.as_unix_nanos(anchor);
+        let begin_time_unix_ns = span
+            .begin_time_override
+            .unwrap_or_else(|| span.begin_instant.as_unix_nanos(anchor));
         let parent_id = if span.parent_id == SpanId::default() {
             parent_id
         } else {
@@ -489,15 +1238,24 @@ fn amend_local_span(
         };
 
         if span.is_event {
+            let timestamp_unix_ns = match span.timestamp_override() {
+                Some((timestamp_unix_ns, _)) => timestamp_unix_ns,
+                None => begin_time_unix_ns,
+            };
             let event = EventRecord {
                 name: span.name.clone(),
-                timestamp_unix_ns: begin_time_unix_ns,
+                timestamp_unix_ns,
                 properties: span.properties.clone(),
+                level: span.event_level,
             };
-            events.entry(parent_id).or_default().push(event);
+            events.push(parent_id, event);
             continue;
         }
 
+        let (status, links) = match span.extension.as_deref() {
+            Some(extension) => (extension.status.clone(), extension.links.clone()),
+            None => Default::default(),
+        };
         let end_time_unix_ns = if span.end_instant == Instant::ZERO {
             local_spans.end_time.as_unix_nanos(anchor)
         } else {
@@ -512,6 +1270,11 @@ fn amend_local_span(
             name: span.name.clone(),
             properties: span.properties.clone(),
             events: vec![],
+            status,
+            kind: span.kind,
+            links,
+            sampled,
+            resource: None,
         });
     }
 }
@@ -520,49 +1283,109 @@ fn amend_span(
     raw_span: &RawSpan,
     trace_id: TraceId,
     parent_id: SpanId,
+    sampled: bool,
     spans: &mut Vec<SpanRecord>,
-    events: &mut HashMap<SpanId, Vec<EventRecord>>,
+    events: &mut DanglingEvents,
     anchor: &Anchor,
 ) {
-    let begin_time_unix_ns = raw_span.begin_instant.as_unix_nanos(anchor);
+    let begin_time_unix_ns = raw_span
+        .begin_time_override
+        .unwrap_or_else(|| raw_span.begin_instant.as_unix_nanos(anchor));
 
     if raw_span.is_event {
         let event = EventRecord {
             name: raw_span.name.clone(),
             timestamp_unix_ns: begin_time_unix_ns,
             properties: raw_span.properties.clone(),
+            level: raw_span.event_level,
         };
-        events.entry(parent_id).or_default().push(event);
+        events.push(parent_id, event);
         return;
     }
 
-    let end_time_unix_ns = raw_span.end_instant.as_unix_nanos(anchor);
+    let (begin_time_unix_ns, duration_ns) = match raw_span.timestamp_override() {
+        Some(timestamp_override) => timestamp_override,
+        None => {
+            let end_time_unix_ns = raw_span.end_instant.as_unix_nanos(anchor);
+            (
+                begin_time_unix_ns,
+                end_time_unix_ns.saturating_sub(begin_time_unix_ns),
+            )
+        }
+    };
+    let (events, status, links) = match raw_span.extension.as_deref() {
+        Some(extension) => (
+            extension.pre_events.clone(),
+            extension.status.clone(),
+            extension.links.clone(),
+        ),
+        None => Default::default(),
+    };
     spans.push(SpanRecord {
         trace_id,
         span_id: raw_span.id,
         parent_id,
         begin_time_unix_ns,
-        duration_ns: end_time_unix_ns.saturating_sub(begin_time_unix_ns),
+        duration_ns,
         name: raw_span.name.clone(),
         properties: raw_span.properties.clone(),
-        events: vec![],
+        events,
+        status,
+        kind: raw_span.kind,
+        links,
+        sampled,
+        resource: None,
     });
 }
 
-fn mount_events(
-    records: &mut [SpanRecord],
-    dangling_events: &mut HashMap<SpanId, Vec<EventRecord>>,
-) {
-    for record in records.iter_mut() {
-        if dangling_events.is_empty() {
-            return;
+/// Events waiting to be mounted to the records of their parent spans.
+#[derive(Clone)]
+struct DanglingEvents {
+    events: HashMap<SpanId, Vec<EventRecord>>,
+    dropped_counts: HashMap<SpanId, usize>,
+    max_events_per_span: Option<usize>,
+}
+
+impl DanglingEvents {
+    fn new(max_events_per_span: Option<usize>) -> Self {
+        DanglingEvents {
+            events: HashMap::new(),
+            dropped_counts: HashMap::new(),
+            max_events_per_span,
         }
+    }
 
-        if let Some(event) = dangling_events.remove(&record.span_id) {
-            if record.events.is_empty() {
-                record.events = event;
-            } else {
-                record.events.extend(event);
+    fn push(&mut self, parent_id: SpanId, event: EventRecord) {
+        let events = self.events.entry(parent_id).or_default();
+        match self.max_events_per_span {
+            Some(max_events) if events.len() >= max_events => {
+                *self.dropped_counts.entry(parent_id).or_default() += 1;
+            }
+            _ => events.push(event),
+        }
+    }
+
+    fn mount(&mut self, records: &mut [SpanRecord]) {
+        for record in records.iter_mut() {
+            if self.events.is_empty() {
+                return;
+            }
+
+            if let Some(events) = self.events.remove(&record.span_id) {
+                if record.events.is_empty() {
+                    record.events = events;
+                } else {
+                    record.events.extend(events);
+                }
+            }
+
+            if let Some(dropped_count) = self.dropped_counts.remove(&record.span_id) {
+                record.events.push(EventRecord {
+                    name: "minitrace::events_truncated".into(),
+                    timestamp_unix_ns: record.begin_time_unix_ns + record.duration_ns,
+                    level: EventLevel::Warn,
+                    properties: vec![("dropped_count".into(), dropped_count.to_string().into())],
+                });
             }
         }
     }
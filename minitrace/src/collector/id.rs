@@ -1,6 +1,13 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
 use std::cell::Cell;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::OnceLock;
+
+use parking_lot::Mutex;
+
+static SPAN_ID_GENERATOR: OnceLock<Box<dyn SpanIdGenerator>> = OnceLock::new();
 
 /// An identifier for a trace, which groups a set of related spans together.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
@@ -14,6 +21,10 @@ impl SpanId {
     #[inline]
     /// Create a non-zero `SpanId`
     pub(crate) fn next_id() -> SpanId {
+        if let Some(generator) = SPAN_ID_GENERATOR.get() {
+            return generator.next_id();
+        }
+
         LOCAL_ID_GENERATOR
             .try_with(|g| {
                 let (prefix, mut suffix) = g.get();
@@ -32,6 +43,127 @@ thread_local! {
     static LOCAL_ID_GENERATOR: Cell<(u32, u32)> = Cell::new((rand::random(), 0))
 }
 
+/// A generator of the ids of the spans, installed by [`set_span_id_generator()`].
+///
+/// [`set_span_id_generator()`]: crate::set_span_id_generator
+pub trait SpanIdGenerator: Send + Sync + 'static {
+    /// Returns the id of a new span, which should be non-zero and unique within the trace.
+    fn next_id(&self) -> SpanId;
+}
+
+/// Installs the generator of the ids of all the spans created afterwards, typically to get
+/// deterministic ids in tests.
+///
+/// The generator can only be installed once, and later calls are ignored. By default, the ids
+/// are generated from a thread-local counter prefixed by a random number.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::SequentialSpanIdGenerator;
+///
+/// minitrace::set_span_id_generator(SequentialSpanIdGenerator::new());
+/// ```
+pub fn set_span_id_generator(generator: impl SpanIdGenerator) {
+    #[cfg(feature = "enable")]
+    {
+        SPAN_ID_GENERATOR.get_or_init(|| Box::new(generator));
+    }
+}
+
+/// A [`SpanIdGenerator`] generating sequential ids from a global counter, starting at 1.
+#[derive(Debug)]
+pub struct SequentialSpanIdGenerator(AtomicU64);
+
+impl SequentialSpanIdGenerator {
+    pub fn new() -> Self {
+        SequentialSpanIdGenerator(AtomicU64::new(1))
+    }
+}
+
+impl Default for SequentialSpanIdGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpanIdGenerator for SequentialSpanIdGenerator {
+    fn next_id(&self) -> SpanId {
+        SpanId(self.0.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// A [`SpanIdGenerator`] generating the ids of a fixed sequence in order, for testing.
+///
+/// # Panics
+///
+/// Panics when a span is created after the sequence is exhausted.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::FixedSequenceSpanIdGenerator;
+///
+/// minitrace::set_span_id_generator(FixedSequenceSpanIdGenerator::new(1..));
+/// ```
+#[derive(Debug)]
+pub struct FixedSequenceSpanIdGenerator<I>(Mutex<I>);
+
+impl<I: Iterator<Item = u64>> FixedSequenceSpanIdGenerator<I> {
+    pub fn new(ids: impl IntoIterator<IntoIter = I>) -> Self {
+        FixedSequenceSpanIdGenerator(Mutex::new(ids.into_iter()))
+    }
+}
+
+impl<I> SpanIdGenerator for FixedSequenceSpanIdGenerator<I>
+where I: Iterator<Item = u64> + Send + 'static
+{
+    fn next_id(&self) -> SpanId {
+        let id = self
+            .0
+            .lock()
+            .next()
+            .expect("the sequence of span ids is exhausted");
+        SpanId(id)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for TraceId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("0x{:032x}", self.0))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TraceId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+        s.strip_prefix("0x")
+            .and_then(|hex| u128::from_str_radix(hex, 16).ok())
+            .map(TraceId)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid trace id: {s:?}")))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SpanId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("0x{:016x}", self.0))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SpanId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+        s.strip_prefix("0x")
+            .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+            .map(SpanId)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid span id: {s:?}")))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashSet;
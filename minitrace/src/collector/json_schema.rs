@@ -0,0 +1,220 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use serde_json::json;
+use serde_json::Value;
+
+/// Returns a [JSON Schema](https://json-schema.org/) (draft 7) document describing the JSON
+/// representation of a [`SpanRecord`], as produced by [`SpanRecord::to_json()`].
+///
+/// The schema also describes [`EventRecord`], [`TraceId`] and [`SpanId`] in its `definitions`,
+/// so that consumers of the serialized spans, for example those reading the lines written by
+/// [`FileReporter`], can validate the records they receive.
+///
+/// [`SpanRecord`]: crate::collector::SpanRecord
+/// [`SpanRecord::to_json()`]: crate::collector::SpanRecord::to_json
+/// [`EventRecord`]: crate::collector::EventRecord
+/// [`TraceId`]: crate::collector::TraceId
+/// [`SpanId`]: crate::collector::SpanId
+/// [`FileReporter`]: crate::collector::FileReporter
+///
+/// # Examples
+///
+/// ```
+/// let schema = minitrace::collector::span_record_json_schema();
+///
+/// assert_eq!(schema["title"], "SpanRecord");
+/// ```
+pub fn span_record_json_schema() -> Value {
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "SpanRecord",
+        "description": "A span collected by minitrace.",
+        "type": "object",
+        "required": [
+            "trace_id",
+            "span_id",
+            "parent_id",
+            "begin_time_unix_ns",
+            "duration_ns",
+            "name",
+            "properties",
+            "events",
+            "status",
+            "kind",
+            "links",
+        ],
+        "properties": {
+            "trace_id": {
+                "description": "The id of the trace the span belongs to.",
+                "$ref": "#/definitions/TraceId",
+            },
+            "span_id": {
+                "description": "The id of the span.",
+                "$ref": "#/definitions/SpanId",
+            },
+            "parent_id": {
+                "description": "The id of the parent span, or zero for a root span without a remote parent.",
+                "$ref": "#/definitions/SpanId",
+            },
+            "begin_time_unix_ns": {
+                "description": "The start time of the span in nanoseconds since the Unix epoch.",
+                "type": "integer",
+                "minimum": 0,
+            },
+            "duration_ns": {
+                "description": "The duration of the span in nanoseconds.",
+                "type": "integer",
+                "minimum": 0,
+            },
+            "name": {
+                "description": "The name of the span.",
+                "type": "string",
+            },
+            "properties": {
+                "description": "The key-value properties of the span.",
+                "$ref": "#/definitions/Properties",
+            },
+            "events": {
+                "description": "The events that occurred during the span.",
+                "type": "array",
+                "items": { "$ref": "#/definitions/EventRecord" },
+            },
+            "status": {
+                "description": "The status of the span: `\"Unset\"`, `\"Ok\"` or `{\"Error\": description}`.",
+                "oneOf": [
+                    { "type": "string", "enum": ["Unset", "Ok"] },
+                    {
+                        "type": "object",
+                        "required": ["Error"],
+                        "properties": { "Error": { "type": "string" } },
+                        "additionalProperties": false,
+                    },
+                ],
+            },
+            "kind": {
+                "description": "The role of the span in the request flow.",
+                "type": "string",
+                "enum": ["Internal", "Server", "Client", "Producer", "Consumer"],
+            },
+            "links": {
+                "description": "The spans linked to the span, as `[trace_id, span_id]` pairs.",
+                "type": "array",
+                "items": {
+                    "type": "array",
+                    "items": [
+                        { "$ref": "#/definitions/TraceId" },
+                        { "$ref": "#/definitions/SpanId" },
+                    ],
+                    "minItems": 2,
+                    "maxItems": 2,
+                },
+            },
+            "sampled": {
+                "description": "Whether the trace is sampled. Defaults to `true` if absent.",
+                "type": "boolean",
+            },
+        },
+        "definitions": {
+            "TraceId": {
+                "title": "TraceId",
+                "description": "A 128-bit trace id as a `0x`-prefixed, zero-padded hexadecimal string.",
+                "type": "string",
+                "pattern": "^0x[0-9a-f]{32}$",
+            },
+            "SpanId": {
+                "title": "SpanId",
+                "description": "A 64-bit span id as a `0x`-prefixed, zero-padded hexadecimal string.",
+                "type": "string",
+                "pattern": "^0x[0-9a-f]{16}$",
+            },
+            "Properties": {
+                "title": "Properties",
+                "description": "Key-value pairs as `[key, value]` arrays of strings.",
+                "type": "array",
+                "items": {
+                    "type": "array",
+                    "items": [{ "type": "string" }, { "type": "string" }],
+                    "minItems": 2,
+                    "maxItems": 2,
+                },
+            },
+            "EventRecord": {
+                "title": "EventRecord",
+                "description": "An event that occurred during a span.",
+                "type": "object",
+                "required": ["name", "timestamp_unix_ns", "properties", "level"],
+                "properties": {
+                    "name": {
+                        "description": "The name of the event.",
+                        "type": "string",
+                    },
+                    "timestamp_unix_ns": {
+                        "description": "The time of the event in nanoseconds since the Unix epoch.",
+                        "type": "integer",
+                        "minimum": 0,
+                    },
+                    "properties": {
+                        "description": "The key-value properties of the event.",
+                        "$ref": "#/definitions/Properties",
+                    },
+                    "level": {
+                        "description": "The severity of the event.",
+                        "type": "string",
+                        "enum": ["Debug", "Info", "Warn", "Error"],
+                    },
+                },
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::EventLevel;
+    use crate::collector::EventRecord;
+    use crate::collector::SpanId;
+    use crate::collector::SpanKind;
+    use crate::collector::SpanRecord;
+    use crate::collector::SpanStatus;
+    use crate::collector::TraceId;
+
+    #[test]
+    fn validate_span_record() {
+        let validator = jsonschema::draft7::new(&span_record_json_schema()).unwrap();
+
+        let record = SpanRecord {
+            trace_id: TraceId(0x0af7651916cd43dd8448eb211c80319c),
+            span_id: SpanId(0xb7ad6b7169203331),
+            parent_id: SpanId(0x1),
+            begin_time_unix_ns: 1_700_000_000_000_000_000,
+            duration_ns: 42,
+            name: "root".into(),
+            properties: vec![("k1".into(), "v1".into())],
+            events: vec![EventRecord {
+                name: "event".into(),
+                timestamp_unix_ns: 1_700_000_000_000_000_001,
+                properties: vec![("k2".into(), "v2".into())],
+                level: EventLevel::Warn,
+            }],
+            status: SpanStatus::Error("failed".into()),
+            kind: SpanKind::Server,
+            links: vec![(TraceId(0x2), SpanId(0x3))],
+            sampled: true,
+            resource: None,
+        };
+        let json: Value = serde_json::from_str(&record.to_json()).unwrap();
+        assert!(validator.is_valid(&json));
+
+        let json: Value = serde_json::from_str(&SpanRecord::default().to_json()).unwrap();
+        assert!(validator.is_valid(&json));
+
+        let mut invalid = json.clone();
+        invalid["span_id"] = json!(42);
+        assert!(!validator.is_valid(&invalid));
+
+        let mut invalid = json;
+        invalid.as_object_mut().unwrap().remove("kind");
+        assert!(!validator.is_valid(&invalid));
+    }
+}
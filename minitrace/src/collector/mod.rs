@@ -4,27 +4,69 @@
 
 #![cfg_attr(test, allow(dead_code))]
 
+mod circuit_breaker_reporter;
 pub(crate) mod command;
 mod console_reporter;
+mod fallback_reporter;
+#[cfg(feature = "serde")]
+mod file_reporter;
+mod filtered_reporter;
 pub(crate) mod global_collector;
 pub(crate) mod id;
+#[cfg(feature = "serde")]
+mod json_schema;
+mod multi_reporter;
+mod rate_limiting_reporter;
+mod ring_buffer_reporter;
 mod test_reporter;
+mod threshold_reporter;
+#[cfg(all(feature = "wasm", target_family = "wasm"))]
+mod wasm_reporter;
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::time::Duration;
 
+pub use circuit_breaker_reporter::CircuitBreakerReporter;
+pub use circuit_breaker_reporter::CircuitState;
 pub use console_reporter::ConsoleReporter;
+pub use console_reporter::PrettyConsoleReporter;
+pub use fallback_reporter::FallbackReporter;
+pub use fallback_reporter::FallibleReporter;
+#[cfg(feature = "serde")]
+pub use file_reporter::FileReporter;
+pub use filtered_reporter::report_only_target;
+pub use filtered_reporter::FilteredReporter;
+pub use global_collector::registered_channel_count;
+pub use global_collector::replace_reporter;
+pub use global_collector::send_command_stats;
 #[cfg(not(test))]
 pub(crate) use global_collector::GlobalCollect;
 #[cfg(test)]
 pub(crate) use global_collector::MockGlobalCollect;
 pub use global_collector::Reporter;
+pub use global_collector::ReporterGuard;
+pub use global_collector::SpscStats;
+pub use id::FixedSequenceSpanIdGenerator;
+pub use id::SequentialSpanIdGenerator;
 pub use id::SpanId;
+pub use id::SpanIdGenerator;
 pub use id::TraceId;
+#[cfg(feature = "serde")]
+pub use json_schema::span_record_json_schema;
+pub use multi_reporter::MultiReporter;
+pub use rate_limiting_reporter::RateLimitingReporter;
+pub use ring_buffer_reporter::RingBufferHandle;
+pub use ring_buffer_reporter::RingBufferReporter;
 #[doc(hidden)]
 pub use test_reporter::TestReporter;
+pub use threshold_reporter::ThresholdReporter;
+#[cfg(all(feature = "wasm", target_family = "wasm"))]
+pub use wasm_reporter::flush_async_promise;
+#[cfg(all(feature = "wasm", target_family = "wasm"))]
+pub use wasm_reporter::WasmConsoleReporter;
 
 use crate::local::local_collector::LocalSpansInner;
 use crate::local::local_span_stack::LOCAL_SPAN_STACK;
@@ -41,9 +83,13 @@ pub enum SpanSet {
     SharedLocalSpans(Arc<LocalSpansInner>),
 }
 
+/// The resource attributes of a trace, see [`SpanRecord::resource`].
+pub(crate) type Resource = HashMap<Cow<'static, str>, Cow<'static, str>>;
+
 /// A record of a span that includes all the information about the span,
 /// such as its identifiers, timing information, name, and associated properties.
-#[derive(Clone, Debug, Default, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SpanRecord {
     pub trace_id: TraceId,
     pub span_id: SpanId,
@@ -53,14 +99,151 @@ pub struct SpanRecord {
     pub name: Cow<'static, str>,
     pub properties: Vec<(Cow<'static, str>, Cow<'static, str>)>,
     pub events: Vec<EventRecord>,
+    pub status: SpanStatus,
+    pub kind: SpanKind,
+    /// The spans linked to the span, which are not its parents, added by [`Span::add_link()`] or
+    /// [`LocalSpan::add_link()`].
+    ///
+    /// [`Span::add_link()`]: crate::Span::add_link
+    /// [`LocalSpan::add_link()`]: crate::local::LocalSpan::add_link
+    pub links: Vec<(TraceId, SpanId)>,
+    /// The resource attributes of the trace, such as `service.name`, given to
+    /// [`Span::root_with_resource()`] or else set by [`set_resource()`].
+    ///
+    /// The resource is shared by all the spans of a trace, and is not serialized.
+    ///
+    /// [`Span::root_with_resource()`]: crate::Span::root_with_resource
+    /// [`set_resource()`]: crate::set_resource
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub resource: Option<Arc<Resource>>,
+    /// Whether the trace is sampled. It is unset for the spans of a trace started with
+    /// [`SamplingDecision::RecordOnly`] or from a [`SpanContext`] that is not sampled, so that
+    /// reporters can, for example, only export those with an error status.
+    ///
+    /// Defaults to `true`.
+    #[cfg_attr(feature = "serde", serde(default = "sampled_by_default"))]
+    pub sampled: bool,
+}
+
+impl Default for SpanRecord {
+    fn default() -> Self {
+        SpanRecord {
+            trace_id: TraceId::default(),
+            span_id: SpanId::default(),
+            parent_id: SpanId::default(),
+            begin_time_unix_ns: 0,
+            duration_ns: 0,
+            name: Cow::default(),
+            properties: Vec::new(),
+            events: Vec::new(),
+            status: SpanStatus::default(),
+            kind: SpanKind::default(),
+            links: Vec::new(),
+            resource: None,
+            sampled: true,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl SpanRecord {
+    /// Serializes the `SpanRecord` into a JSON string.
+    ///
+    /// `TraceId` and `SpanId` are serialized as `0x`-prefixed hexadecimal strings.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("a SpanRecord can always be serialized into JSON")
+    }
+
+    /// Deserializes a `SpanRecord` from a JSON string produced by [`SpanRecord::to_json()`].
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+}
+
+/// The status of a span, following the [OpenTelemetry span status](https://opentelemetry.io/docs/concepts/signals/traces/#span-status).
+///
+/// The status of a span is `Unset` unless it is set by [`Span::set_status()`] or
+/// [`LocalSpan::set_status()`].
+///
+/// [`Span::set_status()`]: crate::Span::set_status
+/// [`LocalSpan::set_status()`]: crate::local::LocalSpan::set_status
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpanStatus {
+    /// The default status.
+    #[default]
+    Unset,
+    /// The operation has completed successfully.
+    Ok,
+    /// The operation contains an error, with a description of the error.
+    Error(Cow<'static, str>),
+}
+
+/// The role of a span in a request flow, following the [OpenTelemetry span kind](https://opentelemetry.io/docs/specs/otel/trace/api/#spankind).
+///
+/// The kind of a span is `Internal` unless it is set by [`Span::with_kind()`] or
+/// [`LocalSpan::with_kind()`].
+///
+/// [`Span::with_kind()`]: crate::Span::with_kind
+/// [`LocalSpan::with_kind()`]: crate::local::LocalSpan::with_kind
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpanKind {
+    /// An internal operation within an application.
+    #[default]
+    Internal,
+    /// The server side of a synchronous remote call.
+    Server,
+    /// The client side of a synchronous remote call.
+    Client,
+    /// The sender of an asynchronous message.
+    Producer,
+    /// The receiver of an asynchronous message.
+    Consumer,
+}
+
+/// The severity of an event.
+///
+/// The level of an event is `Info` unless it is added by [`Event::add_to_parent_with_level()`] or
+/// [`Event::add_to_local_parent_with_level()`]. Events below a level can be dropped by
+/// [`Config::min_event_level()`].
+///
+/// [`Event::add_to_parent_with_level()`]: crate::Event::add_to_parent_with_level
+/// [`Event::add_to_local_parent_with_level()`]: crate::Event::add_to_local_parent_with_level
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EventLevel {
+    /// Detailed information for debugging.
+    Debug,
+    /// Information about the normal operation.
+    #[default]
+    Info,
+    /// A potential problem.
+    Warn,
+    /// A failure.
+    Error,
+}
+
+impl EventLevel {
+    /// Returns the upper-case name of the level, e.g. `"INFO"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventLevel::Debug => "DEBUG",
+            EventLevel::Info => "INFO",
+            EventLevel::Warn => "WARN",
+            EventLevel::Error => "ERROR",
+        }
+    }
 }
 
 /// A record of an event that occurred during the execution of a span.
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EventRecord {
     pub name: Cow<'static, str>,
     pub timestamp_unix_ns: u64,
     pub properties: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    pub level: EventLevel,
 }
 
 #[doc(hidden)]
@@ -70,16 +253,50 @@ pub struct CollectTokenItem {
     pub parent_id: SpanId,
     pub collect_id: usize,
     pub is_root: bool,
+    pub sampled: bool,
+}
+
+/// The sampling decision of a trace started by [`Span::root_with_sampling()`].
+///
+/// [`Span::root_with_sampling()`]: crate::Span::root_with_sampling
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SamplingDecision {
+    /// The spans are recorded and reported with [`SpanRecord::sampled`] set, and the trace is
+    /// propagated downstream as sampled.
+    RecordAndSample,
+    /// The spans are recorded and reported, but [`SpanRecord::sampled`] is not set and the trace
+    /// is propagated downstream as not sampled. Reporters may, for example, only export such
+    /// spans if they have an error status.
+    RecordOnly,
+    /// Nothing is recorded; the root span is a noop span.
+    Drop,
 }
 
 /// A struct representing the context of a span, including its [`TraceId`] and [`SpanId`].
 ///
 /// [`TraceId`]: crate::collector::TraceId
 /// [`SpanId`]: crate::collector::SpanId
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SpanContext {
     pub trace_id: TraceId,
     pub span_id: SpanId,
+    // The vendor-specific W3C `tracestate`, see `SpanContext::tracestate()`.
+    pub(crate) tracestate: Option<Arc<str>>,
+    // Whether the trace is sampled, see `SpanContext::sampled()`.
+    #[cfg_attr(feature = "serde", serde(default = "sampled_by_default"))]
+    pub(crate) sampled: bool,
+}
+
+impl Default for SpanContext {
+    fn default() -> Self {
+        Self::new(TraceId::default(), SpanId::default())
+    }
+}
+
+#[cfg(feature = "serde")]
+fn sampled_by_default() -> bool {
+    true
 }
 
 impl SpanContext {
@@ -96,7 +313,12 @@ impl SpanContext {
     /// [`TraceId`]: crate::collector::TraceId
     /// [`SpanId`]: crate::collector::SpanId
     pub fn new(trace_id: TraceId, span_id: SpanId) -> Self {
-        Self { trace_id, span_id }
+        Self {
+            trace_id,
+            span_id,
+            tracestate: None,
+            sampled: true,
+        }
     }
 
     /// Create a new `SpanContext` with a random trace id.
@@ -112,6 +334,8 @@ impl SpanContext {
         Self {
             trace_id: TraceId(rand::random()),
             span_id: SpanId::default(),
+            tracestate: None,
+            sampled: true,
         }
     }
 
@@ -142,10 +366,68 @@ impl SpanContext {
             Some(Self {
                 trace_id: collect_token.trace_id,
                 span_id: collect_token.parent_id,
+                tracestate: inner.tracestate.clone(),
+                sampled: collect_token.sampled,
             })
         }
     }
 
+    /// Creates a `SpanContext` from a collected [`SpanRecord`].
+    ///
+    /// This is intended for replaying recorded traces and for testing. The returned context
+    /// refers to a span that has already completed, so it can only be used as the parent of
+    /// new spans, for example via [`Span::root()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::SpanRecord;
+    /// use minitrace::prelude::*;
+    ///
+    /// let record = SpanRecord {
+    ///     trace_id: TraceId(12),
+    ///     span_id: SpanId(34),
+    ///     ..SpanRecord::default()
+    /// };
+    /// let context = SpanContext::from_span_record(&record);
+    ///
+    /// assert_eq!(context.trace_id, TraceId(12));
+    /// assert_eq!(context.span_id, SpanId(34));
+    /// ```
+    ///
+    /// [`Span::root()`]: crate::Span::root
+    pub fn from_span_record(record: &SpanRecord) -> Self {
+        Self {
+            trace_id: record.trace_id,
+            span_id: record.span_id,
+            tracestate: None,
+            sampled: record.sampled,
+        }
+    }
+
+    /// Creates the `SpanContext` to start a child of a collected [`SpanRecord`] with, typically
+    /// in a downstream service that the recorded span called.
+    ///
+    /// The recorded span becomes the parent of the new span. Like
+    /// [`SpanContext::from_span_record()`], this is intended for replay and testing scenarios.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::SpanRecord;
+    /// use minitrace::prelude::*;
+    ///
+    /// let record = SpanRecord {
+    ///     trace_id: TraceId(12),
+    ///     span_id: SpanId(34),
+    ///     ..SpanRecord::default()
+    /// };
+    /// let child = Span::root("child", SpanContext::child_context_of_span_record(&record));
+    /// ```
+    pub fn child_context_of_span_record(record: &SpanRecord) -> Self {
+        Self::from_span_record(record)
+    }
+
     /// Creates a `SpanContext` from the current local parent span. If there is no
     /// local parent span, this function will return `None`.
     ///
@@ -169,18 +451,80 @@ impl SpanContext {
         {
             let stack = LOCAL_SPAN_STACK.try_with(Rc::clone).ok()?;
 
-            let mut stack = stack.borrow_mut();
-            let collect_token = stack.current_collect_token()?[0];
+            let stack = stack.borrow();
+            let span_line = stack.current_span_line_ref()?;
 
             Some(Self {
-                trace_id: collect_token.trace_id,
-                span_id: collect_token.parent_id,
+                trace_id: span_line.current_trace_id()?,
+                span_id: span_line.current_span_id()?,
+                tracestate: span_line.tracestate().cloned(),
+                sampled: span_line.current_sampled()?,
             })
         }
     }
 
+    /// Returns the [`SpanId`] of the current local parent span, or `None` if there is no local
+    /// parent span.
+    ///
+    /// This is a cheaper alternative to [`SpanContext::current_local_parent()`] for when only the
+    /// span id is needed, e.g. to correlate logs with the current span.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// assert_eq!(SpanContext::current_span_id(), None);
+    ///
+    /// let span = Span::root("root", SpanContext::random());
+    /// let _guard = span.set_local_parent();
+    ///
+    /// let span_id = SpanContext::current_span_id();
+    /// ```
+    pub fn current_span_id() -> Option<SpanId> {
+        #[cfg(not(feature = "enable"))]
+        {
+            None
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            LOCAL_SPAN_STACK
+                .try_with(|stack| stack.borrow().current_span_line_ref()?.current_span_id())
+                .ok()?
+        }
+    }
+
+    /// Returns the [`TraceId`] of the current local parent span, or `None` if there is no local
+    /// parent span.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span = Span::root("root", SpanContext::random());
+    /// let _guard = span.set_local_parent();
+    ///
+    /// let trace_id = SpanContext::current_trace_id();
+    /// ```
+    pub fn current_trace_id() -> Option<TraceId> {
+        #[cfg(not(feature = "enable"))]
+        {
+            None
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            LOCAL_SPAN_STACK
+                .try_with(|stack| stack.borrow().current_span_line_ref()?.current_trace_id())
+                .ok()?
+        }
+    }
+
     /// Decodes the `SpanContext` from a [W3C Trace Context](https://www.w3.org/TR/trace-context/)
-    /// `traceparent` header string.
+    /// `traceparent` header string. The context is [sampled](SpanContext::sampled()) unless the
+    /// sampled trace-flag is unset.
     ///
     /// # Examples
     ///
@@ -208,17 +552,23 @@ impl SpanContext {
             parts.next(),
             parts.next(),
         ) {
-            (Some("00"), Some(trace_id), Some(span_id), Some(_), None) => {
+            (Some("00"), Some(trace_id), Some(span_id), Some(trace_flags), None) => {
                 let trace_id = u128::from_str_radix(trace_id, 16).ok()?;
                 let span_id = u64::from_str_radix(span_id, 16).ok()?;
-                Some(Self::new(TraceId(trace_id), SpanId(span_id)))
+                let sampled =
+                    u8::from_str_radix(trace_flags, 16).map_or(true, |flags| flags & 1 == 1);
+                Some(Self {
+                    sampled,
+                    ..Self::new(TraceId(trace_id), SpanId(span_id))
+                })
             }
             _ => None,
         }
     }
 
     /// Encodes the `SpanContext` into a [W3C Trace Context](https://www.w3.org/TR/trace-context/)
-    /// `traceparent` header string.
+    /// `traceparent` header string, with the sampled trace-flag set if the context is
+    /// [sampled](SpanContext::sampled()).
     ///
     /// # Examples
     ///
@@ -234,7 +584,7 @@ impl SpanContext {
     /// );
     /// ```
     pub fn encode_w3c_traceparent(&self) -> String {
-        Self::encode_w3c_traceparent_with_sampled(self, true)
+        Self::encode_w3c_traceparent_with_sampled(self, self.sampled)
     }
 
     /// Encodes the `SpanContext` as a [W3C Trace Context](https://www.w3.org/TR/trace-context/)
@@ -259,119 +609,1088 @@ impl SpanContext {
             self.trace_id.0, self.span_id.0, sampled as u8,
         )
     }
-}
-
-/// Configuration of the behavior of the global collector.
-#[must_use]
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub struct Config {
-    pub(crate) max_spans_per_trace: Option<usize>,
-    pub(crate) report_interval: Duration,
-    pub(crate) report_before_root_finish: bool,
-}
 
-impl Config {
-    /// Sets a soft limit for the total number of spans and events in a trace, typically
-    /// used to prevent out-of-memory issues.
+    /// Sets the W3C `tracestate` of the `SpanContext` and returns the modified `SpanContext`.
     ///
-    /// The default value is `None`.
+    /// The `tracestate` is encoded as is by [`SpanContext::encode_w3c_headers()`], so it should
+    /// be a valid `tracestate` header value.
     ///
-    /// # Note
+    /// # Examples
     ///
-    /// The root span will always be collected, so the actual number of collected spans
-    /// may exceed the specified limit.
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span_context = SpanContext::random().with_tracestate("vendor=value");
+    ///
+    /// assert_eq!(span_context.tracestate(), Some("vendor=value"));
+    /// ```
+    pub fn with_tracestate(mut self, tracestate: impl Into<Arc<str>>) -> Self {
+        self.tracestate = Some(tracestate.into());
+        self
+    }
+
+    /// Returns the vendor-specific W3C `tracestate` of the `SpanContext`, which is propagated by
+    /// the W3C headers.
+    ///
+    /// It is kept by the spans started from this context and their descendants, so that
+    /// [`SpanContext::from_span()`] and [`SpanContext::current_local_parent()`] return it again,
+    /// but it is not reported with the spans.
     ///
     /// # Examples
     ///
     /// ```
-    /// use minitrace::collector::Config;
+    /// use minitrace::prelude::*;
     ///
-    /// let config = Config::default().max_spans_per_trace(Some(100));
-    /// minitrace::set_reporter(minitrace::collector::ConsoleReporter, config);
+    /// let span_context = SpanContext::decode_w3c_headers(
+    ///     "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+    ///     Some("rojo=00f067aa0ba902b7"),
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(span_context.tracestate(), Some("rojo=00f067aa0ba902b7"));
     /// ```
-    pub fn max_spans_per_trace(self, max_spans_per_trace: Option<usize>) -> Self {
-        Self {
-            max_spans_per_trace,
-            ..self
-        }
+    pub fn tracestate(&self) -> Option<&str> {
+        self.tracestate.as_deref()
     }
 
-    /// Sets the time duration between two batch reports.
-    #[deprecated(
-        since = "0.6.7",
-        note = "Please use `report_interval` instead. This method is now a no-op."
-    )]
-    pub fn batch_report_interval(self, _batch_report_interval: Duration) -> Self {
+    /// Sets whether the trace is sampled and returns the modified `SpanContext`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span_context = SpanContext::random().with_sampled(false);
+    ///
+    /// assert!(!span_context.sampled());
+    /// ```
+    pub fn with_sampled(mut self, sampled: bool) -> Self {
+        self.sampled = sampled;
         self
     }
 
-    /// Sets the soft limit for the maximum number of spans in a batch report.
-    #[deprecated(
-        since = "0.6.7",
-        note = "Please use `report_interval` instead. This method is now a no-op."
-    )]
-    pub fn batch_report_max_spans(self, _batch_report_max_spans: Option<usize>) -> Self {
-        self
+    /// Returns whether the trace is sampled, which is the trace-flags of the W3C `traceparent`.
+    /// The spans started from a context that is not sampled are reported with
+    /// [`SpanRecord::sampled`] unset.
+    ///
+    /// Defaults to `true`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span_context = SpanContext::decode_w3c_traceparent(
+    ///     "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00",
+    /// )
+    /// .unwrap();
+    ///
+    /// assert!(!span_context.sampled());
+    /// ```
+    pub fn sampled(&self) -> bool {
+        self.sampled
     }
 
-    /// Sets the time duration between two reports. The reporter will be invoked when the specified
-    /// duration elapses, even if no spans have been collected. This allows for batching in the
-    /// reporter.
+    /// Decodes the `SpanContext` from the [W3C Trace Context](https://www.w3.org/TR/trace-context/)
+    /// `traceparent` and `tracestate` header strings.
     ///
-    /// In some scenarios, particularly under high load, you may notice spans being lost. This is
-    /// likely due to the channel being full during the reporting interval. To mitigate this issue,
-    /// consider reducing the report interval, potentially down to zero, to prevent losing spans.
+    /// Returns `None` if the `traceparent` is invalid. A malformed `tracestate`, including one with
+    /// duplicate keys or more than 32 entries, is discarded as required by the specification,
+    /// leaving `tracestate` as `None`. Whitespace around the entries and empty entries are removed.
     ///
-    /// The default value is 10 milliseconds.
-    pub fn report_interval(self, report_interval: Duration) -> Self {
-        Self {
-            report_interval,
-            ..self
-        }
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span_context = SpanContext::decode_w3c_headers(
+    ///     "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+    ///     Some("rojo=00f067aa0ba902b7, congo=t61rcWkgMzE"),
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(span_context.span_id, SpanId(0xb7ad6b7169203331));
+    /// assert_eq!(
+    ///     span_context.tracestate(),
+    ///     Some("rojo=00f067aa0ba902b7,congo=t61rcWkgMzE")
+    /// );
+    /// ```
+    pub fn decode_w3c_headers(traceparent: &str, tracestate: Option<&str>) -> Option<Self> {
+        let mut span_context = Self::decode_w3c_traceparent(traceparent)?;
+        span_context.tracestate = tracestate
+            .and_then(normalize_w3c_tracestate)
+            .map(Arc::from);
+        Some(span_context)
     }
 
-    /// Configures whether to report spans before the root span finishes.
-    ///
-    /// If set to `true`, some spans may be reported before they are canceled, making it
-    /// difficult to cancel all spans in a trace.
+    /// Encodes the `SpanContext` into the [W3C Trace Context](https://www.w3.org/TR/trace-context/)
+    /// `traceparent` and `tracestate` headers.
     ///
-    /// The default value is `false`.
+    /// The `tracestate` header value is empty if the `SpanContext` has no `tracestate`, in which
+    /// case the header should not be sent.
     ///
     /// # Examples
     ///
     /// ```
-    /// use minitrace::collector::Config;
+    /// use minitrace::prelude::*;
     ///
-    /// let config = Config::default().report_before_root_finish(true);
-    /// minitrace::set_reporter(minitrace::collector::ConsoleReporter, config);
+    /// let span_context = SpanContext::new(TraceId(12), SpanId(34)).with_tracestate("rojo=1");
+    ///
+    /// assert_eq!(span_context.encode_w3c_headers(), [
+    ///     (
+    ///         "traceparent",
+    ///         "00-0000000000000000000000000000000c-0000000000000022-01".to_string()
+    ///     ),
+    ///     ("tracestate", "rojo=1".to_string()),
+    /// ]);
     /// ```
-    pub fn report_before_root_finish(self, report_before_root_finish: bool) -> Self {
-        Self {
-            report_before_root_finish,
-            ..self
-        }
+    pub fn encode_w3c_headers(&self) -> [(&'static str, String); 2] {
+        [
+            ("traceparent", self.encode_w3c_traceparent()),
+            ("tracestate", self.tracestate().unwrap_or_default().to_string()),
+        ]
     }
-}
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            max_spans_per_trace: None,
-            report_interval: Duration::from_millis(10),
-            report_before_root_finish: false,
-        }
-    }
-}
+    /// Decodes the `SpanContext` from a [B3](https://github.com/openzipkin/b3-propagation) single
+    /// `b3` header string in the format of `{TraceId}-{SpanId}-{SamplingState}-{ParentSpanId}`.
+    ///
+    /// Both 128-bit and 64-bit trace ids are accepted. The sampling state (`1`, `0` or `d`) and
+    /// the parent span id are optional. The decoded context is [sampled](SpanContext::sampled())
+    /// unless the sampling state is `0`, and the parent span id is validated but not retained. A
+    /// header carrying only the sampling state has no context to decode, so `None` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span_context = SpanContext::decode_b3_single_header(
+    ///     "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-1-05e3ac9a4f6e3b90",
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(
+    ///     span_context.trace_id,
+    ///     TraceId(0x80f198ee56343ba864fe8b2a57d3eff7)
+    /// );
+    /// assert_eq!(span_context.span_id, SpanId(0xe457b5a2e4d86bd1));
+    /// ```
+    pub fn decode_b3_single_header(header: &str) -> Option<Self> {
+        let mut parts = header.split('-');
 
-#[cfg(test)]
-mod tests {
+        let trace_id = decode_b3_trace_id(parts.next()?)?;
+        let span_id = decode_b3_span_id(parts.next()?)?;
+        let sampled = match parts.next() {
+            Some("0") => false,
+            Some("1" | "d") | None => true,
+            Some(_) => return None,
+        };
+        if let Some(parent_span_id) = parts.next() {
+            decode_b3_span_id(parent_span_id)?;
+        }
+        if parts.next().is_some() {
+            return None;
+        }
 
-    use super::*;
+        Some(Self {
+            sampled,
+            ..Self::new(TraceId(trace_id), SpanId(span_id))
+        })
+    }
 
-    #[test]
-    fn w3c_traceparent() {
-        let span_context = SpanContext::decode_w3c_traceparent(
-            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+    /// Encodes the `SpanContext` into a [B3](https://github.com/openzipkin/b3-propagation) single
+    /// `b3` header string, with the sampling state `1` if `sampled`, otherwise `0`.
+    ///
+    /// Pass [`SpanContext::sampled()`] to propagate the sampling decision of the context.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span_context = SpanContext::new(TraceId(12), SpanId(34));
+    ///
+    /// assert_eq!(
+    ///     span_context.encode_b3_single_header(span_context.sampled()),
+    ///     "0000000000000000000000000000000c-0000000000000022-1"
+    /// );
+    /// ```
+    pub fn encode_b3_single_header(&self, sampled: bool) -> String {
+        format!(
+            "{:032x}-{:016x}-{}",
+            self.trace_id.0, self.span_id.0, sampled as u8,
+        )
+    }
+
+    /// Decodes the `SpanContext` from the [B3](https://github.com/openzipkin/b3-propagation)
+    /// `X-B3-TraceId`, `X-B3-SpanId` and the optional `X-B3-Sampled` header values.
+    ///
+    /// Both 128-bit and 64-bit trace ids are accepted. The sampling state may be `1`, `0`, `d`,
+    /// or the legacy `true` and `false`. The decoded context is [sampled](SpanContext::sampled())
+    /// unless the sampling state is `0` or `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span_context = SpanContext::decode_b3_multi_headers(
+    ///     "80f198ee56343ba864fe8b2a57d3eff7",
+    ///     "e457b5a2e4d86bd1",
+    ///     Some("1"),
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(
+    ///     span_context.trace_id,
+    ///     TraceId(0x80f198ee56343ba864fe8b2a57d3eff7)
+    /// );
+    /// assert_eq!(span_context.span_id, SpanId(0xe457b5a2e4d86bd1));
+    /// ```
+    pub fn decode_b3_multi_headers(
+        trace_id: &str,
+        span_id: &str,
+        sampled: Option<&str>,
+    ) -> Option<Self> {
+        let sampled = match sampled {
+            Some("0" | "false") => false,
+            Some("1" | "d" | "true") | None => true,
+            Some(_) => return None,
+        };
+
+        let trace_id = decode_b3_trace_id(trace_id)?;
+        let span_id = decode_b3_span_id(span_id)?;
+        Some(Self {
+            sampled,
+            ..Self::new(TraceId(trace_id), SpanId(span_id))
+        })
+    }
+
+    /// Encodes the `SpanContext` into the [B3](https://github.com/openzipkin/b3-propagation)
+    /// `X-B3-TraceId`, `X-B3-SpanId` and `X-B3-Sampled` headers, with `X-B3-Sampled` set to `1`
+    /// if `sampled`, otherwise `0`.
+    ///
+    /// Pass [`SpanContext::sampled()`] to propagate the sampling decision of the context.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span_context = SpanContext::new(TraceId(12), SpanId(34));
+    ///
+    /// assert_eq!(span_context.encode_b3_multi_headers(false), [
+    ///     (
+    ///         "X-B3-TraceId",
+    ///         "0000000000000000000000000000000c".to_string()
+    ///     ),
+    ///     ("X-B3-SpanId", "0000000000000022".to_string()),
+    ///     ("X-B3-Sampled", "0".to_string()),
+    /// ]);
+    /// ```
+    pub fn encode_b3_multi_headers(&self, sampled: bool) -> [(&'static str, String); 3] {
+        [
+            ("X-B3-TraceId", format!("{:032x}", self.trace_id.0)),
+            ("X-B3-SpanId", format!("{:016x}", self.span_id.0)),
+            ("X-B3-Sampled", (sampled as u8).to_string()),
+        ]
+    }
+
+    /// Decodes the `SpanContext` from the [Datadog] `x-datadog-trace-id`, `x-datadog-parent-id` and
+    /// the optional `x-datadog-sampling-priority` header values.
+    ///
+    /// The ids are 64-bit decimal integers. The upper 64 bits of the decoded trace id are zero.
+    /// The sampling priority is an integer, where a positive value keeps the trace, so the decoded
+    /// context is [sampled](SpanContext::sampled()) unless the priority is zero or negative.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span_context = SpanContext::decode_datadog_headers(
+    ///     "7277407061855694839",
+    ///     "16462263743245208529",
+    ///     Some("1"),
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(span_context.trace_id, TraceId(7277407061855694839));
+    /// assert_eq!(span_context.span_id, SpanId(16462263743245208529));
+    /// ```
+    ///
+    /// [Datadog]: https://docs.datadoghq.com/tracing/trace_collection/trace_context_propagation/
+    pub fn decode_datadog_headers(
+        trace_id: &str,
+        parent_id: &str,
+        sampling_priority: Option<&str>,
+    ) -> Option<Self> {
+        let sampled = match sampling_priority {
+            Some(sampling_priority) => sampling_priority.parse::<i32>().ok()? > 0,
+            None => true,
+        };
+
+        let trace_id = decode_datadog_id(trace_id)?;
+        let parent_id = decode_datadog_id(parent_id)?;
+        Some(Self {
+            sampled,
+            ..Self::new(TraceId(trace_id as u128), SpanId(parent_id))
+        })
+    }
+
+    /// Encodes the `SpanContext` into the [Datadog] `x-datadog-trace-id`, `x-datadog-parent-id` and
+    /// `x-datadog-sampling-priority` headers.
+    ///
+    /// Datadog trace ids are 64-bit, so only the lower 64 bits of the trace id are encoded. A
+    /// trace started with a 128-bit [`TraceId`] loses its upper 64 bits when it is propagated
+    /// this way. The sampling priority is `1` if `sampled`, otherwise `0`. Pass
+    /// [`SpanContext::sampled()`] to propagate the sampling decision of the context.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span_context = SpanContext::new(TraceId(12), SpanId(34));
+    ///
+    /// assert_eq!(span_context.encode_datadog_headers(true), [
+    ///     ("x-datadog-trace-id", "12".to_string()),
+    ///     ("x-datadog-parent-id", "34".to_string()),
+    ///     ("x-datadog-sampling-priority", "1".to_string()),
+    /// ]);
+    /// ```
+    ///
+    /// [Datadog]: https://docs.datadoghq.com/tracing/trace_collection/trace_context_propagation/
+    pub fn encode_datadog_headers(&self, sampled: bool) -> [(&'static str, String); 3] {
+        [
+            ("x-datadog-trace-id", (self.trace_id.0 as u64).to_string()),
+            ("x-datadog-parent-id", self.span_id.0.to_string()),
+            ("x-datadog-sampling-priority", (sampled as u8).to_string()),
+        ]
+    }
+
+    /// Decodes the `SpanContext` from an [AWS X-Ray] `X-Amzn-Trace-Id` header string, e.g.
+    /// `Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1`.
+    ///
+    /// The X-Ray trace id `1-{epoch}-{random}` becomes a [`TraceId`] whose upper 32 bits are the
+    /// 8-hex-digit epoch and whose lower 96 bits are the 24-hex-digit random part. The span id is
+    /// taken from `Parent`, and is `0` if the header has no `Parent`, e.g. when it is added by a
+    /// load balancer. The decoded context is [sampled](SpanContext::sampled()) unless `Sampled` is
+    /// `0`. Other fields are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span_context = SpanContext::decode_xray_header(
+    ///     "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1",
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(
+    ///     span_context.trace_id,
+    ///     TraceId(0x5759e988bd862e3fe1be46a994272793)
+    /// );
+    /// assert_eq!(span_context.span_id, SpanId(0x53995c3f42cd8ad8));
+    /// ```
+    ///
+    /// [AWS X-Ray]: https://docs.aws.amazon.com/xray/latest/devguide/xray-concepts.html#xray-concepts-tracingheader
+    pub fn decode_xray_header(header: &str) -> Option<Self> {
+        let mut trace_id = None;
+        let mut span_id = SpanId::default();
+        let mut sampled = true;
+
+        for field in header.split(';').map(str::trim) {
+            if field.is_empty() {
+                continue;
+            }
+            let (key, value) = field.split_once('=')?;
+            match key {
+                "Root" => {
+                    let (epoch, random) = value.strip_prefix("1-")?.split_once('-')?;
+                    if !is_hex(epoch, 8) || !is_hex(random, 24) {
+                        return None;
+                    }
+                    let epoch = u128::from_str_radix(epoch, 16).ok()?;
+                    let random = u128::from_str_radix(random, 16).ok()?;
+                    trace_id = Some(TraceId((epoch << 96) | random));
+                }
+                "Parent" => {
+                    if !is_hex(value, 16) {
+                        return None;
+                    }
+                    span_id = SpanId(u64::from_str_radix(value, 16).ok()?);
+                }
+                "Sampled" => match value {
+                    "0" => sampled = false,
+                    "1" | "?" => sampled = true,
+                    _ => return None,
+                },
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            sampled,
+            ..Self::new(trace_id?, span_id)
+        })
+    }
+
+    /// Encodes the `SpanContext` into an [AWS X-Ray] `X-Amzn-Trace-Id` header string, with
+    /// `Sampled=1` if `sampled`, otherwise `Sampled=0`. Pass [`SpanContext::sampled()`] to
+    /// propagate the sampling decision of the context.
+    ///
+    /// The upper 32 bits of the [`TraceId`] are encoded as the epoch of the X-Ray trace id, and
+    /// the lower 96 bits as its random part, which is the reverse of
+    /// [`SpanContext::decode_xray_header()`].
+    ///
+    /// X-Ray rejects the traces whose epoch is more than 30 days away from the current time. This
+    /// is the case for a randomly generated `TraceId`, such as one created by
+    /// [`SpanContext::random()`], since its upper 32 bits are not a timestamp. The epoch is then
+    /// replaced by the current time from [`SystemTime::now()`], so the trace id seen by X-Ray
+    /// differs from the one reported by minitrace in its upper 32 bits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span_context = SpanContext::random();
+    /// let header = span_context.encode_xray_header(true);
+    ///
+    /// assert!(header.starts_with("Root=1-"));
+    /// assert!(header.ends_with(";Sampled=1"));
+    /// ```
+    ///
+    /// [AWS X-Ray]: https://docs.aws.amazon.com/xray/latest/devguide/xray-concepts.html#xray-concepts-tracingheader
+    /// [`SystemTime::now()`]: std::time::SystemTime::now
+    pub fn encode_xray_header(&self, sampled: bool) -> String {
+        const MAX_EPOCH_DIFF_SECS: u64 = 30 * 24 * 60 * 60;
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut epoch = (self.trace_id.0 >> 96) as u64;
+        if epoch.abs_diff(now) > MAX_EPOCH_DIFF_SECS {
+            epoch = now;
+        }
+        let random = self.trace_id.0 & ((1 << 96) - 1);
+
+        format!(
+            "Root=1-{:08x}-{:024x};Parent={:016x};Sampled={}",
+            epoch as u32, random, self.span_id.0, sampled as u8
+        )
+    }
+
+    /// Injects the `SpanContext` into gRPC metadata as the
+    /// [W3C Trace Context](https://www.w3.org/TR/trace-context/) `traceparent` and `tracestate`
+    /// entries, encoded by [`SpanContext::encode_w3c_headers()`].
+    ///
+    /// The `tracestate` entry is only inserted if the `SpanContext` has one. Existing entries are
+    /// replaced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let mut metadata = tonic::metadata::MetadataMap::new();
+    /// SpanContext::new(TraceId(12), SpanId(34)).inject_grpc_metadata(&mut metadata);
+    ///
+    /// assert_eq!(
+    ///     metadata.get("traceparent").unwrap().to_str().unwrap(),
+    ///     "00-0000000000000000000000000000000c-0000000000000022-01"
+    /// );
+    /// ```
+    #[cfg(feature = "tonic")]
+    pub fn inject_grpc_metadata(&self, metadata: &mut tonic::metadata::MetadataMap) {
+        for (key, value) in self.encode_w3c_headers() {
+            if value.is_empty() {
+                continue;
+            }
+            if let Ok(value) = value.parse() {
+                metadata.insert(key, value);
+            }
+        }
+    }
+
+    /// Extracts the `SpanContext` from the
+    /// [W3C Trace Context](https://www.w3.org/TR/trace-context/) `traceparent` and `tracestate`
+    /// entries of gRPC metadata, decoded by [`SpanContext::decode_w3c_headers()`].
+    ///
+    /// Returns `None` if the `traceparent` entry is absent or invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let mut metadata = tonic::metadata::MetadataMap::new();
+    /// SpanContext::new(TraceId(12), SpanId(34)).inject_grpc_metadata(&mut metadata);
+    ///
+    /// let span_context = SpanContext::extract_grpc_metadata(&metadata).unwrap();
+    /// assert_eq!(span_context.trace_id, TraceId(12));
+    /// assert_eq!(span_context.span_id, SpanId(34));
+    /// ```
+    #[cfg(feature = "tonic")]
+    pub fn extract_grpc_metadata(metadata: &tonic::metadata::MetadataMap) -> Option<Self> {
+        let traceparent = metadata.get("traceparent")?.to_str().ok()?;
+        let tracestate = metadata
+            .get("tracestate")
+            .and_then(|tracestate| tracestate.to_str().ok());
+        Self::decode_w3c_headers(traceparent, tracestate)
+    }
+}
+
+fn decode_b3_trace_id(trace_id: &str) -> Option<u128> {
+    if !matches!(trace_id.len(), 16 | 32) || !trace_id.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u128::from_str_radix(trace_id, 16).ok()
+}
+
+fn decode_b3_span_id(span_id: &str) -> Option<u64> {
+    if span_id.len() != 16 || !span_id.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u64::from_str_radix(span_id, 16).ok()
+}
+
+fn is_hex(s: &str, len: usize) -> bool {
+    s.len() == len && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn decode_datadog_id(id: &str) -> Option<u64> {
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    id.parse().ok()
+}
+
+/// Validates a W3C `tracestate` and joins its non-empty entries with `,`.
+fn normalize_w3c_tracestate(tracestate: &str) -> Option<String> {
+    const MAX_ENTRIES: usize = 32;
+
+    let mut keys = Vec::new();
+    let mut entries = Vec::new();
+    for entry in tracestate.split(',') {
+        let entry = entry.trim_matches(|c| c == ' ' || c == '\t');
+        if entry.is_empty() {
+            continue;
+        }
+        let (key, value) = entry.split_once('=')?;
+        if !is_valid_w3c_tracestate_key(key)
+            || !is_valid_w3c_tracestate_value(value)
+            || keys.contains(&key)
+        {
+            return None;
+        }
+        keys.push(key);
+        entries.push(entry);
+    }
+
+    if entries.is_empty() || entries.len() > MAX_ENTRIES {
+        return None;
+    }
+    Some(entries.join(","))
+}
+
+fn is_valid_w3c_tracestate_key(key: &str) -> bool {
+    fn is_valid_part(part: &str, max_len: usize, is_valid_first: fn(&u8) -> bool) -> bool {
+        let bytes = part.as_bytes();
+        !bytes.is_empty()
+            && bytes.len() <= max_len
+            && is_valid_first(&bytes[0])
+            && bytes[1..]
+                .iter()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b"_-*/".contains(b))
+    }
+
+    match key.split_once('@') {
+        // A multi-tenant key in the format of `{tenant-id}@{system-id}`
+        Some((tenant_id, system_id)) => {
+            is_valid_part(tenant_id, 241, |b| {
+                b.is_ascii_lowercase() || b.is_ascii_digit()
+            }) && is_valid_part(system_id, 14, u8::is_ascii_lowercase)
+        }
+        None => is_valid_part(key, 256, u8::is_ascii_lowercase),
+    }
+}
+
+fn is_valid_w3c_tracestate_value(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    !bytes.is_empty()
+        && bytes.len() <= 256
+        && bytes
+            .iter()
+            .all(|&b| (b' '..=b'~').contains(&b) && b != b',' && b != b'=')
+        && bytes[bytes.len() - 1] != b' '
+}
+
+/// The strategy to apply when the local span queue of a thread is full.
+///
+/// Each local parent set up by [`Span::set_local_parent()`] buffers its [`LocalSpan`]s and
+/// [`Event`]s in a bounded queue before they are submitted to the global collector.
+///
+/// [`Span::set_local_parent()`]: crate::Span::set_local_parent
+/// [`LocalSpan`]: crate::local::LocalSpan
+/// [`Event`]: crate::Event
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum EvictionPolicy {
+    /// Discard the new spans silently.
+    #[default]
+    DropNewest,
+    /// Discard the oldest finished span to make room for the new span. The children of the
+    /// discarded span are re-attached to its parent.
+    ///
+    /// If all spans in the queue are still running, the new span is discarded.
+    DropOldest,
+    /// Discard the new spans, and insert a span named `"[N spans truncated]"` that covers the
+    /// period in which the spans were discarded.
+    InsertMarker,
+}
+
+// The minimum `report_interval`, which keeps the global collector thread from spinning.
+const MIN_REPORT_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Configuration of the behavior of the global collector.
+#[must_use]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Config {
+    pub(crate) max_spans_per_trace: Option<usize>,
+    pub(crate) report_interval: Duration,
+    pub(crate) report_before_root_finish: bool,
+    pub(crate) report_long_running_threshold: Option<Duration>,
+    pub(crate) eviction_policy: EvictionPolicy,
+    pub(crate) min_event_level: EventLevel,
+    pub(crate) max_events_per_span: Option<usize>,
+    pub(crate) max_spans_per_thread: Option<usize>,
+    pub(crate) report_on_replace: bool,
+}
+
+impl Config {
+    /// Creates a [`ConfigBuilder`] to construct a validated `Config`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use minitrace::collector::Config;
+    ///
+    /// let config = Config::builder()
+    ///     .max_spans_per_trace(Some(100))
+    ///     .report_interval(Duration::from_secs(1))
+    ///     .build()
+    ///     .unwrap();
+    /// minitrace::set_reporter(minitrace::collector::ConsoleReporter, config);
+    /// ```
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    /// Sets a soft limit for the total number of spans and events in a trace, typically
+    /// used to prevent out-of-memory issues.
+    ///
+    /// The default value is `None`, unless minitrace is compiled with the `MINITRACE_MAX_SPANS`
+    /// environment variable set to a number, e.g. `MINITRACE_MAX_SPANS=1000 cargo build`, in which
+    /// case that number becomes the default. Setting the limit here at runtime always overrides
+    /// the compile-time default.
+    ///
+    /// # Note
+    ///
+    /// The root span will always be collected, so the actual number of collected spans
+    /// may exceed the specified limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::Config;
+    ///
+    /// let config = Config::default().max_spans_per_trace(Some(100));
+    /// minitrace::set_reporter(minitrace::collector::ConsoleReporter, config);
+    /// ```
+    pub fn max_spans_per_trace(self, max_spans_per_trace: Option<usize>) -> Self {
+        Self {
+            max_spans_per_trace,
+            ..self
+        }
+    }
+
+    /// Sets the time duration between two batch reports.
+    #[deprecated(
+        since = "0.6.7",
+        note = "Please use `report_interval` instead. This method is now a no-op."
+    )]
+    pub fn batch_report_interval(self, _batch_report_interval: Duration) -> Self {
+        self
+    }
+
+    /// Sets the soft limit for the maximum number of spans in a batch report.
+    #[deprecated(
+        since = "0.6.7",
+        note = "Please use `report_interval` instead. This method is now a no-op."
+    )]
+    pub fn batch_report_max_spans(self, _batch_report_max_spans: Option<usize>) -> Self {
+        self
+    }
+
+    /// Sets the time duration between two reports. The reporter will be invoked when the specified
+    /// duration elapses, even if no spans have been collected. This allows for batching in the
+    /// reporter.
+    ///
+    /// In some scenarios, particularly under high load, you may notice spans being lost. This is
+    /// likely due to the channel being full during the reporting interval. To mitigate this issue,
+    /// consider reducing the report interval to prevent losing spans.
+    ///
+    /// The interval is raised to at least 1 millisecond, because a zero interval would keep the
+    /// background collector thread spinning. Use [`Config::builder()`] to reject a zero interval
+    /// instead.
+    ///
+    /// The default value is 10 milliseconds.
+    pub fn report_interval(self, report_interval: Duration) -> Self {
+        #[cfg(debug_assertions)]
+        if report_interval < MIN_REPORT_INTERVAL {
+            eprintln!(
+                "minitrace: `report_interval` {report_interval:?} is raised to the minimum of \
+                 {MIN_REPORT_INTERVAL:?}"
+            );
+        }
+
+        Self {
+            report_interval: report_interval.max(MIN_REPORT_INTERVAL),
+            ..self
+        }
+    }
+
+    /// Configures whether to report spans before the root span finishes.
+    ///
+    /// If set to `true`, some spans may be reported before they are canceled, making it
+    /// difficult to cancel all spans in a trace.
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::Config;
+    ///
+    /// let config = Config::default().report_before_root_finish(true);
+    /// minitrace::set_reporter(minitrace::collector::ConsoleReporter, config);
+    /// ```
+    pub fn report_before_root_finish(self, report_before_root_finish: bool) -> Self {
+        Self {
+            report_before_root_finish,
+            ..self
+        }
+    }
+
+    /// Sets the age of a trace after which its spans are reported before the root span finishes,
+    /// to detect stuck or infinitely running traces.
+    ///
+    /// Once the root span of a trace has been running for longer than the threshold, the finished
+    /// spans of the trace are reported at the next report interval, and the spans finishing later
+    /// are reported at the following ones. Unlike
+    /// [`Config::report_before_root_finish()`], the spans are kept and reported again when the
+    /// root span finishes, so the backend is expected to deduplicate them by span id. This has no
+    /// effect if `report_before_root_finish` is enabled.
+    ///
+    /// The default value is `None`, which disables the threshold.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use minitrace::collector::Config;
+    ///
+    /// let config = Config::default().report_long_running_threshold(Some(Duration::from_secs(60)));
+    /// minitrace::set_reporter(minitrace::collector::ConsoleReporter, config);
+    /// ```
+    pub fn report_long_running_threshold(
+        self,
+        report_long_running_threshold: Option<Duration>,
+    ) -> Self {
+        Self {
+            report_long_running_threshold,
+            ..self
+        }
+    }
+
+    /// Sets the strategy to apply when the local span queue of a thread is full.
+    ///
+    /// The default value is [`EvictionPolicy::DropNewest`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::Config;
+    /// use minitrace::collector::EvictionPolicy;
+    ///
+    /// let config = Config::default().eviction_policy(EvictionPolicy::InsertMarker);
+    /// minitrace::set_reporter(minitrace::collector::ConsoleReporter, config);
+    /// ```
+    pub fn eviction_policy(self, eviction_policy: EvictionPolicy) -> Self {
+        Self {
+            eviction_policy,
+            ..self
+        }
+    }
+
+    /// Sets the minimum level of the reported events. Events below the level are dropped before
+    /// the spans are reported.
+    ///
+    /// The default value is [`EventLevel::Debug`], which reports all events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::Config;
+    /// use minitrace::collector::EventLevel;
+    ///
+    /// let config = Config::default().min_event_level(EventLevel::Warn);
+    /// minitrace::set_reporter(minitrace::collector::ConsoleReporter, config);
+    /// ```
+    pub fn min_event_level(self, min_event_level: EventLevel) -> Self {
+        Self {
+            min_event_level,
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of events reported for a span, typically used to bound the memory
+    /// taken by spans recording an event in a loop.
+    ///
+    /// The events recorded after the limit is reached are dropped. If any is dropped, an event
+    /// named `"minitrace::events_truncated"` with the number of dropped events in its
+    /// `"dropped_count"` property is appended to the events of the span. The limit is applied
+    /// before [`Config::min_event_level()`] and does not count the events recorded before the span
+    /// started.
+    ///
+    /// The default value is `None`, which keeps all events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::Config;
+    ///
+    /// let config = Config::default().max_events_per_span(Some(100));
+    /// minitrace::set_reporter(minitrace::collector::ConsoleReporter, config);
+    /// ```
+    pub fn max_events_per_span(self, max_events_per_span: Option<usize>) -> Self {
+        Self {
+            max_events_per_span,
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of [`LocalSpan`]s recorded by a thread under the current local
+    /// parent, so that a runaway thread can not take the whole span budget of the application.
+    ///
+    /// Once a thread has recorded `max_spans_per_thread` local spans,
+    /// [`LocalSpan::enter_with_local_parent()`] returns a no-op span. The count, returned by
+    /// [`thread_span_count()`], is reset when the [`LocalParentGuard`] under which the spans were
+    /// recorded is dropped. The limit applies independently of
+    /// [`Config::max_spans_per_trace()`].
+    ///
+    /// The default value is `None`, which disables the limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::Config;
+    ///
+    /// let config = Config::default().max_spans_per_thread(Some(1000));
+    /// minitrace::set_reporter(minitrace::collector::ConsoleReporter, config);
+    /// ```
+    ///
+    /// [`LocalSpan`]: crate::local::LocalSpan
+    /// [`LocalSpan::enter_with_local_parent()`]: crate::local::LocalSpan::enter_with_local_parent
+    /// [`thread_span_count()`]: crate::thread_span_count
+    /// [`LocalParentGuard`]: crate::local::LocalParentGuard
+    pub fn max_spans_per_thread(self, max_spans_per_thread: Option<usize>) -> Self {
+        Self {
+            max_spans_per_thread,
+            ..self
+        }
+    }
+
+    /// Configures whether the pending spans are reported to the reporter when it is replaced by
+    /// another call to [`set_reporter()`].
+    ///
+    /// When enabled, the pending spans of the traces that have finished are reported to the
+    /// previous reporter before the new one is installed. Otherwise, they are left to the new
+    /// reporter, or discarded if their traces were started under the previous reporter.
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::Config;
+    ///
+    /// let config = Config::default().report_on_replace(true);
+    /// minitrace::set_reporter(minitrace::collector::ConsoleReporter, config);
+    /// ```
+    ///
+    /// [`set_reporter()`]: crate::set_reporter
+    pub fn report_on_replace(self, report_on_replace: bool) -> Self {
+        Self {
+            report_on_replace,
+            ..self
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_spans_per_trace: global_collector::default_max_spans_per_trace(),
+            report_interval: Duration::from_millis(10),
+            report_before_root_finish: false,
+            report_long_running_threshold: None,
+            eviction_policy: EvictionPolicy::DropNewest,
+            min_event_level: EventLevel::Debug,
+            max_events_per_span: None,
+            max_spans_per_thread: None,
+            report_on_replace: false,
+        }
+    }
+}
+
+/// A builder for [`Config`], created by [`Config::builder()`].
+///
+/// The setters are the same as those of `Config`. The configuration is validated by
+/// [`ConfigBuilder::build()`].
+#[must_use]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    /// See [`Config::max_spans_per_trace()`].
+    pub fn max_spans_per_trace(self, max_spans_per_trace: Option<usize>) -> Self {
+        Self {
+            config: self.config.max_spans_per_trace(max_spans_per_trace),
+        }
+    }
+
+    /// See [`Config::report_interval()`]. The interval must be greater than zero, and is raised to
+    /// at least 1 millisecond by [`ConfigBuilder::build()`].
+    pub fn report_interval(self, report_interval: Duration) -> Self {
+        Self {
+            config: Config {
+                report_interval,
+                ..self.config
+            },
+        }
+    }
+
+    /// See [`Config::report_before_root_finish()`].
+    pub fn report_before_root_finish(self, report_before_root_finish: bool) -> Self {
+        Self {
+            config: self
+                .config
+                .report_before_root_finish(report_before_root_finish),
+        }
+    }
+
+    /// See [`Config::report_long_running_threshold()`].
+    pub fn report_long_running_threshold(
+        self,
+        report_long_running_threshold: Option<Duration>,
+    ) -> Self {
+        Self {
+            config: self
+                .config
+                .report_long_running_threshold(report_long_running_threshold),
+        }
+    }
+
+    /// See [`Config::eviction_policy()`].
+    pub fn eviction_policy(self, eviction_policy: EvictionPolicy) -> Self {
+        Self {
+            config: self.config.eviction_policy(eviction_policy),
+        }
+    }
+
+    /// See [`Config::min_event_level()`].
+    pub fn min_event_level(self, min_event_level: EventLevel) -> Self {
+        Self {
+            config: self.config.min_event_level(min_event_level),
+        }
+    }
+
+    /// See [`Config::max_events_per_span()`].
+    pub fn max_events_per_span(self, max_events_per_span: Option<usize>) -> Self {
+        Self {
+            config: self.config.max_events_per_span(max_events_per_span),
+        }
+    }
+
+    /// See [`Config::max_spans_per_thread()`].
+    pub fn max_spans_per_thread(self, max_spans_per_thread: Option<usize>) -> Self {
+        Self {
+            config: self.config.max_spans_per_thread(max_spans_per_thread),
+        }
+    }
+
+    /// See [`Config::report_on_replace()`].
+    pub fn report_on_replace(self, report_on_replace: bool) -> Self {
+        Self {
+            config: self.config.report_on_replace(report_on_replace),
+        }
+    }
+
+    /// Validates the configuration and builds the [`Config`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::ZeroReportInterval`] if the report interval is zero.
+    pub fn build(self) -> Result<Config, ConfigError> {
+        if self.config.report_interval.is_zero() {
+            return Err(ConfigError::ZeroReportInterval);
+        }
+
+        Ok(Config {
+            report_interval: self.config.report_interval.max(MIN_REPORT_INTERVAL),
+            ..self.config
+        })
+    }
+}
+
+/// The error returned by [`ConfigBuilder::build()`] when the configuration is invalid.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// The report interval is zero, which would keep the background collector busy.
+    ZeroReportInterval,
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::ZeroReportInterval => {
+                write!(f, "`report_interval` must be greater than zero")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn w3c_traceparent() {
+        let span_context = SpanContext::decode_w3c_traceparent(
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
         )
         .unwrap();
         assert_eq!(
@@ -389,4 +1708,411 @@ mod tests {
             "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00"
         );
     }
+
+    #[test]
+    fn b3_single_header() {
+        let header = "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-1-05e3ac9a4f6e3b90";
+        let span_context = SpanContext::decode_b3_single_header(header).unwrap();
+        assert_eq!(
+            span_context.trace_id,
+            TraceId(0x80f198ee56343ba864fe8b2a57d3eff7)
+        );
+        assert_eq!(span_context.span_id, SpanId(0xe457b5a2e4d86bd1));
+        assert!(span_context.sampled());
+        assert_eq!(
+            span_context.encode_b3_single_header(span_context.sampled()),
+            "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-1"
+        );
+
+        for (header, sampled) in [
+            ("80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1", true),
+            ("80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-0", false),
+            ("80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-d", true),
+        ] {
+            let decoded = SpanContext::decode_b3_single_header(header).unwrap();
+            assert_eq!(decoded.trace_id, span_context.trace_id);
+            assert_eq!(decoded.span_id, span_context.span_id);
+            assert_eq!(decoded.sampled(), sampled, "{header}");
+        }
+        assert_eq!(
+            SpanContext::decode_b3_single_header(
+                "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-0"
+            )
+            .map(|decoded| decoded.encode_b3_single_header(decoded.sampled()))
+            .unwrap(),
+            "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-0"
+        );
+
+        let span_context =
+            SpanContext::decode_b3_single_header("463ac35c9f6413ad-a2fb4a1d1a96d312-1").unwrap();
+        assert_eq!(span_context.trace_id, TraceId(0x463ac35c9f6413ad));
+        assert_eq!(span_context.span_id, SpanId(0xa2fb4a1d1a96d312));
+
+        for header in [
+            "",
+            "0",
+            "1",
+            "d",
+            "80f198ee56343ba864fe8b2a57d3eff7",
+            "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-2",
+            "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-1-05e3ac9a",
+            "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-1-05e3ac9a4f6e3b90-1",
+            "80f198ee56343ba864fe8b2a57d3eff-e457b5a2e4d86bd1-1",
+            "+0f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-1",
+            "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bdz-1",
+        ] {
+            assert!(
+                SpanContext::decode_b3_single_header(header).is_none(),
+                "{header}"
+            );
+        }
+    }
+
+    #[test]
+    fn b3_multi_headers() {
+        let span_context = SpanContext::decode_b3_multi_headers(
+            "80f198ee56343ba864fe8b2a57d3eff7",
+            "e457b5a2e4d86bd1",
+            Some("1"),
+        )
+        .unwrap();
+        assert_eq!(
+            span_context.trace_id,
+            TraceId(0x80f198ee56343ba864fe8b2a57d3eff7)
+        );
+        assert_eq!(span_context.span_id, SpanId(0xe457b5a2e4d86bd1));
+        assert_eq!(span_context.encode_b3_multi_headers(span_context.sampled()), [
+            (
+                "X-B3-TraceId",
+                "80f198ee56343ba864fe8b2a57d3eff7".to_string()
+            ),
+            ("X-B3-SpanId", "e457b5a2e4d86bd1".to_string()),
+            ("X-B3-Sampled", "1".to_string()),
+        ]);
+
+        for (sampling_state, sampled) in [
+            (None, true),
+            (Some("0"), false),
+            (Some("d"), true),
+            (Some("true"), true),
+            (Some("false"), false),
+        ] {
+            let decoded = SpanContext::decode_b3_multi_headers(
+                "463ac35c9f6413ad",
+                "a2fb4a1d1a96d312",
+                sampling_state,
+            )
+            .unwrap();
+            assert_eq!(decoded.sampled(), sampled, "{sampling_state:?}");
+            assert_eq!(
+                decoded.encode_b3_multi_headers(decoded.sampled())[2],
+                ("X-B3-Sampled", (sampled as u8).to_string())
+            );
+        }
+        assert!(SpanContext::decode_b3_multi_headers(
+            "463ac35c9f6413ad",
+            "a2fb4a1d1a96d312",
+            Some("yes")
+        )
+        .is_none());
+        assert!(
+            SpanContext::decode_b3_multi_headers("463ac35c9f6413ad", "a2fb4a1d", None).is_none()
+        );
+    }
+
+    #[test]
+    fn w3c_tracestate() {
+        let traceparent = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+
+        let span_context = SpanContext::decode_w3c_headers(
+            traceparent,
+            Some("rojo=00f067aa0ba902b7,, congo=t61rcWkgMzE,\tfw529a3039@dt=abc 123"),
+        )
+        .unwrap();
+        assert_eq!(
+            span_context.tracestate(),
+            Some("rojo=00f067aa0ba902b7,congo=t61rcWkgMzE,fw529a3039@dt=abc 123")
+        );
+        let [(_, encoded_traceparent), (_, encoded_tracestate)] = span_context.encode_w3c_headers();
+        assert_eq!(encoded_traceparent, traceparent);
+        let decoded =
+            SpanContext::decode_w3c_headers(&encoded_traceparent, Some(&encoded_tracestate))
+                .unwrap();
+        assert_eq!(decoded.tracestate, span_context.tracestate);
+
+        let span_context = SpanContext::decode_w3c_headers(traceparent, None).unwrap();
+        assert_eq!(span_context.tracestate, None);
+        assert_eq!(
+            span_context.encode_w3c_headers()[1],
+            ("tracestate", String::new())
+        );
+
+        let max_entries = (0..32).map(|i| format!("k{i}=v")).collect::<Vec<_>>();
+        assert!(
+            SpanContext::decode_w3c_headers(traceparent, Some(&max_entries.join(",")))
+                .unwrap()
+                .tracestate
+                .is_some()
+        );
+
+        let too_many_entries = (0..33).map(|i| format!("k{i}=v")).collect::<Vec<_>>();
+        for tracestate in [
+            "",
+            " , ",
+            "rojo",
+            "rojo=",
+            "Rojo=1",
+            "1rojo=1",
+            "rojo=1,rojo=2",
+            "rojo=a,b",
+            "rojo=a=b",
+            "rojo=café",
+            "tenant@System=1",
+            "tenant@=1",
+            "@system=1",
+            &format!("{}=1", "k".repeat(257)),
+            &format!("rojo={}", "v".repeat(257)),
+            &too_many_entries.join(","),
+        ] {
+            let span_context =
+                SpanContext::decode_w3c_headers(traceparent, Some(tracestate)).unwrap();
+            assert_eq!(span_context.tracestate, None, "{tracestate:?}");
+        }
+
+        assert!(SpanContext::decode_w3c_headers("00-xyz", Some("rojo=1")).is_none());
+    }
+
+    #[test]
+    fn datadog_headers() {
+        let span_context = SpanContext::new(TraceId(7277407061855694839), SpanId(34));
+        let [(_, trace_id), (_, parent_id), (_, sampling_priority)] =
+            span_context.encode_datadog_headers(span_context.sampled());
+        assert_eq!(sampling_priority, "1");
+        let decoded =
+            SpanContext::decode_datadog_headers(&trace_id, &parent_id, Some(&sampling_priority))
+                .unwrap();
+        assert_eq!(decoded.trace_id, span_context.trace_id);
+        assert_eq!(decoded.span_id, span_context.span_id);
+        assert!(decoded.sampled());
+        assert_eq!(
+            span_context.encode_datadog_headers(false)[2],
+            ("x-datadog-sampling-priority", "0".to_string())
+        );
+
+        // Only the lower 64 bits of the trace id are propagated.
+        let span_context = SpanContext::new(TraceId((1 << 64) | 12), SpanId(34));
+        let [(_, trace_id), (_, parent_id), _] = span_context.encode_datadog_headers(true);
+        assert_eq!(trace_id, "12");
+        let decoded = SpanContext::decode_datadog_headers(&trace_id, &parent_id, None).unwrap();
+        assert_eq!(decoded.trace_id, TraceId(12));
+        assert_eq!(decoded.span_id, SpanId(34));
+
+        for (sampling_priority, sampled) in [
+            (None, true),
+            (Some("-1"), false),
+            (Some("0"), false),
+            (Some("2"), true),
+        ] {
+            let decoded =
+                SpanContext::decode_datadog_headers("12", "34", sampling_priority).unwrap();
+            assert_eq!(decoded.sampled(), sampled, "{sampling_priority:?}");
+        }
+
+        for (trace_id, parent_id, sampling_priority) in [
+            ("", "34", None),
+            ("12", "", None),
+            ("+12", "34", None),
+            ("12", "-34", None),
+            ("0xc", "34", None),
+            ("18446744073709551616", "34", None),
+            ("12", "34", Some("keep")),
+            ("12", "34", Some("")),
+        ] {
+            assert!(
+                SpanContext::decode_datadog_headers(trace_id, parent_id, sampling_priority)
+                    .is_none(),
+                "{trace_id} {parent_id} {sampling_priority:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn xray_header() {
+        let span_context = SpanContext::decode_xray_header(
+            "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1",
+        )
+        .unwrap();
+        assert_eq!(
+            span_context.trace_id,
+            TraceId(0x5759e988bd862e3fe1be46a994272793)
+        );
+        assert_eq!(span_context.span_id, SpanId(0x53995c3f42cd8ad8));
+        assert!(span_context.sampled());
+
+        let span_context =
+            SpanContext::decode_xray_header("Root=1-5759e988-bd862e3fe1be46a994272793;Sampled=0")
+                .unwrap();
+        assert_eq!(
+            span_context.trace_id,
+            TraceId(0x5759e988bd862e3fe1be46a994272793)
+        );
+        assert_eq!(span_context.span_id, SpanId::default());
+        assert!(!span_context.sampled());
+        assert!(span_context
+            .encode_xray_header(span_context.sampled())
+            .ends_with(";Sampled=0"));
+
+        let span_context = SpanContext::decode_xray_header(
+            "Sampled=?; Root=1-58406520-a006649127e371903a2de979; Self=1-58406520-bf42676c05e20ba4a90e448e; Parent=53995c3f42cd8ad8;",
+        )
+        .unwrap();
+        assert_eq!(
+            span_context.trace_id,
+            TraceId(0x58406520a006649127e371903a2de979)
+        );
+        assert_eq!(span_context.span_id, SpanId(0x53995c3f42cd8ad8));
+        assert!(span_context.sampled());
+
+        for header in [
+            "",
+            "Parent=53995c3f42cd8ad8;Sampled=1",
+            "Root=2-5759e988-bd862e3fe1be46a994272793",
+            "Root=1-5759e98-bd862e3fe1be46a994272793",
+            "Root=1-5759e988-bd862e3fe1be46a99427279",
+            "Root=1-5759e988-bd862e3fe1be46a99427279g",
+            "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad",
+            "Root=1-5759e988-bd862e3fe1be46a994272793;Sampled=yes",
+            "Root=1-5759e988-bd862e3fe1be46a994272793;Parent",
+        ] {
+            assert!(
+                SpanContext::decode_xray_header(header).is_none(),
+                "{header}"
+            );
+        }
+
+        // A trace id with a current epoch is encoded as is.
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as u128;
+        let trace_id = TraceId((now << 96) | 0xbd862e3fe1be46a994272793);
+        let span_context = SpanContext::new(trace_id, SpanId(0x53995c3f42cd8ad8));
+        let header = span_context.encode_xray_header(true);
+        assert_eq!(
+            header,
+            format!("Root=1-{now:08x}-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1")
+        );
+        let decoded = SpanContext::decode_xray_header(&header).unwrap();
+        assert_eq!(decoded.trace_id, span_context.trace_id);
+        assert_eq!(decoded.span_id, span_context.span_id);
+
+        // The epoch of a trace id which is not a recent timestamp is replaced by the current time.
+        let span_context = SpanContext::new(
+            TraceId(0x5759e988bd862e3fe1be46a994272793),
+            SpanId(0x53995c3f42cd8ad8),
+        );
+        let decoded =
+            SpanContext::decode_xray_header(&span_context.encode_xray_header(true)).unwrap();
+        assert!(((decoded.trace_id.0 >> 96) as i128 - now as i128).abs() <= 60);
+        assert_eq!(
+            decoded.trace_id.0 & ((1 << 96) - 1),
+            0xbd862e3fe1be46a994272793
+        );
+        assert_eq!(decoded.span_id, span_context.span_id);
+    }
+
+    #[test]
+    fn config_builder() {
+        assert_eq!(Config::builder().build(), Ok(Config::default()));
+
+        let config = Config::builder()
+            .max_spans_per_trace(Some(100))
+            .report_interval(Duration::from_secs(1))
+            .report_before_root_finish(true)
+            .report_long_running_threshold(Some(Duration::from_secs(60)))
+            .eviction_policy(EvictionPolicy::DropOldest)
+            .max_events_per_span(Some(10))
+            .max_spans_per_thread(Some(1000))
+            .report_on_replace(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            config,
+            Config::default()
+                .max_spans_per_trace(Some(100))
+                .report_interval(Duration::from_secs(1))
+                .report_before_root_finish(true)
+                .report_long_running_threshold(Some(Duration::from_secs(60)))
+                .eviction_policy(EvictionPolicy::DropOldest)
+                .max_events_per_span(Some(10))
+                .max_spans_per_thread(Some(1000))
+                .report_on_replace(true)
+        );
+
+        let err = Config::builder()
+            .report_interval(Duration::ZERO)
+            .build()
+            .unwrap_err();
+        assert_eq!(err, ConfigError::ZeroReportInterval);
+        assert_eq!(
+            err.to_string(),
+            "`report_interval` must be greater than zero"
+        );
+
+        let config = Config::builder()
+            .report_interval(Duration::from_micros(10))
+            .build()
+            .unwrap();
+        assert_eq!(config.report_interval, MIN_REPORT_INTERVAL);
+    }
+
+    #[test]
+    fn config_min_report_interval() {
+        let config = Config::default().report_interval(Duration::ZERO);
+        assert_eq!(config.report_interval, MIN_REPORT_INTERVAL);
+
+        let config = Config::default().report_interval(Duration::from_secs(1));
+        assert_eq!(config.report_interval, Duration::from_secs(1));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_span_record() {
+        let record = SpanRecord {
+            trace_id: TraceId(0x0af7651916cd43dd8448eb211c80319c),
+            span_id: SpanId(0xb7ad6b7169203331),
+            parent_id: SpanId(0x1),
+            begin_time_unix_ns: 1_700_000_000_000_000_000,
+            duration_ns: 42,
+            name: "root".into(),
+            properties: vec![("k1".into(), "v1".into()), ("k2".into(), "".into())],
+            events: vec![EventRecord {
+                name: "event".into(),
+                timestamp_unix_ns: 1_700_000_000_000_000_001,
+                properties: vec![("k3".into(), "v3".into())],
+                level: EventLevel::Warn,
+            }],
+            status: SpanStatus::Error("failed".into()),
+            kind: SpanKind::Server,
+            links: vec![(TraceId(0x2), SpanId(0x3))],
+            sampled: true,
+            resource: None,
+        };
+
+        let json = record.to_json();
+        assert!(json.contains(r#""trace_id":"0x0af7651916cd43dd8448eb211c80319c""#));
+        assert!(json
+            .contains(r#""links":[["0x00000000000000000000000000000002","0x0000000000000003"]]"#));
+        assert!(json.contains(r#""span_id":"0xb7ad6b7169203331""#));
+        assert!(json.contains(r#""parent_id":"0x0000000000000001""#));
+        assert_eq!(SpanRecord::from_json(&json).unwrap(), record);
+
+        let msgpack = rmp_serde::to_vec(&record).unwrap();
+        assert_eq!(
+            rmp_serde::from_slice::<SpanRecord>(&msgpack).unwrap(),
+            record
+        );
+
+        assert!(SpanRecord::from_json(&json.replace("0x0af7", "0af7")).is_err());
+    }
 }
@@ -0,0 +1,106 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::panic::catch_unwind;
+use std::panic::AssertUnwindSafe;
+
+use super::global_collector::Reporter;
+use super::SpanRecord;
+
+/// A reporter that forwards the spans to several reporters.
+///
+/// A panic in one of the reporters is caught so that the other reporters still receive the spans.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::ConsoleReporter;
+/// use minitrace::collector::MultiReporter;
+///
+/// let mut reporter = MultiReporter::new([]);
+/// reporter.add(ConsoleReporter);
+/// reporter.add(ConsoleReporter);
+///
+/// minitrace::set_reporter(reporter, Config::default());
+/// ```
+#[derive(Default)]
+pub struct MultiReporter {
+    reporters: Vec<Box<dyn Reporter>>,
+}
+
+impl MultiReporter {
+    pub fn new(reporters: impl IntoIterator<Item = Box<dyn Reporter>>) -> Self {
+        MultiReporter {
+            reporters: reporters.into_iter().collect(),
+        }
+    }
+
+    pub fn add(&mut self, reporter: impl Reporter) {
+        self.reporters.push(Box::new(reporter));
+    }
+
+    fn for_each(&mut self, mut f: impl FnMut(&mut dyn Reporter)) {
+        for reporter in &mut self.reporters {
+            let _ = catch_unwind(AssertUnwindSafe(|| f(reporter.as_mut())));
+        }
+    }
+}
+
+impl Reporter for MultiReporter {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        self.for_each(|reporter| reporter.report(spans));
+    }
+
+    fn start(&mut self) {
+        self.for_each(|reporter| reporter.start());
+    }
+
+    fn stop(&mut self) {
+        self.for_each(|reporter| reporter.stop());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::SpanId;
+    use crate::collector::TestReporter;
+    use crate::collector::TraceId;
+
+    struct PanicReporter;
+
+    impl Reporter for PanicReporter {
+        fn report(&mut self, _spans: &[SpanRecord]) {
+            panic!("failed to report");
+        }
+    }
+
+    #[test]
+    fn multi_reporter() {
+        let (reporter1, spans1) = TestReporter::new();
+        let (reporter2, spans2) = TestReporter::new();
+        let mut reporter = MultiReporter::new([Box::new(reporter1) as Box<dyn Reporter>]);
+        reporter.add(PanicReporter);
+        reporter.add(reporter2);
+
+        let spans = vec![
+            SpanRecord {
+                trace_id: TraceId(1),
+                span_id: SpanId(1),
+                name: "root".into(),
+                ..SpanRecord::default()
+            },
+            SpanRecord {
+                trace_id: TraceId(1),
+                span_id: SpanId(2),
+                parent_id: SpanId(1),
+                name: "child".into(),
+                ..SpanRecord::default()
+            },
+        ];
+        reporter.report(&spans);
+
+        assert_eq!(*spans1.lock(), spans);
+        assert_eq!(*spans2.lock(), spans);
+    }
+}
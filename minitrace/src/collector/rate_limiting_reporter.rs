@@ -0,0 +1,174 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::time::Duration;
+use std::time::Instant;
+
+use super::global_collector::Reporter;
+use super::SpanId;
+use super::SpanRecord;
+use super::TraceId;
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// A reporter that forwards at most `max_spans_per_sec` spans per second to the inner reporter,
+/// and drops the rest.
+///
+/// When a batch of spans has to be truncated, the parents are kept in favor of their children, so
+/// that a partially reported trace always includes its root span.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::ConsoleReporter;
+/// use minitrace::collector::RateLimitingReporter;
+///
+/// minitrace::set_reporter(
+///     RateLimitingReporter::new(ConsoleReporter, 1000),
+///     Config::default(),
+/// );
+/// ```
+pub struct RateLimitingReporter<R> {
+    inner: R,
+    max_spans_per_sec: usize,
+    window_start: Instant,
+    window_count: usize,
+    dropped_count: u64,
+}
+
+impl<R: Reporter> RateLimitingReporter<R> {
+    pub fn new(inner: R, max_spans_per_sec: usize) -> Self {
+        RateLimitingReporter {
+            inner,
+            max_spans_per_sec,
+            window_start: Instant::now(),
+            window_count: 0,
+            dropped_count: 0,
+        }
+    }
+
+    /// Returns the number of spans dropped so far because of the rate limit.
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped_count
+    }
+}
+
+impl<R: Reporter> Reporter for RateLimitingReporter<R> {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        let now = Instant::now();
+        if now.duration_since(self.window_start) >= WINDOW {
+            self.window_start = now;
+            self.window_count = 0;
+        }
+
+        let budget = self.max_spans_per_sec.saturating_sub(self.window_count);
+        if spans.len() <= budget {
+            self.window_count += spans.len();
+            self.inner.report(spans);
+            return;
+        }
+
+        self.dropped_count += (spans.len() - budget) as u64;
+        self.window_count += budget;
+        if budget == 0 {
+            return;
+        }
+
+        let mut retained = vec![false; spans.len()];
+        for i in parents_first(spans).take(budget) {
+            retained[i] = true;
+        }
+        let spans = spans
+            .iter()
+            .zip(retained)
+            .filter(|(_, retained)| *retained)
+            .map(|(span, _)| span.clone())
+            .collect::<Vec<_>>();
+        self.inner.report(&spans);
+    }
+
+    fn start(&mut self) {
+        self.inner.start();
+    }
+
+    fn stop(&mut self) {
+        self.inner.stop();
+    }
+}
+
+/// Returns the indices of the spans in breadth-first order, starting from the spans whose parent
+/// is not in the batch, so that every span comes after its parent.
+fn parents_first(spans: &[SpanRecord]) -> impl Iterator<Item = usize> {
+    let index: HashMap<(TraceId, SpanId), usize> = spans
+        .iter()
+        .enumerate()
+        .map(|(i, span)| ((span.trace_id, span.span_id), i))
+        .collect();
+
+    let mut children = vec![Vec::new(); spans.len()];
+    let mut queue = VecDeque::new();
+    for (i, span) in spans.iter().enumerate() {
+        match index.get(&(span.trace_id, span.parent_id)) {
+            Some(&parent) if parent != i => children[parent].push(i),
+            _ => queue.push_back(i),
+        }
+    }
+
+    std::iter::from_fn(move || {
+        let i = queue.pop_front()?;
+        queue.extend(children[i].drain(..));
+        Some(i)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::TestReporter;
+
+    fn span(trace_id: u128, span_id: u64, parent_id: u64) -> SpanRecord {
+        SpanRecord {
+            trace_id: TraceId(trace_id),
+            span_id: SpanId(span_id),
+            parent_id: SpanId(parent_id),
+            ..SpanRecord::default()
+        }
+    }
+
+    #[test]
+    fn rate_limiting_reporter() {
+        let (inner, collected_spans) = TestReporter::new();
+        let mut reporter = RateLimitingReporter::new(inner, 5);
+
+        // Children are collected before their parents.
+        let spans = vec![
+            span(1, 3, 2),
+            span(1, 2, 1),
+            span(1, 4, 1),
+            span(1, 1, 0),
+            span(2, 3, 2),
+            span(2, 2, 1),
+            span(2, 1, 0),
+        ];
+        reporter.report(&spans);
+
+        let reported = collected_spans
+            .lock()
+            .iter()
+            .map(|span| (span.trace_id.0, span.span_id.0))
+            .collect::<Vec<_>>();
+        assert_eq!(reported, vec![(1, 2), (1, 4), (1, 1), (2, 2), (2, 1)]);
+        assert_eq!(reporter.dropped_count(), 2);
+
+        reporter.report(&spans);
+        assert_eq!(collected_spans.lock().len(), 5);
+        assert_eq!(reporter.dropped_count(), 9);
+
+        reporter.window_start -= WINDOW;
+        reporter.report(&spans[..3]);
+        assert_eq!(collected_spans.lock().len(), 8);
+        assert_eq!(reporter.dropped_count(), 9);
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use super::global_collector::Reporter;
+use super::SpanRecord;
+
+/// A reporter that keeps the last `capacity` reported spans in memory, so that recent traces can
+/// be inspected in process, e.g. from a debug endpoint, without any backend.
+///
+/// Once the buffer is full, the oldest spans are dropped to make room for the new ones. The spans
+/// are read through the [`RingBufferHandle`] returned by [`RingBufferReporter::new()`].
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::RingBufferReporter;
+/// use minitrace::prelude::*;
+///
+/// let (reporter, handle) = RingBufferReporter::new(10_000);
+/// minitrace::set_reporter(reporter, Config::default());
+///
+/// {
+///     let _root = Span::root("root", SpanContext::random());
+/// }
+///
+/// minitrace::flush();
+///
+/// let spans = handle.last_n(100);
+/// assert_eq!(spans[0].name, "root");
+/// ```
+pub struct RingBufferReporter {
+    buffer: Arc<Mutex<VecDeque<SpanRecord>>>,
+    capacity: usize,
+}
+
+/// A handle to read the spans kept by a [`RingBufferReporter`].
+#[derive(Clone)]
+pub struct RingBufferHandle {
+    buffer: Arc<Mutex<VecDeque<SpanRecord>>>,
+}
+
+impl RingBufferReporter {
+    /// Creates a reporter keeping at most `capacity` spans, and the handle to read them.
+    pub fn new(capacity: usize) -> (Self, RingBufferHandle) {
+        let buffer = Arc::new(Mutex::new(VecDeque::with_capacity(capacity)));
+        (
+            RingBufferReporter {
+                buffer: buffer.clone(),
+                capacity,
+            },
+            RingBufferHandle { buffer },
+        )
+    }
+}
+
+impl Reporter for RingBufferReporter {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        // Only the spans that fit in the buffer are cloned.
+        let spans = &spans[spans.len().saturating_sub(self.capacity)..];
+
+        let mut buffer = self.buffer.lock();
+        let overflow = (buffer.len() + spans.len()).saturating_sub(self.capacity);
+        buffer.drain(..overflow);
+        buffer.extend(spans.iter().cloned());
+    }
+}
+
+impl RingBufferHandle {
+    /// Removes and returns all the spans in the buffer, from the oldest to the latest.
+    pub fn take_all(&self) -> Vec<SpanRecord> {
+        self.buffer.lock().drain(..).collect()
+    }
+
+    /// Returns the latest `n` spans in the buffer, from the oldest to the latest, without removing
+    /// them.
+    pub fn last_n(&self, n: usize) -> Vec<SpanRecord> {
+        let buffer = self.buffer.lock();
+        buffer
+            .range(buffer.len().saturating_sub(n)..)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::SpanId;
+
+    fn span_record(span_id: u64) -> SpanRecord {
+        SpanRecord {
+            span_id: SpanId(span_id),
+            ..SpanRecord::default()
+        }
+    }
+
+    fn span_ids(spans: &[SpanRecord]) -> Vec<u64> {
+        spans.iter().map(|span| span.span_id.0).collect()
+    }
+
+    #[test]
+    fn ring_buffer_capacity() {
+        let (mut reporter, handle) = RingBufferReporter::new(3);
+
+        reporter.report(&[span_record(1), span_record(2)]);
+        assert_eq!(span_ids(&handle.last_n(5)), [1, 2]);
+
+        reporter.report(&[span_record(3), span_record(4)]);
+        assert_eq!(span_ids(&handle.last_n(5)), [2, 3, 4]);
+        assert_eq!(span_ids(&handle.last_n(2)), [3, 4]);
+
+        reporter.report(&(5..10).map(span_record).collect::<Vec<_>>());
+        assert_eq!(span_ids(&handle.take_all()), [7, 8, 9]);
+        assert!(handle.take_all().is_empty());
+
+        let (mut reporter, handle) = RingBufferReporter::new(0);
+        reporter.report(&[span_record(1)]);
+        assert!(handle.last_n(1).is_empty());
+    }
+
+    #[test]
+    fn ring_buffer_concurrent_access() {
+        let (mut reporter, handle) = RingBufferReporter::new(100);
+
+        let readers = (0..4)
+            .map(|_| {
+                let handle = handle.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        assert!(handle.last_n(50).len() <= 50);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        let writer = std::thread::spawn(move || {
+            for i in 0..1000 {
+                reporter.report(&[span_record(i)]);
+            }
+        });
+
+        writer.join().unwrap();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+
+        assert_eq!(
+            span_ids(&handle.take_all()),
+            (900..1000).collect::<Vec<_>>()
+        );
+    }
+}
@@ -1,12 +1,47 @@
 // Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::fmt::Write;
 use std::sync::Arc;
 
 use parking_lot::Mutex;
 
 use super::global_collector::Reporter;
 use super::SpanRecord;
+use super::TraceId;
+use crate::util::tree::tree_str_from_span_records;
 
+/// A reporter that keeps the reported spans in memory, to be checked by tests.
+///
+/// The reporter can be cloned before being installed, so that the assertion helpers can still be
+/// called on the clone afterwards.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::TestReporter;
+/// use minitrace::prelude::*;
+///
+/// let (reporter, _) = TestReporter::new();
+/// let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+///
+/// {
+///     let root = Span::root("root", SpanContext::random());
+///     let _child = Span::enter_with_parent("child", &root);
+/// }
+///
+/// minitrace::flush();
+///
+/// reporter.assert_span_count(2);
+/// reporter.assert_contains_span("child");
+/// reporter.assert_tree(
+///     r#"
+/// root []
+///     child []
+/// "#,
+/// );
+/// ```
+#[derive(Clone)]
 pub struct TestReporter {
     pub spans: Arc<Mutex<Vec<SpanRecord>>>,
 }
@@ -21,6 +56,62 @@ impl TestReporter {
             spans,
         )
     }
+
+    /// Asserts that the reported spans form the `expected` tree, in the format of
+    /// [`tree_str_from_span_records()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics with a line diff between the expected and the reported trees if they differ.
+    ///
+    /// [`tree_str_from_span_records()`]: crate::util::tree::tree_str_from_span_records
+    #[track_caller]
+    pub fn assert_tree(&self, expected: &str) {
+        let actual = tree_str_from_span_records(self.spans.lock().clone());
+        if actual != expected {
+            panic!(
+                "the reported span tree differs from the expected one (-expected +actual):\n{}",
+                line_diff(expected, &actual)
+            );
+        }
+    }
+
+    /// Asserts that exactly `n` spans have been reported.
+    #[track_caller]
+    pub fn assert_span_count(&self, n: usize) {
+        let spans = self.spans.lock();
+        if spans.len() != n {
+            panic!(
+                "expected {n} reported spans, but found {}: {:?}",
+                spans.len(),
+                spans.iter().map(|span| &span.name).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    /// Asserts that a span named `name` has been reported.
+    #[track_caller]
+    pub fn assert_contains_span(&self, name: &str) {
+        let spans = self.spans.lock();
+        if !spans.iter().any(|span| span.name == name) {
+            panic!(
+                "expected a reported span named {name:?}, but found {:?}",
+                spans.iter().map(|span| &span.name).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    /// Asserts that a span of the trace `trace_id` has been reported.
+    #[track_caller]
+    pub fn assert_trace_id(&self, trace_id: TraceId) {
+        let spans = self.spans.lock();
+        if !spans.iter().any(|span| span.trace_id == trace_id) {
+            let mut trace_ids = spans.iter().map(|span| span.trace_id).collect::<Vec<_>>();
+            trace_ids.sort_by_key(|trace_id| trace_id.0);
+            trace_ids.dedup();
+            panic!("expected a reported span of {trace_id:?}, but found {trace_ids:?}");
+        }
+    }
 }
 
 impl Reporter for TestReporter {
@@ -28,3 +119,54 @@ impl Reporter for TestReporter {
         self.spans.lock().extend_from_slice(spans);
     }
 }
+
+/// Renders the lines removed from `expected` with `-` and the lines added in `actual` with `+`,
+/// keeping their longest common subsequence as context.
+fn line_diff(expected: &str, actual: &str) -> String {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+
+    // lcs[i][j] is the length of the longest common subsequence of expected[i..] and actual[j..].
+    let mut lcs = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            writeln!(diff, "  {}", expected[i]).unwrap();
+            i += 1;
+            j += 1;
+        } else if j == actual.len() || (i < expected.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            writeln!(diff, "- {}", expected[i]).unwrap();
+            i += 1;
+        } else {
+            writeln!(diff, "+ {}", actual[j]).unwrap();
+            j += 1;
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_diff() {
+        let expected = "root []\n    a []\n    b []\n";
+        let actual = "root []\n    b []\n    c []\n";
+        assert_eq!(
+            line_diff(expected, actual),
+            "  root []\n-     a []\n      b []\n+     c []\n"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::global_collector::Reporter;
+use super::SpanId;
+use super::SpanRecord;
+use super::TraceId;
+
+/// A reporter that only forwards the spans lasting at least a minimum duration to the inner
+/// reporter.
+///
+/// A shorter span is still forwarded if it is an ancestor of a forwarded span in the same batch,
+/// so that the reported trees are not broken apart.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use minitrace::collector::Config;
+/// use minitrace::collector::ConsoleReporter;
+/// use minitrace::collector::ThresholdReporter;
+///
+/// minitrace::set_reporter(
+///     ThresholdReporter::new(ConsoleReporter, Duration::from_millis(1)),
+///     Config::default(),
+/// );
+/// ```
+pub struct ThresholdReporter<R: Reporter> {
+    inner: R,
+    min_duration_ns: u64,
+}
+
+impl<R: Reporter> ThresholdReporter<R> {
+    pub fn new(inner: R, min_duration: Duration) -> Self {
+        ThresholdReporter {
+            inner,
+            min_duration_ns: min_duration.as_nanos().try_into().unwrap_or(u64::MAX),
+        }
+    }
+}
+
+impl<R: Reporter> Reporter for ThresholdReporter<R> {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        let index: HashMap<(TraceId, SpanId), usize> = spans
+            .iter()
+            .enumerate()
+            .map(|(i, span)| ((span.trace_id, span.span_id), i))
+            .collect();
+
+        let mut retained = vec![false; spans.len()];
+        for (i, span) in spans.iter().enumerate() {
+            if span.duration_ns < self.min_duration_ns {
+                continue;
+            }
+
+            retained[i] = true;
+            let mut parent = span;
+            while let Some(&parent_index) = index.get(&(parent.trace_id, parent.parent_id)) {
+                if retained[parent_index] {
+                    break;
+                }
+                retained[parent_index] = true;
+                parent = &spans[parent_index];
+            }
+        }
+
+        let spans = spans
+            .iter()
+            .zip(retained)
+            .filter(|(_, retained)| *retained)
+            .map(|(span, _)| span.clone())
+            .collect::<Vec<_>>();
+        if !spans.is_empty() {
+            self.inner.report(&spans);
+        }
+    }
+
+    fn start(&mut self) {
+        self.inner.start();
+    }
+
+    fn stop(&mut self) {
+        self.inner.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::TestReporter;
+
+    fn span(span_id: u64, parent_id: u64, duration_ns: u64) -> SpanRecord {
+        SpanRecord {
+            trace_id: TraceId(1),
+            span_id: SpanId(span_id),
+            parent_id: SpanId(parent_id),
+            duration_ns,
+            ..SpanRecord::default()
+        }
+    }
+
+    #[test]
+    fn threshold_reporter() {
+        let (inner, collected_spans) = TestReporter::new();
+        let mut reporter = ThresholdReporter::new(inner, Duration::from_nanos(100));
+
+        // 1 -> 2 -> 3 -> 4 (slow)
+        //        -> 5
+        //   -> 6 (slow)
+        //   -> 7
+        let spans = vec![
+            span(4, 3, 150),
+            span(3, 2, 10),
+            span(5, 2, 10),
+            span(2, 1, 50),
+            span(6, 1, 100),
+            span(7, 1, 99),
+            span(1, 0, 20),
+        ];
+        reporter.report(&spans);
+
+        let span_ids = collected_spans
+            .lock()
+            .iter()
+            .map(|span| span.span_id.0)
+            .collect::<Vec<_>>();
+        assert_eq!(span_ids, vec![4, 3, 2, 6, 1]);
+    }
+
+    #[test]
+    fn threshold_reporter_all_fast() {
+        let (inner, collected_spans) = TestReporter::new();
+        let mut reporter = ThresholdReporter::new(inner, Duration::from_nanos(100));
+
+        reporter.report(&[span(2, 1, 10), span(1, 0, 20)]);
+
+        assert!(collected_spans.lock().is_empty());
+    }
+}
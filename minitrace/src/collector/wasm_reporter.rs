@@ -0,0 +1,41 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use wasm_bindgen::JsValue;
+
+use super::global_collector::Reporter;
+use super::SpanRecord;
+
+/// A reporter for `wasm32` targets that prints each span record as a JSON string to the
+/// browser console via `console.log()`.
+///
+/// Requires the `wasm` feature.
+///
+/// # Examples
+///
+/// ```ignore
+/// use minitrace::collector::Config;
+/// use minitrace::collector::WasmConsoleReporter;
+///
+/// minitrace::set_reporter(WasmConsoleReporter, Config::default());
+/// ```
+pub struct WasmConsoleReporter;
+
+impl Reporter for WasmConsoleReporter {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        for span in spans {
+            web_sys::console::log_1(&JsValue::from_str(&span.to_json()));
+        }
+    }
+}
+
+/// Flushes all pending span records to the reporter, returning a JS `Promise` that resolves
+/// once the spans are reported.
+///
+/// This is the counterpart of [`flush_async()`](crate::flush_async) to be awaited from
+/// JavaScript. Requires the `wasm` feature.
+pub fn flush_async_promise() -> js_sys::Promise {
+    wasm_bindgen_futures::future_to_promise(async {
+        crate::flush_async().await;
+        Ok(JsValue::UNDEFINED)
+    })
+}
@@ -2,6 +2,7 @@
 
 use std::borrow::Cow;
 
+use crate::collector::EventLevel;
 use crate::local::local_span_stack::LOCAL_SPAN_STACK;
 use crate::Span;
 
@@ -9,7 +10,8 @@ use crate::Span;
 pub struct Event;
 
 impl Event {
-    /// Adds an event to the parent span with the given name and properties.
+    /// Adds an event to the parent span with the given name and properties, at the level
+    /// [`EventLevel::Info`].
     ///
     /// # Examples
     ///
@@ -24,18 +26,45 @@ impl Event {
     where
         I: IntoIterator<Item = (Cow<'static, str>, Cow<'static, str>)>,
         F: FnOnce() -> I,
+    {
+        Self::add_to_parent_with_level(name, parent, EventLevel::Info, properties)
+    }
+
+    /// Adds an event to the parent span with the given name, level and properties.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    ///
+    /// Event::add_to_parent_with_level("retrying", &root, EventLevel::Warn, || {
+    ///     [("attempt".into(), "2".into())]
+    /// });
+    /// ```
+    pub fn add_to_parent_with_level<I, F>(
+        name: impl Into<Cow<'static, str>>,
+        parent: &Span,
+        level: EventLevel,
+        properties: F,
+    ) where
+        I: IntoIterator<Item = (Cow<'static, str>, Cow<'static, str>)>,
+        F: FnOnce() -> I,
     {
         #[cfg(feature = "enable")]
         {
             let mut span = Span::enter_with_parent(name, parent).with_properties(properties);
             if let Some(mut inner) = span.inner.take() {
                 inner.raw_span.is_event = true;
+                inner.raw_span.event_level = level;
                 inner.submit_spans();
             }
         }
     }
 
-    /// Adds an event to the current local parent span with the given name and properties.
+    /// Adds an event to the current local parent span with the given name and properties, at the
+    /// level [`EventLevel::Info`].
     ///
     /// # Examples
     ///
@@ -51,11 +80,67 @@ impl Event {
     where
         I: IntoIterator<Item = (Cow<'static, str>, Cow<'static, str>)>,
         F: FnOnce() -> I,
+    {
+        Self::add_to_local_parent_with_level(name, EventLevel::Info, properties)
+    }
+
+    /// Adds an event to the current local parent span with the given name, level and properties.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let _guard = root.set_local_parent();
+    ///
+    /// Event::add_to_local_parent_with_level("cache miss", EventLevel::Debug, || []);
+    /// ```
+    pub fn add_to_local_parent_with_level<I, F>(
+        name: impl Into<Cow<'static, str>>,
+        level: EventLevel,
+        properties: F,
+    ) where
+        I: IntoIterator<Item = (Cow<'static, str>, Cow<'static, str>)>,
+        F: FnOnce() -> I,
+    {
+        #[cfg(feature = "enable")]
+        {
+            LOCAL_SPAN_STACK
+                .try_with(|stack| stack.borrow_mut().add_event(name, level, properties))
+                .ok();
+        }
+    }
+
+    /// Adds a batch of events to the current local parent span.
+    ///
+    /// Each event is given as a tuple of the name, the `timestamp_unix_ns` and the properties, and
+    /// is at the level [`EventLevel::Info`]. The
+    /// thread-local span stack is accessed only once for the whole batch, which is cheaper than
+    /// calling [`Event::add_to_local_parent()`] for every event in a tight loop.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let _guard = root.set_local_parent();
+    ///
+    /// Event::add_batch_to_local_parent((0..3).map(|i| {
+    ///     let properties = [("i".into(), i.to_string().into())];
+    ///     ("iteration", 1_700_000_000_000_000_000 + i, properties)
+    /// }));
+    /// ```
+    pub fn add_batch_to_local_parent<N, P>(events: impl IntoIterator<Item = (N, u64, P)>)
+    where
+        N: Into<Cow<'static, str>>,
+        P: IntoIterator<Item = (Cow<'static, str>, Cow<'static, str>)>,
     {
         #[cfg(feature = "enable")]
         {
             LOCAL_SPAN_STACK
-                .try_with(|stack| stack.borrow_mut().add_event(name, properties))
+                .try_with(|stack| stack.borrow_mut().add_events(events))
                 .ok();
         }
     }
@@ -305,7 +305,7 @@
 //!
 //! For an easy start, `minitrace` offers a [`ConsoleReporter`] that prints span
 //! records to stderr. For more advanced use, crates like `minitrace-jaeger`, `minitrace-datadog`,
-//! and `minitrace-opentelemetry` are available.
+//! `minitrace-zipkin` and `minitrace-opentelemetry` are available.
 //!
 //! By default, the reporter is triggered every 500 milliseconds. The reporter can also be
 //! triggered manually by calling [`flush()`]. See [`Config`] for customizing the reporting
@@ -339,9 +339,23 @@
 //!
 //! - **Full Tracing with Tail Sampling**: If `enable` is set in the application, and all traces are
 //!   enabled, however, only a select few interesting tracing records (e.g., P99) are reported,
-//!   while normal traces are dismissed by using [`Span::cancel()`] to avoid being reported, the
-//!   overhead of collecting traces is still very small. This could be useful when you are
-//!   interested in examining program's tail latency.
+//!   while normal traces are dismissed by using [`Span::cancel()`] or
+//!   [`Span::cancel_if_faster_than()`] to avoid being reported, the overhead of collecting traces
+//!   is still very small. This could be useful when you are interested in examining program's tail
+//!   latency.
+//!
+//!   ```
+//!   use std::time::Duration;
+//!
+//!   use minitrace::prelude::*;
+//!
+//!   let mut root = Span::root("root", SpanContext::random());
+//!
+//!   // ...
+//!
+//!   // Only report the traces that take longer than 100ms.
+//!   root.cancel_if_faster_than(Duration::from_millis(100));
+//!   ```
 //!
 //! - **Full Tracing**: If `enable` is set in the application, and all traces are reported,
 //!   `minitrace` performs 10x to 100x faster than other tracing libraries in this case.
@@ -356,6 +370,7 @@
 //! [`Span::root()`]: crate::Span::root
 //! [`Span::noop()`]: crate::Span::noop
 //! [`Span::cancel()`]: crate::Span::cancel
+//! [`Span::cancel_if_faster_than()`]: crate::Span::cancel_if_faster_than
 //! [`Span::enter_with_parent()`]: crate::Span::enter_with_parent
 //! [`Span::set_local_parent()`]: crate::Span::set_local_parent
 //! [`LocalSpan::enter_with_local_parent()`]: crate::local::LocalSpan::enter_with_local_parent
@@ -378,36 +393,66 @@ mod event;
 pub mod future;
 pub mod local;
 mod macros;
+mod panic_hook;
+pub mod propagation;
 mod span;
+mod span_fields;
+mod span_tags;
 #[doc(hidden)]
 pub mod util;
 
 pub use minitrace_macro::trace;
+pub use minitrace_macro::SpanFields;
 
 pub use crate::collector::global_collector::flush;
+pub use crate::collector::global_collector::flush_async;
+pub use crate::collector::global_collector::scoped_reporter;
 pub use crate::collector::global_collector::set_reporter;
+pub use crate::collector::global_collector::set_reporter_factory;
+pub use crate::collector::global_collector::set_resource;
+pub use crate::collector::global_collector::shutdown;
+pub use crate::collector::id::set_span_id_generator;
 pub use crate::event::Event;
+pub use crate::local::local_span_stack::thread_span_count;
+pub use crate::panic_hook::install_panic_hook;
+pub use crate::panic_hook::install_panic_hook_with_prev_hook;
 pub use crate::span::Span;
+pub use crate::span::SpanParent;
+pub use crate::span::WeakSpan;
+pub use crate::span_fields::SpanFields;
+pub use crate::span_tags::SpanTags;
 
 pub mod prelude {
     //! A "prelude" for crates using `minitrace`.
     #[doc(no_inline)]
+    pub use crate::collector::EventLevel;
+    #[doc(no_inline)]
+    pub use crate::collector::SamplingDecision;
+    #[doc(no_inline)]
     pub use crate::collector::SpanContext;
     #[doc(no_inline)]
     pub use crate::collector::SpanId;
     #[doc(no_inline)]
+    pub use crate::collector::SpanKind;
+    #[doc(no_inline)]
     pub use crate::collector::SpanRecord;
     #[doc(no_inline)]
+    pub use crate::collector::SpanStatus;
+    #[doc(no_inline)]
     pub use crate::collector::TraceId;
     #[doc(no_inline)]
     pub use crate::event::Event;
     #[doc(no_inline)]
+    pub use crate::column_location;
+    #[doc(no_inline)]
     pub use crate::file_location;
     #[doc(no_inline)]
     pub use crate::full_name;
     #[doc(no_inline)]
     pub use crate::func_name;
     #[doc(no_inline)]
+    pub use crate::func_path;
+    #[doc(no_inline)]
     pub use crate::future::FutureExt as _;
     #[doc(no_inline)]
     pub use crate::local::LocalSpan;
@@ -10,6 +10,7 @@ use crate::local::local_span_stack::LocalSpanStack;
 use crate::local::local_span_stack::SpanLineHandle;
 use crate::local::local_span_stack::LOCAL_SPAN_STACK;
 use crate::prelude::SpanContext;
+use crate::prelude::SpanId;
 use crate::prelude::SpanRecord;
 use crate::util::CollectToken;
 use crate::util::RawSpans;
@@ -108,11 +109,55 @@ impl LocalCollector {
         #[cfg(feature = "enable")]
         {
             LOCAL_SPAN_STACK
-                .try_with(|stack| Self::new(None, stack.clone()))
+                .try_with(|stack| Self::new(None, None, stack.clone()))
                 .unwrap_or_default()
         }
     }
 
+    /// Returns the number of [`LocalSpan`]s and [`Event`]s recorded by the collector so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::local::LocalCollector;
+    /// use minitrace::prelude::*;
+    ///
+    /// let collector = LocalCollector::start();
+    /// let span = LocalSpan::enter_with_local_parent("a child span");
+    /// drop(span);
+    ///
+    /// if !collector.is_empty() {
+    ///     let local_spans = collector.collect();
+    ///     let root = Span::root("root", SpanContext::random());
+    ///     root.push_child_spans(local_spans);
+    /// }
+    /// ```
+    ///
+    /// [`LocalSpan`]: crate::local::LocalSpan
+    /// [`Event`]: crate::Event
+    pub fn len(&self) -> usize {
+        #[cfg(not(feature = "enable"))]
+        {
+            0
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            self.inner
+                .as_ref()
+                .map(|inner| inner.stack.borrow().span_count(&inner.span_line_handle))
+                .unwrap_or(0)
+        }
+    }
+
+    /// Returns `true` if no [`LocalSpan`] or [`Event`] has been recorded by the collector so far.
+    ///
+    /// [`LocalSpan`]: crate::local::LocalSpan
+    /// [`Event`]: crate::Event
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn collect(self) -> LocalSpans {
         #[cfg(not(feature = "enable"))]
         {
@@ -132,11 +177,12 @@ impl LocalCollector {
 impl LocalCollector {
     pub(crate) fn new(
         collect_token: Option<CollectToken>,
+        tracestate: Option<Arc<str>>,
         stack: Rc<RefCell<LocalSpanStack>>,
     ) -> Self {
         let span_line_epoch = {
             let stack = &mut (*stack).borrow_mut();
-            stack.register_span_line(collect_token)
+            stack.register_span_line(collect_token, tracestate)
         };
 
         Self {
@@ -187,6 +233,110 @@ impl Drop for LocalCollector {
 }
 
 impl LocalSpans {
+    /// Returns the number of collected [`LocalSpan`]s and [`Event`]s.
+    ///
+    /// [`LocalSpan`]: crate::local::LocalSpan
+    /// [`Event`]: crate::Event
+    pub fn len(&self) -> usize {
+        #[cfg(not(feature = "enable"))]
+        {
+            0
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            self.inner.spans.len()
+        }
+    }
+
+    /// Returns `true` if no [`LocalSpan`] or [`Event`] was collected.
+    ///
+    /// [`LocalSpan`]: crate::local::LocalSpan
+    /// [`Event`]: crate::Event
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Combines the spans of two `LocalSpans` collected in separate scopes into one.
+    ///
+    /// The top spans of both remain top spans, which will become children of the span that the
+    /// merged `LocalSpans` are pushed to. The end time of the merged spans is the later one of
+    /// the two.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::local::LocalCollector;
+    /// use minitrace::prelude::*;
+    ///
+    /// let collector = LocalCollector::start();
+    /// let span = LocalSpan::enter_with_local_parent("first");
+    /// drop(span);
+    /// let first = collector.collect();
+    ///
+    /// let collector = LocalCollector::start();
+    /// let span = LocalSpan::enter_with_local_parent("second");
+    /// drop(span);
+    /// let second = collector.collect();
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// root.push_child_spans(first.merge(second));
+    /// ```
+    pub fn merge(self, other: LocalSpans) -> LocalSpans {
+        #[cfg(not(feature = "enable"))]
+        {
+            LocalSpans {}
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            let end_time = self.inner.end_time.max(other.inner.end_time);
+            let mut spans = Arc::try_unwrap(self.inner)
+                .map(|inner| inner.spans)
+                .unwrap_or_else(|inner| inner.spans.iter().cloned().collect());
+            match Arc::try_unwrap(other.inner) {
+                Ok(mut inner) => spans.extend(inner.spans.drain(..)),
+                Err(inner) => spans.extend(inner.spans.iter().cloned()),
+            }
+
+            LocalSpans {
+                inner: Arc::new(LocalSpansInner { spans, end_time }),
+            }
+        }
+    }
+
+    /// Sets the parent of the top spans and events to the span of `new_parent_id`.
+    ///
+    /// This is used to nest a fragment under a span of another `LocalSpans` before
+    /// [merging](LocalSpans::merge) them. If no span of `new_parent_id` ends up in the same
+    /// trace, the reparented spans will be orphans.
+    pub fn reparent(self, new_parent_id: SpanId) -> LocalSpans {
+        #[cfg(not(feature = "enable"))]
+        {
+            LocalSpans {}
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            let LocalSpansInner {
+                mut spans,
+                end_time,
+            } = Arc::try_unwrap(self.inner).unwrap_or_else(|inner| LocalSpansInner {
+                spans: inner.spans.iter().cloned().collect(),
+                end_time: inner.end_time,
+            });
+            for span in spans.iter_mut() {
+                if span.parent_id == SpanId::default() {
+                    span.parent_id = new_parent_id;
+                }
+            }
+
+            LocalSpans {
+                inner: Arc::new(LocalSpansInner { spans, end_time }),
+            }
+        }
+    }
+
     /// Converts the `LocalSpans` to `SpanRecord`s.
     ///
     /// The converted spans will appear as if they were collected within the given parent context.
@@ -239,7 +389,7 @@ impl LocalSpans {
 mod tests {
     use super::*;
     use crate::collector::CollectTokenItem;
-    use crate::collector::SpanId;
+    use crate::collector::EventLevel;
     use crate::prelude::LocalSpan;
     use crate::prelude::TraceId;
     use crate::util::tree::tree_str_from_raw_spans;
@@ -248,7 +398,7 @@ mod tests {
     #[test]
     fn local_collector_basic() {
         let stack = Rc::new(RefCell::new(LocalSpanStack::with_capacity(16)));
-        let collector1 = LocalCollector::new(None, stack.clone());
+        let collector1 = LocalCollector::new(None, None, stack.clone());
 
         let span1 = stack.borrow_mut().enter_span("span1").unwrap();
         {
@@ -257,8 +407,9 @@ mod tests {
                 parent_id: SpanId::default(),
                 collect_id: 42,
                 is_root: false,
+                sampled: true,
             };
-            let collector2 = LocalCollector::new(Some(token2.into()), stack.clone());
+            let collector2 = LocalCollector::new(Some(token2.into()), None, stack.clone());
             let span2 = stack.borrow_mut().enter_span("span2").unwrap();
             let span3 = stack.borrow_mut().enter_span("span3").unwrap();
             stack.borrow_mut().exit_span(span3);
@@ -287,7 +438,7 @@ span1 []
     #[test]
     fn drop_without_collect() {
         let stack = Rc::new(RefCell::new(LocalSpanStack::with_capacity(16)));
-        let collector1 = LocalCollector::new(None, stack.clone());
+        let collector1 = LocalCollector::new(None, None, stack.clone());
 
         let span1 = stack.borrow_mut().enter_span("span1").unwrap();
         {
@@ -296,8 +447,9 @@ span1 []
                 parent_id: SpanId::default(),
                 collect_id: 42,
                 is_root: false,
+                sampled: true,
             };
-            let collector2 = LocalCollector::new(Some(token2.into()), stack.clone());
+            let collector2 = LocalCollector::new(Some(token2.into()), None, stack.clone());
             let span2 = stack.borrow_mut().enter_span("span2").unwrap();
             let span3 = stack.borrow_mut().enter_span("span3").unwrap();
             stack.borrow_mut().exit_span(span3);
@@ -314,6 +466,43 @@ span1 []
         );
     }
 
+    #[test]
+    fn local_collector_len() {
+        let stack = Rc::new(RefCell::new(LocalSpanStack::with_capacity(16)));
+        let collector1 = LocalCollector::new(None, None, stack.clone());
+        assert!(collector1.is_empty());
+        assert_eq!(collector1.len(), 0);
+
+        let span1 = stack.borrow_mut().enter_span("span1").unwrap();
+        assert!(!collector1.is_empty());
+        assert_eq!(collector1.len(), 1);
+        {
+            let collector2 = LocalCollector::new(None, None, stack.clone());
+            assert!(collector2.is_empty());
+
+            let span2 = stack.borrow_mut().enter_span("span2").unwrap();
+            stack
+                .borrow_mut()
+                .add_event("event", EventLevel::Info, || []);
+            stack.borrow_mut().exit_span(span2);
+            assert_eq!(collector2.len(), 2);
+            assert_eq!(collector1.len(), 1);
+
+            let spans = collector2.collect();
+            assert!(!spans.is_empty());
+            assert_eq!(spans.len(), 2);
+        }
+        stack.borrow_mut().exit_span(span1);
+
+        let spans = collector1.collect();
+        assert_eq!(spans.len(), 1);
+
+        let collector3 = LocalCollector::new(None, None, stack);
+        let spans = collector3.collect();
+        assert!(spans.is_empty());
+        assert_eq!(spans.len(), 0);
+    }
+
     #[test]
     fn local_spans_to_span_record() {
         let collector = LocalCollector::start();
@@ -2,11 +2,24 @@
 
 use std::borrow::Cow;
 use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
 use std::rc::Rc;
+use std::task::Context;
+use std::task::Poll;
+use std::time::Duration;
+use std::time::SystemTime;
 
+use crate::collector::SpanContext;
+use crate::collector::SpanKind;
+use crate::collector::SpanStatus;
+use crate::event::Event;
 use crate::local::local_span_line::LocalSpanHandle;
 use crate::local::local_span_stack::LocalSpanStack;
 use crate::local::local_span_stack::LOCAL_SPAN_STACK;
+use crate::span_fields::SpanFields;
+use crate::util::unix_nanos_from_system_time;
+use crate::Span;
 
 /// An optimized [`Span`] for tracing operations within a single thread.
 ///
@@ -54,8 +67,143 @@ impl LocalSpan {
         }
     }
 
+    /// Returns a future that enters a new child span of the current local span when it is
+    /// polled, which is immediately ready with the [`LocalSpan`]. The `LocalSpan` is the guard of
+    /// the span, which is exited when it is dropped.
+    ///
+    /// The span is entered on the thread polling the future rather than the thread creating it,
+    /// which is useful for code that has to produce a future, for example in combinators or code
+    /// generated by macros. See [`LocalSpan::enter_with_local_parent()`] for the span itself.
+    ///
+    /// # Drop the span before the next `.await`
+    ///
+    /// **The returned `LocalSpan` must be dropped before the next `.await`.** Like every
+    /// `LocalSpan`, it lives on the local span stack of the thread, which is shared by all the
+    /// tasks polled by that thread. If it is held across an `.await`, another task polled in the
+    /// meantime, even on a current-thread executor, enters and exits its own spans on top of it,
+    /// and the task may be resumed on another thread, so the spans are no longer exited in the
+    /// reverse order of entering and the local span stack is corrupted. To trace a future across
+    /// `.await` points, use a [`Span`] with [`FutureExt::in_span()`] instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::FutureExt;
+    /// use minitrace::prelude::*;
+    ///
+    /// # futures::executor::block_on(async {
+    /// let root = Span::root("root", SpanContext::random());
+    /// let _g = root.set_local_parent();
+    ///
+    /// let span = async {}
+    ///     .then(|_| LocalSpan::enter_async_with_local_parent("child"))
+    ///     .await;
+    /// // ...
+    /// drop(span);
+    ///
+    /// async {}.await;
+    /// # });
+    /// ```
+    ///
+    /// [`FutureExt::in_span()`]: crate::future::FutureExt::in_span
+    #[inline]
+    pub fn enter_async_with_local_parent(
+        name: impl Into<Cow<'static, str>>,
+    ) -> LocalSpanAsyncGuard {
+        LocalSpanAsyncGuard {
+            name: Some(name.into()),
+        }
+    }
+
+    /// Create a new child span of `parent`, regardless of the current local parent, which is
+    /// reported to the traces of `parent` when the returned [`LocalSpanWithParent`] is dropped.
+    ///
+    /// Unlike [`LocalSpan::enter_with_local_parent()`], the span is not pushed to the local span
+    /// stack of the thread, so it does not become the local parent, and the current local parent
+    /// is left untouched. This is useful in callbacks and closures carrying their parent span,
+    /// where the implicit local parent is not the right one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    ///
+    /// let callback = |parent: &Span| {
+    ///     let _span = LocalSpan::enter_with_parent_span("callback", parent);
+    /// };
+    /// callback(&root);
+    /// ```
+    #[inline]
+    pub fn enter_with_parent_span(
+        name: impl Into<Cow<'static, str>>,
+        parent: &Span,
+    ) -> LocalSpanWithParent {
+        LocalSpanWithParent {
+            span: Span::enter_with_parent(name, parent),
+        }
+    }
+
+    /// Returns an [`EventBuilder`] to add an event with the given name to the current local
+    /// parent once [`EventBuilder::record()`] is called.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let _guard = root.set_local_parent();
+    ///
+    /// LocalSpan::event("cache miss")
+    ///     .with_property(|| ("key", "user:42"))
+    ///     .record();
+    /// ```
+    #[inline]
+    pub fn event(name: impl Into<Cow<'static, str>>) -> EventBuilder {
+        EventBuilder {
+            #[cfg(feature = "enable")]
+            name: name.into(),
+            #[cfg(feature = "enable")]
+            properties: Vec::new(),
+        }
+    }
+
+    /// Adds an event with the given name and properties to the current local parent.
+    ///
+    /// This is a shorthand for [`Event::add_to_local_parent()`] which accepts any key and value
+    /// convertible into `Cow<'static, str>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let _guard = root.set_local_parent();
+    ///
+    /// LocalSpan::event_now("cache miss", || [("key", "user:42")]);
+    /// ```
+    ///
+    /// [`Event::add_to_local_parent()`]: crate::Event::add_to_local_parent
+    #[inline]
+    pub fn event_now<K, V, I, F>(name: impl Into<Cow<'static, str>>, properties: F)
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce() -> I,
+    {
+        Event::add_to_local_parent(name, || {
+            properties()
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+        })
+    }
+
     /// Add a single property to the current local parent. If the local parent is a [`Span`],
-    /// the property will not be added to the `Span`.
+    /// the property will not be added to the `Span`, use [`Span::add_property()`] instead.
     ///
     /// A property is an arbitrary key-value pair associated with a span.
     ///
@@ -68,6 +216,7 @@ impl LocalSpan {
     /// ```
     ///
     /// [`Span`]: crate::Span
+    /// [`Span::add_property()`]: crate::Span::add_property
     #[inline]
     pub fn add_property<K, V, F>(property: F)
     where
@@ -79,7 +228,7 @@ impl LocalSpan {
     }
 
     /// Add multiple properties to the current local parent. If the local parent is a [`Span`],
-    /// the properties will not be added to the `Span`.
+    /// the properties will not be added to the `Span`, use [`Span::add_properties()`] instead.
     ///
     /// # Examples
     ///
@@ -90,6 +239,7 @@ impl LocalSpan {
     /// ```
     ///
     /// [`Span`]: crate::Span
+    /// [`Span::add_properties()`]: crate::Span::add_properties
     #[inline]
     pub fn add_properties<K, V, I, F>(properties: F)
     where
@@ -112,6 +262,234 @@ impl LocalSpan {
         }
     }
 
+    /// Record an error on the current local parent. If the local parent is a [`Span`], the
+    /// properties will not be added to the `Span`.
+    ///
+    /// The properties `error` and `error.message` are added, as well as `error.cause` if the
+    /// error has a source, and the status is set to [`SpanStatus::Error`] with the error message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let err = std::io::Error::new(std::io::ErrorKind::Other, "oh no");
+    /// LocalSpan::record_error(&err);
+    /// ```
+    ///
+    /// [`Span`]: crate::Span
+    #[inline]
+    pub fn record_error(err: &dyn std::error::Error) {
+        #[cfg(feature = "enable")]
+        {
+            LOCAL_SPAN_STACK
+                .try_with(|s| {
+                    let span_stack = &mut *s.borrow_mut();
+                    let span_line = span_stack.current_span_line()?;
+                    let parent_handle = span_line.current_parent_handle()?;
+                    span_line
+                        .add_properties(&parent_handle, || crate::util::error_properties(err));
+                    span_line.set_status(&parent_handle, SpanStatus::Error(err.to_string().into()));
+                    Some(())
+                })
+                .ok();
+        }
+    }
+
+    /// Set the status of the current local parent. If the local parent is a [`Span`], the status
+    /// will not be set on the `Span`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// LocalSpan::set_status(SpanStatus::Error("connection refused".into()));
+    /// ```
+    ///
+    /// [`Span`]: crate::Span
+    #[inline]
+    pub fn set_status(status: SpanStatus) {
+        #[cfg(feature = "enable")]
+        {
+            LOCAL_SPAN_STACK
+                .try_with(|s| {
+                    let span_stack = &mut *s.borrow_mut();
+                    let span_line = span_stack.current_span_line()?;
+                    let parent_handle = span_line.current_parent_handle()?;
+                    span_line.set_status(&parent_handle, status);
+                    Some(())
+                })
+                .ok();
+        }
+    }
+
+    /// Link the current local parent to a span that is not its parent, such as one of the
+    /// upstream requests processed by a batch job. If the local parent is a [`Span`], the link
+    /// will not be added to the `Span`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let upstream = SpanContext::random();
+    /// LocalSpan::add_link(upstream);
+    /// ```
+    ///
+    /// [`Span`]: crate::Span
+    #[inline]
+    pub fn add_link(linked: SpanContext) {
+        #[cfg(feature = "enable")]
+        {
+            LOCAL_SPAN_STACK
+                .try_with(|s| {
+                    let span_stack = &mut *s.borrow_mut();
+                    let span_line = span_stack.current_span_line()?;
+                    let parent_handle = span_line.current_parent_handle()?;
+                    span_line.add_link(&parent_handle, (linked.trace_id, linked.span_id));
+                    Some(())
+                })
+                .ok();
+        }
+    }
+
+    /// Rename the current local parent. If the local parent is a [`Span`], the `Span` will not
+    /// be renamed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// LocalSpan::set_name("GET /users/{id}");
+    /// ```
+    ///
+    /// [`Span`]: crate::Span
+    #[inline]
+    pub fn set_name(name: impl Into<Cow<'static, str>>) {
+        #[cfg(feature = "enable")]
+        {
+            LOCAL_SPAN_STACK
+                .try_with(|s| {
+                    let span_stack = &mut *s.borrow_mut();
+                    let span_line = span_stack.current_span_line()?;
+                    let parent_handle = span_line.current_parent_handle()?;
+                    span_line.set_name(&parent_handle, name.into());
+                    Some(())
+                })
+                .ok();
+        }
+    }
+
+    /// Returns the name of the current local parent, or `None` if there is no local parent or the
+    /// local parent is a [`Span`].
+    ///
+    /// The name is returned by value because it is stored in a thread-local stack.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let name = LocalSpan::name();
+    /// ```
+    ///
+    /// [`Span`]: crate::Span
+    #[inline]
+    pub fn name() -> Option<Cow<'static, str>> {
+        #[cfg(not(feature = "enable"))]
+        {
+            None
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            LOCAL_SPAN_STACK
+                .try_with(|s| {
+                    let span_stack = &mut *s.borrow_mut();
+                    let span_line = span_stack.current_span_line()?;
+                    let parent_handle = span_line.current_parent_handle()?;
+                    span_line.name(&parent_handle).cloned()
+                })
+                .ok()
+                .flatten()
+        }
+    }
+
+    /// Returns the elapsed time since the current local parent began, or `None` if there is no
+    /// local parent or the local parent is a [`Span`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use minitrace::prelude::*;
+    ///
+    /// let _span = LocalSpan::enter_with_local_parent("a child span");
+    ///
+    /// // ...
+    ///
+    /// if LocalSpan::elapsed()
+    ///     .map(|elapsed| elapsed > Duration::from_secs(1))
+    ///     .unwrap_or(false)
+    /// {
+    ///     println!("slow operation");
+    /// }
+    /// ```
+    ///
+    /// [`Span`]: crate::Span
+    #[inline]
+    pub fn elapsed() -> Option<Duration> {
+        #[cfg(not(feature = "enable"))]
+        {
+            None
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            LOCAL_SPAN_STACK
+                .try_with(|s| {
+                    let span_stack = &*s.borrow();
+                    let span_line = span_stack.current_span_line_ref()?;
+                    let parent_handle = span_line.current_parent_handle()?;
+                    span_line.elapsed(&parent_handle)
+                })
+                .ok()
+                .flatten()
+        }
+    }
+
+    /// Returns `true` if the current local parent is a `LocalSpan`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// assert!(!LocalSpan::is_active());
+    /// ```
+    #[inline]
+    pub fn is_active() -> bool {
+        #[cfg(not(feature = "enable"))]
+        {
+            false
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            LOCAL_SPAN_STACK
+                .try_with(|s| {
+                    s.borrow()
+                        .current_span_line_ref()
+                        .and_then(|span_line| span_line.current_parent_handle())
+                        .is_some()
+                })
+                .unwrap_or(false)
+        }
+    }
+
     /// Add a single property to the `LocalSpan` and return the modified `LocalSpan`.
     ///
     /// A property is an arbitrary key-value pair associated with a span.
@@ -160,6 +538,112 @@ impl LocalSpan {
 
         self
     }
+
+    /// Add the fields of a struct as properties to the `LocalSpan` and return the modified
+    /// `LocalSpan`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    /// use minitrace::SpanFields;
+    ///
+    /// #[derive(SpanFields)]
+    /// struct Request {
+    ///     pub method: String,
+    ///     pub path: String,
+    /// }
+    ///
+    /// let request = Request {
+    ///     method: "GET".to_string(),
+    ///     path: "/".to_string(),
+    /// };
+    /// let span =
+    ///     LocalSpan::enter_with_local_parent("a child span").with_properties_from_struct(&request);
+    /// ```
+    #[inline]
+    pub fn with_properties_from_struct(self, fields: &impl SpanFields) -> Self {
+        self.with_properties(|| fields.span_fields())
+    }
+
+    /// Record an error on the `LocalSpan` and return the modified `LocalSpan`.
+    ///
+    /// See [`LocalSpan::record_error()`] for the properties and the status being set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let err = std::io::Error::new(std::io::ErrorKind::Other, "oh no");
+    /// let span = LocalSpan::enter_with_local_parent("a child span").with_error(&err);
+    /// ```
+    #[inline]
+    pub fn with_error(self, err: &dyn std::error::Error) -> Self {
+        #[cfg(feature = "enable")]
+        if let Some(LocalSpanInner { stack, span_handle }) = &self.inner {
+            let span_stack = &mut *stack.borrow_mut();
+            if let Some(span_line) = span_stack.current_span_line() {
+                span_line.add_properties(span_handle, || crate::util::error_properties(err));
+                span_line.set_status(span_handle, SpanStatus::Error(err.to_string().into()));
+            }
+        }
+
+        self
+    }
+
+    /// Set the kind of the `LocalSpan` and return the modified `LocalSpan`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span = LocalSpan::enter_with_local_parent("a child span").with_kind(SpanKind::Client);
+    /// ```
+    #[inline]
+    pub fn with_kind(self, kind: SpanKind) -> Self {
+        #[cfg(feature = "enable")]
+        if let Some(LocalSpanInner { stack, span_handle }) = &self.inner {
+            let span_stack = &mut *stack.borrow_mut();
+            if let Some(span_line) = span_stack.current_span_line() {
+                span_line.set_kind(span_handle, kind);
+            }
+        }
+
+        self
+    }
+
+    /// Set the begin time of the `LocalSpan` and return the modified `LocalSpan`.
+    ///
+    /// See [`Span::with_begin_time()`] for how a begin time later than the end of the span is
+    /// reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use std::time::SystemTime;
+    ///
+    /// use minitrace::prelude::*;
+    ///
+    /// let span = LocalSpan::enter_with_local_parent("a child span")
+    ///     .with_begin_time(SystemTime::now() - Duration::from_millis(10));
+    /// ```
+    ///
+    /// [`Span::with_begin_time()`]: crate::Span::with_begin_time
+    #[inline]
+    pub fn with_begin_time(self, begin_time: SystemTime) -> Self {
+        #[cfg(feature = "enable")]
+        if let Some(LocalSpanInner { stack, span_handle }) = &self.inner {
+            let span_stack = &mut *stack.borrow_mut();
+            if let Some(span_line) = span_stack.current_span_line() {
+                span_line.set_begin_time(span_handle, unix_nanos_from_system_time(begin_time));
+            }
+        }
+
+        self
+    }
 }
 
 #[cfg(feature = "enable")]
@@ -191,6 +675,157 @@ impl Drop for LocalSpan {
     }
 }
 
+/// A future entering a [`LocalSpan`] when it is polled, returned by
+/// [`LocalSpan::enter_async_with_local_parent()`].
+///
+/// The resulting `LocalSpan` must be dropped before the next `.await`; see
+/// [`LocalSpan::enter_async_with_local_parent()`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct LocalSpanAsyncGuard {
+    name: Option<Cow<'static, str>>,
+}
+
+impl Future for LocalSpanAsyncGuard {
+    type Output = LocalSpan;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let name = self
+            .name
+            .take()
+            .expect("`LocalSpanAsyncGuard` polled after completion");
+        Poll::Ready(LocalSpan::enter_with_local_parent(name))
+    }
+}
+
+/// A span with an explicit parent, returned by [`LocalSpan::enter_with_parent_span()`].
+///
+/// The span is reported to the traces of its parent when the guard is dropped.
+#[must_use]
+pub struct LocalSpanWithParent {
+    span: Span,
+}
+
+impl LocalSpanWithParent {
+    /// Add a single property to the span and return the modified `LocalSpanWithParent`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let _span = LocalSpan::enter_with_parent_span("span", &root).with_property(|| ("key", "value"));
+    /// ```
+    #[inline]
+    pub fn with_property<K, V, F>(self, property: F) -> Self
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+        F: FnOnce() -> (K, V),
+    {
+        self.with_properties(|| [property()])
+    }
+
+    /// Add multiple properties to the span and return the modified `LocalSpanWithParent`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let _span = LocalSpan::enter_with_parent_span("span", &root)
+    ///     .with_properties(|| [("key1", "value1"), ("key2", "value2")]);
+    /// ```
+    #[inline]
+    pub fn with_properties<K, V, I, F>(self, properties: F) -> Self
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce() -> I,
+    {
+        LocalSpanWithParent {
+            span: self.span.with_properties(properties),
+        }
+    }
+}
+
+/// A builder of an event added to the current local parent, returned by [`LocalSpan::event()`].
+///
+/// The properties are evaluated when they are added to the builder, and the event is added by
+/// [`EventBuilder::record()`].
+#[must_use = "the event is only added by `EventBuilder::record()`"]
+#[derive(Debug)]
+pub struct EventBuilder {
+    #[cfg(feature = "enable")]
+    name: Cow<'static, str>,
+    #[cfg(feature = "enable")]
+    properties: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+}
+
+impl EventBuilder {
+    /// Add a single property to the event and return the modified `EventBuilder`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// LocalSpan::event("event").with_property(|| ("key", "value")).record();
+    /// ```
+    #[inline]
+    pub fn with_property<K, V, F>(self, property: F) -> Self
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+        F: FnOnce() -> (K, V),
+    {
+        self.with_properties(|| [property()])
+    }
+
+    /// Add multiple properties to the event and return the modified `EventBuilder`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// LocalSpan::event("event")
+    ///     .with_properties(|| [("key1", "value1"), ("key2", "value2")])
+    ///     .record();
+    /// ```
+    #[inline]
+    #[allow(unused_mut)]
+    pub fn with_properties<K, V, I, F>(mut self, properties: F) -> Self
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce() -> I,
+    {
+        #[cfg(feature = "enable")]
+        self.properties.extend(
+            properties()
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into())),
+        );
+
+        self
+    }
+
+    /// Add the event to the current local parent with [`Event::add_to_local_parent()`].
+    ///
+    /// [`Event::add_to_local_parent()`]: crate::Event::add_to_local_parent
+    #[inline]
+    pub fn record(self) {
+        #[cfg(feature = "enable")]
+        Event::add_to_local_parent(self.name, || self.properties);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,8 +844,9 @@ mod tests {
             parent_id: SpanId::default(),
             collect_id: 42,
             is_root: false,
+            sampled: true,
         };
-        let collector = LocalCollector::new(Some(token.into()), stack.clone());
+        let collector = LocalCollector::new(Some(token.into()), None, stack.clone());
 
         {
             let _g = LocalSpan::enter_with_stack("span1", stack.clone());
@@ -231,6 +867,34 @@ span1 []
         );
     }
 
+    #[test]
+    fn local_span_elapsed() {
+        let collector = LocalCollector::start();
+        assert!(!LocalSpan::is_active());
+        assert_eq!(LocalSpan::elapsed(), None);
+
+        {
+            let _span1 = LocalSpan::enter_with_local_parent("span1");
+            assert!(LocalSpan::is_active());
+            std::thread::sleep(Duration::from_millis(10));
+            let elapsed1 = LocalSpan::elapsed().unwrap();
+            assert!(elapsed1 >= Duration::from_millis(10));
+
+            {
+                let _span2 = LocalSpan::enter_with_local_parent("span2");
+                assert!(LocalSpan::is_active());
+                assert!(LocalSpan::elapsed().unwrap() < elapsed1);
+            }
+
+            assert!(LocalSpan::is_active());
+            assert!(LocalSpan::elapsed().unwrap() >= elapsed1);
+        }
+
+        assert!(!LocalSpan::is_active());
+        assert_eq!(LocalSpan::elapsed(), None);
+        collector.collect();
+    }
+
     #[test]
     fn local_span_noop() {
         let _span1 = LocalSpan::enter_with_local_parent("span1").with_property(|| ("k1", "v1"));
@@ -246,8 +910,9 @@ span1 []
             parent_id: SpanId::default(),
             collect_id: 42,
             is_root: false,
+            sampled: true,
         };
-        let collector = LocalCollector::new(Some(token.into()), stack.clone());
+        let collector = LocalCollector::new(Some(token.into()), None, stack.clone());
 
         {
             let span1 = LocalSpan::enter_with_stack("span1", stack.clone());
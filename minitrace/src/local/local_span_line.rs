@@ -1,8 +1,16 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
 use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::Duration;
 
 use crate::collector::CollectTokenItem;
+use crate::collector::EventLevel;
+use crate::collector::EvictionPolicy;
+use crate::collector::SpanId;
+use crate::collector::SpanKind;
+use crate::collector::SpanStatus;
+use crate::collector::TraceId;
 use crate::local::span_queue::SpanHandle;
 use crate::local::span_queue::SpanQueue;
 use crate::util::CollectToken;
@@ -12,6 +20,7 @@ pub struct SpanLine {
     span_queue: SpanQueue,
     epoch: usize,
     collect_token: Option<CollectToken>,
+    tracestate: Option<Arc<str>>,
 }
 
 impl SpanLine {
@@ -24,9 +33,25 @@ impl SpanLine {
             span_queue: SpanQueue::with_capacity(capacity),
             epoch: span_line_epoch,
             collect_token,
+            tracestate: None,
         }
     }
 
+    #[inline]
+    pub fn set_eviction_policy(&mut self, eviction_policy: EvictionPolicy) {
+        self.span_queue.set_eviction_policy(eviction_policy);
+    }
+
+    #[inline]
+    pub fn set_tracestate(&mut self, tracestate: Option<Arc<str>>) {
+        self.tracestate = tracestate;
+    }
+
+    #[inline]
+    pub fn tracestate(&self) -> Option<&Arc<str>> {
+        self.tracestate.as_ref()
+    }
+
     #[inline]
     pub fn span_line_epoch(&self) -> usize {
         self.epoch
@@ -48,12 +73,25 @@ impl SpanLine {
     }
 
     #[inline]
-    pub fn add_event<I, F>(&mut self, name: impl Into<Cow<'static, str>>, properties: F)
-    where
+    pub fn add_event<I, F>(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        level: EventLevel,
+        properties: F,
+    ) where
         I: IntoIterator<Item = (Cow<'static, str>, Cow<'static, str>)>,
         F: FnOnce() -> I,
     {
-        self.span_queue.add_event(name, properties);
+        self.span_queue.add_event(name, level, properties);
+    }
+
+    #[inline]
+    pub fn add_events<N, P>(&mut self, events: impl IntoIterator<Item = (N, u64, P)>)
+    where
+        N: Into<Cow<'static, str>>,
+        P: IntoIterator<Item = (Cow<'static, str>, Cow<'static, str>)>,
+    {
+        self.span_queue.add_events(events);
     }
 
     #[inline]
@@ -70,6 +108,58 @@ impl SpanLine {
         }
     }
 
+    #[inline]
+    pub fn set_status(&mut self, handle: &LocalSpanHandle, status: SpanStatus) {
+        if self.epoch == handle.span_line_epoch {
+            self.span_queue.set_status(&handle.span_handle, status);
+        }
+    }
+
+    #[inline]
+    pub fn set_kind(&mut self, handle: &LocalSpanHandle, kind: SpanKind) {
+        if self.epoch == handle.span_line_epoch {
+            self.span_queue.set_kind(&handle.span_handle, kind);
+        }
+    }
+
+    #[inline]
+    pub fn add_link(&mut self, handle: &LocalSpanHandle, link: (TraceId, SpanId)) {
+        if self.epoch == handle.span_line_epoch {
+            self.span_queue.add_link(&handle.span_handle, link);
+        }
+    }
+
+    #[inline]
+    pub fn set_name(&mut self, handle: &LocalSpanHandle, name: Cow<'static, str>) {
+        if self.epoch == handle.span_line_epoch {
+            self.span_queue.set_name(&handle.span_handle, name);
+        }
+    }
+
+    #[inline]
+    pub fn name(&self, handle: &LocalSpanHandle) -> Option<&Cow<'static, str>> {
+        (self.epoch == handle.span_line_epoch).then(|| self.span_queue.name(&handle.span_handle))
+    }
+
+    #[inline]
+    pub fn set_begin_time(&mut self, handle: &LocalSpanHandle, begin_unix_ns: u64) {
+        if self.epoch == handle.span_line_epoch {
+            self.span_queue
+                .set_begin_time(&handle.span_handle, begin_unix_ns);
+        }
+    }
+
+    #[inline]
+    pub fn elapsed(&self, handle: &LocalSpanHandle) -> Option<Duration> {
+        (self.epoch == handle.span_line_epoch)
+            .then(|| self.span_queue.elapsed(&handle.span_handle))
+    }
+
+    #[inline]
+    pub fn span_count(&self) -> usize {
+        self.span_queue.span_count()
+    }
+
     #[inline]
     pub fn current_collect_token(&self) -> Option<CollectToken> {
         self.collect_token.as_ref().map(|collect_token| {
@@ -83,11 +173,32 @@ impl SpanLine {
                         .unwrap_or(item.parent_id),
                     collect_id: item.collect_id,
                     is_root: false,
+                    sampled: item.sampled,
                 })
                 .collect()
         })
     }
 
+    #[inline]
+    pub fn current_trace_id(&self) -> Option<TraceId> {
+        Some(self.collect_token.as_ref()?.first()?.trace_id)
+    }
+
+    #[inline]
+    pub fn current_sampled(&self) -> Option<bool> {
+        Some(self.collect_token.as_ref()?.first()?.sampled)
+    }
+
+    #[inline]
+    pub fn current_span_id(&self) -> Option<SpanId> {
+        let item = self.collect_token.as_ref()?.first()?;
+        Some(
+            self.span_queue
+                .current_parent_id()
+                .unwrap_or(item.parent_id),
+        )
+    }
+
     #[inline]
     pub fn current_parent_handle(&self) -> Option<LocalSpanHandle> {
         let span_handle = self.span_queue.current_parent_handle()?;
@@ -152,12 +263,14 @@ span1 []
             parent_id: SpanId::default(),
             collect_id: 42,
             is_root: false,
+            sampled: true,
         };
         let token2 = CollectTokenItem {
             trace_id: TraceId(1235),
             parent_id: SpanId::default(),
             collect_id: 43,
             is_root: false,
+            sampled: true,
         };
         let token = [token1, token2].iter().collect();
         let mut span_line = SpanLine::new(16, 1, Some(token));
@@ -174,12 +287,14 @@ span1 []
                 parent_id: span_line.span_queue.current_parent_id().unwrap(),
                 collect_id: 42,
                 is_root: false,
+                sampled: true,
             },
             CollectTokenItem {
                 trace_id: TraceId(1235),
                 parent_id: span_line.span_queue.current_parent_id().unwrap(),
                 collect_id: 43,
                 is_root: false,
+                sampled: true,
             }
         ]);
         span_line.finish_span(span);
@@ -223,6 +338,7 @@ span []
             parent_id: SpanId::default(),
             collect_id: 42,
             is_root: false,
+            sampled: true,
         };
         let mut span_line1 = SpanLine::new(16, 1, Some(item.into()));
         let mut span_line2 = SpanLine::new(16, 2, None);
@@ -3,7 +3,11 @@
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::sync::Arc;
 
+use crate::collector::global_collector::eviction_policy;
+use crate::collector::global_collector::max_spans_per_thread;
+use crate::collector::EventLevel;
 use crate::local::local_span_line::LocalSpanHandle;
 use crate::local::local_span_line::SpanLine;
 use crate::util::CollectToken;
@@ -16,10 +20,46 @@ thread_local! {
     pub static LOCAL_SPAN_STACK: Rc<RefCell<LocalSpanStack>> = Rc::new(RefCell::new(LocalSpanStack::with_capacity(DEFAULT_SPAN_STACK_SIZE)));
 }
 
+/// Returns the number of [`LocalSpan`]s recorded by the current thread under its current local
+/// parent, which is limited by [`Config::max_spans_per_thread()`].
+///
+/// The count is reset when the [`LocalParentGuard`] under which the spans were recorded is
+/// dropped.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::prelude::*;
+///
+/// let root = Span::root("root", SpanContext::random());
+/// let _guard = root.set_local_parent();
+/// let _span = LocalSpan::enter_with_local_parent("child");
+///
+/// let count = minitrace::thread_span_count();
+/// ```
+///
+/// [`LocalSpan`]: crate::local::LocalSpan
+/// [`Config::max_spans_per_thread()`]: crate::collector::Config::max_spans_per_thread
+/// [`LocalParentGuard`]: crate::local::LocalParentGuard
+pub fn thread_span_count() -> usize {
+    #[cfg(not(feature = "enable"))]
+    {
+        0
+    }
+
+    #[cfg(feature = "enable")]
+    {
+        LOCAL_SPAN_STACK
+            .try_with(|stack| stack.borrow().thread_span_count)
+            .unwrap_or(0)
+    }
+}
+
 pub struct LocalSpanStack {
     span_lines: Vec<SpanLine>,
     capacity: usize,
     next_span_line_epoch: usize,
+    thread_span_count: usize,
 }
 
 impl LocalSpanStack {
@@ -29,13 +69,20 @@ impl LocalSpanStack {
             span_lines: Vec::with_capacity(capacity / 8),
             capacity,
             next_span_line_epoch: 0,
+            thread_span_count: 0,
         }
     }
 
     #[inline]
     pub fn enter_span(&mut self, name: impl Into<Cow<'static, str>>) -> Option<LocalSpanHandle> {
+        if max_spans_per_thread().is_some_and(|max| self.thread_span_count >= max) {
+            return None;
+        }
+
         let span_line = self.current_span_line()?;
-        span_line.start_span(name)
+        let span_handle = span_line.start_span(name)?;
+        self.thread_span_count += 1;
+        Some(span_handle)
     }
 
     #[inline]
@@ -50,13 +97,28 @@ impl LocalSpanStack {
     }
 
     #[inline]
-    pub fn add_event<I, F>(&mut self, name: impl Into<Cow<'static, str>>, properties: F)
-    where
+    pub fn add_event<I, F>(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        level: EventLevel,
+        properties: F,
+    ) where
         I: IntoIterator<Item = (Cow<'static, str>, Cow<'static, str>)>,
         F: FnOnce() -> I,
     {
         if let Some(span_line) = self.current_span_line() {
-            span_line.add_event(name, properties);
+            span_line.add_event(name, level, properties);
+        }
+    }
+
+    #[inline]
+    pub fn add_events<N, P>(&mut self, events: impl IntoIterator<Item = (N, u64, P)>)
+    where
+        N: Into<Cow<'static, str>>,
+        P: IntoIterator<Item = (Cow<'static, str>, Cow<'static, str>)>,
+    {
+        if let Some(span_line) = self.current_span_line() {
+            span_line.add_events(events);
         }
     }
 
@@ -70,6 +132,7 @@ impl LocalSpanStack {
     pub fn register_span_line(
         &mut self,
         collect_token: Option<CollectToken>,
+        tracestate: Option<Arc<str>>,
     ) -> Option<SpanLineHandle> {
         if self.span_lines.len() >= self.capacity {
             return None;
@@ -78,10 +141,13 @@ impl LocalSpanStack {
         let epoch = self.next_span_line_epoch;
         self.next_span_line_epoch = self.next_span_line_epoch.wrapping_add(1);
 
-        let span_line = SpanLine::new(DEFAULT_SPAN_QUEUE_SIZE, epoch, collect_token);
+        let mut span_line = SpanLine::new(DEFAULT_SPAN_QUEUE_SIZE, epoch, collect_token);
+        span_line.set_eviction_policy(eviction_policy());
+        span_line.set_tracestate(tracestate);
         self.span_lines.push(span_line);
         Some(SpanLineHandle {
             span_line_epoch: epoch,
+            thread_span_count: self.thread_span_count,
         })
     }
 
@@ -93,6 +159,8 @@ impl LocalSpanStack {
             self.current_span_line().unwrap().span_line_epoch(),
             span_line_handle.span_line_epoch,
         );
+        // Forget the spans recorded by the span line, which are collected now.
+        self.thread_span_count = span_line_handle.thread_span_count;
         let span_line = self.span_lines.pop()?;
         span_line.collect(span_line_handle.span_line_epoch)
     }
@@ -120,14 +188,32 @@ impl LocalSpanStack {
         span_line.current_collect_token()
     }
 
+    /// Returns the number of spans recorded in the span line, or `0` if the span line has been
+    /// unregistered.
+    pub fn span_count(&self, span_line_handle: &SpanLineHandle) -> usize {
+        self.span_lines
+            .iter()
+            .rev()
+            .find(|span_line| span_line.span_line_epoch() == span_line_handle.span_line_epoch)
+            .map(SpanLine::span_count)
+            .unwrap_or(0)
+    }
+
     #[inline]
     pub fn current_span_line(&mut self) -> Option<&mut SpanLine> {
         self.span_lines.last_mut()
     }
+
+    #[inline]
+    pub fn current_span_line_ref(&self) -> Option<&SpanLine> {
+        self.span_lines.last()
+    }
 }
 
 pub struct SpanLineHandle {
     span_line_epoch: usize,
+    /// The number of spans recorded by the thread when the span line was registered.
+    thread_span_count: usize,
 }
 
 #[cfg(test)]
@@ -147,8 +233,11 @@ mod tests {
             parent_id: SpanId::default(),
             collect_id: 42,
             is_root: false,
+            sampled: true,
         };
-        let span_line1 = span_stack.register_span_line(Some(token1.into())).unwrap();
+        let span_line1 = span_stack
+            .register_span_line(Some(token1.into()), None)
+            .unwrap();
         {
             {
                 let span1 = span_stack.enter_span("span1").unwrap();
@@ -164,8 +253,11 @@ mod tests {
                 parent_id: SpanId::default(),
                 collect_id: 48,
                 is_root: false,
+                sampled: true,
             };
-            let span_line2 = span_stack.register_span_line(Some(token2.into())).unwrap();
+            let span_line2 = span_stack
+                .register_span_line(Some(token2.into()), None)
+                .unwrap();
             {
                 let span3 = span_stack.enter_span("span3").unwrap();
                 {
@@ -201,58 +293,66 @@ span1 []
     fn span_stack_is_full() {
         let mut span_stack = LocalSpanStack::with_capacity(4);
 
-        let span_line1 = span_stack.register_span_line(None).unwrap();
+        let span_line1 = span_stack.register_span_line(None, None).unwrap();
         {
-            let span_line2 = span_stack.register_span_line(None).unwrap();
+            let span_line2 = span_stack.register_span_line(None, None).unwrap();
             {
                 let span_line3 = span_stack
-                    .register_span_line(Some(
-                        CollectTokenItem {
-                            trace_id: TraceId(1234),
-                            parent_id: SpanId::default(),
-                            collect_id: 42,
-                            is_root: false,
-                        }
-                        .into(),
-                    ))
+                    .register_span_line(
+                        Some(
+                            CollectTokenItem {
+                                trace_id: TraceId(1234),
+                                parent_id: SpanId::default(),
+                                collect_id: 42,
+                                is_root: false,
+                                sampled: true,
+                            }
+                            .into(),
+                        ),
+                        None,
+                    )
                     .unwrap();
                 {
-                    let span_line4 = span_stack.register_span_line(None).unwrap();
+                    let span_line4 = span_stack.register_span_line(None, None).unwrap();
                     {
-                        assert!(
-                            span_stack
-                                .register_span_line(Some(
+                        assert!(span_stack
+                            .register_span_line(
+                                Some(
                                     CollectTokenItem {
                                         trace_id: TraceId(1235),
                                         parent_id: SpanId::default(),
                                         collect_id: 43,
                                         is_root: false,
+                                        sampled: true,
                                     }
                                     .into()
-                                ))
-                                .is_none()
-                        );
-                        assert!(span_stack.register_span_line(None).is_none());
+                                ),
+                                None
+                            )
+                            .is_none());
+                        assert!(span_stack.register_span_line(None, None).is_none());
                     }
                     let _ = span_stack.unregister_and_collect(span_line4).unwrap();
                 }
                 {
-                    let span_line5 = span_stack.register_span_line(None).unwrap();
+                    let span_line5 = span_stack.register_span_line(None, None).unwrap();
                     {
-                        assert!(
-                            span_stack
-                                .register_span_line(Some(
+                        assert!(span_stack
+                            .register_span_line(
+                                Some(
                                     CollectTokenItem {
                                         trace_id: TraceId(1236),
                                         parent_id: SpanId::default(),
                                         collect_id: 44,
                                         is_root: false,
+                                        sampled: true,
                                     }
                                     .into()
-                                ))
-                                .is_none()
-                        );
-                        assert!(span_stack.register_span_line(None).is_none());
+                                ),
+                                None
+                            )
+                            .is_none());
+                        assert!(span_stack.register_span_line(None, None).is_none());
                     }
                     let _ = span_stack.unregister_and_collect(span_line5).unwrap();
                 }
@@ -272,13 +372,16 @@ span1 []
             parent_id: SpanId(1),
             collect_id: 1,
             is_root: false,
+            sampled: true,
         };
-        let span_line1 = span_stack.register_span_line(Some(token1.into())).unwrap();
+        let span_line1 = span_stack
+            .register_span_line(Some(token1.into()), None)
+            .unwrap();
         assert_eq!(span_stack.current_collect_token().unwrap().as_slice(), &[
             token1
         ]);
         {
-            let span_line2 = span_stack.register_span_line(None).unwrap();
+            let span_line2 = span_stack.register_span_line(None, None).unwrap();
             assert!(span_stack.current_collect_token().is_none());
             {
                 let token3 = CollectTokenItem {
@@ -286,8 +389,11 @@ span1 []
                     parent_id: SpanId(3),
                     collect_id: 3,
                     is_root: false,
+                    sampled: true,
                 };
-                let span_line3 = span_stack.register_span_line(Some(token3.into())).unwrap();
+                let span_line3 = span_stack
+                    .register_span_line(Some(token3.into()), None)
+                    .unwrap();
                 assert_eq!(span_stack.current_collect_token().unwrap().as_slice(), &[
                     token3
                 ]);
@@ -301,8 +407,11 @@ span1 []
                 parent_id: SpanId(4),
                 collect_id: 4,
                 is_root: false,
+                sampled: true,
             };
-            let span_line4 = span_stack.register_span_line(Some(token4.into())).unwrap();
+            let span_line4 = span_stack
+                .register_span_line(Some(token4.into()), None)
+                .unwrap();
             assert_eq!(span_stack.current_collect_token().unwrap().as_slice(), &[
                 token4
             ]);
@@ -319,19 +428,23 @@ span1 []
     #[should_panic]
     fn unmatched_span_line_exit_span() {
         let mut span_stack = LocalSpanStack::with_capacity(16);
-        let span_line1 = span_stack.register_span_line(None).unwrap();
+        let span_line1 = span_stack.register_span_line(None, None).unwrap();
         let span1 = span_stack.enter_span("span1").unwrap();
         {
             let span_line2 = span_stack
-                .register_span_line(Some(
-                    CollectTokenItem {
-                        trace_id: TraceId(1234),
-                        parent_id: SpanId::default(),
-                        collect_id: 42,
-                        is_root: false,
-                    }
-                    .into(),
-                ))
+                .register_span_line(
+                    Some(
+                        CollectTokenItem {
+                            trace_id: TraceId(1234),
+                            parent_id: SpanId::default(),
+                            collect_id: 42,
+                            is_root: false,
+                            sampled: true,
+                        }
+                        .into(),
+                    ),
+                    None,
+                )
                 .unwrap();
             span_stack.exit_span(span1);
             let _ = span_stack.unregister_and_collect(span_line2).unwrap();
@@ -343,19 +456,23 @@ span1 []
     #[should_panic]
     fn unmatched_span_line_add_properties() {
         let mut span_stack = LocalSpanStack::with_capacity(16);
-        let span_line1 = span_stack.register_span_line(None).unwrap();
+        let span_line1 = span_stack.register_span_line(None, None).unwrap();
         let span1 = span_stack.enter_span("span1").unwrap();
         {
             let span_line2 = span_stack
-                .register_span_line(Some(
-                    CollectTokenItem {
-                        trace_id: TraceId(1234),
-                        parent_id: SpanId::default(),
-                        collect_id: 42,
-                        is_root: false,
-                    }
-                    .into(),
-                ))
+                .register_span_line(
+                    Some(
+                        CollectTokenItem {
+                            trace_id: TraceId(1234),
+                            parent_id: SpanId::default(),
+                            collect_id: 42,
+                            is_root: false,
+                            sampled: true,
+                        }
+                        .into(),
+                    ),
+                    None,
+                )
                 .unwrap();
             span_stack.add_properties(&span1, || [("k1", "v1")]);
             let _ = span_stack.unregister_and_collect(span_line2).unwrap();
@@ -368,18 +485,22 @@ span1 []
     #[should_panic]
     fn unmatched_span_line_collect() {
         let mut span_stack = LocalSpanStack::with_capacity(16);
-        let span_line1 = span_stack.register_span_line(None).unwrap();
+        let span_line1 = span_stack.register_span_line(None, None).unwrap();
         {
             let span_line2 = span_stack
-                .register_span_line(Some(
-                    CollectTokenItem {
-                        trace_id: TraceId(1234),
-                        parent_id: SpanId::default(),
-                        collect_id: 42,
-                        is_root: false,
-                    }
-                    .into(),
-                ))
+                .register_span_line(
+                    Some(
+                        CollectTokenItem {
+                            trace_id: TraceId(1234),
+                            parent_id: SpanId::default(),
+                            collect_id: 42,
+                            is_root: false,
+                            sampled: true,
+                        }
+                        .into(),
+                    ),
+                    None,
+                )
                 .unwrap();
             let _ = span_stack.unregister_and_collect(span_line1).unwrap();
             let _ = span_stack.unregister_and_collect(span_line2).unwrap();
@@ -11,5 +11,8 @@ pub(crate) mod span_queue;
 
 pub use self::local_collector::LocalCollector;
 pub use self::local_collector::LocalSpans;
+pub use self::local_span::EventBuilder;
 pub use self::local_span::LocalSpan;
+pub use self::local_span::LocalSpanAsyncGuard;
+pub use self::local_span::LocalSpanWithParent;
 pub use crate::span::LocalParentGuard;
@@ -1,10 +1,18 @@
 // Copyright 2021 TiKV Project Authors. Licensed under Apache-2.0.
 
 use std::borrow::Cow;
+use std::time::Duration;
+use std::time::SystemTime;
 
 use minstant::Instant;
 
+use crate::collector::EventLevel;
+use crate::collector::EventRecord;
 use crate::collector::SpanId;
+use crate::collector::SpanKind;
+use crate::collector::SpanStatus;
+use crate::collector::TraceId;
+use crate::util::unix_nanos_from_system_time;
 use crate::util::Properties;
 
 #[derive(Debug)]
@@ -15,11 +23,28 @@ pub struct RawSpan {
     pub name: Cow<'static, str>,
     pub properties: Properties,
     pub is_event: bool,
+    pub event_level: EventLevel,
+    pub kind: SpanKind,
+    // Replaces the begin time in the reported `SpanRecord`, in nanoseconds since the unix epoch,
+    // or the timestamp of an event. It's kept inline because every event of a batch sets it.
+    pub begin_time_override: Option<u64>,
+    // The rarely set fields, boxed so that they don't grow the span queue
+    pub extension: Option<Box<RawSpanExtension>>,
 
     // Will write this field at post processing
     pub end_instant: Instant,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct RawSpanExtension {
+    // Replaces the begin time and the duration in the reported `SpanRecord`
+    pub timestamp_override: Option<(u64, u64)>,
+    // Events that happened before the span was created
+    pub pre_events: Vec<EventRecord>,
+    pub status: SpanStatus,
+    pub links: Vec<(TraceId, SpanId)>,
+}
+
 impl RawSpan {
     #[inline]
     pub(crate) fn begin_with(
@@ -36,6 +61,10 @@ impl RawSpan {
             name: name.into(),
             properties: Properties::default(),
             is_event,
+            event_level: EventLevel::Info,
+            kind: SpanKind::Internal,
+            begin_time_override: None,
+            extension: None,
             end_instant: Instant::ZERO,
         }
     }
@@ -44,6 +73,29 @@ impl RawSpan {
     pub(crate) fn end_with(&mut self, end_instant: Instant) {
         self.end_instant = end_instant;
     }
+
+    /// Returns the extension of the span, allocating it on the first call.
+    #[inline]
+    pub(crate) fn extension_mut(&mut self) -> &mut RawSpanExtension {
+        self.extension.get_or_insert_with(Default::default)
+    }
+
+    #[inline]
+    pub(crate) fn timestamp_override(&self) -> Option<(u64, u64)> {
+        self.extension.as_ref()?.timestamp_override
+    }
+
+    /// Returns the time elapsed since the span began, taking the overridden begin time into
+    /// account.
+    #[inline]
+    pub(crate) fn elapsed(&self) -> Duration {
+        match self.begin_time_override {
+            Some(begin_unix_ns) => Duration::from_nanos(
+                unix_nanos_from_system_time(SystemTime::now()).saturating_sub(begin_unix_ns),
+            ),
+            None => self.begin_instant.elapsed(),
+        }
+    }
 }
 
 impl Clone for RawSpan {
@@ -58,6 +110,10 @@ impl Clone for RawSpan {
             name: self.name.clone(),
             properties,
             is_event: self.is_event,
+            event_level: self.event_level,
+            kind: self.kind,
+            begin_time_override: self.begin_time_override,
+            extension: self.extension.clone(),
             end_instant: self.end_instant,
         }
     }
@@ -1,35 +1,65 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
 use std::borrow::Cow;
+use std::time::Duration;
 
 use minstant::Instant;
 
+use crate::collector::EventLevel;
+use crate::collector::EvictionPolicy;
 use crate::collector::SpanId;
+use crate::collector::SpanKind;
+use crate::collector::SpanStatus;
+use crate::collector::TraceId;
 use crate::local::raw_span::RawSpan;
 use crate::util::RawSpans;
 
 pub struct SpanQueue {
     span_queue: RawSpans,
+    // The number of spans at the front of `span_queue` that have been evicted but not yet removed.
+    evicted: usize,
+    // The number of evicted spans removed from the front of `span_queue`, which is subtracted from
+    // the index of a `SpanHandle` to locate its span.
+    base: usize,
     capacity: usize,
     next_parent_id: Option<SpanId>,
+    eviction_policy: EvictionPolicy,
+    truncated: Option<Truncated>,
+}
+
+/// The spans discarded under [`EvictionPolicy::InsertMarker`].
+struct Truncated {
+    count: usize,
+    begin_instant: Instant,
+    end_instant: Instant,
 }
 
 pub struct SpanHandle {
     index: usize,
+    id: SpanId,
 }
 
 impl SpanQueue {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             span_queue: RawSpans::default(),
+            evicted: 0,
+            base: 0,
             capacity,
             next_parent_id: None,
+            eviction_policy: EvictionPolicy::default(),
+            truncated: None,
         }
     }
 
+    #[inline]
+    pub fn set_eviction_policy(&mut self, eviction_policy: EvictionPolicy) {
+        self.eviction_policy = eviction_policy;
+    }
+
     #[inline]
     pub fn start_span(&mut self, name: impl Into<Cow<'static, str>>) -> Option<SpanHandle> {
-        if self.span_queue.len() >= self.capacity {
+        if self.span_count() >= self.capacity && !self.make_room() {
             return None;
         }
 
@@ -42,33 +72,35 @@ impl SpanQueue {
         );
         self.next_parent_id = Some(span.id);
 
-        let index = self.span_queue.len();
+        let index = self.base + self.span_queue.len();
+        let id = span.id;
         self.span_queue.push(span);
 
-        Some(SpanHandle { index })
+        Some(SpanHandle { index, id })
     }
 
     #[inline]
     pub fn finish_span(&mut self, span_handle: SpanHandle) {
-        debug_assert!(span_handle.index < self.span_queue.len());
-        debug_assert_eq!(
-            self.next_parent_id,
-            Some(self.span_queue[span_handle.index].id)
-        );
+        debug_assert_eq!(self.next_parent_id, Some(span_handle.id));
 
-        let span = &mut self.span_queue[span_handle.index];
+        let index = self.index_of(&span_handle);
+        let span = &mut self.span_queue[index];
         span.end_with(Instant::now());
 
         self.next_parent_id = Some(span.parent_id).filter(|id| *id != SpanId::default());
     }
 
     #[inline]
-    pub fn add_event<I, F>(&mut self, name: impl Into<Cow<'static, str>>, properties: F)
-    where
+    pub fn add_event<I, F>(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        level: EventLevel,
+        properties: F,
+    ) where
         I: IntoIterator<Item = (Cow<'static, str>, Cow<'static, str>)>,
         F: FnOnce() -> I,
     {
-        if self.span_queue.len() >= self.capacity {
+        if self.span_count() >= self.capacity && !self.make_room() {
             return;
         }
 
@@ -79,11 +111,39 @@ impl SpanQueue {
             name,
             true,
         );
+        span.event_level = level;
         span.properties.extend(properties());
 
         self.span_queue.push(span);
     }
 
+    #[inline]
+    pub fn add_events<N, P>(&mut self, events: impl IntoIterator<Item = (N, u64, P)>)
+    where
+        N: Into<Cow<'static, str>>,
+        P: IntoIterator<Item = (Cow<'static, str>, Cow<'static, str>)>,
+    {
+        // The reported timestamps are given explicitly, so the clock is read only once.
+        let now = Instant::now();
+        for (name, timestamp_unix_ns, properties) in events {
+            if self.span_count() >= self.capacity && !self.make_room() {
+                return;
+            }
+
+            let mut span = RawSpan::begin_with(
+                SpanId::next_id(),
+                self.next_parent_id.unwrap_or_default(),
+                now,
+                name,
+                true,
+            );
+            span.properties.extend(properties);
+            span.begin_time_override = Some(timestamp_unix_ns);
+
+            self.span_queue.push(span);
+        }
+    }
+
     #[inline]
     pub fn add_properties<K, V, I>(&mut self, span_handle: &SpanHandle, properties: I)
     where
@@ -91,18 +151,76 @@ impl SpanQueue {
         V: Into<Cow<'static, str>>,
         I: IntoIterator<Item = (K, V)>,
     {
-        debug_assert!(span_handle.index < self.span_queue.len());
-
-        let span = &mut self.span_queue[span_handle.index];
+        let index = self.index_of(span_handle);
+        let span = &mut self.span_queue[index];
         span.properties
             .extend(properties.into_iter().map(|(k, v)| (k.into(), v.into())));
     }
 
     #[inline]
-    pub fn take_queue(self) -> RawSpans {
+    pub fn set_status(&mut self, span_handle: &SpanHandle, status: SpanStatus) {
+        let index = self.index_of(span_handle);
+        self.span_queue[index].extension_mut().status = status;
+    }
+
+    #[inline]
+    pub fn set_kind(&mut self, span_handle: &SpanHandle, kind: SpanKind) {
+        let index = self.index_of(span_handle);
+        self.span_queue[index].kind = kind;
+    }
+
+    #[inline]
+    pub fn add_link(&mut self, span_handle: &SpanHandle, link: (TraceId, SpanId)) {
+        let index = self.index_of(span_handle);
+        self.span_queue[index].extension_mut().links.push(link);
+    }
+
+    #[inline]
+    pub fn set_name(&mut self, span_handle: &SpanHandle, name: Cow<'static, str>) {
+        let index = self.index_of(span_handle);
+        self.span_queue[index].name = name;
+    }
+
+    #[inline]
+    pub fn name(&self, span_handle: &SpanHandle) -> &Cow<'static, str> {
+        &self.span_queue[self.index_of(span_handle)].name
+    }
+
+    #[inline]
+    pub fn set_begin_time(&mut self, span_handle: &SpanHandle, begin_unix_ns: u64) {
+        let index = self.index_of(span_handle);
+        self.span_queue[index].begin_time_override = Some(begin_unix_ns);
+    }
+
+    #[inline]
+    pub fn elapsed(&self, span_handle: &SpanHandle) -> Duration {
+        self.span_queue[self.index_of(span_handle)].elapsed()
+    }
+
+    #[inline]
+    pub fn take_queue(mut self) -> RawSpans {
+        self.span_queue.drain(..self.evicted);
+
+        if let Some(truncated) = self.truncated.take() {
+            let mut marker = RawSpan::begin_with(
+                SpanId::next_id(),
+                SpanId::default(),
+                truncated.begin_instant,
+                format!("[{} spans truncated]", truncated.count),
+                false,
+            );
+            marker.end_with(truncated.end_instant);
+            self.span_queue.push(marker);
+        }
+
         self.span_queue
     }
 
+    #[inline]
+    pub fn span_count(&self) -> usize {
+        self.span_queue.len() - self.evicted
+    }
+
     #[inline]
     pub fn current_parent_id(&self) -> Option<SpanId> {
         self.next_parent_id
@@ -111,17 +229,119 @@ impl SpanQueue {
     #[inline]
     pub fn current_parent_handle(&self) -> Option<SpanHandle> {
         self.next_parent_id.and_then(|id| {
-            let index = self.span_queue.iter().position(|span| span.id == id);
+            let index = self.span_queue[self.evicted..]
+                .iter()
+                .position(|span| span.id == id);
 
             debug_assert!(index.is_some());
 
-            Some(SpanHandle { index: index? })
+            Some(SpanHandle {
+                index: self.base + self.evicted + index?,
+                id,
+            })
         })
     }
 
     #[cfg(test)]
     pub fn get_raw_span(&self, handle: &SpanHandle) -> &RawSpan {
-        &self.span_queue[handle.index]
+        &self.span_queue[self.index_of(handle)]
+    }
+
+    /// Returns the position of the span in the queue.
+    #[inline]
+    fn index_of(&self, span_handle: &SpanHandle) -> usize {
+        if self.base == 0 && self.evicted == 0 {
+            return span_handle.index;
+        }
+
+        let index = span_handle.index.saturating_sub(self.base);
+        match self.span_queue.get(index) {
+            Some(span) if span.id == span_handle.id => index,
+            _ => self.find_moved(span_handle),
+        }
+    }
+
+    /// Finds a running span that has been moved toward the end of the queue by
+    /// [`SpanQueue::evict_oldest()`].
+    #[cold]
+    fn find_moved(&self, span_handle: &SpanHandle) -> usize {
+        let from = span_handle
+            .index
+            .saturating_sub(self.base)
+            .max(self.evicted);
+        let index = self.span_queue[from.min(self.span_queue.len())..]
+            .iter()
+            .position(|span| span.id == span_handle.id);
+
+        debug_assert!(index.is_some());
+
+        from + index.unwrap_or(0)
+    }
+
+    /// Applies the eviction policy to a full queue. Returns `true` if there is room for a new
+    /// span afterwards.
+    #[cold]
+    fn make_room(&mut self) -> bool {
+        match self.eviction_policy {
+            EvictionPolicy::DropNewest => false,
+            EvictionPolicy::DropOldest => self.evict_oldest(),
+            EvictionPolicy::InsertMarker => {
+                let now = Instant::now();
+                let truncated = self.truncated.get_or_insert(Truncated {
+                    count: 0,
+                    begin_instant: now,
+                    end_instant: now,
+                });
+                truncated.count += 1;
+                truncated.end_instant = now;
+                false
+            }
+        }
+    }
+
+    /// Evicts the oldest span that has finished and re-attaches its children to its parent.
+    ///
+    /// Only the running ancestors of the evicted span can be in front of it. They are moved one
+    /// position toward the end of the queue, so that the evicted spans stay at the front of the
+    /// queue, where they are removed in batches.
+    fn evict_oldest(&mut self) -> bool {
+        let index = match self.span_queue[self.evicted..]
+            .iter()
+            .position(|span| span.is_event || span.end_instant != Instant::ZERO)
+        {
+            Some(index) => self.evicted + index,
+            None => return false,
+        };
+
+        self.span_queue[self.evicted..=index].rotate_right(1);
+        let evicted = &self.span_queue[self.evicted];
+        let (id, parent_id, is_event, end_instant) = (
+            evicted.id,
+            evicted.parent_id,
+            evicted.is_event,
+            evicted.end_instant,
+        );
+        self.evicted += 1;
+
+        // The descendants of the evicted span follow it and began before it ended.
+        if !is_event {
+            for span in self.span_queue[index + 1..]
+                .iter_mut()
+                .take_while(|span| span.begin_instant <= end_instant)
+            {
+                if span.parent_id == id {
+                    span.parent_id = parent_id;
+                }
+            }
+        }
+
+        if self.evicted * 2 >= self.span_queue.len() {
+            self.span_queue.drain(..self.evicted);
+            self.base += self.evicted;
+            self.evicted = 0;
+        }
+
+        true
     }
 }
 
@@ -244,6 +464,161 @@ span1 []
         );
     }
 
+    #[test]
+    fn span_queue_drop_newest() {
+        let mut queue = SpanQueue::with_capacity(3);
+        queue.set_eviction_policy(EvictionPolicy::DropNewest);
+        {
+            let span1 = queue.start_span("span1").unwrap();
+            queue.finish_span(span1);
+        }
+        {
+            let span2 = queue.start_span("span2").unwrap();
+            {
+                let span3 = queue.start_span("span3").unwrap();
+                queue.finish_span(span3);
+            }
+            assert!(queue.start_span("span4").is_none());
+            queue.add_event("event1", EventLevel::Info, || []);
+            queue.finish_span(span2);
+        }
+        assert_eq!(
+            tree_str_from_raw_spans(queue.take_queue()),
+            r"
+span1 []
+
+span2 []
+    span3 []
+"
+        );
+    }
+
+    #[test]
+    fn span_queue_drop_oldest() {
+        let mut queue = SpanQueue::with_capacity(3);
+        queue.set_eviction_policy(EvictionPolicy::DropOldest);
+        {
+            let span1 = queue.start_span("span1").unwrap();
+            {
+                let span2 = queue.start_span("span2").unwrap();
+                queue.finish_span(span2);
+            }
+            queue.finish_span(span1);
+        }
+        {
+            let span3 = queue.start_span("span3").unwrap();
+            {
+                // Evicts `span1`, and `span2` takes its place at the top level.
+                let span4 = queue.start_span("span4").unwrap();
+                queue.add_properties(&span4, [("k1", "v1")]);
+                queue.finish_span(span4);
+            }
+            {
+                // Evicts `span2`.
+                let span5 = queue.start_span("span5").unwrap();
+                {
+                    // Evicts `span4`.
+                    let span6 = queue.start_span("span6").unwrap();
+                    {
+                        // All spans in the queue are still running.
+                        assert!(queue.start_span("span7").is_none());
+                    }
+                    queue.finish_span(span6);
+                }
+                queue.finish_span(span5);
+            }
+            queue.add_properties(&span3, [("k2", "v2")]);
+            queue.finish_span(span3);
+        }
+        assert_eq!(
+            tree_str_from_raw_spans(queue.take_queue()),
+            r#"
+span3 [("k2", "v2")]
+    span5 []
+        span6 []
+"#
+        );
+    }
+
+    #[test]
+    fn span_queue_drop_oldest_reparent() {
+        let mut queue = SpanQueue::with_capacity(3);
+        queue.set_eviction_policy(EvictionPolicy::DropOldest);
+        let span1 = queue.start_span("span1").unwrap();
+        {
+            let span2 = queue.start_span("span2").unwrap();
+            {
+                let span3 = queue.start_span("span3").unwrap();
+                queue.finish_span(span3);
+            }
+            queue.finish_span(span2);
+        }
+        // Evicts `span2`, and `span3` is re-attached to `span1`.
+        queue.add_event("event1", EventLevel::Info, || []);
+        queue.finish_span(span1);
+        assert_eq!(
+            tree_str_from_raw_spans(queue.take_queue()),
+            r"
+span1 []
+    event1 []
+    span3 []
+"
+        );
+    }
+
+    #[test]
+    fn span_queue_drop_oldest_many() {
+        let mut queue = SpanQueue::with_capacity(4);
+        queue.set_eviction_policy(EvictionPolicy::DropOldest);
+        let root = queue.start_span("root").unwrap();
+        for i in 0..100 {
+            let parent = queue.start_span(format!("parent{i}")).unwrap();
+            {
+                let child = queue.start_span(format!("child{i}")).unwrap();
+                queue.finish_span(child);
+            }
+            queue.add_properties(&parent, [("k", i.to_string())]);
+            queue.finish_span(parent);
+        }
+        queue.add_properties(&root, [("k", "v")]);
+        queue.finish_span(root);
+        assert_eq!(
+            tree_str_from_raw_spans(queue.take_queue()),
+            r#"
+root [("k", "v")]
+    child98 []
+    parent99 [("k", "99")]
+        child99 []
+"#
+        );
+    }
+
+    #[test]
+    fn span_queue_insert_marker() {
+        let mut queue = SpanQueue::with_capacity(2);
+        queue.set_eviction_policy(EvictionPolicy::InsertMarker);
+        {
+            let span1 = queue.start_span("span1").unwrap();
+            {
+                let span2 = queue.start_span("span2").unwrap();
+                assert!(queue.start_span("span3").is_none());
+                queue.add_event("event1", EventLevel::Info, || []);
+                queue.finish_span(span2);
+            }
+            assert!(queue.start_span("span4").is_none());
+            queue.finish_span(span1);
+        }
+        assert_eq!(
+            tree_str_from_raw_spans(queue.take_queue()),
+            r"
+[3 spans truncated] []
+
+span1 []
+    span2 []
+"
+        );
+    }
+
     #[test]
     fn last_span_id() {
         let mut queue = SpanQueue::with_capacity(16);
@@ -51,7 +51,10 @@ macro_rules! full_name {
     }};
 }
 
-/// Get the source file location where the macro is invoked. Returns a `&'static str`.
+/// Get the source file location where the macro is invoked, as `file:line`. Returns a
+/// `Cow<'static, str>` borrowing a `&'static str`, so it does not allocate.
+///
+/// See [`column_location!()`](crate::column_location) for the location including the column.
 ///
 /// # Example
 ///
@@ -59,13 +62,124 @@ macro_rules! full_name {
 /// use minitrace::file_location;
 ///
 /// fn foo() {
-///    assert_eq!(file_location!(), "minitrace/src/macros.rs:8:15");
+///    assert_eq!(file_location!(), "minitrace/src/macros.rs:8");
 /// }
 /// # #[cfg(not(target_os = "windows"))]
 /// # foo()
 #[macro_export]
 macro_rules! file_location {
     () => {
-        std::concat!(file!(), ":", line!(), ":", column!())
+        std::borrow::Cow::<'static, str>::Borrowed(std::concat!(file!(), ":", line!()))
     };
 }
+
+/// Get the source file location where the macro is invoked, as `file:line:column`. Returns a
+/// `Cow<'static, str>` borrowing a `&'static str`, so it does not allocate.
+///
+/// # Example
+///
+/// ```
+/// use minitrace::column_location;
+///
+/// fn foo() {
+///    assert_eq!(column_location!(), "minitrace/src/macros.rs:8:15");
+/// }
+/// # #[cfg(not(target_os = "windows"))]
+/// # foo()
+#[macro_export]
+macro_rules! column_location {
+    () => {
+        std::borrow::Cow::<'static, str>::Borrowed(std::concat!(
+            file!(),
+            ":",
+            line!(),
+            ":",
+            column!()
+        ))
+    };
+}
+
+/// Get the full path of the function where the macro is invoked, i.e. its module path followed
+/// by its name. Returns a `Cow<'static, str>` borrowing a `&'static str`.
+///
+/// This is the same as [`full_name!()`], as a `Cow` to be used where a span name is expected.
+///
+/// # Example
+///
+/// ```
+/// use minitrace::func_path;
+///
+/// fn foo() {
+///    assert_eq!(func_path!(), "rust_out::main::_doctest_main_minitrace_src_macros_rs_109_0::foo");
+/// }
+/// # foo()
+#[macro_export]
+macro_rules! func_path {
+    () => {
+        std::borrow::Cow::<'static, str>::Borrowed($crate::full_name!())
+    };
+}
+
+/// Create a [`LocalSpan`] under the current local parent named after `name` and the source file
+/// location where the macro is invoked, as `name@file:line`. `name` must be a string literal.
+///
+/// [`LocalSpan`]: crate::local::LocalSpan
+///
+/// # Example
+///
+/// ```
+/// use minitrace::location_span;
+/// use minitrace::prelude::*;
+///
+/// let root = Span::root("root", SpanContext::random());
+/// let _g = root.set_local_parent();
+///
+/// // A span named `query@src/main.rs:8`.
+/// let _span = location_span!("query");
+/// ```
+#[macro_export]
+macro_rules! location_span {
+    ($name:literal) => {
+        $crate::local::LocalSpan::enter_with_local_parent(std::concat!(
+            $name,
+            "@",
+            file!(),
+            ":",
+            line!()
+        ))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use crate::local::LocalCollector;
+    use crate::prelude::*;
+
+    #[test]
+    fn location_macros() {
+        let (location, line): (Cow<'static, str>, _) = (file_location!(), line!());
+        assert!(matches!(location, Cow::Borrowed(_)));
+        assert_eq!(location, format!("{}:{line}", file!()));
+
+        let (location, line) = (column_location!(), line!());
+        assert!(matches!(location, Cow::Borrowed(_)));
+        assert_eq!(location, format!("{}:{line}:33", file!()));
+
+        let path = func_path!();
+        assert!(matches!(path, Cow::Borrowed(_)));
+        assert_eq!(path, "minitrace::macros::tests::location_macros");
+    }
+
+    #[test]
+    fn location_span() {
+        let collector = LocalCollector::start();
+        let (span, line) = (location_span!("span"), line!());
+        drop(span);
+        let spans = collector.collect().to_span_records(SpanContext::random());
+
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].name, format!("span@{}:{line}", file!()));
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::panic::PanicHookInfo;
+#[cfg(feature = "enable")]
+use std::sync::Once;
+
+#[cfg(feature = "enable")]
+use crate::collector::EventLevel;
+#[cfg(feature = "enable")]
+use crate::local::local_span_stack::LOCAL_SPAN_STACK;
+
+/// Installs a panic hook that adds a `panic` event to the current local parent span before
+/// running the previously installed hook, which prints the panic message by default.
+///
+/// The event is at the level [`EventLevel::Error`] with the properties `message`, `location`
+/// and `thread`. A panic outside a local parent span is only handled by the previous hook.
+///
+/// Calling this function more than once, from any thread, installs the hook only once. Use
+/// [`install_panic_hook_with_prev_hook()`] to compose the hook with another one.
+///
+/// [`EventLevel::Error`]: crate::collector::EventLevel::Error
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::prelude::*;
+///
+/// minitrace::install_panic_hook();
+///
+/// let root = Span::root("root", SpanContext::random());
+/// let _guard = root.set_local_parent();
+/// let result = std::panic::catch_unwind(|| panic!("boom"));
+/// assert!(result.is_err());
+/// ```
+pub fn install_panic_hook() {
+    #[cfg(feature = "enable")]
+    {
+        static INSTALLED: Once = Once::new();
+        INSTALLED.call_once(|| install_panic_hook_with_prev_hook(std::panic::take_hook()));
+    }
+}
+
+/// Installs a panic hook that adds a `panic` event to the current local parent span, like
+/// [`install_panic_hook()`], and then runs `prev`, e.g. a hook returned by
+/// [`std::panic::take_hook()`].
+///
+/// # Examples
+///
+/// ```
+/// minitrace::install_panic_hook_with_prev_hook(Box::new(|info| {
+///     eprintln!("custom panic handler: {info}");
+/// }));
+/// ```
+pub fn install_panic_hook_with_prev_hook(prev: Box<dyn Fn(&PanicHookInfo<'_>) + Send + Sync>) {
+    #[cfg(not(feature = "enable"))]
+    {
+        std::panic::set_hook(prev);
+    }
+
+    #[cfg(feature = "enable")]
+    {
+        std::panic::set_hook(Box::new(move |info| {
+            add_panic_event(info);
+            prev(info);
+        }));
+    }
+}
+
+#[cfg(feature = "enable")]
+fn add_panic_event(info: &PanicHookInfo<'_>) {
+    // The panic may have been raised while the span stack is borrowed, in which case the event is
+    // skipped rather than panicking again in the hook.
+    LOCAL_SPAN_STACK
+        .try_with(|stack| {
+            let Ok(mut stack) = stack.try_borrow_mut() else {
+                return;
+            };
+            stack.add_event("panic", EventLevel::Error, || {
+                let payload = info.payload();
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "Box<dyn Any>".to_string());
+                let location = info
+                    .location()
+                    .map(|location| location.to_string())
+                    .unwrap_or_default();
+                let thread = std::thread::current()
+                    .name()
+                    .unwrap_or("<unnamed>")
+                    .to_string();
+                [
+                    ("message".into(), message.into()),
+                    ("location".into(), location.into()),
+                    ("thread".into(), thread.into()),
+                ]
+            });
+        })
+        .ok();
+}
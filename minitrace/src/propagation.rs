@@ -0,0 +1,269 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Pluggable propagation of [`SpanContext`] across process boundaries.
+//!
+//! An [`Injector`] turns a [`SpanContext`] into header name-value pairs, and an [`Extractor`]
+//! decodes a [`SpanContext`] from the headers of an incoming request. Both are implemented by
+//! [`W3cPropagator`], [`B3SinglePropagator`], [`B3MultiPropagator`] and [`DatadogPropagator`],
+//! and a [`CompositePropagator`] combines several extractors, so that the propagation formats
+//! supported by a service can be configured in one place.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use minitrace::prelude::*;
+//! use minitrace::propagation::B3SinglePropagator;
+//! use minitrace::propagation::CompositePropagator;
+//! use minitrace::propagation::Extractor;
+//! use minitrace::propagation::Injector;
+//! use minitrace::propagation::W3cPropagator;
+//!
+//! let span_context = SpanContext::new(TraceId(12), SpanId(34));
+//! let headers: HashMap<_, _> = W3cPropagator
+//!     .inject(&span_context, true)
+//!     .into_iter()
+//!     .collect();
+//!
+//! let propagator =
+//!     CompositePropagator(vec![Box::new(B3SinglePropagator), Box::new(W3cPropagator)]);
+//! let extracted = propagator
+//!     .extract(&|name| headers.get(name).cloned())
+//!     .unwrap();
+//!
+//! assert_eq!(extracted.trace_id, TraceId(12));
+//! assert_eq!(extracted.span_id, SpanId(34));
+//! ```
+
+use std::borrow::Cow;
+
+use crate::collector::SpanContext;
+
+/// Encodes a [`SpanContext`] into the headers of an outgoing request.
+pub trait Injector {
+    /// Returns the header name-value pairs carrying `span_context` and the sampling decision.
+    ///
+    /// Header names are case-insensitive, so the names returned may not be lowercase.
+    fn inject(&self, span_context: &SpanContext, sampled: bool)
+        -> Vec<(Cow<'static, str>, String)>;
+}
+
+/// Decodes a [`SpanContext`] from the headers of an incoming request.
+pub trait Extractor {
+    /// Decodes a [`SpanContext`] from the header values returned by `get_header`, or returns
+    /// `None` if the headers are missing or malformed.
+    ///
+    /// `get_header` is called with lowercase header names, and should look them up
+    /// case-insensitively.
+    fn extract(&self, get_header: &dyn Fn(&str) -> Option<String>) -> Option<SpanContext>;
+}
+
+/// Propagates the [`SpanContext`] in the [W3C Trace Context](https://www.w3.org/TR/trace-context/)
+/// `traceparent` and `tracestate` headers.
+///
+/// The `tracestate` header is only injected if the span context has one.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct W3cPropagator;
+
+impl Injector for W3cPropagator {
+    fn inject(
+        &self,
+        span_context: &SpanContext,
+        sampled: bool,
+    ) -> Vec<(Cow<'static, str>, String)> {
+        let mut headers = vec![(
+            "traceparent".into(),
+            span_context.encode_w3c_traceparent_with_sampled(sampled),
+        )];
+        if let Some(tracestate) = span_context.tracestate() {
+            headers.push(("tracestate".into(), tracestate.to_string()));
+        }
+        headers
+    }
+}
+
+impl Extractor for W3cPropagator {
+    fn extract(&self, get_header: &dyn Fn(&str) -> Option<String>) -> Option<SpanContext> {
+        let traceparent = get_header("traceparent")?;
+        SpanContext::decode_w3c_headers(&traceparent, get_header("tracestate").as_deref())
+    }
+}
+
+/// Propagates the [`SpanContext`] in the [B3](https://github.com/openzipkin/b3-propagation)
+/// single `b3` header.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct B3SinglePropagator;
+
+impl Injector for B3SinglePropagator {
+    fn inject(
+        &self,
+        span_context: &SpanContext,
+        sampled: bool,
+    ) -> Vec<(Cow<'static, str>, String)> {
+        vec![("b3".into(), span_context.encode_b3_single_header(sampled))]
+    }
+}
+
+impl Extractor for B3SinglePropagator {
+    fn extract(&self, get_header: &dyn Fn(&str) -> Option<String>) -> Option<SpanContext> {
+        SpanContext::decode_b3_single_header(&get_header("b3")?)
+    }
+}
+
+/// Propagates the [`SpanContext`] in the [B3](https://github.com/openzipkin/b3-propagation)
+/// `X-B3-TraceId`, `X-B3-SpanId` and `X-B3-Sampled` headers.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct B3MultiPropagator;
+
+impl Injector for B3MultiPropagator {
+    fn inject(
+        &self,
+        span_context: &SpanContext,
+        sampled: bool,
+    ) -> Vec<(Cow<'static, str>, String)> {
+        span_context
+            .encode_b3_multi_headers(sampled)
+            .into_iter()
+            .map(|(name, value)| (name.into(), value))
+            .collect()
+    }
+}
+
+impl Extractor for B3MultiPropagator {
+    fn extract(&self, get_header: &dyn Fn(&str) -> Option<String>) -> Option<SpanContext> {
+        SpanContext::decode_b3_multi_headers(
+            &get_header("x-b3-traceid")?,
+            &get_header("x-b3-spanid")?,
+            get_header("x-b3-sampled").as_deref(),
+        )
+    }
+}
+
+/// Propagates the [`SpanContext`] in the [Datadog] `x-datadog-trace-id`, `x-datadog-parent-id`
+/// and `x-datadog-sampling-priority` headers.
+///
+/// Only the lower 64 bits of the trace id are propagated, see
+/// [`SpanContext::encode_datadog_headers()`].
+///
+/// [Datadog]: https://docs.datadoghq.com/tracing/trace_collection/trace_context_propagation/
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DatadogPropagator;
+
+impl Injector for DatadogPropagator {
+    fn inject(
+        &self,
+        span_context: &SpanContext,
+        sampled: bool,
+    ) -> Vec<(Cow<'static, str>, String)> {
+        span_context
+            .encode_datadog_headers(sampled)
+            .into_iter()
+            .map(|(name, value)| (name.into(), value))
+            .collect()
+    }
+}
+
+impl Extractor for DatadogPropagator {
+    fn extract(&self, get_header: &dyn Fn(&str) -> Option<String>) -> Option<SpanContext> {
+        SpanContext::decode_datadog_headers(
+            &get_header("x-datadog-trace-id")?,
+            &get_header("x-datadog-parent-id")?,
+            get_header("x-datadog-sampling-priority").as_deref(),
+        )
+    }
+}
+
+/// An [`Extractor`] that tries each of its extractors in order, and returns the first
+/// [`SpanContext`] that can be decoded.
+#[derive(Default)]
+pub struct CompositePropagator(pub Vec<Box<dyn Extractor + Send + Sync>>);
+
+impl Extractor for CompositePropagator {
+    fn extract(&self, get_header: &dyn Fn(&str) -> Option<String>) -> Option<SpanContext> {
+        self.0
+            .iter()
+            .find_map(|extractor| extractor.extract(get_header))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::collector::SpanId;
+    use crate::collector::TraceId;
+
+    fn extract_from(extractor: &dyn Extractor, headers: &[(&str, &str)]) -> Option<SpanContext> {
+        let headers: HashMap<String, String> = headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        extractor.extract(&|name| headers.get(name).cloned())
+    }
+
+    #[test]
+    fn composite_propagator() {
+        let propagator =
+            CompositePropagator(vec![Box::new(B3SinglePropagator), Box::new(W3cPropagator)]);
+
+        let span_context = extract_from(&propagator, &[
+            (
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            ),
+            ("tracestate", "foo=bar"),
+        ])
+        .unwrap();
+        assert_eq!(
+            span_context.trace_id,
+            TraceId(0x0af7651916cd43dd8448eb211c80319c)
+        );
+        assert_eq!(span_context.span_id, SpanId(0xb7ad6b7169203331));
+        assert_eq!(span_context.tracestate(), Some("foo=bar"));
+
+        let span_context = extract_from(&propagator, &[
+            ("b3", "0000000000000000000000000000000c-0000000000000022-1"),
+            (
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            ),
+        ])
+        .unwrap();
+        assert_eq!(span_context.trace_id, TraceId(12));
+        assert_eq!(span_context.span_id, SpanId(34));
+
+        assert!(extract_from(&propagator, &[("b3", "invalid")]).is_none());
+        assert!(extract_from(&propagator, &[]).is_none());
+    }
+
+    #[test]
+    fn inject_and_extract() {
+        let span_context = SpanContext::new(TraceId(12), SpanId(34));
+        let propagators: [(&dyn Injector, &dyn Extractor); 4] = [
+            (&W3cPropagator, &W3cPropagator),
+            (&B3SinglePropagator, &B3SinglePropagator),
+            (&B3MultiPropagator, &B3MultiPropagator),
+            (&DatadogPropagator, &DatadogPropagator),
+        ];
+
+        for (injector, extractor) in propagators {
+            let headers: HashMap<String, String> = injector
+                .inject(&span_context, true)
+                .into_iter()
+                .map(|(name, value)| (name.to_lowercase(), value))
+                .collect();
+            let extracted = extractor
+                .extract(&|name| headers.get(name).cloned())
+                .unwrap();
+            assert_eq!(extracted.trace_id, span_context.trace_id);
+            assert_eq!(extracted.span_id, span_context.span_id);
+        }
+
+        assert_eq!(W3cPropagator.inject(&span_context, false), vec![(
+            "traceparent".into(),
+            "00-0000000000000000000000000000000c-0000000000000022-00".to_string()
+        )]);
+    }
+}
@@ -2,24 +2,39 @@
 
 use std::borrow::Cow;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::sync::OnceLock;
+use std::sync::Weak;
 use std::time::Duration;
+use std::time::SystemTime;
 
 use minstant::Instant;
 
 use crate::collector::global_collector::reporter_ready;
+use crate::collector::global_collector::run_reporter_factory;
 use crate::collector::CollectTokenItem;
+use crate::collector::EventLevel;
+use crate::collector::EventRecord;
 use crate::collector::GlobalCollect;
+use crate::collector::Resource;
+use crate::collector::SamplingDecision;
 use crate::collector::SpanContext;
 use crate::collector::SpanId;
+use crate::collector::SpanKind;
 use crate::collector::SpanSet;
+use crate::collector::SpanStatus;
+use crate::collector::TraceId;
 use crate::local::local_collector::LocalSpansInner;
 use crate::local::local_span_stack::LocalSpanStack;
 use crate::local::local_span_stack::LOCAL_SPAN_STACK;
 use crate::local::raw_span::RawSpan;
 use crate::local::LocalCollector;
 use crate::local::LocalSpans;
+use crate::span_fields::SpanFields;
+use crate::span_tags::SpanTags;
+use crate::util::unix_nanos_from_system_time;
 use crate::util::CollectToken;
 
 /// A thread-safe span.
@@ -36,6 +51,11 @@ pub(crate) struct SpanInner {
     // If the span is not a root span, this field will be `None`.
     collect_id: Option<usize>,
     collect: GlobalCollect,
+    // The W3C `tracestate` inherited from the `SpanContext` of the root span.
+    pub(crate) tracestate: Option<Arc<str>>,
+    // Created by the first `Span::downgrade()`, and dropped with the span so that its `WeakSpan`s
+    // can no longer be upgraded.
+    alive: OnceLock<Arc<()>>,
 }
 
 impl Span {
@@ -77,20 +97,237 @@ impl Span {
 
         #[cfg(feature = "enable")]
         {
-            if !reporter_ready() {
+            Self::start_collect(name, parent, true, None)
+        }
+    }
+
+    /// Create a new trace and return its root span, like [`Span::root()`], with the resource
+    /// attributes, such as `service.name`, of the trace.
+    ///
+    /// The resource is attached to all the [`SpanRecord`]s of the trace in place of the one set
+    /// by [`set_resource()`], so that reporters such as `OpenTelemetryReporter` can report the
+    /// traces of different services from a single process.
+    ///
+    /// [`SpanRecord`]: crate::collector::SpanRecord
+    /// [`set_resource()`]: crate::set_resource
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use std::sync::Arc;
+    ///
+    /// use minitrace::prelude::*;
+    ///
+    /// let resource = Arc::new(HashMap::from([("service.name".into(), "billing".into())]));
+    /// let root = Span::root_with_resource("root", SpanContext::random(), resource);
+    /// ```
+    #[inline]
+    pub fn root_with_resource(
+        name: impl Into<Cow<'static, str>>,
+        parent: SpanContext,
+        resource: Arc<HashMap<Cow<'static, str>, Cow<'static, str>>>,
+    ) -> Self {
+        #[cfg(not(feature = "enable"))]
+        {
+            Self::noop()
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            Self::start_collect(name, parent, true, Some(resource))
+        }
+    }
+
+    /// Create a new trace and return its root span, like [`Span::root()`], with an explicit
+    /// sampling decision in place of the one carried by `parent`.
+    ///
+    /// The decision is inherited by all the spans of the trace and by the [`SpanContext`]s built
+    /// from them, and is reported as [`SpanRecord::sampled`]. A root span with
+    /// [`SamplingDecision::Drop`] is a noop span, so nothing of the trace is recorded.
+    ///
+    /// [`SpanRecord::sampled`]: crate::collector::SpanRecord::sampled
+    /// [`SamplingDecision::Drop`]: crate::collector::SamplingDecision::Drop
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root =
+    ///     Span::root_with_sampling("root", SpanContext::random(), SamplingDecision::RecordOnly);
+    /// ```
+    #[inline]
+    pub fn root_with_sampling(
+        name: impl Into<Cow<'static, str>>,
+        parent: SpanContext,
+        decision: SamplingDecision,
+    ) -> Self {
+        #[cfg(not(feature = "enable"))]
+        {
+            Self::noop()
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            if decision == SamplingDecision::Drop {
                 return Self::noop();
             }
 
-            let collect = current_collect();
-            let collect_id = collect.start_collect();
-            let token = CollectTokenItem {
-                trace_id: parent.trace_id,
-                parent_id: parent.span_id,
-                collect_id,
-                is_root: true,
+            let parent = SpanContext {
+                sampled: decision == SamplingDecision::RecordAndSample,
+                ..parent
+            };
+            Self::start_collect(name, parent, true, None)
+        }
+    }
+
+    /// Create a span continuing an existing trace, typically one started in another service or
+    /// reconstructed by [`SpanContext::from_span_record()`], and return it.
+    ///
+    /// Like [`Span::root()`], the span submits all associated child spans to the reporter once
+    /// dropped. Unlike a root span, it is not exempted from
+    /// [`Config::max_spans_per_trace()`], so it can be dropped with its children when the limit
+    /// is reached.
+    ///
+    /// [`Config::max_spans_per_trace()`]: crate::collector::Config::max_spans_per_trace
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let upstream = SpanContext::decode_w3c_traceparent(
+    ///     "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+    /// )
+    /// .unwrap();
+    /// let span = Span::enter_with_context("handle", upstream);
+    /// ```
+    #[inline]
+    pub fn enter_with_context(name: impl Into<Cow<'static, str>>, context: SpanContext) -> Self {
+        #[cfg(not(feature = "enable"))]
+        {
+            Self::noop()
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            Self::start_collect(name, context, false, None)
+        }
+    }
+
+    /// Create a new trace and return its root span, which began at `begin_time`.
+    ///
+    /// This is the same as [`Span::root()`] followed by [`Span::with_begin_time()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use std::time::SystemTime;
+    ///
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root_at(
+    ///     "root",
+    ///     SpanContext::random(),
+    ///     SystemTime::now() - Duration::from_secs(1),
+    /// );
+    /// ```
+    #[inline]
+    pub fn root_at(
+        name: impl Into<Cow<'static, str>>,
+        parent: SpanContext,
+        begin_time: SystemTime,
+    ) -> Self {
+        Self::root(name, parent).with_begin_time(begin_time)
+    }
+
+    /// Create a new trace with a static name and return its root span.
+    ///
+    /// This is the same as [`Span::root()`], except that the name is guaranteed to be borrowed
+    /// rather than allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root_static("root", SpanContext::random());
+    /// ```
+    #[inline]
+    pub fn root_static(name: &'static str, parent: SpanContext) -> Self {
+        Self::root(Cow::Borrowed(name), parent)
+    }
+
+    /// Create a new trace and return its root span tagged with the given tags.
+    ///
+    /// Tags are recorded as properties of the root span only. Unlike the properties of individual
+    /// spans, which describe an operation, tags describe the entity producing the trace, such as
+    /// the service version, the region or the hostname. They are analogous to the resource
+    /// attributes in OpenTelemetry.
+    ///
+    /// See [`SpanTags`] for the accepted collections of tags.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root_with_tags("root", SpanContext::random(), &[
+    ///     ("service.version", "1.0.0"),
+    ///     ("region", "us-east-1"),
+    /// ]);
+    /// ```
+    ///
+    /// [`SpanTags`]: crate::SpanTags
+    #[inline]
+    pub fn root_with_tags(
+        name: impl Into<Cow<'static, str>>,
+        parent: SpanContext,
+        tags: impl SpanTags,
+    ) -> Self {
+        Self::root(name, parent).with_properties(|| tags.into_tags())
+    }
+
+    /// Create a new trace and return its root span with a probability of `sample_rate`, otherwise
+    /// return a noop span.
+    ///
+    /// `sample_rate` is clamped to `[0.0, 1.0]`. Use [`Span::is_noop()`] to tell whether the trace
+    /// is sampled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// // Sample 10% of the traces.
+    /// let root = Span::root_sampled("root", SpanContext::random(), 0.1);
+    /// ```
+    #[inline]
+    pub fn root_sampled(
+        name: impl Into<Cow<'static, str>>,
+        parent: SpanContext,
+        sample_rate: f64,
+    ) -> Self {
+        debug_assert!(
+            (0.0..=1.0).contains(&sample_rate),
+            "sample_rate must be in [0.0, 1.0], got {sample_rate}"
+        );
+
+        #[cfg(not(feature = "enable"))]
+        {
+            Self::noop()
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            let sample_rate = sample_rate.clamp(0.0, 1.0);
+            if rand::random::<f64>() < sample_rate {
+                Self::root(name, parent)
+            } else {
+                Self::noop()
             }
-            .into();
-            Self::new(token, name, Some(collect_id))
         }
     }
 
@@ -151,12 +388,69 @@ impl Span {
 
         #[cfg(feature = "enable")]
         {
+            let mut tracestate = None;
             let token = parents
                 .into_iter()
                 .filter_map(|span| span.inner.as_ref())
-                .flat_map(|inner| inner.issue_collect_token())
+                .flat_map(|inner| {
+                    if tracestate.is_none() {
+                        tracestate = inner.tracestate.clone();
+                    }
+                    inner.issue_collect_token()
+                })
                 .collect();
-            Self::new(token, name, None)
+            Self::new(token, name, None, tracestate)
+        }
+    }
+
+    /// Create a new child span associated with the specified parent span, and attach events that
+    /// were recorded before the span is created.
+    ///
+    /// Each event is given as a pair of the event name and its `timestamp_unix_ns`. The events are
+    /// reported in the order of their timestamps, ahead of the events added to the span later.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    ///
+    /// let child = Span::enter_with_parent_and_events("child", &root, [
+    ///     ("connected", 1_700_000_000_000_000_000),
+    ///     ("authenticated", 1_700_000_000_001_000_000),
+    /// ]);
+    /// ```
+    #[inline]
+    pub fn enter_with_parent_and_events<E>(
+        name: impl Into<Cow<'static, str>>,
+        parent: &Span,
+        events: impl IntoIterator<Item = (E, u64)>,
+    ) -> Self
+    where
+        E: Into<Cow<'static, str>>,
+    {
+        #[cfg(not(feature = "enable"))]
+        {
+            Self::noop()
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            let mut span = Self::enter_with_parent(name, parent);
+            if let Some(inner) = span.inner.as_mut() {
+                let pre_events = &mut inner.raw_span.extension_mut().pre_events;
+                pre_events.extend(events.into_iter().map(|(name, timestamp_unix_ns)| {
+                    EventRecord {
+                        name: name.into(),
+                        timestamp_unix_ns,
+                        properties: vec![],
+                        level: EventLevel::Info,
+                    }
+                }));
+                pre_events.sort_by_key(|event| event.timestamp_unix_ns);
+            }
+            span
         }
     }
 
@@ -189,6 +483,134 @@ impl Span {
         }
     }
 
+    /// Create a new child span associated with the current local span in the current thread, or
+    /// a new root span in the trace `trace_id` if no local span is active.
+    ///
+    /// The returned `bool` is `true` if a root span was created, in which case the trace is
+    /// reported once the span is dropped, and the caller may want to [`flush()`] afterwards.
+    ///
+    /// [`flush()`]: crate::flush
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// fn handle() {
+    ///     let trace_id = SpanContext::random().trace_id;
+    ///     let (span, is_root) = Span::enter_with_local_parent_or_root("handle", trace_id);
+    ///     {
+    ///         let _g = span.set_local_parent();
+    ///         // ...
+    ///     }
+    ///
+    ///     drop(span);
+    ///     if is_root {
+    ///         minitrace::flush();
+    ///     }
+    /// }
+    /// ```
+    #[inline]
+    pub fn enter_with_local_parent_or_root(
+        name: impl Into<Cow<'static, str>>,
+        trace_id: TraceId,
+    ) -> (Self, bool) {
+        #[cfg(not(feature = "enable"))]
+        {
+            (Self::noop(), false)
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            let local_parent = LOCAL_SPAN_STACK
+                .try_with(|stack| Self::local_parent_token(&mut stack.borrow_mut()))
+                .ok()
+                .flatten();
+            match local_parent {
+                Some((token, tracestate)) => (Span::new(token, name, None, tracestate), false),
+                None => (
+                    Span::root(name, SpanContext::new(trace_id, SpanId::default())),
+                    true,
+                ),
+            }
+        }
+    }
+
+    /// Create a new child span associated with the current local span in the current thread, or
+    /// a new root span if no local span is active.
+    ///
+    /// `trace_id_factory` is only called when a root span is created, to provide the trace id of
+    /// the new trace.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span = Span::enter_root_or_child("handle", || TraceId(42));
+    /// ```
+    #[inline]
+    pub fn enter_root_or_child(
+        name: impl Into<Cow<'static, str>>,
+        trace_id_factory: impl FnOnce() -> TraceId,
+    ) -> Self {
+        #[cfg(not(feature = "enable"))]
+        {
+            Self::noop()
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            let local_parent = LOCAL_SPAN_STACK
+                .try_with(|stack| Self::local_parent_token(&mut stack.borrow_mut()))
+                .ok()
+                .flatten();
+            match local_parent {
+                Some((token, tracestate)) => Span::new(token, name, None, tracestate),
+                None => Span::root(
+                    name,
+                    SpanContext::new(trace_id_factory(), SpanId::default()),
+                ),
+            }
+        }
+    }
+
+    /// Create a new child span associated with the current local span in the current thread, or
+    /// a new root span in a random trace if no local span is active.
+    ///
+    /// This is the same as [`Span::enter_root_or_child()`] with a random trace id.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span = Span::enter_root_or_child_random("handle");
+    /// ```
+    #[inline]
+    pub fn enter_root_or_child_random(name: impl Into<Cow<'static, str>>) -> Self {
+        Self::enter_root_or_child(name, || TraceId(rand::random()))
+    }
+
+    /// Create a new child span associated with the current local span in the current thread, or
+    /// a no-op span if no local span is active.
+    ///
+    /// This is the same as [`Span::enter_with_local_parent()`], named after
+    /// [`Span::enter_with_local_parent_or_root()`] for the call sites that deliberately do not
+    /// start a trace.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span = Span::enter_with_local_parent_or_noop("child");
+    /// ```
+    #[inline]
+    pub fn enter_with_local_parent_or_noop(name: impl Into<Cow<'static, str>>) -> Self {
+        Self::enter_with_local_parent(name)
+    }
+
     /// Sets the current `Span` as the local parent for the current thread.
     ///
     /// This method is used to establish a `Span` as the local parent within the current scope.
@@ -227,84 +649,489 @@ impl Span {
 
     /// Add a single property to the `Span` and return the modified `Span`.
     ///
-    /// A property is an arbitrary key-value pair associated with a span.
+    /// A property is an arbitrary key-value pair associated with a span.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random()).with_property(|| ("key", "value"));
+    /// ```
+    #[inline]
+    pub fn with_property<K, V, F>(self, property: F) -> Self
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+        F: FnOnce() -> (K, V),
+    {
+        self.with_properties(move || [property()])
+    }
+
+    /// Add multiple properties to the `Span` and return the modified `Span`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random())
+    ///     .with_properties(|| [("key1", "value1"), ("key2", "value2")]);
+    /// ```
+    #[inline]
+    pub fn with_properties<K, V, I, F>(mut self, properties: F) -> Self
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce() -> I,
+    {
+        #[cfg(feature = "enable")]
+        if let Some(inner) = self.inner.as_mut() {
+            inner.add_properties(properties);
+        }
+
+        self
+    }
+
+    /// Add the fields of a struct as properties to the `Span` and return the modified `Span`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    /// use minitrace::SpanFields;
+    ///
+    /// #[derive(SpanFields)]
+    /// struct Request {
+    ///     pub method: String,
+    ///     pub path: String,
+    /// }
+    ///
+    /// let request = Request {
+    ///     method: "GET".to_string(),
+    ///     path: "/".to_string(),
+    /// };
+    /// let root = Span::root("root", SpanContext::random()).with_properties_from_struct(&request);
+    /// ```
+    #[inline]
+    pub fn with_properties_from_struct(self, fields: &impl SpanFields) -> Self {
+        self.with_properties(|| fields.span_fields())
+    }
+
+    /// Add a single property to a started `Span`, e.g. when the value is only known after the
+    /// children of the span have started.
+    ///
+    /// A property is an arbitrary key-value pair associated with a span.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let mut root = Span::root("root", SpanContext::random());
+    /// let rows = 42;
+    ///
+    /// root.add_property(|| ("rows", rows.to_string()));
+    /// ```
+    #[inline]
+    pub fn add_property<K, V, F>(&mut self, property: F) -> &mut Self
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+        F: FnOnce() -> (K, V),
+    {
+        self.add_properties(move || [property()])
+    }
+
+    /// Add multiple properties to a started `Span`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let mut root = Span::root("root", SpanContext::random());
+    ///
+    /// root.add_properties(|| [("key1", "value1"), ("key2", "value2")]);
+    /// ```
+    #[inline]
+    pub fn add_properties<K, V, I, F>(&mut self, properties: F) -> &mut Self
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce() -> I,
+    {
+        #[cfg(feature = "enable")]
+        if let Some(inner) = self.inner.as_mut() {
+            inner.add_properties(properties);
+        }
+
+        self
+    }
+
+    /// Record an error on the `Span` and return the modified `Span`.
+    ///
+    /// The properties `error` and `error.message` are added, as well as `error.cause` if the
+    /// error has a source, and the status is set to [`SpanStatus::Error`] with the error message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let err = std::io::Error::new(std::io::ErrorKind::Other, "oh no");
+    /// let root = Span::root("root", SpanContext::random()).with_error(&err);
+    /// ```
+    #[inline]
+    pub fn with_error(mut self, err: &dyn std::error::Error) -> Self {
+        #[cfg(feature = "enable")]
+        if let Some(inner) = self.inner.as_mut() {
+            inner.add_properties(|| crate::util::error_properties(err));
+            inner.raw_span.extension_mut().status = SpanStatus::Error(err.to_string().into());
+        }
+
+        self
+    }
+
+    /// Override the begin time and the duration of the `Span` and return the modified `Span`.
+    ///
+    /// The reported [`SpanRecord`] will have exactly the given `begin_time_unix_ns` and
+    /// `duration_ns` instead of the time measured by the `Span`. This is useful for replaying
+    /// historical data and for writing deterministic tests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random())
+    ///     .with_timestamp_override(1_700_000_000_000_000_000, 1_000_000);
+    /// ```
+    ///
+    /// [`SpanRecord`]: crate::collector::SpanRecord
+    #[inline]
+    pub fn with_timestamp_override(mut self, begin_unix_ns: u64, duration_ns: u64) -> Self {
+        #[cfg(feature = "enable")]
+        if let Some(inner) = self.inner.as_mut() {
+            inner.raw_span.extension_mut().timestamp_override = Some((begin_unix_ns, duration_ns));
+        }
+
+        self
+    }
+
+    /// Set the begin time of the `Span` and return the modified `Span`.
+    ///
+    /// This is useful for replaying historical data and for writing tests, where the span
+    /// started before it is created. Unlike [`Span::with_timestamp_override()`], the end of the
+    /// span is still measured when it is dropped. If the span ends before the given begin time,
+    /// the reported [`SpanRecord::duration_ns`] is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use std::time::SystemTime;
+    ///
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random())
+    ///     .with_begin_time(SystemTime::now() - Duration::from_secs(1));
+    /// ```
+    ///
+    /// [`SpanRecord::duration_ns`]: crate::collector::SpanRecord::duration_ns
+    #[inline]
+    pub fn with_begin_time(mut self, begin_time: SystemTime) -> Self {
+        #[cfg(feature = "enable")]
+        if let Some(inner) = self.inner.as_mut() {
+            inner.raw_span.begin_time_override = Some(unix_nanos_from_system_time(begin_time));
+        }
+
+        self
+    }
+
+    /// Set the kind of the `Span` and return the modified `Span`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random()).with_kind(SpanKind::Server);
+    /// ```
+    #[inline]
+    pub fn with_kind(mut self, kind: SpanKind) -> Self {
+        #[cfg(feature = "enable")]
+        if let Some(inner) = self.inner.as_mut() {
+            inner.raw_span.kind = kind;
+        }
+
+        self
+    }
+
+    /// Attach a collection of [`LocalSpan`] instances as child spans to the current span.
+    ///
+    /// This method allows you to associate previously collected `LocalSpan` instances with the
+    /// current span. This is particularly useful when the `LocalSpan` instances were initiated
+    /// before their parent span, and were collected manually using a [`LocalCollector`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::local::LocalCollector;
+    /// use minitrace::prelude::*;
+    ///
+    /// // Collect local spans manually without a parent
+    /// let collector = LocalCollector::start();
+    /// let span = LocalSpan::enter_with_local_parent("a child span");
+    /// drop(span);
+    /// let local_spans = collector.collect();
+    ///
+    /// // Attach the local spans to a parent
+    /// let root = Span::root("root", SpanContext::random());
+    /// root.push_child_spans(local_spans);
+    /// ```
+    ///
+    /// [`LocalSpan`]: crate::local::LocalSpan
+    /// [`LocalSpans`]: crate::local::LocalSpans
+    /// [`LocalCollector`]: crate::local::LocalCollector
+    #[inline]
+    pub fn push_child_spans(&self, local_spans: LocalSpans) {
+        #[cfg(feature = "enable")]
+        {
+            if let Some(inner) = self.inner.as_ref() {
+                inner.push_child_spans(local_spans.inner)
+            }
+        }
+    }
+
+    /// Attach a collection of [`LocalSpan`] instances as child spans to the span, taking the
+    /// ownership of them.
+    ///
+    /// This is the consuming builder version of [`Span::adopt_local_spans_mut()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::local::LocalCollector;
+    /// use minitrace::prelude::*;
+    ///
+    /// let collector = LocalCollector::start();
+    /// let span = LocalSpan::enter_with_local_parent("a child span");
+    /// drop(span);
+    /// let local_spans = collector.collect();
+    ///
+    /// let root = Span::root("root", SpanContext::random()).adopt_local_spans(local_spans);
+    /// ```
+    ///
+    /// [`LocalSpan`]: crate::local::LocalSpan
+    #[inline]
+    pub fn adopt_local_spans(self, local_spans: LocalSpans) -> Self {
+        self.adopt_local_spans_mut(local_spans);
+        self
+    }
+
+    /// Attach a collection of [`LocalSpan`] instances as child spans to the span, taking the
+    /// ownership of them.
+    ///
+    /// Unlike [`Span::push_child_spans()`], which always shares the spans with the collector,
+    /// the spans are moved to the collector if `local_spans` is not cloned, saving the copy of
+    /// the spans for each parent. Otherwise it falls back to sharing the spans.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::local::LocalCollector;
+    /// use minitrace::prelude::*;
+    ///
+    /// let collector = LocalCollector::start();
+    /// let span = LocalSpan::enter_with_local_parent("a child span");
+    /// drop(span);
+    /// let local_spans = collector.collect();
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// root.adopt_local_spans_mut(local_spans);
+    /// ```
+    ///
+    /// [`LocalSpan`]: crate::local::LocalSpan
+    #[inline]
+    pub fn adopt_local_spans_mut(&self, local_spans: LocalSpans) {
+        #[cfg(feature = "enable")]
+        {
+            if let Some(inner) = self.inner.as_ref() {
+                inner.adopt_local_spans(local_spans.inner)
+            }
+        }
+    }
+
+    /// Returns `true` if the `Span` belongs to any of the traces that `ancestor` belongs to.
+    ///
+    /// Spans only record the traces they are collected into, not their full ancestry. So if
+    /// `ancestor` is a root span, this tells precisely whether the `Span` is one of its
+    /// descendants, otherwise any other non-root span in the same trace, such as a sibling, is
+    /// considered a descendant too. A span is not a descendant of itself, and noop spans are not
+    /// related to any span.
+    ///
+    /// This is a set intersection of the traces of both spans, so the cost is linear in the number
+    /// of traces each span belongs to, which is usually one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::Config;
+    /// use minitrace::collector::ConsoleReporter;
+    /// use minitrace::prelude::*;
+    ///
+    /// minitrace::set_reporter(ConsoleReporter, Config::default());
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let child = Span::enter_with_parent("child", &root);
+    ///
+    /// assert!(child.is_descendant_of(&root));
+    /// assert!(!root.is_descendant_of(&child));
+    /// ```
+    pub fn is_descendant_of(&self, ancestor: &Span) -> bool {
+        #[cfg(not(feature = "enable"))]
+        {
+            false
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            match (&self.inner, &ancestor.inner) {
+                (Some(inner), Some(ancestor)) => {
+                    inner.raw_span.id != ancestor.raw_span.id
+                        && inner.collect_token.iter().any(|item| {
+                            // A span is never a descendant within the trace it is the root of.
+                            inner.collect_id != Some(item.collect_id)
+                                && ancestor.collect_token.iter().any(|ancestor_item| {
+                                    ancestor_item.collect_id == item.collect_id
+                                })
+                        })
+                }
+                _ => false,
+            }
+        }
+    }
+
+    /// Sets the status of the span.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let mut root = Span::root("root", SpanContext::random());
+    ///
+    /// root.set_status(SpanStatus::Error("connection refused".into()));
+    /// ```
+    #[inline]
+    pub fn set_status(&mut self, status: SpanStatus) {
+        #[cfg(feature = "enable")]
+        if let Some(inner) = self.inner.as_mut() {
+            inner.raw_span.extension_mut().status = status;
+        }
+    }
+
+    /// Links the span to a span that is not its parent, such as one of the upstream requests
+    /// processed by a batch job.
+    ///
+    /// The links are reported in [`SpanRecord::links`], and exported as the links of the span by
+    /// the reporters that support them, such as the OpenTelemetry reporter.
     ///
     /// # Examples
     ///
     /// ```
     /// use minitrace::prelude::*;
     ///
-    /// let root = Span::root("root", SpanContext::random()).with_property(|| ("key", "value"));
+    /// let upstream = Span::root("upstream", SpanContext::random());
+    /// let mut batch = Span::root("batch", SpanContext::random());
+    ///
+    /// if let Some(upstream) = SpanContext::from_span(&upstream) {
+    ///     batch.add_link(upstream);
+    /// }
     /// ```
+    ///
+    /// [`SpanRecord::links`]: crate::collector::SpanRecord::links
     #[inline]
-    pub fn with_property<K, V, F>(self, property: F) -> Self
-    where
-        K: Into<Cow<'static, str>>,
-        V: Into<Cow<'static, str>>,
-        F: FnOnce() -> (K, V),
-    {
-        self.with_properties(move || [property()])
+    pub fn add_link(&mut self, linked: SpanContext) {
+        #[cfg(feature = "enable")]
+        if let Some(inner) = self.inner.as_mut() {
+            inner.raw_span
+                .extension_mut()
+                .links
+                .push((linked.trace_id, linked.span_id));
+        }
     }
 
-    /// Add multiple properties to the `Span` and return the modified `Span`.
+    /// Renames the span, e.g. when the route of a request is only known after the span is
+    /// started.
     ///
     /// # Examples
     ///
     /// ```
     /// use minitrace::prelude::*;
     ///
-    /// let root = Span::root("root", SpanContext::random())
-    ///     .with_properties(|| [("key1", "value1"), ("key2", "value2")]);
+    /// let mut root = Span::root("request", SpanContext::random());
+    ///
+    /// root.set_name("GET /users/{id}");
     /// ```
     #[inline]
-    pub fn with_properties<K, V, I, F>(mut self, properties: F) -> Self
-    where
-        K: Into<Cow<'static, str>>,
-        V: Into<Cow<'static, str>>,
-        I: IntoIterator<Item = (K, V)>,
-        F: FnOnce() -> I,
-    {
+    pub fn set_name(&mut self, name: impl Into<Cow<'static, str>>) {
         #[cfg(feature = "enable")]
         if let Some(inner) = self.inner.as_mut() {
-            inner.add_properties(properties);
+            inner.raw_span.name = name.into();
         }
-
-        self
     }
 
-    /// Attach a collection of [`LocalSpan`] instances as child spans to the current span.
-    ///
-    /// This method allows you to associate previously collected `LocalSpan` instances with the
-    /// current span. This is particularly useful when the `LocalSpan` instances were initiated
-    /// before their parent span, and were collected manually using a [`LocalCollector`].
+    /// Returns the name of the span, or `None` if the span is a noop span.
     ///
     /// # Examples
     ///
     /// ```
-    /// use minitrace::local::LocalCollector;
     /// use minitrace::prelude::*;
     ///
-    /// // Collect local spans manually without a parent
-    /// let collector = LocalCollector::start();
-    /// let span = LocalSpan::enter_with_local_parent("a child span");
-    /// drop(span);
-    /// let local_spans = collector.collect();
-    ///
-    /// // Attach the local spans to a parent
     /// let root = Span::root("root", SpanContext::random());
-    /// root.push_child_spans(local_spans);
+    /// let name = root.name();
+    ///
+    /// assert_eq!(Span::noop().name(), None);
+    /// ```
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        #[cfg(not(feature = "enable"))]
+        {
+            None
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            self.inner
+                .as_ref()
+                .map(|inner| inner.raw_span.name.as_ref())
+        }
+    }
+
+    /// Returns `true` if the span is a noop span which records nothing, for example, when no
+    /// reporter is set or the trace is not sampled.
+    ///
+    /// # Examples
+    ///
     /// ```
+    /// use minitrace::prelude::*;
     ///
-    /// [`LocalSpan`]: crate::local::LocalSpan
-    /// [`LocalSpans`]: crate::local::LocalSpans
-    /// [`LocalCollector`]: crate::local::LocalCollector
+    /// assert!(Span::noop().is_noop());
+    /// ```
     #[inline]
-    pub fn push_child_spans(&self, local_spans: LocalSpans) {
+    pub fn is_noop(&self) -> bool {
+        #[cfg(not(feature = "enable"))]
+        {
+            true
+        }
+
         #[cfg(feature = "enable")]
         {
-            if let Some(inner) = self.inner.as_ref() {
-                inner.push_child_spans(local_spans.inner)
-            }
+            self.inner.is_none()
         }
     }
 
@@ -332,7 +1159,7 @@ impl Span {
     pub fn elapsed(&self) -> Option<Duration> {
         #[cfg(feature = "enable")]
         if let Some(inner) = self.inner.as_ref() {
-            return Some(inner.raw_span.begin_instant.elapsed());
+            return Some(inner.raw_span.elapsed());
         }
 
         None
@@ -366,15 +1193,161 @@ impl Span {
             }
         }
     }
+
+    /// Calls `predicate` with the [elapsed time](Span::elapsed) of the span, and
+    /// [cancels](Span::cancel) the span if it returns `true`.
+    ///
+    /// The predicate is not called if the `Span` is a noop span.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use minitrace::prelude::*;
+    ///
+    /// let mut root = Span::root("root", SpanContext::random());
+    ///
+    /// // ...
+    ///
+    /// root.cancel_if(|elapsed| elapsed < Duration::from_secs(1));
+    /// ```
+    #[inline]
+    pub fn cancel_if(&mut self, predicate: impl FnOnce(Duration) -> bool) {
+        #[cfg(feature = "enable")]
+        if let Some(elapsed) = self.elapsed() {
+            if predicate(elapsed) {
+                self.cancel();
+            }
+        }
+    }
+
+    /// Cancels the span if it has been running for less than `threshold`, keeping only the slow
+    /// traces when called on the root span.
+    ///
+    /// This is a shortcut for [`Span::cancel_if()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use minitrace::prelude::*;
+    ///
+    /// let mut root = Span::root("root", SpanContext::random());
+    ///
+    /// // ...
+    ///
+    /// root.cancel_if_faster_than(Duration::from_secs(1));
+    /// ```
+    #[inline]
+    pub fn cancel_if_faster_than(&mut self, threshold: Duration) {
+        self.cancel_if(|elapsed| elapsed < threshold);
+    }
+
+    /// Creates a [`WeakSpan`] referring to the `Span` without keeping it alive, e.g. to store it
+    /// in a shared state that the `Span` may outlive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let weak = root.downgrade();
+    ///
+    /// if let Some(parent) = weak.upgrade() {
+    ///     let _child = parent.enter_child("child");
+    /// }
+    /// ```
+    pub fn downgrade(&self) -> WeakSpan {
+        #[cfg(not(feature = "enable"))]
+        {
+            WeakSpan::default()
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            let Some(inner) = self.inner.as_ref() else {
+                return WeakSpan::default();
+            };
+
+            let alive = inner.alive.get_or_init(|| Arc::new(()));
+            WeakSpan {
+                inner: Some(WeakSpanInner {
+                    alive: Arc::downgrade(alive),
+                    span_id: inner.raw_span.id,
+                    collect_token: inner.issue_collect_token().collect(),
+                    tracestate: inner.tracestate.clone(),
+                }),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tonic")]
+impl Span {
+    /// Create a new trace and return its root span, using the `traceparent` and `tracestate`
+    /// entries of the gRPC metadata, extracted by [`SpanContext::extract_grpc_metadata()`], as
+    /// the parent context.
+    ///
+    /// If the `traceparent` entry is absent or is not a valid
+    /// [W3C Trace Context](https://www.w3.org/TR/trace-context/), this function returns a no-op
+    /// span.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let mut metadata = tonic::metadata::MetadataMap::new();
+    /// SpanContext::random().inject_grpc_metadata(&mut metadata);
+    ///
+    /// let root = Span::root_from_grpc_metadata("root", &metadata);
+    /// ```
+    pub fn root_from_grpc_metadata(
+        name: impl Into<Cow<'static, str>>,
+        metadata: &tonic::metadata::MetadataMap,
+    ) -> Self {
+        SpanContext::extract_grpc_metadata(metadata)
+            .map(|parent| Span::root(name, parent))
+            .unwrap_or_else(Span::noop)
+    }
 }
 
 #[cfg(feature = "enable")]
 impl Span {
+    #[inline]
+    fn start_collect(
+        name: impl Into<Cow<'static, str>>,
+        parent: SpanContext,
+        is_root: bool,
+        resource: Option<Arc<Resource>>,
+    ) -> Self {
+        if !reporter_ready() {
+            return Self::noop();
+        }
+        run_reporter_factory();
+
+        let collect = current_collect();
+        let collect_id = collect.start_collect(resource);
+        let token = CollectTokenItem {
+            trace_id: parent.trace_id,
+            parent_id: parent.span_id,
+            collect_id,
+            is_root,
+            sampled: parent.sampled,
+        }
+        .into();
+        Self::new(token, name, Some(collect_id), parent.tracestate)
+    }
+
     #[inline]
     fn new(
         collect_token: CollectToken,
         name: impl Into<Cow<'static, str>>,
         collect_id: Option<usize>,
+        tracestate: Option<Arc<str>>,
     ) -> Self {
         let span_id = SpanId::next_id();
         let begin_instant = Instant::now();
@@ -387,6 +1360,8 @@ impl Span {
                 collect_token,
                 collect_id,
                 collect,
+                tracestate,
+                alive: OnceLock::new(),
             }),
         }
     }
@@ -395,12 +1370,20 @@ impl Span {
         name: impl Into<Cow<'static, str>>,
         stack: &mut LocalSpanStack,
     ) -> Self {
-        match stack.current_collect_token() {
-            Some(token) => Span::new(token, name, None),
+        match Self::local_parent_token(stack) {
+            Some((token, tracestate)) => Span::new(token, name, None, tracestate),
             None => Self::noop(),
         }
     }
 
+    fn local_parent_token(stack: &mut LocalSpanStack) -> Option<(CollectToken, Option<Arc<str>>)> {
+        let token = stack.current_collect_token()?;
+        let tracestate = stack
+            .current_span_line_ref()
+            .and_then(|span_line| span_line.tracestate().cloned());
+        Some((token, tracestate))
+    }
+
     pub(crate) fn attach_into_stack(
         &self,
         stack: &Rc<RefCell<LocalSpanStack>>,
@@ -430,7 +1413,7 @@ impl SpanInner {
     #[inline]
     fn capture_local_spans(&self, stack: Rc<RefCell<LocalSpanStack>>) -> LocalParentGuard {
         let token = self.issue_collect_token().collect();
-        let collector = LocalCollector::new(Some(token), stack);
+        let collector = LocalCollector::new(Some(token), self.tracestate.clone(), stack);
 
         LocalParentGuard::new(collector, self.collect.clone())
     }
@@ -447,6 +1430,20 @@ impl SpanInner {
         );
     }
 
+    #[inline]
+    fn adopt_local_spans(&self, local_spans: Arc<LocalSpansInner>) {
+        if local_spans.spans.is_empty() {
+            return;
+        }
+
+        let spans = match Arc::try_unwrap(local_spans) {
+            Ok(local_spans) => SpanSet::LocalSpansInner(local_spans),
+            Err(local_spans) => SpanSet::SharedLocalSpans(local_spans),
+        };
+        self.collect
+            .submit_spans(spans, self.issue_collect_token().collect());
+    }
+
     #[inline]
     pub(crate) fn issue_collect_token(&self) -> impl Iterator<Item = CollectTokenItem> + '_ {
         self.collect_token
@@ -456,6 +1453,7 @@ impl SpanInner {
                 parent_id: self.raw_span.id,
                 collect_id: collect_item.collect_id,
                 is_root: false,
+                sampled: collect_item.sampled,
             })
     }
 
@@ -484,6 +1482,176 @@ impl Drop for Span {
     }
 }
 
+/// A weak reference to a [`Span`], created by [`Span::downgrade()`].
+///
+/// A `WeakSpan` does not keep the span alive, so it can be stored in a structure that the span
+/// refers to, directly or not, without creating a reference cycle.
+#[derive(Clone, Default)]
+pub struct WeakSpan {
+    #[cfg(feature = "enable")]
+    inner: Option<WeakSpanInner>,
+}
+
+#[derive(Clone)]
+struct WeakSpanInner {
+    alive: Weak<()>,
+    span_id: SpanId,
+    // Issued for the children of the span.
+    collect_token: Vec<CollectTokenItem>,
+    tracestate: Option<Arc<str>>,
+}
+
+impl WeakSpan {
+    /// Returns a [`SpanParent`] to start child spans of the span if it has not been dropped yet,
+    /// or `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let weak = root.downgrade();
+    ///
+    /// drop(root);
+    /// assert!(weak.upgrade().is_none());
+    /// ```
+    pub fn upgrade(&self) -> Option<SpanParent> {
+        #[cfg(not(feature = "enable"))]
+        {
+            None
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            let inner = self.inner.as_ref()?;
+            inner.alive.upgrade()?;
+
+            Some(SpanParent {
+                collect_token: inner.collect_token.clone(),
+                tracestate: inner.tracestate.clone(),
+            })
+        }
+    }
+
+    /// Returns the [`SpanContext`] of the span if it has not been dropped yet, or `None`
+    /// otherwise, for example to propagate it without upgrading the `WeakSpan`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let weak = root.downgrade();
+    ///
+    /// let traceparent = weak
+    ///     .context()
+    ///     .map(|context| context.encode_w3c_traceparent());
+    /// ```
+    pub fn context(&self) -> Option<SpanContext> {
+        #[cfg(not(feature = "enable"))]
+        {
+            None
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            let inner = self.inner.as_ref()?;
+            inner.alive.upgrade()?;
+            let item = inner.collect_token.first()?;
+
+            Some(SpanContext {
+                trace_id: item.trace_id,
+                span_id: inner.span_id,
+                tracestate: inner.tracestate.clone(),
+                sampled: item.sampled,
+            })
+        }
+    }
+}
+
+/// A handle to start child spans of a [`Span`], returned by [`WeakSpan::upgrade()`].
+///
+/// It's not the span itself, so it does not keep the span alive, and can not be used to modify
+/// or report it.
+pub struct SpanParent {
+    #[cfg(feature = "enable")]
+    collect_token: Vec<CollectTokenItem>,
+    #[cfg(feature = "enable")]
+    tracestate: Option<Arc<str>>,
+}
+
+impl SpanParent {
+    /// Create a new child span of the span, like [`Span::enter_with_parent()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let weak = root.downgrade();
+    ///
+    /// if let Some(parent) = weak.upgrade() {
+    ///     let _child = parent.enter_child("child");
+    /// }
+    /// ```
+    pub fn enter_child(&self, name: impl Into<Cow<'static, str>>) -> Span {
+        #[cfg(not(feature = "enable"))]
+        {
+            Span::noop()
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            Span::new(
+                self.collect_token.iter().collect(),
+                name,
+                None,
+                self.tracestate.clone(),
+            )
+        }
+    }
+
+    /// Sets the span as the local parent for the current thread, like
+    /// [`Span::set_local_parent()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let weak = root.downgrade();
+    ///
+    /// if let Some(parent) = weak.upgrade() {
+    ///     let _guard = parent.set_local_parent();
+    ///     let _child = LocalSpan::enter_with_local_parent("child");
+    /// }
+    /// ```
+    pub fn set_local_parent(&self) -> LocalParentGuard {
+        #[cfg(not(feature = "enable"))]
+        {
+            LocalParentGuard::noop()
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            LOCAL_SPAN_STACK
+                .try_with(|stack| {
+                    let collector = LocalCollector::new(
+                        Some(self.collect_token.iter().collect()),
+                        self.tracestate.clone(),
+                        stack.clone(),
+                    );
+                    LocalParentGuard::new(collector, current_collect())
+                })
+                .unwrap_or_default()
+        }
+    }
+}
+
 /// A guard created by [`Span::set_local_parent()`].
 #[derive(Default)]
 pub struct LocalParentGuard {
@@ -596,6 +1764,7 @@ mod tests {
                         parent_id: SpanId::default(),
                         collect_id: 42,
                         is_root: true,
+                        sampled: true,
                     }
                     .into(),
                 ),
@@ -708,10 +1877,10 @@ root []
 
         let routine = || {
             let parent_ctx = SpanContext::random();
-            let parent1 = Span::root("parent1", parent_ctx);
-            let parent2 = Span::root("parent2", parent_ctx);
-            let parent3 = Span::root("parent3", parent_ctx);
-            let parent4 = Span::root("parent4", parent_ctx);
+            let parent1 = Span::root("parent1", parent_ctx.clone());
+            let parent2 = Span::root("parent2", parent_ctx.clone());
+            let parent3 = Span::root("parent3", parent_ctx.clone());
+            let parent4 = Span::root("parent4", parent_ctx.clone());
             let parent5 = Span::root("parent5", parent_ctx);
             let child1 = Span::enter_with_parent("child1", &parent5);
             let child2 = Span::enter_with_parents("child2", [
@@ -747,7 +1916,7 @@ root []
             .in_sequence(&mut seq)
             .returning({
                 let id = Arc::new(AtomicUsize::new(1));
-                move || id.fetch_add(1, Ordering::SeqCst)
+                move |_| id.fetch_add(1, Ordering::SeqCst)
             });
         mock.expect_submit_spans()
             .times(7)
@@ -802,14 +1971,14 @@ parent5 []
 
         let routine = || {
             let parent_ctx = SpanContext::random();
-            let parent1 = Span::root("parent1", parent_ctx);
-            let parent2 = Span::root("parent2", parent_ctx);
-            let parent3 = Span::root("parent3", parent_ctx);
-            let parent4 = Span::root("parent4", parent_ctx);
+            let parent1 = Span::root("parent1", parent_ctx.clone());
+            let parent2 = Span::root("parent2", parent_ctx.clone());
+            let parent3 = Span::root("parent3", parent_ctx.clone());
+            let parent4 = Span::root("parent4", parent_ctx.clone());
             let parent5 = Span::root("parent5", parent_ctx);
 
             let stack = Rc::new(RefCell::new(LocalSpanStack::with_capacity(16)));
-            let collector = LocalCollector::new(None, stack.clone());
+            let collector = LocalCollector::new(None, None, stack.clone());
             {
                 let _s = LocalSpan::enter_with_stack("child", stack);
             }
@@ -838,7 +2007,7 @@ parent5 []
             .in_sequence(&mut seq)
             .returning({
                 let id = Arc::new(AtomicUsize::new(1));
-                move || id.fetch_add(1, Ordering::SeqCst)
+                move |_| id.fetch_add(1, Ordering::SeqCst)
             });
         mock.expect_submit_spans()
             .times(10)
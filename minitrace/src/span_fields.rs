@@ -0,0 +1,34 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::borrow::Cow;
+
+/// A type whose fields can be recorded as span properties.
+///
+/// This trait is usually implemented by `#[derive(SpanFields)]`, which exposes every `pub` field of
+/// a struct as a property, and is consumed by [`Span::with_properties_from_struct()`] and
+/// [`LocalSpan::with_properties_from_struct()`].
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// use minitrace::SpanFields;
+///
+/// struct Peer {
+///     addr: String,
+/// }
+///
+/// impl SpanFields for Peer {
+///     fn span_fields(&self) -> Vec<(Cow<'static, str>, Cow<'static, str>)> {
+///         vec![("peer.addr".into(), self.addr.clone().into())]
+///     }
+/// }
+/// ```
+///
+/// [`Span::with_properties_from_struct()`]: crate::Span::with_properties_from_struct
+/// [`LocalSpan::with_properties_from_struct()`]: crate::local::LocalSpan::with_properties_from_struct
+pub trait SpanFields {
+    /// Returns the properties to be recorded on a span.
+    fn span_fields(&self) -> Vec<(Cow<'static, str>, Cow<'static, str>)>;
+}
@@ -0,0 +1,51 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+type Tag = (Cow<'static, str>, Cow<'static, str>);
+
+/// A collection of tags accepted by [`Span::root_with_tags()`].
+///
+/// It is implemented for slices and arrays of `&'static str` pairs, which are recorded without
+/// allocation, and for `&HashMap<&str, &str>`, whose keys and values are copied.
+///
+/// [`Span::root_with_tags()`]: crate::Span::root_with_tags
+pub trait SpanTags {
+    type Iter: Iterator<Item = Tag>;
+
+    /// Returns the tags as span properties.
+    fn into_tags(self) -> Self::Iter;
+}
+
+impl<'a> SpanTags for &'a [(&'static str, &'static str)] {
+    type Iter = std::iter::Map<
+        std::slice::Iter<'a, (&'static str, &'static str)>,
+        fn(&(&'static str, &'static str)) -> Tag,
+    >;
+
+    fn into_tags(self) -> Self::Iter {
+        self.iter()
+            .map(|(k, v)| (Cow::Borrowed(*k), Cow::Borrowed(*v)))
+    }
+}
+
+impl<'a, const N: usize> SpanTags for &'a [(&'static str, &'static str); N] {
+    type Iter = <&'a [(&'static str, &'static str)] as SpanTags>::Iter;
+
+    fn into_tags(self) -> Self::Iter {
+        self.as_slice().into_tags()
+    }
+}
+
+impl<'a, 'b, S> SpanTags for &'a HashMap<&'b str, &'b str, S> {
+    type Iter = std::iter::Map<
+        std::collections::hash_map::Iter<'a, &'b str, &'b str>,
+        fn((&&str, &&str)) -> Tag,
+    >;
+
+    fn into_tags(self) -> Self::Iter {
+        self.iter()
+            .map(|(k, v)| (Cow::Owned(k.to_string()), Cow::Owned(v.to_string())))
+    }
+}
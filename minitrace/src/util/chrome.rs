@@ -0,0 +1,191 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Exports spans in the [Chrome trace event format], which can be opened by `chrome://tracing`,
+//! [Perfetto](https://ui.perfetto.dev) or [Speedscope](https://www.speedscope.app).
+//!
+//! [Chrome trace event format]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use crate::collector::SpanRecord;
+
+/// The number of threads the spans are spread over by [`to_chrome_trace_events()`].
+pub const DEFAULT_THREAD_COUNT: u64 = 16;
+
+/// Converts the spans into a JSON array of Chrome trace events, spreading the spans over
+/// [`DEFAULT_THREAD_COUNT`] threads.
+///
+/// See [`to_chrome_trace_events_with_thread_count()`] for the format of the events.
+pub fn to_chrome_trace_events(records: &[SpanRecord]) -> String {
+    to_chrome_trace_events_with_thread_count(records, DEFAULT_THREAD_COUNT)
+}
+
+/// Converts the spans into a JSON array of Chrome trace events.
+///
+/// Each span becomes a complete event (`"ph":"X"`) with the properties of the span as `args`,
+/// and each of its events becomes an instant event (`"ph":"i"`) on the same thread. Timestamps
+/// and durations are in microseconds. All the events belong to the process `1`, and the thread of
+/// a span is its `span_id` modulo `thread_count`.
+///
+/// # Panics
+///
+/// Panics if `thread_count` is zero.
+pub fn to_chrome_trace_events_with_thread_count(
+    records: &[SpanRecord],
+    thread_count: u64,
+) -> String {
+    assert!(thread_count > 0, "`thread_count` must be greater than zero");
+
+    let mut json = String::from("[");
+    for record in records {
+        let tid = record.span_id.0 % thread_count;
+
+        if json.len() > 1 {
+            json.push(',');
+        }
+        write!(
+            json,
+            r#"{{"ph":"X","pid":1,"tid":{tid},"ts":{},"dur":{},"name":"#,
+            Micros(record.begin_time_unix_ns),
+            Micros(record.duration_ns),
+        )
+        .unwrap();
+        write_str(&mut json, &record.name);
+        json.push_str(r#","args":"#);
+        write_args(&mut json, &record.properties);
+        json.push('}');
+
+        for event in &record.events {
+            write!(
+                json,
+                r#",{{"ph":"i","s":"t","pid":1,"tid":{tid},"ts":{},"name":"#,
+                Micros(event.timestamp_unix_ns),
+            )
+            .unwrap();
+            write_str(&mut json, &event.name);
+            json.push_str(r#","args":"#);
+            write_args(&mut json, &event.properties);
+            json.push('}');
+        }
+    }
+    json.push(']');
+    json
+}
+
+/// Formats nanoseconds as microseconds with three decimal places.
+struct Micros(u64);
+
+impl std::fmt::Display for Micros {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:03}", self.0 / 1_000, self.0 % 1_000)
+    }
+}
+
+fn write_args(json: &mut String, properties: &[(Cow<'static, str>, Cow<'static, str>)]) {
+    json.push('{');
+    for (i, (k, v)) in properties.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        write_str(json, k);
+        json.push(':');
+        write_str(json, v);
+    }
+    json.push('}');
+}
+
+fn write_str(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::collector::EventLevel;
+    use crate::collector::EventRecord;
+    use crate::collector::SpanId;
+    use crate::collector::TraceId;
+
+    #[test]
+    fn chrome_trace_events() {
+        let records = vec![
+            SpanRecord {
+                trace_id: TraceId(1),
+                span_id: SpanId(17),
+                begin_time_unix_ns: 1_000_000,
+                duration_ns: 5_500,
+                name: "root".into(),
+                properties: vec![("k1".into(), "v1".into())],
+                events: vec![EventRecord {
+                    name: "event \"quoted\"\n".into(),
+                    timestamp_unix_ns: 1_002_001,
+                    level: EventLevel::Info,
+                    properties: vec![("k2".into(), "v2".into())],
+                }],
+                ..SpanRecord::default()
+            },
+            SpanRecord {
+                trace_id: TraceId(1),
+                span_id: SpanId(2),
+                parent_id: SpanId(17),
+                begin_time_unix_ns: 1_001_000,
+                duration_ns: 1_000,
+                name: "child".into(),
+                ..SpanRecord::default()
+            },
+        ];
+
+        let events: Value =
+            serde_json::from_str(&to_chrome_trace_events_with_thread_count(&records, 4)).unwrap();
+        assert_eq!(
+            events,
+            json!([
+                {
+                    "ph": "X",
+                    "pid": 1,
+                    "tid": 1,
+                    "ts": 1000.0,
+                    "dur": 5.5,
+                    "name": "root",
+                    "args": { "k1": "v1" },
+                },
+                {
+                    "ph": "i",
+                    "s": "t",
+                    "pid": 1,
+                    "tid": 1,
+                    "ts": 1002.001,
+                    "name": "event \"quoted\"\n",
+                    "args": { "k2": "v2" },
+                },
+                {
+                    "ph": "X",
+                    "pid": 1,
+                    "tid": 2,
+                    "ts": 1001.0,
+                    "dur": 1.0,
+                    "name": "child",
+                    "args": {},
+                },
+            ])
+        );
+
+        assert_eq!(to_chrome_trace_events(&[]), "[]");
+    }
+}
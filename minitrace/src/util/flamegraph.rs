@@ -0,0 +1,257 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Renders spans as a [flamegraph](https://www.brendangregg.com/flamegraphs.html).
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use crate::collector::SpanId;
+use crate::collector::SpanRecord;
+use crate::collector::TraceId;
+
+/// Converts the spans into the collapsed stack format, which is the input of
+/// [`inferno`](https://crates.io/crates/inferno) and of the original `flamegraph.pl` script.
+///
+/// Each line is a stack of span names from a root span down to a span, separated by `;`,
+/// followed by the duration of the span in nanoseconds, excluding the duration of its children.
+/// Therefore, the width of a frame in the flamegraph is the duration of its span. The parent of a
+/// span is looked up by its `parent_id` among the spans of the same trace, and a span whose
+/// parent is not among them is a root span. The stacks of the spans with the same names are
+/// merged, and the lines are sorted.
+///
+/// Semicolons and line breaks in the span names are replaced by `_`.
+pub fn spans_to_collapsed_stacks(records: &[SpanRecord]) -> String {
+    let mut collapsed = String::new();
+    for (stack, duration_ns) in collapse(records) {
+        collapsed.push_str(&stack);
+        collapsed.push(' ');
+        collapsed.push_str(&duration_ns.to_string());
+        collapsed.push('\n');
+    }
+    collapsed
+}
+
+/// Converts the spans into the collapsed stack format with one line per leaf span, e.g. to be
+/// piped to `inferno-flamegraph`.
+///
+/// Each line is the stack of span names from a root span down to a span without children,
+/// separated by `;`, followed by the duration of the leaf span in nanoseconds, or `1` if the
+/// duration is zero so that the span remains visible. The lines are in the order of the records
+/// and are not merged. Unlike [`spans_to_collapsed_stacks()`], the time spent in a parent span
+/// outside of its children is not shown, which suits traces where the leaf spans do the actual
+/// work.
+///
+/// The parents are looked up the same way as in [`spans_to_collapsed_stacks()`], and the span
+/// names are escaped the same way.
+pub fn to_collapsed_stacks(records: &[SpanRecord]) -> String {
+    let parents = parent_indices(records);
+    let mut has_children = vec![false; records.len()];
+    for parent in parents.iter().flatten() {
+        has_children[*parent] = true;
+    }
+
+    let mut collapsed = String::new();
+    for (i, record) in records.iter().enumerate() {
+        if has_children[i] {
+            continue;
+        }
+        collapsed.push_str(&stack(records, &parents, i));
+        collapsed.push(' ');
+        collapsed.push_str(&record.duration_ns.max(1).to_string());
+        collapsed.push('\n');
+    }
+    collapsed
+}
+
+/// Renders the spans as a flamegraph in SVG, from the stacks of
+/// [`spans_to_collapsed_stacks()`].
+///
+/// The frames of the spans with the property `category` are colored after the value of the
+/// property, so that the spans of a category share a color. Other frames are colored by the
+/// default palette of `inferno`.
+///
+/// This requires the `flamegraph` feature.
+#[cfg(feature = "flamegraph")]
+pub fn spans_to_svg(records: &[SpanRecord]) -> String {
+    use inferno::flamegraph;
+
+    let collapsed = spans_to_collapsed_stacks(records);
+
+    let mut palette_map = flamegraph::color::PaletteMap::default();
+    for record in records {
+        if let Some((_, category)) = record.properties.iter().find(|(k, _)| k == "category") {
+            palette_map.insert(frame_name(&record.name), category_color(category));
+        }
+    }
+
+    let mut options = flamegraph::Options::default();
+    options.title = "Span Flamegraph".to_string();
+    options.count_name = "ns".to_string();
+    options.palette_map = Some(&mut palette_map);
+
+    let mut svg = Vec::new();
+    flamegraph::from_lines(&mut options, collapsed.lines(), &mut svg)
+        .expect("writing to a Vec never fails");
+    String::from_utf8(svg).expect("the SVG is valid UTF-8")
+}
+
+#[cfg(feature = "flamegraph")]
+fn category_color(category: &str) -> inferno::flamegraph::color::Color {
+    const COLORS: [(u8, u8, u8); 8] = [
+        (0x4e, 0x79, 0xa7),
+        (0xf2, 0x8e, 0x2b),
+        (0xe1, 0x57, 0x59),
+        (0x76, 0xb7, 0xb2),
+        (0x59, 0xa1, 0x4f),
+        (0xed, 0xc9, 0x48),
+        (0xb0, 0x7a, 0xa1),
+        (0xff, 0x9d, 0xa7),
+    ];
+
+    // FNV-1a, which is stable across runs unlike the hasher of `HashMap`.
+    let hash = category.bytes().fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    let (r, g, b) = COLORS[(hash % COLORS.len() as u64) as usize];
+    inferno::flamegraph::color::Color { r, g, b }
+}
+
+/// Returns the self duration of each stack of span names, merged by stack.
+fn collapse(records: &[SpanRecord]) -> BTreeMap<String, u64> {
+    let parents = parent_indices(records);
+
+    let mut self_durations: Vec<u64> = records.iter().map(|record| record.duration_ns).collect();
+    for (record, parent) in records.iter().zip(&parents) {
+        if let Some(parent) = *parent {
+            self_durations[parent] = self_durations[parent].saturating_sub(record.duration_ns);
+        }
+    }
+
+    let mut stacks = BTreeMap::new();
+    for (i, self_duration) in self_durations.into_iter().enumerate() {
+        *stacks.entry(stack(records, &parents, i)).or_insert(0) += self_duration;
+    }
+    stacks
+}
+
+/// Returns the index of the parent of each span among the spans of the same trace.
+fn parent_indices(records: &[SpanRecord]) -> Vec<Option<usize>> {
+    let index: HashMap<(TraceId, SpanId), usize> = records
+        .iter()
+        .enumerate()
+        .map(|(i, record)| ((record.trace_id, record.span_id), i))
+        .collect();
+    records
+        .iter()
+        .enumerate()
+        .map(|(i, record)| {
+            index
+                .get(&(record.trace_id, record.parent_id))
+                .copied()
+                .filter(|&parent| parent != i)
+        })
+        .collect()
+}
+
+/// Returns the names of the spans from the root span down to the span `i`, separated by `;`.
+fn stack(records: &[SpanRecord], parents: &[Option<usize>], i: usize) -> String {
+    let mut frames = Vec::new();
+    let mut current = Some(i);
+    // The length of a chain of parents is bounded in case of a cycle.
+    while let Some(j) = current.filter(|_| frames.len() <= records.len()) {
+        frames.push(frame_name(&records[j].name));
+        current = parents[j];
+    }
+    frames.reverse();
+    frames.join(";")
+}
+
+fn frame_name(name: &str) -> String {
+    name.replace([';', '\n', '\r'], "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(span_id: u64, parent_id: u64, name: &'static str, duration_ns: u64) -> SpanRecord {
+        SpanRecord {
+            trace_id: TraceId(1),
+            span_id: SpanId(span_id),
+            parent_id: SpanId(parent_id),
+            name: name.into(),
+            duration_ns,
+            ..SpanRecord::default()
+        }
+    }
+
+    #[test]
+    fn collapsed_stacks() {
+        let records = vec![
+            span(1, 0, "root", 100),
+            span(2, 1, "query", 30),
+            span(3, 1, "query", 20),
+            span(4, 2, "parse;sql", 10),
+            span(5, 1, "render", 40),
+            SpanRecord {
+                trace_id: TraceId(2),
+                ..span(2, 0, "other trace", 5)
+            },
+        ];
+
+        assert_eq!(
+            spans_to_collapsed_stacks(&records),
+            "other trace 5
+root 10
+root;query 40
+root;query;parse_sql 10
+root;render 40
+"
+        );
+        assert_eq!(spans_to_collapsed_stacks(&[]), "");
+    }
+
+    #[test]
+    fn leaf_collapsed_stacks() {
+        let records = vec![
+            span(1, 0, "root", 100),
+            span(2, 1, "query", 30),
+            span(3, 1, "query", 20),
+            span(4, 2, "parse;sql", 10),
+            span(5, 1, "render", 0),
+            span(6, 9, "orphan", 7),
+        ];
+
+        let collapsed = to_collapsed_stacks(&records);
+        assert_eq!(collapsed.lines().collect::<Vec<_>>(), vec![
+            "root;query 20",
+            "root;query;parse_sql 10",
+            "root;render 1",
+            "orphan 7",
+        ]);
+        assert_eq!(to_collapsed_stacks(&[]), "");
+    }
+
+    #[cfg(feature = "flamegraph")]
+    #[test]
+    fn svg() {
+        let mut records = vec![
+            span(1, 0, "root", 100),
+            span(2, 1, "query", 30),
+            span(3, 1, "render", 40),
+        ];
+        records[1].properties = vec![("category".into(), "db".into())];
+
+        let svg = spans_to_svg(&records);
+        assert!(svg.starts_with("<?xml"));
+        for name in ["root", "query", "render"] {
+            assert!(svg.contains(&format!("<title>{name} (")), "{name}");
+        }
+
+        let (r, g, b) = {
+            let color = category_color("db");
+            (color.r, color.g, color.b)
+        };
+        assert!(svg.contains(&format!("rgb({r},{g},{b})")));
+    }
+}
@@ -1,13 +1,19 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
 
+pub mod chrome;
+pub mod flamegraph;
 pub mod legacy_spsc;
 pub mod object_pool;
+pub mod oneshot;
 pub mod spsc;
 #[doc(hidden)]
 pub mod tree;
 
 use std::borrow::Cow;
 use std::cell::RefCell;
+use std::error::Error;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 use once_cell::sync::Lazy;
 
@@ -17,18 +23,26 @@ use crate::util::object_pool::Pool;
 use crate::util::object_pool::Puller;
 use crate::util::object_pool::Reusable;
 
-static RAW_SPANS_POOL: Lazy<Pool<Vec<RawSpan>>> = Lazy::new(|| Pool::new(Vec::new, Vec::clear));
+const POOL_MAX_SIZE: usize = 16384;
+const PULLER_BATCH_SIZE: usize = 512;
+
+// A puller refills its buffer from the pool in batches, so the pool has to be able to retain at
+// least one batch.
+const _: () = assert!(POOL_MAX_SIZE >= PULLER_BATCH_SIZE);
+
+static RAW_SPANS_POOL: Lazy<Pool<Vec<RawSpan>>> =
+    Lazy::new(|| Pool::with_capacity(Vec::new, Vec::clear, POOL_MAX_SIZE));
 static COLLECT_TOKEN_ITEMS_POOL: Lazy<Pool<Vec<CollectTokenItem>>> =
-    Lazy::new(|| Pool::new(Vec::new, Vec::clear));
+    Lazy::new(|| Pool::with_capacity(Vec::new, Vec::clear, POOL_MAX_SIZE));
 #[allow(clippy::type_complexity)]
 static PROPERTIES_POOL: Lazy<Pool<Vec<(Cow<'static, str>, Cow<'static, str>)>>> =
-    Lazy::new(|| Pool::new(Vec::new, Vec::clear));
+    Lazy::new(|| Pool::with_capacity(Vec::new, Vec::clear, POOL_MAX_SIZE));
 
 thread_local! {
-    static RAW_SPANS_PULLER: RefCell<Puller<'static, Vec<RawSpan>>> = RefCell::new(RAW_SPANS_POOL.puller(512));
-    static COLLECT_TOKEN_ITEMS_PULLER: RefCell<Puller<'static, Vec<CollectTokenItem>>>  = RefCell::new(COLLECT_TOKEN_ITEMS_POOL.puller(512));
+    static RAW_SPANS_PULLER: RefCell<Puller<'static, Vec<RawSpan>>> = RefCell::new(RAW_SPANS_POOL.puller(PULLER_BATCH_SIZE));
+    static COLLECT_TOKEN_ITEMS_PULLER: RefCell<Puller<'static, Vec<CollectTokenItem>>>  = RefCell::new(COLLECT_TOKEN_ITEMS_POOL.puller(PULLER_BATCH_SIZE));
     #[allow(clippy::type_complexity)]
-    static PROPERTIES_PULLER: RefCell<Puller<'static, Vec<(Cow<'static, str>, Cow<'static, str>)>>>  = RefCell::new(PROPERTIES_POOL.puller(512));
+    static PROPERTIES_PULLER: RefCell<Puller<'static, Vec<(Cow<'static, str>, Cow<'static, str>)>>>  = RefCell::new(PROPERTIES_POOL.puller(PULLER_BATCH_SIZE));
 }
 
 pub type RawSpans = Reusable<'static, Vec<RawSpan>>;
@@ -51,6 +65,30 @@ impl Default for Properties {
     }
 }
 
+/// Returns the properties describing an error: `error`, `error.message` and, if the error has a
+/// source, `error.cause`.
+pub(crate) fn error_properties(
+    err: &dyn Error,
+) -> impl Iterator<Item = (&'static str, String)> + '_ {
+    [
+        ("error", "true".to_string()),
+        ("error.message", err.to_string()),
+    ]
+    .into_iter()
+    .chain(
+        err.source()
+            .map(|source| ("error.cause", source.to_string())),
+    )
+}
+
+/// Converts a wall-clock time into nanoseconds since the unix epoch. A time before the epoch is
+/// clamped to the epoch.
+pub(crate) fn unix_nanos_from_system_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
 fn new_collect_token(items: impl IntoIterator<Item = CollectTokenItem>) -> CollectToken {
     let mut token = COLLECT_TOKEN_ITEMS_PULLER
         .try_with(|puller| puller.borrow_mut().pull())
@@ -4,6 +4,8 @@ use std::cell::Cell;
 use std::mem::ManuallyDrop;
 use std::ops::Deref;
 use std::ops::DerefMut;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 
 use parking_lot::Mutex;
 
@@ -26,15 +28,51 @@ pub struct Pool<T> {
     objects: Mutex<Vec<T>>,
     init: fn() -> T,
     reset: fn(&mut T),
+    // The maximum number of objects retained in the pool. Objects recycled into a full pool
+    // are dropped.
+    max_size: usize,
+    // The number of objects created for the pool and not dropped yet, either retained in the pool
+    // or in use. It's only updated when a batch of objects is created or an object is dropped
+    // instead of being recycled, so that reusing the pooled objects doesn't touch it.
+    allocated: AtomicUsize,
+}
+
+/// A snapshot of the objects managed by a [`Pool`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// The number of objects either retained in the pool or in use.
+    pub allocated: usize,
+    /// The number of objects handed out by the pool and not yet recycled.
+    pub in_use: usize,
+    /// The maximum number of objects retained in the pool.
+    pub capacity: usize,
 }
 
 impl<T> Pool<T> {
     #[inline]
     pub fn new(init: fn() -> T, reset: fn(&mut T)) -> Pool<T> {
+        Self::with_capacity(init, reset, usize::MAX)
+    }
+
+    /// Creates a pool that retains at most `max_size` objects for reuse.
+    #[inline]
+    pub fn with_capacity(init: fn() -> T, reset: fn(&mut T), max_size: usize) -> Pool<T> {
         Pool {
             objects: Mutex::new(Vec::new()),
             init,
             reset,
+            max_size,
+            allocated: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let pooled = self.objects.lock().len();
+        let allocated = self.allocated.load(Ordering::Relaxed);
+        PoolStats {
+            allocated,
+            in_use: allocated.saturating_sub(pooled),
+            capacity: self.max_size,
         }
     }
 
@@ -45,14 +83,20 @@ impl<T> Pool<T> {
         buffer.extend(
             objects
                 .drain(len.saturating_sub(n)..)
-                .map(|obj| Reusable::new(self, obj)),
+                .map(|obj| Reusable::from_pool(self, obj)),
         );
         drop(objects);
-        buffer.resize_with(n, || Reusable::new(self, (self.init)()));
+
+        let created = n.saturating_sub(buffer.len());
+        if created > 0 {
+            self.allocated.fetch_add(created, Ordering::Relaxed);
+            buffer.resize_with(n, || Reusable::from_pool(self, (self.init)()));
+        }
     }
 
-    pub fn puller(&self, buffer_size: usize) -> Puller<T> {
+    pub fn puller(&self, buffer_size: usize) -> Puller<'_, T> {
         assert!(buffer_size > 0);
+        debug_assert!(buffer_size <= self.max_size);
         Puller {
             pool: self,
             buffer: Vec::with_capacity(buffer_size),
@@ -63,9 +107,19 @@ impl<T> Pool<T> {
     #[inline]
     pub fn recycle(&self, mut obj: T) {
         if is_reusable() {
-            (self.reset)(&mut obj);
-            self.objects.lock().push(obj)
+            let mut objects = self.objects.lock();
+            if objects.len() < self.max_size {
+                (self.reset)(&mut obj);
+                objects.push(obj);
+                return;
+            }
         }
+        self.discard();
+    }
+
+    #[inline]
+    fn discard(&self) {
+        self.allocated.fetch_sub(1, Ordering::Relaxed);
     }
 }
 
@@ -93,6 +147,13 @@ pub struct Reusable<'a, T> {
 impl<'a, T> Reusable<'a, T> {
     #[inline]
     pub fn new(pool: &'a Pool<T>, obj: T) -> Self {
+        pool.allocated.fetch_add(1, Ordering::Relaxed);
+        Self::from_pool(pool, obj)
+    }
+
+    /// Wraps an object that is already counted by the pool.
+    #[inline]
+    fn from_pool(pool: &'a Pool<T>, obj: T) -> Self {
         Self {
             pool,
             obj: ManuallyDrop::new(obj),
@@ -101,6 +162,7 @@ impl<'a, T> Reusable<'a, T> {
 
     #[inline]
     pub fn into_inner(mut self) -> T {
+        self.pool.discard();
         unsafe {
             let obj = ManuallyDrop::take(&mut self.obj);
             std::mem::forget(self);
@@ -151,3 +213,33 @@ impl<'a, T> Drop for Reusable<'a, T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pool_max_size() {
+        enable_reuse_in_current_thread();
+
+        let pool: Pool<Vec<u8>> = Pool::with_capacity(Vec::new, Vec::clear, 64);
+        let mut puller = pool.puller(16);
+
+        let burst = (0..10_000).map(|_| puller.pull()).collect::<Vec<_>>();
+        assert_eq!(pool.stats().in_use, 10_000);
+        drop(burst);
+        drop(puller);
+
+        assert_eq!(pool.stats(), PoolStats {
+            allocated: 64,
+            in_use: 0,
+            capacity: 64,
+        });
+
+        let mut puller = pool.puller(16);
+        let mut obj = puller.pull();
+        obj.push(42);
+        assert_eq!(*obj, vec![42]);
+        assert_eq!(pool.stats().allocated, 64);
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! A minimal oneshot channel whose receiver is a future, so that it can be awaited in any async
+//! runtime.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+
+use parking_lot::Mutex;
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Mutex::new(Shared {
+        value: None,
+        closed: false,
+        waker: None,
+    }));
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+#[derive(Debug)]
+struct Shared<T> {
+    value: Option<T>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+#[derive(Debug)]
+pub struct Sender<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+#[derive(Debug)]
+pub struct Receiver<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+impl<T> Sender<T> {
+    pub fn send(self, value: T) {
+        self.shared.lock().value = Some(value);
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let waker = {
+            let mut shared = self.shared.lock();
+            shared.closed = true;
+            shared.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Future for Receiver<T> {
+    /// `None` if the sender is dropped without sending a value.
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut shared = self.shared.lock();
+        if shared.closed {
+            Poll::Ready(shared.value.take())
+        } else {
+            shared.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_and_receive() {
+        let (tx, rx) = channel();
+        let handle = std::thread::spawn(move || tx.send(42));
+        assert_eq!(futures::executor::block_on(rx), Some(42));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn sender_dropped() {
+        let (tx, rx) = channel::<()>();
+        drop(tx);
+        assert_eq!(futures::executor::block_on(rx), None);
+    }
+}
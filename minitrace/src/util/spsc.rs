@@ -1,5 +1,7 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::collections::VecDeque;
+
 use rtrb::Consumer;
 use rtrb::Producer;
 use rtrb::PushError;
@@ -10,7 +12,7 @@ pub fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
     (
         Sender {
             tx,
-            pending_messages: Vec::new(),
+            pending_messages: VecDeque::new(),
         },
         Receiver { rx },
     )
@@ -18,7 +20,8 @@ pub fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
 
 pub struct Sender<T> {
     tx: Producer<T>,
-    pending_messages: Vec<T>,
+    // The messages that did not fit into the channel, sent in order before the next message.
+    pending_messages: VecDeque<T>,
 }
 
 pub struct Receiver<T> {
@@ -31,29 +34,71 @@ pub struct ChannelFull;
 #[derive(Debug)]
 pub struct ChannelClosed;
 
+/// The outcome of [`Sender::send_or_buffer()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SendOutcome {
+    /// The number of pending messages moved into the channel before the message.
+    pub flushed: usize,
+    /// Whether the message was dropped because both the channel and the pending buffer are full.
+    pub dropped: bool,
+}
+
 impl<T> Sender<T> {
     pub fn send(&mut self, value: T) -> Result<(), ChannelFull> {
-        while let Some(value) = self.pending_messages.pop() {
-            if let Err(PushError::Full(value)) = self.tx.push(value) {
-                self.pending_messages.push(value);
-                return Err(ChannelFull);
-            }
+        self.flush_pending();
+        if !self.pending_messages.is_empty() {
+            return Err(ChannelFull);
         }
 
         self.tx.push(value).map_err(|_| ChannelFull)
     }
 
+    /// Sends the message, or keeps it pending if the channel is full and fewer than
+    /// `max_pending` messages are pending. The pending messages are sent before the next message.
+    pub fn send_or_buffer(&mut self, value: T, max_pending: usize) -> SendOutcome {
+        let flushed = self.flush_pending();
+        let value = if self.pending_messages.is_empty() {
+            match self.tx.push(value) {
+                Ok(()) => {
+                    return SendOutcome {
+                        flushed,
+                        dropped: false,
+                    };
+                }
+                Err(PushError::Full(value)) => value,
+            }
+        } else {
+            value
+        };
+
+        let dropped = self.pending_messages.len() >= max_pending;
+        if !dropped {
+            self.pending_messages.push_back(value);
+        }
+        SendOutcome { flushed, dropped }
+    }
+
     pub fn force_send(&mut self, value: T) {
-        while let Some(value) = self.pending_messages.pop() {
+        self.flush_pending();
+        if !self.pending_messages.is_empty() {
+            self.pending_messages.push_back(value);
+        } else if let Err(PushError::Full(value)) = self.tx.push(value) {
+            self.pending_messages.push_back(value);
+        }
+    }
+
+    /// Moves the pending messages into the channel in order until it is full, and returns the
+    /// number of messages moved.
+    fn flush_pending(&mut self) -> usize {
+        let mut flushed = 0;
+        while let Some(value) = self.pending_messages.pop_front() {
             if let Err(PushError::Full(value)) = self.tx.push(value) {
-                self.pending_messages.push(value);
+                self.pending_messages.push_front(value);
                 break;
             }
+            flushed += 1;
         }
-
-        if let Err(PushError::Full(value)) = self.tx.push(value) {
-            self.pending_messages.push(value);
-        }
+        flushed
     }
 }
 
@@ -66,3 +111,45 @@ impl<T> Receiver<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_or_buffer() {
+        let (mut tx, mut rx) = bounded(4);
+        let mut received = Vec::new();
+        let mut recv_all = |rx: &mut Receiver<i32>| {
+            while let Some(i) = rx.try_recv().unwrap() {
+                received.push(i);
+            }
+        };
+
+        // A burst of messages exceeding the capacity of the channel.
+        for i in 0..12 {
+            assert_eq!(tx.send_or_buffer(i, 8), SendOutcome {
+                flushed: 0,
+                dropped: false,
+            });
+        }
+        assert_eq!(tx.send_or_buffer(12, 8), SendOutcome {
+            flushed: 0,
+            dropped: true,
+        });
+        assert!(tx.send(13).is_err());
+        recv_all(&mut rx);
+
+        assert_eq!(tx.send_or_buffer(14, 8), SendOutcome {
+            flushed: 4,
+            dropped: false,
+        });
+        recv_all(&mut rx);
+        tx.force_send(15);
+        recv_all(&mut rx);
+        tx.send(16).unwrap();
+        recv_all(&mut rx);
+
+        assert_eq!(received, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 14, 15, 16]);
+    }
+}
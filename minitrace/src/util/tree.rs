@@ -4,8 +4,10 @@
 
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::time::Duration;
 
 use crate::collector::SpanId;
 use crate::collector::SpanRecord;
@@ -261,3 +263,515 @@ pub fn tree_str_from_span_sets(span_sets: &[(SpanSet, CollectToken)]) -> String
 pub fn tree_str_from_span_records(span_records: Vec<SpanRecord>) -> String {
     format!("\n{}", Tree::from_span_records(span_records))
 }
+
+/// Renders span records as a tree annotated with the duration of each span and its share of the
+/// root span's duration.
+///
+/// Children are ordered by their begin time. A span that begins before its parent, which happens
+/// when the clocks of the reporting threads are skewed, is shown as `[?ms]`.
+pub fn tree_str_with_timing(span_records: &[SpanRecord]) -> String {
+    tree_str_with_timing_by(span_records, &|_, line| line)
+}
+
+/// Like [`tree_str_with_timing()`], but passes each rendered line, without the trailing newline,
+/// through `paint` together with the span it describes.
+pub(crate) fn tree_str_with_timing_by(
+    span_records: &[SpanRecord],
+    paint: &dyn Fn(&SpanRecord, String) -> String,
+) -> String {
+    let span_ids = span_records
+        .iter()
+        .map(|span| span.span_id)
+        .collect::<HashSet<_>>();
+    let mut children = HashMap::<SpanId, Vec<&SpanRecord>>::new();
+    let mut roots = Vec::new();
+    for span in span_records {
+        if span_ids.contains(&span.parent_id) && span.parent_id != span.span_id {
+            children.entry(span.parent_id).or_default().push(span);
+        } else {
+            roots.push(span);
+        }
+    }
+    for spans in children.values_mut() {
+        spans.sort_by(|a, b| (a.begin_time_unix_ns, &a.name).cmp(&(b.begin_time_unix_ns, &b.name)));
+    }
+    roots.sort_by(|a, b| (a.begin_time_unix_ns, &a.name).cmp(&(b.begin_time_unix_ns, &b.name)));
+
+    let mut output = String::new();
+    for root in roots {
+        output.push('\n');
+        write_timing_line(&mut output, "", root, None, root.duration_ns, paint);
+        write_timing_children(&mut output, "", root, &children, root.duration_ns, paint);
+    }
+    output
+}
+
+fn write_timing_children(
+    output: &mut String,
+    prefix: &str,
+    parent: &SpanRecord,
+    children: &HashMap<SpanId, Vec<&SpanRecord>>,
+    root_duration_ns: u64,
+    paint: &dyn Fn(&SpanRecord, String) -> String,
+) {
+    let Some(spans) = children.get(&parent.span_id) else {
+        return;
+    };
+    for (i, span) in spans.iter().enumerate() {
+        let is_last = i + 1 == spans.len();
+        let (branch, indent) = if is_last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        write_timing_line(
+            output,
+            &format!("{prefix}{branch}"),
+            span,
+            Some(parent),
+            root_duration_ns,
+            paint,
+        );
+        write_timing_children(
+            output,
+            &format!("{prefix}{indent}"),
+            span,
+            children,
+            root_duration_ns,
+            paint,
+        );
+    }
+}
+
+fn write_timing_line(
+    output: &mut String,
+    prefix: &str,
+    span: &SpanRecord,
+    parent: Option<&SpanRecord>,
+    root_duration_ns: u64,
+    paint: &dyn Fn(&SpanRecord, String) -> String,
+) {
+    use std::fmt::Write;
+
+    let mut line = String::new();
+    let skewed = parent.is_some_and(|parent| span.begin_time_unix_ns < parent.begin_time_unix_ns);
+    if skewed {
+        write!(line, "{prefix}{} [?ms]", span.name).unwrap();
+    } else {
+        let duration_ms = (span.duration_ns as f64 / 1_000.0).round() / 1_000.0;
+        write!(line, "{prefix}{} [{duration_ms}ms]", span.name).unwrap();
+        if root_duration_ns > 0 {
+            let percentage = span.duration_ns as f64 * 100.0 / root_duration_ns as f64;
+            write!(line, " {percentage:.1}%").unwrap();
+        }
+    }
+    output.push_str(&paint(span, line));
+    output.push('\n');
+}
+
+/// A [`SpanRecord`] together with its self duration, see [`self_duration()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpanRecordWithSelfDuration {
+    pub record: SpanRecord,
+    /// The time in nanoseconds spent in the span itself, excluding the time covered by its
+    /// direct children.
+    pub self_duration_ns: u64,
+}
+
+impl SpanRecordWithSelfDuration {
+    /// Pairs each span record with its self duration, computed with [`self_duration()`].
+    pub fn from_records(span_records: Vec<SpanRecord>) -> Vec<Self> {
+        let self_durations = self_duration(&span_records);
+        span_records
+            .into_iter()
+            .map(|record| {
+                let self_duration_ns = self_durations[&record.span_id].as_nanos() as u64;
+                SpanRecordWithSelfDuration {
+                    record,
+                    self_duration_ns,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Computes the exclusive duration of each span, that is its duration minus the time covered by
+/// its direct children.
+///
+/// The time covered by the children is the union of their intervals, so that concurrent children
+/// are not subtracted twice. The intervals are clipped to the interval of the parent, so a child
+/// ending after its parent, which happens when the clocks of the reporting threads are skewed,
+/// never makes the self duration of the parent negative.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use minitrace::collector::SpanId;
+/// use minitrace::collector::SpanRecord;
+/// use minitrace::util::tree::self_duration;
+///
+/// let root = SpanRecord {
+///     span_id: SpanId(1),
+///     duration_ns: 100,
+///     ..SpanRecord::default()
+/// };
+/// let child = SpanRecord {
+///     span_id: SpanId(2),
+///     parent_id: SpanId(1),
+///     begin_time_unix_ns: 10,
+///     duration_ns: 30,
+///     ..SpanRecord::default()
+/// };
+///
+/// let self_durations = self_duration(&[root, child]);
+/// assert_eq!(self_durations[&SpanId(1)], Duration::from_nanos(70));
+/// assert_eq!(self_durations[&SpanId(2)], Duration::from_nanos(30));
+/// ```
+pub fn self_duration(span_records: &[SpanRecord]) -> HashMap<SpanId, Duration> {
+    let spans = span_records
+        .iter()
+        .map(|span| (span.span_id, span))
+        .collect::<HashMap<_, _>>();
+    let mut children = HashMap::<SpanId, Vec<(u64, u64)>>::new();
+    for span in span_records {
+        if let Some(parent) = spans.get(&span.parent_id) {
+            if span.parent_id == span.span_id {
+                continue;
+            }
+            let parent_end = parent.begin_time_unix_ns.saturating_add(parent.duration_ns);
+            let begin = span.begin_time_unix_ns.max(parent.begin_time_unix_ns);
+            let end = span
+                .begin_time_unix_ns
+                .saturating_add(span.duration_ns)
+                .min(parent_end);
+            if begin < end {
+                children
+                    .entry(span.parent_id)
+                    .or_default()
+                    .push((begin, end));
+            }
+        }
+    }
+
+    span_records
+        .iter()
+        .map(|span| {
+            let covered_ns = children
+                .get_mut(&span.span_id)
+                .map_or(0, |intervals| union_len(intervals));
+            let self_duration_ns = span.duration_ns.saturating_sub(covered_ns);
+            (span.span_id, Duration::from_nanos(self_duration_ns))
+        })
+        .collect()
+}
+
+/// Returns the total length of the union of the half-open intervals.
+fn union_len(intervals: &mut [(u64, u64)]) -> u64 {
+    intervals.sort_unstable();
+
+    let mut len = 0;
+    let mut current: Option<(u64, u64)> = None;
+    for &(begin, end) in intervals.iter() {
+        match &mut current {
+            Some((_, current_end)) if begin <= *current_end => {
+                *current_end = (*current_end).max(end);
+            }
+            _ => {
+                if let Some((current_begin, current_end)) = current {
+                    len += current_end - current_begin;
+                }
+                current = Some((begin, end));
+            }
+        }
+    }
+    if let Some((current_begin, current_end)) = current {
+        len += current_end - current_begin;
+    }
+    len
+}
+
+/// Statistics of the durations of a collection of spans, see [`timing_stats()`].
+///
+/// The percentiles are computed with the nearest-rank method, so they are always the duration of
+/// one of the spans.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimingStats {
+    /// The time in nanoseconds from the earliest begin to the latest end of the spans.
+    pub total_duration_ns: u64,
+    pub p50_ns: u64,
+    pub p95_ns: u64,
+    pub p99_ns: u64,
+    pub max_ns: u64,
+    pub span_count: usize,
+}
+
+impl TimingStats {
+    /// Returns the `n` spans with the longest `duration_ns`, the longest first.
+    ///
+    /// Spans with the same duration are kept in the order of `span_records`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::SpanRecord;
+    /// use minitrace::util::tree::TimingStats;
+    ///
+    /// let span_records = [10, 30, 20].map(|duration_ns| SpanRecord {
+    ///     duration_ns,
+    ///     ..SpanRecord::default()
+    /// });
+    ///
+    /// let slowest = TimingStats::slowest_spans(&span_records, 2);
+    /// assert_eq!(slowest[0].duration_ns, 30);
+    /// assert_eq!(slowest[1].duration_ns, 20);
+    /// ```
+    pub fn slowest_spans(span_records: &[SpanRecord], n: usize) -> Vec<&SpanRecord> {
+        let mut spans = span_records.iter().collect::<Vec<_>>();
+        spans.sort_by_key(|span| std::cmp::Reverse(span.duration_ns));
+        spans.truncate(n);
+        spans
+    }
+}
+
+/// Computes the [`TimingStats`] of the `duration_ns` of all span records.
+///
+/// All fields are zero if `span_records` is empty.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::SpanRecord;
+/// use minitrace::util::tree::timing_stats;
+///
+/// let span_records = (1..=100)
+///     .map(|duration_ns| SpanRecord {
+///         duration_ns,
+///         ..SpanRecord::default()
+///     })
+///     .collect::<Vec<_>>();
+///
+/// let stats = timing_stats(&span_records);
+/// assert_eq!(stats.p50_ns, 50);
+/// assert_eq!(stats.p95_ns, 95);
+/// assert_eq!(stats.max_ns, 100);
+/// assert_eq!(stats.span_count, 100);
+/// ```
+pub fn timing_stats(span_records: &[SpanRecord]) -> TimingStats {
+    timing_stats_of(span_records.iter())
+}
+
+/// Computes the [`TimingStats`] of the span records of each span name, for example to compare
+/// the latency of the different operations of a trace.
+pub fn timing_stats_by_name(
+    span_records: &[SpanRecord],
+) -> HashMap<Cow<'static, str>, TimingStats> {
+    let mut by_name = HashMap::<Cow<'static, str>, Vec<&SpanRecord>>::new();
+    for span in span_records {
+        by_name.entry(span.name.clone()).or_default().push(span);
+    }
+    by_name
+        .into_iter()
+        .map(|(name, spans)| (name, timing_stats_of(spans.into_iter())))
+        .collect()
+}
+
+fn timing_stats_of<'a>(span_records: impl Iterator<Item = &'a SpanRecord>) -> TimingStats {
+    let mut durations = Vec::new();
+    let mut begin = u64::MAX;
+    let mut end = 0;
+    for span in span_records {
+        durations.push(span.duration_ns);
+        begin = begin.min(span.begin_time_unix_ns);
+        end = end.max(span.begin_time_unix_ns.saturating_add(span.duration_ns));
+    }
+    durations.sort_unstable();
+
+    TimingStats {
+        total_duration_ns: end.saturating_sub(begin),
+        p50_ns: percentile(&durations, 50),
+        p95_ns: percentile(&durations, 95),
+        p99_ns: percentile(&durations, 99),
+        max_ns: durations.last().copied().unwrap_or(0),
+        span_count: durations.len(),
+    }
+}
+
+/// Returns the nearest-rank `percent`-th percentile of the sorted durations.
+fn percentile(sorted: &[u64], percent: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::TraceId;
+
+    fn span_record(
+        span_id: u64,
+        parent_id: u64,
+        name: &'static str,
+        begin_ms: u64,
+        duration_ms: u64,
+    ) -> SpanRecord {
+        SpanRecord {
+            trace_id: TraceId(1),
+            span_id: SpanId(span_id),
+            parent_id: SpanId(parent_id),
+            begin_time_unix_ns: 1_000_000_000 + begin_ms * 1_000_000,
+            duration_ns: duration_ms * 1_000_000,
+            name: name.into(),
+            ..SpanRecord::default()
+        }
+    }
+
+    #[test]
+    fn tree_with_timing() {
+        let span_records = [
+            span_record(4, 2, "query", 15, 20),
+            span_record(3, 1, "encode", 60, 40),
+            span_record(1, 0, "root", 0, 100),
+            span_record(2, 1, "decode", 10, 50),
+            span_record(5, 2, "skewed", 5, 3),
+        ];
+
+        assert_eq!(
+            tree_str_with_timing(&span_records),
+            r#"
+root [100ms] 100.0%
+├── decode [50ms] 50.0%
+│   ├── skewed [?ms]
+│   └── query [20ms] 20.0%
+└── encode [40ms] 40.0%
+"#
+        );
+    }
+
+    #[test]
+    fn tree_with_timing_sub_millisecond() {
+        let mut root = span_record(1, 0, "root", 0, 0);
+        root.duration_ns = 1_500_000;
+        let mut child = span_record(2, 1, "child", 0, 0);
+        child.duration_ns = 500_000;
+
+        assert_eq!(
+            tree_str_with_timing(&[root, child]),
+            r#"
+root [1.5ms] 100.0%
+└── child [0.5ms] 33.3%
+"#
+        );
+    }
+
+    fn self_duration_ms(span_records: &[SpanRecord]) -> HashMap<u64, u64> {
+        self_duration(span_records)
+            .into_iter()
+            .map(|(span_id, duration)| (span_id.0, duration.as_millis() as u64))
+            .collect()
+    }
+
+    #[test]
+    fn self_duration_of_tree() {
+        let span_records = [
+            span_record(1, 0, "root", 0, 100),
+            span_record(2, 1, "decode", 10, 50),
+            span_record(3, 1, "encode", 60, 20),
+            span_record(4, 2, "query", 15, 20),
+            span_record(5, 2, "fetch", 30, 10),
+        ];
+
+        assert_eq!(
+            self_duration_ms(&span_records),
+            HashMap::from([(1, 30), (2, 25), (3, 20), (4, 20), (5, 10)])
+        );
+    }
+
+    #[test]
+    fn self_duration_of_overlapping_children() {
+        let span_records = [
+            span_record(1, 0, "root", 0, 100),
+            span_record(2, 1, "a", 10, 40),
+            span_record(3, 1, "b", 30, 40),
+            span_record(4, 1, "c", 20, 10),
+        ];
+
+        assert_eq!(
+            self_duration_ms(&span_records),
+            HashMap::from([(1, 40), (2, 40), (3, 40), (4, 10)])
+        );
+    }
+
+    #[test]
+    fn self_duration_with_clock_skew() {
+        let span_records = [
+            span_record(1, 0, "root", 10, 50),
+            span_record(2, 1, "early", 0, 20),
+            span_record(3, 1, "late", 50, 30),
+            span_record(4, 0, "longer-child-root", 0, 10),
+            span_record(5, 4, "longer-child", 0, 20),
+        ];
+
+        assert_eq!(
+            self_duration_ms(&span_records),
+            HashMap::from([(1, 30), (2, 20), (3, 30), (4, 0), (5, 20)])
+        );
+
+        let with_self_duration = SpanRecordWithSelfDuration::from_records(span_records.to_vec());
+        assert_eq!(with_self_duration[0].record, span_records[0]);
+        assert_eq!(with_self_duration[0].self_duration_ns, 30_000_000);
+    }
+
+    #[test]
+    fn timing_stats_percentiles() {
+        let span_records = (1..=1000)
+            .map(|i| span_record(i, 0, if i % 2 == 0 { "even" } else { "odd" }, i, i))
+            .collect::<Vec<_>>();
+
+        let stats = timing_stats(&span_records);
+        assert_eq!(stats, TimingStats {
+            total_duration_ns: 1_999_000_000,
+            p50_ns: 500_000_000,
+            p95_ns: 950_000_000,
+            p99_ns: 990_000_000,
+            max_ns: 1_000_000_000,
+            span_count: 1000,
+        });
+
+        let by_name = timing_stats_by_name(&span_records);
+        assert_eq!(by_name.len(), 2);
+        assert_eq!(by_name["even"].p50_ns, 500_000_000);
+        assert_eq!(by_name["even"].p99_ns, 990_000_000);
+        assert_eq!(by_name["even"].span_count, 500);
+        assert_eq!(by_name["odd"].p50_ns, 499_000_000);
+        assert_eq!(by_name["odd"].max_ns, 999_000_000);
+        assert_eq!(by_name["odd"].total_duration_ns, 1_997_000_000);
+    }
+
+    #[test]
+    fn timing_stats_of_few_spans() {
+        assert_eq!(timing_stats(&[]), TimingStats::default());
+
+        let span_records = [
+            span_record(1, 0, "root", 0, 100),
+            span_record(2, 1, "a", 10, 20),
+            span_record(3, 1, "b", 90, 30),
+        ];
+        assert_eq!(timing_stats(&span_records), TimingStats {
+            total_duration_ns: 120_000_000,
+            p50_ns: 30_000_000,
+            p95_ns: 100_000_000,
+            p99_ns: 100_000_000,
+            max_ns: 100_000_000,
+            span_count: 3,
+        });
+
+        let slowest = TimingStats::slowest_spans(&span_records, 2);
+        assert_eq!(slowest, [&span_records[0], &span_records[2]]);
+        assert_eq!(TimingStats::slowest_spans(&span_records, 5).len(), 3);
+    }
+}
@@ -1,10 +1,15 @@
 // Copyright 2021 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 use futures::executor::block_on;
 use minitrace::collector::Config;
 use minitrace::collector::ConsoleReporter;
+use minitrace::collector::Reporter;
 use minitrace::collector::TestReporter;
 use minitrace::local::LocalCollector;
 use minitrace::prelude::*;
@@ -39,8 +44,8 @@ fn four_spans() {
 #[test]
 #[serial]
 fn single_thread_single_span() {
-    let (reporter, collected_spans) = TestReporter::new();
-    minitrace::set_reporter(reporter, Config::default());
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
 
     {
         let root = Span::root("root", SpanContext::random());
@@ -58,17 +63,14 @@ root []
     rec-span []
         rec-span []
 "#;
-    assert_eq!(
-        tree_str_from_span_records(collected_spans.lock().clone()),
-        expected_graph
-    );
+    reporter.assert_tree(expected_graph);
 }
 
 #[test]
 #[serial]
 fn single_thread_multiple_spans() {
     let (reporter, collected_spans) = TestReporter::new();
-    minitrace::set_reporter(reporter, Config::default());
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
 
     {
         let root1 = Span::root("root1", SpanContext::new(TraceId(12), SpanId::default()));
@@ -147,8 +149,8 @@ root3 []
 #[test]
 #[serial]
 fn multiple_threads_single_span() {
-    let (reporter, collected_spans) = TestReporter::new();
-    minitrace::set_reporter(reporter, Config::default());
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
 
     crossbeam::scope(|scope| {
         let root = Span::root("root", SpanContext::random());
@@ -200,17 +202,14 @@ root []
     rec-span []
         rec-span []
 "#;
-    assert_eq!(
-        tree_str_from_span_records(collected_spans.lock().clone()),
-        expected_graph
-    );
+    reporter.assert_tree(expected_graph);
 }
 
 #[test]
 #[serial]
 fn multiple_threads_multiple_spans() {
     let (reporter, collected_spans) = TestReporter::new();
-    minitrace::set_reporter(reporter, Config::default());
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
 
     crossbeam::scope(|scope| {
         let root1 = Span::root("root1", SpanContext::new(TraceId(12), SpanId::default()));
@@ -337,7 +336,7 @@ root2 []
 #[serial]
 fn multiple_spans_without_local_spans() {
     let (reporter, collected_spans) = TestReporter::new();
-    minitrace::set_reporter(reporter, Config::default());
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
 
     {
         let root1 = Span::root("root1", SpanContext::new(TraceId(12), SpanId::default()));
@@ -433,8 +432,8 @@ fn test_macro() {
             .await;
     }
 
-    let (reporter, collected_spans) = TestReporter::new();
-    minitrace::set_reporter(reporter, Config::default());
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
 
     {
         let root = Span::root("root", SpanContext::random());
@@ -481,10 +480,7 @@ root []
         sleep []
         work-inner []
 "#;
-    assert_eq!(
-        tree_str_from_span_records(collected_spans.lock().clone()),
-        expected_graph
-    );
+    reporter.assert_tree(expected_graph);
 }
 
 #[test]
@@ -510,8 +506,8 @@ fn macro_example() {
         futures_timer::Delay::new(Duration::from_millis(i)).await;
     }
 
-    let (reporter, collected_spans) = TestReporter::new();
-    minitrace::set_reporter(reporter, Config::default());
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
 
     {
         let root = Span::root("root", SpanContext::random());
@@ -531,17 +527,14 @@ root []
     lib::macro_example::{{closure}}::do_something []
     lib::macro_example::{{closure}}::do_something_async []
 "#;
-    assert_eq!(
-        tree_str_from_span_records(collected_spans.lock().clone()),
-        expected_graph
-    );
+    reporter.assert_tree(expected_graph);
 }
 
 #[test]
 #[serial]
 fn multiple_local_parent() {
-    let (reporter, collected_spans) = TestReporter::new();
-    minitrace::set_reporter(reporter, Config::default());
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
 
     {
         let root = Span::root("root", SpanContext::random());
@@ -564,17 +557,14 @@ root []
             span3 []
         span4 []
 "#;
-    assert_eq!(
-        tree_str_from_span_records(collected_spans.lock().clone()),
-        expected_graph
-    );
+    reporter.assert_tree(expected_graph);
 }
 
 #[test]
 #[serial]
 fn early_local_collect() {
-    let (reporter, collected_spans) = TestReporter::new();
-    minitrace::set_reporter(reporter, Config::default());
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
 
     {
         let local_collector = LocalCollector::start();
@@ -594,10 +584,121 @@ root []
     span1 []
         span2 []
 "#;
-    assert_eq!(
-        tree_str_from_span_records(collected_spans.lock().clone()),
-        expected_graph
-    );
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn merge_local_spans() {
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let local_collector = LocalCollector::start();
+        let span = LocalSpan::enter_with_local_parent("parse");
+        drop(span);
+        let parse = local_collector.collect();
+
+        let local_collector = LocalCollector::start();
+        let span1 = LocalSpan::enter_with_local_parent("execute");
+        let span2 = LocalSpan::enter_with_local_parent("scan");
+        drop(span2);
+        drop(span1);
+        let execute = local_collector.collect();
+
+        let local_collector = LocalCollector::start();
+        let span = LocalSpan::enter_with_local_parent("fetch");
+        drop(span);
+        let fetch = local_collector.collect();
+
+        // Nest `fetch` under `execute`.
+        let execute_id = execute
+            .to_span_records(SpanContext::random())
+            .into_iter()
+            .find(|span| span.name == "execute")
+            .unwrap()
+            .span_id;
+        let local_spans = parse.merge(execute.merge(fetch.reparent(execute_id)));
+        assert_eq!(local_spans.len(), 4);
+
+        let root = Span::root("root", SpanContext::random());
+        root.push_child_spans(local_spans);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    execute []
+        fetch []
+        scan []
+    parse []
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_macro_max_depth() {
+    #[trace(short_name = true, max_depth = 3)]
+    fn factorial(n: u64) -> u64 {
+        if n <= 1 {
+            1
+        } else {
+            n * factorial(n - 1)
+        }
+    }
+
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        assert_eq!(factorial(20), 2432902008176640000);
+        assert_eq!(factorial(2), 2);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    factorial []
+        factorial []
+    factorial []
+        factorial []
+            factorial []
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_macro_name_expr() {
+    #[trace(name_expr = "format!(\"query:{}\", table)")]
+    fn query(table: &str) {}
+
+    #[trace(name_expr = "method.to_string()")]
+    async fn handle(method: String) {}
+
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        query("users");
+        block_on(handle("GET".to_string()));
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    GET []
+    query:users []
+"#;
+    reporter.assert_tree(expected_graph);
 }
 
 #[test]
@@ -610,8 +711,11 @@ fn max_spans_per_trace() {
         }
     }
 
-    let (reporter, collected_spans) = TestReporter::new();
-    minitrace::set_reporter(reporter, Config::default().max_spans_per_trace(Some(5)));
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(
+        reporter.clone(),
+        Config::default().max_spans_per_trace(Some(5)),
+    );
 
     {
         let root = Span::root("root", SpanContext::random());
@@ -645,16 +749,13 @@ root []
         recursive []
             recursive []
 "#;
-    assert_eq!(
-        tree_str_from_span_records(collected_spans.lock().clone()),
-        expected_graph
-    );
+    reporter.assert_tree(expected_graph);
 }
 
 #[test]
 #[serial]
 fn test_elapsed() {
-    minitrace::set_reporter(ConsoleReporter, Config::default());
+    let _guard = minitrace::scoped_reporter(ConsoleReporter, Config::default());
 
     {
         let root = Span::root("root", SpanContext::random());
@@ -669,9 +770,142 @@ fn test_elapsed() {
 
 #[test]
 #[serial]
-fn test_add_property() {
+fn test_cancel_if() {
     let (reporter, collected_spans) = TestReporter::new();
-    minitrace::set_reporter(reporter, Config::default());
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let mut root = Span::root("fast", SpanContext::random());
+        let _child = Span::enter_with_parent("child", &root);
+        root.cancel_if_faster_than(Duration::from_secs(3600));
+    }
+
+    {
+        let mut root = Span::root("slow", SpanContext::random());
+        std::thread::sleep(Duration::from_millis(10));
+        root.cancel_if_faster_than(Duration::from_millis(1));
+    }
+
+    {
+        let mut root = Span::root("cancelled", SpanContext::random());
+        root.cancel_if(|elapsed| elapsed < Duration::from_secs(3600));
+    }
+
+    {
+        let mut noop = Span::noop();
+        noop.cancel_if(|_| panic!("the predicate should not be called for a noop span"));
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+slow []
+"#;
+    reporter.assert_tree(expected_graph);
+    assert_eq!(collected_spans.lock().len(), 1);
+}
+
+#[test]
+#[serial]
+fn test_root_with_resource() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+    minitrace::set_resource(HashMap::from([("service.name".into(), "default".into())]));
+
+    let resource = Arc::new(HashMap::from([("service.name".into(), "billing".into())]));
+    {
+        let root = Span::root_with_resource("billing", SpanContext::random(), resource.clone());
+        let _child = Span::enter_with_parent("child", &root);
+    }
+    {
+        let _root = Span::root("other", SpanContext::random());
+    }
+
+    minitrace::flush();
+    minitrace::set_resource(HashMap::new());
+
+    let service_names = collected_spans
+        .lock()
+        .iter()
+        .map(|span| {
+            let resource = span.resource.as_ref().unwrap();
+            (span.name.clone(), resource["service.name"].clone())
+        })
+        .collect::<HashMap<_, _>>();
+    assert_eq!(
+        service_names,
+        HashMap::from([
+            ("billing".into(), "billing".into()),
+            ("child".into(), "billing".into()),
+            ("other".into(), "default".into()),
+        ])
+    );
+
+    {
+        let _root = Span::root("unset", SpanContext::random());
+    }
+    minitrace::flush();
+    assert!(collected_spans.lock()[3].resource.is_none());
+}
+
+#[test]
+#[serial]
+fn test_root_with_sampling() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root_with_sampling(
+            "record-only",
+            SpanContext::random(),
+            SamplingDecision::RecordOnly,
+        );
+        let _guard = root.set_local_parent();
+        let _child = LocalSpan::enter_with_local_parent("record-only-child");
+        let context = SpanContext::current_local_parent().unwrap();
+        assert!(!context.sampled());
+        assert!(context.encode_w3c_traceparent().ends_with("-00"));
+    }
+    {
+        let root =
+            Span::root_with_sampling("dropped", SpanContext::random(), SamplingDecision::Drop);
+        let _child = Span::enter_with_parent("dropped-child", &root);
+    }
+    {
+        let parent = SpanContext::random().with_sampled(false);
+        let _root = Span::root_with_sampling("sampled", parent, SamplingDecision::RecordAndSample);
+    }
+    {
+        let parent = SpanContext::decode_w3c_traceparent(
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00",
+        )
+        .unwrap();
+        let _root = Span::root("inherited", parent);
+    }
+
+    minitrace::flush();
+
+    let sampled = collected_spans
+        .lock()
+        .iter()
+        .map(|span| (span.name.clone(), span.sampled))
+        .collect::<HashMap<_, _>>();
+    assert_eq!(
+        sampled,
+        HashMap::from([
+            ("record-only".into(), false),
+            ("record-only-child".into(), false),
+            ("sampled".into(), true),
+            ("inherited".into(), false),
+        ])
+    );
+}
+
+#[test]
+#[serial]
+fn test_add_property() {
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
 
     {
         let root = Span::root("root", SpanContext::random());
@@ -689,74 +923,1927 @@ fn test_add_property() {
 root []
     span [("k1", "v1"), ("k2", "v2"), ("k3", "v3")]
 "#;
-    assert_eq!(
-        tree_str_from_span_records(collected_spans.lock().clone()),
-        expected_graph
-    );
+    reporter.assert_tree(expected_graph);
 }
 
 #[test]
 #[serial]
-fn test_macro_properties() {
-    #[allow(clippy::drop_non_drop)]
-    #[trace(short_name = true, properties = { "k1": "v1", "a": "argument a is {a:?}", "b": "{b:?}", "escaped1": "{c:?}{{}}", "escaped2": "{{ \"a\": \"b\"}}" })]
-    fn foo(a: i64, b: &Bar, c: Bar) {
-        drop(c);
+fn test_span_add_property() {
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let mut root = Span::root("root", SpanContext::random()).with_property(|| ("k1", "v1"));
+        let mut child = Span::enter_with_parent("child", &root);
+        {
+            let _g = root.set_local_parent();
+            let _span = LocalSpan::enter_with_local_parent("local");
+        }
+
+        root.add_property(|| ("k2", "v2"))
+            .add_properties(|| [("k3", "v3"), ("k4", "v4")]);
+        child.add_property(|| ("k5", "v5"));
     }
 
-    #[allow(clippy::drop_non_drop)]
-    #[trace(short_name = true, properties = { "k1": "v1", "a": "argument a is {a:?}", "b": "{b:?}", "escaped1": "{c:?}{{}}", "escaped2": "{{ \"a\": \"b\"}}" })]
-    async fn foo_async(a: i64, b: &Bar, c: Bar) {
-        drop(c);
+    minitrace::flush();
+
+    let expected_graph = r#"
+root [("k1", "v1"), ("k2", "v2"), ("k3", "v3"), ("k4", "v4")]
+    child [("k5", "v5")]
+    local []
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_span_links() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    let upstream1 = Span::root("upstream1", SpanContext::random());
+    let upstream2 = Span::root("upstream2", SpanContext::random());
+    let context1 = SpanContext::from_span(&upstream1).unwrap();
+    let context2 = SpanContext::from_span(&upstream2).unwrap();
+    drop(upstream1);
+    drop(upstream2);
+
+    {
+        let mut batch = Span::root("batch", SpanContext::random());
+        batch.add_link(context1.clone());
+
+        let _g = batch.set_local_parent();
+        let _span = LocalSpan::enter_with_local_parent("local");
+        LocalSpan::add_link(context2.clone());
     }
 
-    #[trace(short_name = true, properties = {})]
-    fn bar() {}
+    minitrace::flush();
 
-    #[trace(short_name = true, properties = {})]
-    async fn bar_async() {}
+    reporter.assert_span_count(4);
+    reporter.assert_contains_span("local");
 
-    #[derive(Debug)]
-    struct Bar;
+    let spans = collected_spans.lock().clone();
+    let links = |name| {
+        spans
+            .iter()
+            .find(|span| span.name == name)
+            .unwrap()
+            .links
+            .clone()
+    };
+    assert_eq!(links("batch"), vec![(context1.trace_id, context1.span_id)]);
+    assert_eq!(links("local"), vec![(context2.trace_id, context2.span_id)]);
+    assert!(links("upstream1").is_empty());
+}
 
+#[test]
+#[serial]
+fn test_span_with_begin_time() {
     let (reporter, collected_spans) = TestReporter::new();
-    minitrace::set_reporter(reporter, Config::default());
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
 
+    let root_begin_time = SystemTime::now() - Duration::from_secs(10);
+    let local_begin_time = SystemTime::now() - Duration::from_secs(5);
     {
-        let root = Span::root("root", SpanContext::random());
+        let root = Span::root_at("root", SpanContext::random(), root_begin_time);
+        let _child = Span::enter_with_parent("future", &root)
+            .with_begin_time(SystemTime::now() + Duration::from_secs(5));
+
         let _g = root.set_local_parent();
-        foo(1, &Bar, Bar);
-        bar();
+        let _span = LocalSpan::enter_with_local_parent("local").with_begin_time(local_begin_time);
+    }
 
-        let runtime = Builder::new_multi_thread()
-            .worker_threads(4)
-            .enable_all()
-            .build()
-            .unwrap();
+    minitrace::flush();
 
-        block_on(
-            runtime.spawn(
-                async {
-                    foo_async(1, &Bar, Bar).await;
-                    bar_async().await;
-                }
-                .in_span(root),
-            ),
-        )
-        .unwrap();
+    let spans = collected_spans.lock().clone();
+    let span = |name| spans.iter().find(|span| span.name == name).unwrap();
+    let unix_ns = |time: SystemTime| time.duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64;
+    let tolerance_ns = Duration::from_millis(500).as_nanos() as u64;
+
+    let root = span("root");
+    assert!(root.begin_time_unix_ns.abs_diff(unix_ns(root_begin_time)) < tolerance_ns);
+    assert!(root.duration_ns >= Duration::from_secs(9).as_nanos() as u64);
+
+    let local = span("local");
+    assert!(local.begin_time_unix_ns.abs_diff(unix_ns(local_begin_time)) < tolerance_ns);
+    assert!(local.duration_ns >= Duration::from_secs(4).as_nanos() as u64);
+
+    assert_eq!(span("future").duration_ns, 0);
+}
+
+#[test]
+#[serial]
+fn test_current_local_parent() {
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    assert!(SpanContext::current_local_parent().is_none());
+    assert_eq!(SpanContext::current_span_id(), None);
+    assert_eq!(SpanContext::current_trace_id(), None);
+
+    let parent = SpanContext::new(TraceId(12), SpanId(34)).with_tracestate("rojo=1");
+    let root = Span::root("root", parent);
+    let root_context = SpanContext::from_span(&root).unwrap();
+    assert_eq!(root_context.trace_id, TraceId(12));
+    assert_eq!(root_context.tracestate(), Some("rojo=1"));
+
+    {
+        let _g = root.set_local_parent();
+        let context = SpanContext::current_local_parent().unwrap();
+        assert_eq!(context.trace_id, TraceId(12));
+        assert_eq!(context.span_id, root_context.span_id);
+        assert_eq!(context.tracestate(), Some("rojo=1"));
+        assert_eq!(SpanContext::current_span_id(), Some(root_context.span_id));
+        assert_eq!(SpanContext::current_trace_id(), Some(TraceId(12)));
+
+        let _local = LocalSpan::enter_with_local_parent("local");
+        let local_span_id = SpanContext::current_span_id().unwrap();
+        assert_ne!(local_span_id, root_context.span_id);
+        assert_eq!(SpanContext::current_trace_id(), Some(TraceId(12)));
+
+        let child = Span::enter_with_local_parent("child");
+        let child_context = SpanContext::from_span(&child).unwrap();
+        assert_eq!(child_context.tracestate(), Some("rojo=1"));
+
+        let _g = child.set_local_parent();
+        let context = SpanContext::current_local_parent().unwrap();
+        assert_eq!(context.span_id, child_context.span_id);
+        assert_eq!(context.tracestate(), Some("rojo=1"));
+
+        let _local = LocalSpan::enter_with_local_parent("grandchild");
+        let grandchild_span_id = SpanContext::current_span_id().unwrap();
+        assert_ne!(grandchild_span_id, child_context.span_id);
+        assert_eq!(
+            SpanContext::current_local_parent().unwrap().span_id,
+            grandchild_span_id
+        );
+    }
+
+    assert_eq!(SpanContext::current_span_id(), None);
+    assert_eq!(SpanContext::current_trace_id(), None);
+}
+
+#[test]
+#[serial]
+fn test_record_error() {
+    #[derive(Debug)]
+    struct Error {
+        message: &'static str,
+        source: Option<Box<Error>>,
+    }
+
+    impl std::fmt::Display for Error {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(self.message)
+        }
+    }
+
+    impl std::error::Error for Error {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            self.source
+                .as_deref()
+                .map(|source| source as &(dyn std::error::Error + 'static))
+        }
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    let err = Error {
+        message: "request failed",
+        source: Some(Box::new(Error {
+            message: "connection reset",
+            source: Some(Box::new(Error {
+                message: "broken pipe",
+                source: None,
+            })),
+        })),
+    };
+    let leaf = err.source.as_ref().unwrap().source.as_ref().unwrap();
+
+    {
+        let root = Span::root("root", SpanContext::random()).with_error(&err);
+        let _g = root.set_local_parent();
+        let _span1 = LocalSpan::enter_with_local_parent("span1").with_error(&**leaf);
+        let _span2 = LocalSpan::enter_with_local_parent("span2");
+        LocalSpan::record_error(err.source.as_deref().unwrap());
     }
 
     minitrace::flush();
 
     let expected_graph = r#"
-root []
-    bar []
-    bar_async []
-    foo [("k1", "v1"), ("a", "argument a is 1"), ("b", "Bar"), ("escaped1", "Bar{}"), ("escaped2", "{ \"a\": \"b\"}")]
-    foo_async [("k1", "v1"), ("a", "argument a is 1"), ("b", "Bar"), ("escaped1", "Bar{}"), ("escaped2", "{ \"a\": \"b\"}")]
+root [("error", "true"), ("error.message", "request failed"), ("error.cause", "connection reset")]
+    span1 [("error", "true"), ("error.message", "broken pipe")]
+        span2 [("error", "true"), ("error.message", "connection reset"), ("error.cause", "broken pipe")]
 "#;
-    assert_eq!(
-        tree_str_from_span_records(collected_spans.lock().clone()),
-        expected_graph
-    );
+    reporter.assert_tree(expected_graph);
+
+    let status = |name: &str| {
+        collected_spans
+            .lock()
+            .iter()
+            .find(|span| span.name == name)
+            .unwrap()
+            .status
+            .clone()
+    };
+    assert_eq!(status("root"), SpanStatus::Error("request failed".into()));
+    assert_eq!(status("span1"), SpanStatus::Error("broken pipe".into()));
+    assert_eq!(status("span2"), SpanStatus::Error("connection reset".into()));
+}
+
+#[test]
+#[serial]
+fn test_timestamp_override() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random())
+            .with_timestamp_override(1_700_000_000_000_000_000, 42_000);
+        let _child = Span::enter_with_parent("child", &root).with_timestamp_override(0, 0);
+        let _g = root.set_local_parent();
+        let _span = LocalSpan::enter_with_local_parent("span");
+    }
+
+    minitrace::flush();
+
+    let collected_spans = collected_spans.lock();
+    let find = |name: &str| {
+        collected_spans
+            .iter()
+            .find(|span| span.name == name)
+            .unwrap()
+    };
+    assert_eq!(find("root").begin_time_unix_ns, 1_700_000_000_000_000_000);
+    assert_eq!(find("root").duration_ns, 42_000);
+    assert_eq!(find("child").begin_time_unix_ns, 0);
+    assert_eq!(find("child").duration_ns, 0);
+    assert_ne!(find("span").begin_time_unix_ns, 1_700_000_000_000_000_000);
+}
+
+#[test]
+#[serial]
+fn test_enter_with_parent_and_events() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let child = Span::enter_with_parent_and_events("child", &root, [
+            ("second", 1_700_000_000_002_000_000),
+            ("first", 1_700_000_000_001_000_000),
+            ("third", 1_700_000_000_003_000_000),
+        ]);
+        Event::add_to_parent("fourth", &child, || []);
+    }
+
+    minitrace::flush();
+
+    let collected_spans = collected_spans.lock();
+    let child = collected_spans
+        .iter()
+        .find(|span| span.name == "child")
+        .unwrap();
+    let events = child
+        .events
+        .iter()
+        .map(|event| event.name.as_ref())
+        .collect::<Vec<_>>();
+    assert_eq!(events, ["first", "second", "third", "fourth"]);
+    assert_eq!(child.events[0].timestamp_unix_ns, 1_700_000_000_001_000_000);
+    assert_eq!(child.events[1].timestamp_unix_ns, 1_700_000_000_002_000_000);
+    assert_eq!(child.events[2].timestamp_unix_ns, 1_700_000_000_003_000_000);
+    assert!(child.events[..3]
+        .iter()
+        .all(|event| event.properties.is_empty()));
+}
+
+#[test]
+#[serial]
+fn test_enter_with_local_parent_or_root() {
+    fn handle(trace_id: TraceId) -> bool {
+        let (span, is_root) = Span::enter_with_local_parent_or_root("handle", trace_id);
+        let _g = span.set_local_parent();
+        let _child = Span::enter_with_local_parent_or_noop("child");
+        is_root
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    assert!(handle(TraceId(42)));
+    minitrace::flush();
+
+    let expected_graph = r#"
+handle []
+    child []
+"#;
+    reporter.assert_tree(expected_graph);
+    reporter.assert_trace_id(TraceId(42));
+    collected_spans.lock().clear();
+
+    {
+        let root = Span::root("root", SpanContext::new(TraceId(7), SpanId::default()));
+        let _g = root.set_local_parent();
+        assert!(!handle(TraceId(42)));
+    }
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    handle []
+        child []
+"#;
+    reporter.assert_tree(expected_graph);
+    assert!(collected_spans
+        .lock()
+        .iter()
+        .all(|span| span.trace_id == TraceId(7)));
+
+    let child = Span::enter_with_local_parent_or_noop("child");
+    assert!(SpanContext::from_span(&child).is_none());
+}
+
+#[test]
+#[serial]
+fn test_enter_root_or_child() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let root = Span::enter_root_or_child("root", || TraceId(42));
+        let _g = root.set_local_parent();
+        let _child = Span::enter_root_or_child("child", || unreachable!());
+        let _random_child = Span::enter_root_or_child_random("random-child");
+    }
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    child []
+    random-child []
+"#;
+    reporter.assert_tree(expected_graph);
+    reporter.assert_trace_id(TraceId(42));
+    collected_spans.lock().clear();
+
+    {
+        let root = Span::enter_root_or_child_random("random-root");
+        let _g = root.set_local_parent();
+        let _child = Span::enter_root_or_child_random("child");
+    }
+    minitrace::flush();
+
+    let expected_graph = r#"
+random-root []
+    child []
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_local_span_event() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        let _span = LocalSpan::enter_with_local_parent("local");
+
+        Event::add_to_local_parent("event", || {
+            [("k1".into(), "v1".into()), ("k2".into(), "v2".into())]
+        });
+        LocalSpan::event("event")
+            .with_property(|| ("k1", "v1"))
+            .with_properties(|| [("k2", "v2")])
+            .record();
+        LocalSpan::event_now("event", || [("k1", "v1"), ("k2", "v2")]);
+    }
+
+    minitrace::flush();
+
+    let collected_spans = collected_spans.lock();
+    let local = collected_spans
+        .iter()
+        .find(|span| span.name == "local")
+        .unwrap();
+    let events = local
+        .events
+        .iter()
+        .map(|event| (&event.name, &event.properties, event.level))
+        .collect::<Vec<_>>();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0], events[1]);
+    assert_eq!(events[0], events[2]);
+}
+
+#[test]
+#[serial]
+fn test_weak_span() {
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    let weak = {
+        let root = Span::root("root", SpanContext::random());
+        let weak = root.downgrade();
+        let context = weak.context().unwrap();
+        let expected = SpanContext::from_span(&root).unwrap();
+        assert_eq!(context.trace_id, expected.trace_id);
+        assert_eq!(context.span_id, expected.span_id);
+
+        {
+            let parent = weak.upgrade().unwrap();
+            let _child = parent.enter_child("child");
+            let _g = parent.set_local_parent();
+            let _local_child = LocalSpan::enter_with_local_parent("local-child");
+        }
+        assert!(weak.upgrade().is_some());
+
+        weak
+    };
+
+    assert!(weak.upgrade().is_none());
+    assert!(weak.context().is_none());
+    assert!(Span::noop().downgrade().upgrade().is_none());
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    child []
+    local-child []
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_registered_channels_of_exited_threads() {
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    for _ in 0..1000 {
+        std::thread::spawn(|| {
+            let _root = Span::root("root", SpanContext::random());
+        })
+        .join()
+        .unwrap();
+    }
+
+    minitrace::flush();
+
+    reporter.assert_span_count(1000);
+    let num_threads = std::thread::available_parallelism().unwrap().get();
+    assert!(minitrace::collector::registered_channel_count() <= num_threads * 2);
+}
+
+#[test]
+#[serial]
+fn test_panic_hook() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+    minitrace::install_panic_hook();
+    minitrace::install_panic_hook();
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        let result = std::panic::catch_unwind(|| {
+            let _span = LocalSpan::enter_with_local_parent("child");
+            panic!("boom");
+        });
+        assert!(result.is_err());
+    }
+
+    minitrace::flush();
+
+    let collected_spans = collected_spans.lock();
+    assert!(collected_spans
+        .iter()
+        .find(|span| span.name == "root")
+        .unwrap()
+        .events
+        .is_empty());
+    let child = collected_spans
+        .iter()
+        .find(|span| span.name == "child")
+        .unwrap();
+    assert_eq!(child.events.len(), 1);
+    let event = &child.events[0];
+    assert_eq!(event.name, "panic");
+    assert_eq!(event.level, EventLevel::Error);
+    let properties = event.properties.iter().cloned().collect::<HashMap<_, _>>();
+    assert_eq!(properties["message"], "boom");
+    assert!(properties["location"].starts_with("minitrace/tests/lib.rs:"));
+    assert_eq!(properties["thread"], std::thread::current().name().unwrap());
+}
+
+#[test]
+#[serial]
+fn test_properties_from_struct() {
+    #[derive(minitrace::SpanFields)]
+    struct Request {
+        pub method: String,
+        pub path: String,
+        pub status: u16,
+        #[allow(dead_code)]
+        token: String,
+    }
+
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    let request = Request {
+        method: "GET".to_string(),
+        path: "/index.html".to_string(),
+        status: 200,
+        token: "secret".to_string(),
+    };
+
+    {
+        let root = Span::root("root", SpanContext::random()).with_properties_from_struct(&request);
+        let _g = root.set_local_parent();
+        let _span =
+            LocalSpan::enter_with_local_parent("span").with_properties_from_struct(&request);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root [("method", "GET"), ("path", "/index.html"), ("status", "200")]
+    span [("method", "GET"), ("path", "/index.html"), ("status", "200")]
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_root_with_tags() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    let tags = [
+        ("service.name", "minitrace"),
+        ("service.version", "0.6.7"),
+        ("region", "us-east-1"),
+        ("zone", "us-east-1a"),
+        ("hostname", "localhost"),
+        ("pid", "42"),
+        ("os", "linux"),
+        ("arch", "x86_64"),
+        ("env", "test"),
+        ("team", "tracing"),
+    ];
+    let map = std::collections::HashMap::from([("k1", "v1"), ("k2", "v2")]);
+
+    {
+        let _root = Span::root_with_tags("slice", SpanContext::random(), &tags);
+        let _root = Span::root_with_tags("map", SpanContext::random(), &map);
+    }
+
+    minitrace::flush();
+
+    let collected_spans = collected_spans.lock();
+    let find = |name: &str| {
+        let mut properties = collected_spans
+            .iter()
+            .find(|span| span.name == name)
+            .unwrap()
+            .properties
+            .clone();
+        properties.sort();
+        properties
+    };
+    let mut expected = tags.map(|(k, v)| (k.into(), v.into())).to_vec();
+    expected.sort();
+    assert_eq!(find("slice"), expected);
+    assert_eq!(find("map"), [
+        ("k1".into(), "v1".into()),
+        ("k2".into(), "v2".into())
+    ]);
+}
+
+#[test]
+#[serial]
+fn test_add_batch_to_local_parent() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        Event::add_to_local_parent("before", || []);
+        Event::add_batch_to_local_parent((0..3).map(|i| {
+            let properties = [("i".into(), i.to_string().into())];
+            (
+                format!("event-{i}"),
+                1_700_000_000_000_000_000 + i,
+                properties,
+            )
+        }));
+    }
+
+    minitrace::flush();
+
+    let collected_spans = collected_spans.lock();
+    assert_eq!(collected_spans.len(), 1);
+    let events = &collected_spans[0].events;
+    assert_eq!(events.len(), 4);
+    assert_eq!(events[0].name, "before");
+    for (i, event) in events[1..].iter().enumerate() {
+        assert_eq!(event.name, format!("event-{i}"));
+        assert_eq!(
+            event.timestamp_unix_ns,
+            1_700_000_000_000_000_000 + i as u64
+        );
+        assert_eq!(event.properties, [("i".into(), i.to_string().into())]);
+    }
+}
+
+#[test]
+#[serial]
+fn test_is_descendant_of() {
+    let _guard = minitrace::scoped_reporter(ConsoleReporter, Config::default());
+
+    let root1 = Span::root("root1", SpanContext::random());
+    let child1 = Span::enter_with_parent("child1", &root1);
+    let grandchild1 = Span::enter_with_parent("grandchild1", &child1);
+    let root2 = Span::root("root2", SpanContext::random());
+    let child2 = Span::enter_with_parent("child2", &root2);
+    let merged = Span::enter_with_parents("merged", [&child1, &child2]);
+    let local = {
+        let _g = child2.set_local_parent();
+        Span::enter_with_local_parent("local")
+    };
+
+    assert!(child1.is_descendant_of(&root1));
+    assert!(grandchild1.is_descendant_of(&root1));
+    assert!(grandchild1.is_descendant_of(&child1));
+    assert!(!root1.is_descendant_of(&root1));
+    assert!(!root1.is_descendant_of(&child1));
+
+    assert!(!child1.is_descendant_of(&root2));
+    assert!(!child2.is_descendant_of(&root1));
+    assert!(!root2.is_descendant_of(&root1));
+
+    assert!(merged.is_descendant_of(&root1));
+    assert!(merged.is_descendant_of(&root2));
+    assert!(!root1.is_descendant_of(&merged));
+    assert!(local.is_descendant_of(&root2));
+    assert!(!local.is_descendant_of(&root1));
+
+    assert!(!Span::noop().is_descendant_of(&root1));
+    assert!(!child1.is_descendant_of(&Span::noop()));
+}
+
+#[test]
+#[serial]
+fn reporter_start_and_stop() {
+    use std::sync::Arc;
+
+    use minitrace::collector::Reporter;
+    use parking_lot::Mutex;
+
+    #[derive(Default)]
+    struct LifecycleReporter {
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Reporter for LifecycleReporter {
+        fn report(&mut self, spans: &[SpanRecord]) {
+            let mut calls = self.calls.lock();
+            calls.extend(spans.iter().map(|span| format!("report {}", span.name)));
+        }
+
+        fn start(&mut self) {
+            self.calls.lock().push("start".to_string());
+        }
+
+        fn stop(&mut self) {
+            self.calls.lock().push("stop".to_string());
+        }
+    }
+
+    let reporter = LifecycleReporter::default();
+    let calls = reporter.calls.clone();
+    minitrace::set_reporter(reporter, Config::default());
+    assert_eq!(*calls.lock(), ["start"]);
+
+    {
+        let _root = Span::root("before shutdown", SpanContext::random());
+    }
+
+    minitrace::shutdown();
+
+    {
+        let _root = Span::root("after shutdown", SpanContext::random());
+    }
+
+    minitrace::flush();
+    minitrace::shutdown();
+
+    assert_eq!(*calls.lock(), ["start", "report before shutdown", "stop"]);
+}
+
+#[test]
+#[serial]
+fn scoped_reporter_restores_previous_reporter() {
+    fn names(spans: &[SpanRecord]) -> Vec<String> {
+        spans.iter().map(|span| span.name.to_string()).collect()
+    }
+
+    let (reporter1, collected_spans1) = TestReporter::new();
+    minitrace::set_reporter(reporter1, Config::default());
+
+    let (reporter2, collected_spans2) = TestReporter::new();
+    {
+        let _guard = minitrace::scoped_reporter(reporter2, Config::default());
+        drop(Span::root("scoped", SpanContext::random()));
+    }
+    assert_eq!(names(&collected_spans2.lock()), ["scoped"]);
+
+    drop(Span::root("restored", SpanContext::random()));
+    minitrace::flush();
+
+    assert_eq!(names(&collected_spans1.lock()), ["restored"]);
+    assert_eq!(names(&collected_spans2.lock()), ["scoped"]);
+
+    minitrace::shutdown();
+}
+
+#[test]
+#[serial]
+fn reporter_stopped_on_replace() {
+    use std::sync::Arc;
+
+    use minitrace::collector::Reporter;
+    use parking_lot::Mutex;
+
+    struct LifecycleReporter {
+        name: &'static str,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Reporter for LifecycleReporter {
+        fn report(&mut self, _spans: &[SpanRecord]) {}
+
+        fn start(&mut self) {
+            self.calls.lock().push(format!("start {}", self.name));
+        }
+
+        fn stop(&mut self) {
+            self.calls.lock().push(format!("stop {}", self.name));
+        }
+    }
+
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    minitrace::set_reporter(
+        LifecycleReporter {
+            name: "first",
+            calls: calls.clone(),
+        },
+        Config::default(),
+    );
+    minitrace::set_reporter(
+        LifecycleReporter {
+            name: "second",
+            calls: calls.clone(),
+        },
+        Config::default(),
+    );
+    minitrace::shutdown();
+
+    assert_eq!(
+        *calls.lock(),
+        ["start first", "start second", "stop first", "stop second"]
+    );
+}
+
+#[test]
+#[serial]
+fn test_local_span_enter_async() {
+    use futures::FutureExt;
+
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        let guard = LocalSpan::enter_async_with_local_parent("lazy");
+        let _span = LocalSpan::enter_with_local_parent("eager");
+        block_on(async {
+            let _lazy = guard.await;
+            let _chained = futures::future::ready(())
+                .then(|_| LocalSpan::enter_async_with_local_parent("chained"))
+                .await;
+        });
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    eager []
+        lazy []
+            chained []
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_local_span_enter_async_interleaved() {
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    // The spans are dropped before the next `.await`, so the tasks interleaving on the same
+    // thread never see each other's spans on the local span stack.
+    async fn task(name: &'static str, parent: &Span) {
+        let span = Span::enter_with_parent(name, parent);
+        async {
+            for step in ["step1", "step2"] {
+                let span = LocalSpan::enter_async_with_local_parent(step).await;
+                LocalSpan::add_property(|| ("task", name));
+                drop(span);
+                tokio::task::yield_now().await;
+            }
+        }
+        .in_span(span)
+        .await
+    }
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            tokio::join!(task("a", &root), task("b", &root));
+        });
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    a []
+        step1 [("task", "a")]
+        step2 [("task", "a")]
+    b []
+        step1 [("task", "b")]
+        step2 [("task", "b")]
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_macro_properties() {
+    #[allow(clippy::drop_non_drop)]
+    #[trace(short_name = true, properties = { "k1": "v1", "a": "argument a is {a:?}", "b": "{b:?}", "escaped1": "{c:?}{{}}", "escaped2": "{{ \"a\": \"b\"}}" })]
+    fn foo(a: i64, b: &Bar, c: Bar) {
+        drop(c);
+    }
+
+    #[allow(clippy::drop_non_drop)]
+    #[trace(short_name = true, properties = { "k1": "v1", "a": "argument a is {a:?}", "b": "{b:?}", "escaped1": "{c:?}{{}}", "escaped2": "{{ \"a\": \"b\"}}" })]
+    async fn foo_async(a: i64, b: &Bar, c: Bar) {
+        drop(c);
+    }
+
+    #[trace(short_name = true, properties = {})]
+    fn bar() {}
+
+    #[trace(short_name = true, properties = {})]
+    async fn bar_async() {}
+
+    #[derive(Debug)]
+    struct Bar;
+
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        foo(1, &Bar, Bar);
+        bar();
+
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(4)
+            .enable_all()
+            .build()
+            .unwrap();
+
+        block_on(
+            runtime.spawn(
+                async {
+                    foo_async(1, &Bar, Bar).await;
+                    bar_async().await;
+                }
+                .in_span(root),
+            ),
+        )
+        .unwrap();
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    bar []
+    bar_async []
+    foo [("k1", "v1"), ("a", "argument a is 1"), ("b", "Bar"), ("escaped1", "Bar{}"), ("escaped2", "{ \"a\": \"b\"}")]
+    foo_async [("k1", "v1"), ("a", "argument a is 1"), ("b", "Bar"), ("escaped1", "Bar{}"), ("escaped2", "{ \"a\": \"b\"}")]
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_macro_properties_display_and_raw() {
+    struct Request {
+        id: Id,
+        path: String,
+    }
+
+    struct Id(u64);
+
+    impl std::fmt::Display for Id {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "id-{}", self.0)
+        }
+    }
+
+    impl Request {
+        #[trace(short_name = true, properties = { "id": display("{self.id}"), "path": raw("{self.path.clone()}"), "method": raw("{\"GET\"}") })]
+        fn handle(&self) {}
+
+        #[trace(short_name = true, properties = { "id": display("{ self.id }") })]
+        async fn handle_async(&self) {}
+    }
+
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let request = Request {
+            id: Id(42),
+            path: "/users".to_string(),
+        };
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        request.handle();
+        block_on(request.handle_async());
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    handle [("id", "id-42"), ("path", "/users"), ("method", "GET")]
+    handle_async [("id", "id-42")]
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_macro_impl_block() {
+    trait Storage {
+        fn get(&self, key: &str) -> Option<u64>;
+        fn put(&mut self, key: &str, value: u64);
+        fn len(&self) -> usize;
+    }
+
+    struct MemStorage(Vec<(String, u64)>);
+
+    #[trace(short_name = true)]
+    impl Storage for MemStorage {
+        fn get(&self, key: &str) -> Option<u64> {
+            self.0.iter().find(|(k, _)| k == key).map(|(_, v)| *v)
+        }
+
+        fn put(&mut self, key: &str, value: u64) {
+            self.0.push((key.to_string(), value));
+        }
+
+        #[trace(skip)]
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    #[trace(short_name = true, properties = { "k": "v" })]
+    impl MemStorage {
+        async fn flush(&self) {}
+
+        #[trace(name = "custom")]
+        fn clear(&mut self) {
+            self.0.clear();
+        }
+    }
+
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        let mut storage = MemStorage(Vec::new());
+        storage.put("a", 1);
+        assert_eq!(storage.get("a"), Some(1));
+        assert_eq!(storage.len(), 1);
+        block_on(storage.flush());
+        storage.clear();
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    custom []
+    flush [("k", "v")]
+    get []
+    put []
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_macro_ret() {
+    use async_trait::async_trait;
+
+    #[trace(short_name = true, ret = "{:?}")]
+    fn foo(a: i64) -> i64 {
+        if a < 0 {
+            return 0;
+        }
+        a
+    }
+
+    #[trace(short_name = true, ret = "{:?}")]
+    fn parse(a: &str) -> Result<i64, std::num::ParseIntError> {
+        let a = a.parse::<i64>()?;
+        Ok(a)
+    }
+
+    #[trace(short_name = true, ret = "{:?}")]
+    fn bar() {}
+
+    #[trace(short_name = true, properties = { "a": "{a}" }, ret = "returned {}")]
+    async fn foo_async(a: i64) -> i64 {
+        async {}.enter_on_poll("child").await;
+        a
+    }
+
+    #[trace(short_name = true, ret = "{:?}")]
+    async fn bar_async() {}
+
+    #[async_trait]
+    trait Baz {
+        async fn baz(&self) -> u64;
+    }
+
+    struct BazImpl;
+
+    #[async_trait]
+    impl Baz for BazImpl {
+        #[trace(name = "baz", ret = "{:?}")]
+        async fn baz(&self) -> u64 {
+            42
+        }
+    }
+
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        foo(1);
+        foo(-1);
+        parse("1").unwrap();
+        parse("x").unwrap_err();
+        bar();
+
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(4)
+            .enable_all()
+            .build()
+            .unwrap();
+
+        block_on(
+            runtime.spawn(
+                async {
+                    foo_async(1).await;
+                    bar_async().await;
+                    BazImpl.baz().await;
+                }
+                .in_span(root),
+            ),
+        )
+        .unwrap();
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    bar []
+    bar_async []
+    baz [("return", "42")]
+    foo [("return", "0")]
+    foo [("return", "1")]
+    foo_async [("a", "1"), ("return", "returned 1")]
+        child []
+    parse [("return", "Err(ParseIntError { kind: InvalidDigit })")]
+    parse [("return", "Ok(1)")]
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_macro_returns_future() {
+    use std::future::Future;
+    use std::pin::Pin;
+
+    #[trace(short_name = true)]
+    fn impl_future(a: u64) -> impl Future<Output = u64> {
+        let _span = LocalSpan::enter_with_local_parent("eager");
+        async move {
+            async {}.enter_on_poll("child").await;
+            a
+        }
+    }
+
+    #[trace(short_name = true, ret = "{:?}")]
+    fn boxed_future(a: u64) -> Pin<Box<dyn Future<Output = u64> + Send>> {
+        Box::pin(async move { a + 1 })
+    }
+
+    type BoxedFuture = Pin<Box<dyn Future<Output = u64> + Send>>;
+
+    #[trace(short_name = true, force_async = true)]
+    fn aliased_future() -> BoxedFuture {
+        Box::pin(async { 3 })
+    }
+
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(4)
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let sum = block_on(
+            runtime.spawn(
+                async {
+                    impl_future(1).await + boxed_future(1).await + aliased_future().await
+                }
+                .in_span(root),
+            ),
+        )
+        .unwrap();
+        assert_eq!(sum, 6);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    aliased_future []
+    boxed_future [("return", "2")]
+    impl_future []
+        child []
+        eager []
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_macro_target() {
+    use minitrace::collector::report_only_target;
+
+    #[trace(target = "my_module", short_name = true)]
+    fn foo() {
+        bar();
+    }
+
+    #[trace(short_name = true)]
+    fn bar() {}
+
+    #[trace(target = "my_module", name = "baz")]
+    async fn baz_async() {}
+
+    #[trace(target = "other_module", short_name = true)]
+    fn qux() {}
+
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard =
+        minitrace::scoped_reporter(report_only_target(reporter, "my_module"), Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        foo();
+        block_on(baz_async());
+        qux();
+    }
+
+    minitrace::flush();
+
+    let mut names = collected_spans
+        .lock()
+        .iter()
+        .map(|span| span.name.clone())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["my_module::baz", "my_module::foo"]);
+}
+
+#[test]
+#[serial]
+fn span_context_from_span_record() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::new(TraceId(12), SpanId::default()));
+        let _child = Span::enter_with_parent("child", &root);
+    }
+
+    minitrace::flush();
+
+    let record = collected_spans
+        .lock()
+        .iter()
+        .find(|record| record.name == "child")
+        .unwrap()
+        .clone();
+    let context = SpanContext::from_span_record(&record);
+    assert_eq!(context.trace_id, TraceId(12));
+    assert_eq!(context.span_id, record.span_id);
+
+    {
+        let _replayed = Span::root("replayed", SpanContext::from_span_record(&record));
+        let _downstream = Span::root(
+            "downstream",
+            SpanContext::child_context_of_span_record(&record),
+        );
+    }
+
+    minitrace::flush();
+
+    let collected_spans = collected_spans.lock();
+    for name in ["replayed", "downstream"] {
+        let span = collected_spans
+            .iter()
+            .find(|span| span.name == name)
+            .unwrap();
+        assert_eq!(span.trace_id, record.trace_id);
+        assert_eq!(span.parent_id, record.span_id);
+        assert_ne!(span.span_id, record.span_id);
+    }
+}
+
+#[test]
+#[serial]
+fn span_enter_with_context() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    // The frontend service starts the trace and calls the backend service.
+    let traceparent = {
+        let root = Span::root("frontend", SpanContext::new(TraceId(12), SpanId::default()));
+        let child = Span::enter_with_parent("call backend", &root);
+        SpanContext::from_span(&child)
+            .unwrap()
+            .encode_w3c_traceparent()
+    };
+
+    // The backend service continues the trace.
+    {
+        let upstream = SpanContext::decode_w3c_traceparent(&traceparent).unwrap();
+        let span = Span::enter_with_context("backend", upstream);
+        let child = Span::enter_with_parent("query", &span);
+        assert!(child.is_descendant_of(&span));
+        assert!(!span.is_descendant_of(&child));
+    }
+
+    minitrace::flush();
+
+    let record = collected_spans
+        .lock()
+        .iter()
+        .find(|record| record.name == "backend")
+        .unwrap()
+        .clone();
+
+    // A late-arriving span continues the trace of the collected backend span.
+    {
+        let _late = Span::enter_with_context("late", SpanContext::from_span_record(&record));
+    }
+
+    minitrace::flush();
+
+    let collected_spans = collected_spans.lock();
+    let span = |name| {
+        collected_spans
+            .iter()
+            .find(|span| span.name == name)
+            .unwrap()
+    };
+    assert!(collected_spans
+        .iter()
+        .all(|span| span.trace_id == TraceId(12)));
+    assert_eq!(span("backend").parent_id, span("call backend").span_id);
+    assert_eq!(span("query").parent_id, span("backend").span_id);
+    assert_eq!(span("late").parent_id, span("backend").span_id);
+}
+
+#[test]
+#[serial]
+fn grpc_metadata_propagation() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    let mut metadata = tonic::metadata::MetadataMap::new();
+    assert!(SpanContext::from_span(&Span::root_from_grpc_metadata("root", &metadata)).is_none());
+
+    metadata.insert("traceparent", "invalid".parse().unwrap());
+    assert!(SpanContext::from_span(&Span::root_from_grpc_metadata("root", &metadata)).is_none());
+
+    SpanContext::new(TraceId(12), SpanId(34)).inject_grpc_metadata(&mut metadata);
+    assert_eq!(
+        metadata.get("traceparent").unwrap().to_str().unwrap(),
+        "00-0000000000000000000000000000000c-0000000000000022-01"
+    );
+
+    assert!(metadata.get("tracestate").is_none());
+
+    {
+        let _root = Span::root_from_grpc_metadata("root", &metadata);
+    }
+
+    minitrace::flush();
+
+    reporter.assert_span_count(1);
+    reporter.assert_trace_id(TraceId(12));
+    assert_eq!(collected_spans.lock()[0].parent_id, SpanId(34));
+
+    SpanContext::new(TraceId(12), SpanId(34))
+        .with_tracestate("rojo=1")
+        .inject_grpc_metadata(&mut metadata);
+    assert_eq!(
+        metadata.get("tracestate").unwrap().to_str().unwrap(),
+        "rojo=1"
+    );
+
+    let root = Span::root_from_grpc_metadata("root", &metadata);
+    let span_context = SpanContext::from_span(&root).unwrap();
+    assert_eq!(span_context.tracestate(), Some("rojo=1"));
+}
+
+#[test]
+#[serial]
+fn test_root_sampled() {
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    assert!(Span::root_sampled("root", SpanContext::random(), 0.0).is_noop());
+    assert!(!Span::root_sampled("root", SpanContext::random(), 1.0).is_noop());
+
+    let sampled = (0..10000)
+        .filter(|_| {
+            let mut root = Span::root_sampled("root", SpanContext::random(), 0.1);
+            let sampled = !root.is_noop();
+            root.cancel();
+            sampled
+        })
+        .count();
+    assert!((500..=1500).contains(&sampled), "sampled {sampled}");
+}
+
+#[test]
+#[serial]
+fn test_flush_async() {
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(
+        reporter.clone(),
+        Config::default().report_interval(Duration::from_secs(3600)),
+    );
+
+    let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    runtime.block_on(async {
+        {
+            let root = Span::root("root", SpanContext::random());
+            let _g = root.set_local_parent();
+            let _span = LocalSpan::enter_with_local_parent("span");
+        }
+
+        minitrace::flush_async().await;
+    });
+
+    let expected_graph = r#"
+root []
+    span []
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_span_status() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let mut root = Span::root("root", SpanContext::random());
+        root.set_status(SpanStatus::Ok);
+        let _g = root.set_local_parent();
+        {
+            let _span = LocalSpan::enter_with_local_parent("failed");
+            LocalSpan::set_status(SpanStatus::Error("connection refused".into()));
+        }
+        let _span = LocalSpan::enter_with_local_parent("unset");
+    }
+
+    minitrace::flush();
+
+    let mut collected_spans = collected_spans.lock().clone();
+    collected_spans.sort_by(|a, b| a.name.cmp(&b.name));
+    let statuses = collected_spans
+        .iter()
+        .map(|span| (span.name.as_ref(), span.status.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(statuses, vec![
+        ("failed", SpanStatus::Error("connection refused".into())),
+        ("root", SpanStatus::Ok),
+        ("unset", SpanStatus::Unset),
+    ]);
+}
+
+#[test]
+#[serial]
+fn test_macro_error_if() {
+    #[trace(short_name = true, error_if = "result.is_err()")]
+    fn parse(a: &str) -> Result<i64, std::num::ParseIntError> {
+        let a = a.parse::<i64>()?;
+        Ok(a)
+    }
+
+    #[trace(short_name = true, error_if = "result.is_none()")]
+    async fn parse_async(a: &str) -> Option<i64> {
+        a.parse::<i64>().ok()
+    }
+
+    #[trace(short_name = true, error_if = "result.is_none()")]
+    fn first_even(v: &mut Vec<i64>) -> Option<&i64> {
+        if v.is_empty() {
+            return None;
+        }
+        v.retain(|n| n % 2 == 0);
+        let first = v.first()?;
+        Some(first)
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        parse("1").unwrap();
+        parse("x").unwrap_err();
+        block_on(parse_async("1"));
+        block_on(parse_async("x"));
+        first_even(&mut vec![]);
+        first_even(&mut vec![1]);
+        first_even(&mut vec![1, 2]);
+    }
+
+    minitrace::flush();
+
+    let mut collected_spans = collected_spans.lock().clone();
+    collected_spans.sort_by_key(|span| span.begin_time_unix_ns);
+    let statuses = collected_spans
+        .iter()
+        .map(|span| (span.name.as_ref(), span.status.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(statuses, vec![
+        ("root", SpanStatus::Unset),
+        ("parse", SpanStatus::Ok),
+        ("parse", SpanStatus::Error("".into())),
+        ("parse_async", SpanStatus::Ok),
+        ("parse_async", SpanStatus::Error("".into())),
+        ("first_even", SpanStatus::Error("".into())),
+        ("first_even", SpanStatus::Error("".into())),
+        ("first_even", SpanStatus::Ok),
+    ]);
+}
+
+#[test]
+#[serial]
+fn test_span_kind() {
+    #[trace(short_name = true, kind = "client")]
+    fn call() {}
+
+    #[trace(short_name = true, kind = "consumer")]
+    async fn consume() {}
+
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random()).with_kind(SpanKind::Server);
+        let _g = root.set_local_parent();
+        let _span = LocalSpan::enter_with_local_parent("produce").with_kind(SpanKind::Producer);
+        call();
+        block_on(consume());
+    }
+
+    minitrace::flush();
+
+    let mut collected_spans = collected_spans.lock().clone();
+    collected_spans.sort_by(|a, b| a.name.cmp(&b.name));
+    let kinds = collected_spans
+        .iter()
+        .map(|span| (span.name.as_ref(), span.kind))
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, vec![
+        ("call", SpanKind::Client),
+        ("consume", SpanKind::Consumer),
+        ("produce", SpanKind::Producer),
+        ("root", SpanKind::Server),
+    ]);
+}
+
+#[test]
+#[serial]
+fn test_set_name() {
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let mut root = Span::root("request", SpanContext::random());
+        assert_eq!(root.name(), Some("request"));
+        root.set_name("GET /users/{id}");
+        assert_eq!(root.name(), Some("GET /users/{id}"));
+
+        let _g = root.set_local_parent();
+        assert_eq!(LocalSpan::name(), None);
+        LocalSpan::set_name("ignored");
+
+        let mut child = Span::enter_with_local_parent("child");
+        child.set_name(format!("child of {}", root.name().unwrap()));
+
+        let _span = LocalSpan::enter_with_local_parent("local");
+        assert_eq!(LocalSpan::name().as_deref(), Some("local"));
+        LocalSpan::set_name("renamed local");
+        assert_eq!(LocalSpan::name().as_deref(), Some("renamed local"));
+    }
+
+    assert_eq!(Span::noop().name(), None);
+    assert_eq!(LocalSpan::name(), None);
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+GET /users/{id} []
+    child of GET /users/{id} []
+    renamed local []
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_event_level() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(
+        reporter,
+        Config::default().min_event_level(EventLevel::Info),
+    );
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        Event::add_to_parent("info", &root, || []);
+        Event::add_to_parent_with_level("error", &root, EventLevel::Error, || []);
+
+        let _g = root.set_local_parent();
+        Event::add_to_local_parent_with_level("debug", EventLevel::Debug, || []);
+        Event::add_to_local_parent_with_level("warn", EventLevel::Warn, || []);
+    }
+
+    minitrace::flush();
+
+    let collected_spans = collected_spans.lock();
+    assert_eq!(collected_spans.len(), 1);
+    let events = collected_spans[0]
+        .events
+        .iter()
+        .map(|event| (event.name.as_ref(), event.level))
+        .collect::<Vec<_>>();
+    assert_eq!(events, vec![
+        ("info", EventLevel::Info),
+        ("error", EventLevel::Error),
+        ("warn", EventLevel::Warn),
+    ]);
+}
+
+#[test]
+#[serial]
+fn test_report_long_running_threshold() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(
+        reporter,
+        Config::default().report_long_running_threshold(Some(Duration::from_millis(100))),
+    );
+
+    let names = || {
+        let mut names = collected_spans
+            .lock()
+            .iter()
+            .map(|span| span.name.to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+
+    let root = Span::root("root", SpanContext::random());
+    let _ = Span::enter_with_parent("child1", &root);
+    minitrace::flush();
+    assert!(names().is_empty());
+
+    std::thread::sleep(Duration::from_millis(150));
+    minitrace::flush();
+    assert_eq!(names(), ["child1"]);
+
+    // The reported spans are not reported again until the root span finishes.
+    let _ = Span::enter_with_parent("child2", &root);
+    minitrace::flush();
+    minitrace::flush();
+    assert_eq!(names(), ["child1", "child2"]);
+
+    drop(root);
+    minitrace::flush();
+    assert_eq!(names(), ["child1", "child1", "child2", "child2", "root"]);
+}
+
+#[test]
+#[serial]
+fn test_max_spans_per_thread() {
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(
+        reporter.clone(),
+        Config::default()
+            .max_spans_per_thread(Some(3))
+            .max_spans_per_trace(Some(100)),
+    );
+
+    fn record_spans(prefix: &'static str) {
+        for i in 0..5 {
+            let _span = LocalSpan::enter_with_local_parent(format!("{prefix}-{i}"));
+        }
+    }
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        {
+            let _g = root.set_local_parent();
+            record_spans("a");
+            assert_eq!(minitrace::thread_span_count(), 3);
+
+            // Another thread has its own count.
+            let child = Span::enter_with_parent("thread", &root);
+            std::thread::spawn(move || {
+                let _g = child.set_local_parent();
+                assert_eq!(minitrace::thread_span_count(), 0);
+                record_spans("b");
+                assert_eq!(minitrace::thread_span_count(), 3);
+            })
+            .join()
+            .unwrap();
+        }
+        assert_eq!(minitrace::thread_span_count(), 0);
+
+        let _g = root.set_local_parent();
+        record_spans("c");
+        assert_eq!(minitrace::thread_span_count(), 3);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    a-0 []
+    a-1 []
+    a-2 []
+    c-0 []
+    c-1 []
+    c-2 []
+    thread []
+        b-0 []
+        b-1 []
+        b-2 []
+"#;
+    reporter.assert_tree(expected_graph);
+}
+
+#[test]
+#[serial]
+fn test_max_events_per_span() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard =
+        minitrace::scoped_reporter(reporter, Config::default().max_events_per_span(Some(10)));
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        for i in 0..200 {
+            Event::add_to_local_parent(format!("event-{i}"), || []);
+        }
+
+        let _span = LocalSpan::enter_with_local_parent("child");
+        Event::add_to_local_parent("child-event", || []);
+    }
+
+    minitrace::flush();
+
+    let collected_spans = collected_spans.lock();
+    let root = collected_spans.iter().find(|s| s.name == "root").unwrap();
+    assert_eq!(root.events.len(), 11);
+    for (i, event) in root.events[..10].iter().enumerate() {
+        assert_eq!(event.name, format!("event-{i}"));
+    }
+    let notice = &root.events[10];
+    assert_eq!(notice.name, "minitrace::events_truncated");
+    assert_eq!(notice.properties, vec![(
+        "dropped_count".into(),
+        "190".into()
+    )]);
+
+    let child = collected_spans.iter().find(|s| s.name == "child").unwrap();
+    assert_eq!(child.events.len(), 1);
+}
+
+#[test]
+#[serial]
+fn test_reporter_factory() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let (tx, rx) = std::sync::mpsc::channel::<()>();
+    minitrace::set_reporter_factory(
+        move || {
+            // Blocks until the test has recorded the spans to be buffered.
+            rx.recv().unwrap();
+            let reporter: Box<dyn Reporter> = Box::new(reporter);
+            (reporter, Config::default())
+        },
+        2,
+    );
+
+    for name in ["root1", "root2", "root3"] {
+        let root = Span::root(name, SpanContext::random());
+        let _g = root.set_local_parent();
+        let _span = LocalSpan::enter_with_local_parent("child");
+    }
+    minitrace::flush();
+    assert!(collected_spans.lock().is_empty());
+
+    tx.send(()).unwrap();
+    let start = std::time::Instant::now();
+    while collected_spans.lock().is_empty() && start.elapsed() < Duration::from_secs(10) {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    {
+        let root = Span::root("root4", SpanContext::random());
+        let _g = root.set_local_parent();
+        let _span = LocalSpan::enter_with_local_parent("child");
+    }
+    minitrace::shutdown();
+
+    // Only the two most recent spans are kept in the buffer.
+    let mut names = collected_spans
+        .lock()
+        .iter()
+        .map(|span| span.name.to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["child", "child", "root3", "root4"]);
+}
+
+#[test]
+#[serial]
+fn test_adopt_local_spans() {
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let local_collector = LocalCollector::start();
+        let _ = LocalSpan::enter_with_local_parent("owned");
+        let local_spans = local_collector.collect();
+
+        let root = Span::root("root", SpanContext::new(TraceId(12), SpanId::default()))
+            .adopt_local_spans(local_spans);
+
+        let local_collector = LocalCollector::start();
+        let _ = LocalSpan::enter_with_local_parent("shared");
+        let local_spans = local_collector.collect();
+
+        let child = Span::enter_with_parent("child", &root);
+        child.adopt_local_spans_mut(local_spans.clone());
+        root.adopt_local_spans_mut(local_spans);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    child []
+        shared []
+    owned []
+    shared []
+"#;
+    reporter.assert_tree(expected_graph);
+    assert_eq!(collected_spans.lock().len(), 5);
+}
+
+#[test]
+#[serial]
+fn test_report_on_replace() {
+    fn names(spans: &[SpanRecord]) -> Vec<String> {
+        spans.iter().map(|span| span.name.to_string()).collect()
+    }
+
+    let (reporter1, collected_spans1) = TestReporter::new();
+    minitrace::set_reporter(reporter1, Config::default().report_on_replace(true));
+
+    drop(Span::root("root1", SpanContext::random()));
+
+    let (reporter2, collected_spans2) = TestReporter::new();
+    minitrace::collector::replace_reporter(
+        reporter2,
+        Config::default().report_interval(Duration::from_secs(60)),
+    );
+    // Let the background collector go back to sleep for the long report interval.
+    std::thread::sleep(Duration::from_millis(100));
+
+    drop(Span::root("root2", SpanContext::random()));
+
+    let (reporter3, collected_spans3) = TestReporter::new();
+    minitrace::set_reporter(reporter3, Config::default());
+    minitrace::flush();
+
+    assert_eq!(names(&collected_spans1.lock()), ["root1"]);
+    assert!(names(&collected_spans2.lock()).is_empty());
+    assert_eq!(names(&collected_spans3.lock()), ["root2"]);
+
+    minitrace::shutdown();
+}
+
+#[test]
+#[serial]
+fn test_local_span_enter_with_parent_span() {
+    let (reporter, _) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter.clone(), Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let parent = Span::enter_with_parent("parent", &root);
+        let _g = root.set_local_parent();
+        let _local = LocalSpan::enter_with_local_parent("local");
+
+        {
+            let _span = LocalSpan::enter_with_parent_span("callback", &parent)
+                .with_property(|| ("k1", "v1"))
+                .with_properties(|| [("k2", "v2")]);
+            // The local parent is left untouched.
+            let _sibling = LocalSpan::enter_with_local_parent("local-child");
+        }
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    local []
+        local-child []
+    parent []
+        callback [("k1", "v1"), ("k2", "v2")]
+"#;
+    reporter.assert_tree(expected_graph);
 }
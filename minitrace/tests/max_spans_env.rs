@@ -0,0 +1,46 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::process::Command;
+
+use minitrace::collector::Config;
+
+#[test]
+fn default_max_spans_per_trace() {
+    // Cargo passes the same environment to the compiler and to the test binary.
+    let max_spans = std::env::var("MINITRACE_MAX_SPANS")
+        .ok()
+        .and_then(|max_spans| max_spans.parse::<usize>().ok());
+
+    assert_eq!(
+        Config::default(),
+        Config::default().max_spans_per_trace(max_spans)
+    );
+    if max_spans.is_some() {
+        // The runtime setting overrides the compile-time default.
+        assert_ne!(
+            Config::default().max_spans_per_trace(None),
+            Config::default()
+        );
+    }
+}
+
+#[test]
+#[ignore = "rebuilds minitrace in a separate target directory"]
+fn compile_time_max_spans_per_trace() {
+    let target_dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("max-spans-env");
+    let status = Command::new(env!("CARGO"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args([
+            "test",
+            "--test",
+            "max_spans_env",
+            "--",
+            "--exact",
+            "default_max_spans_per_trace",
+        ])
+        .env("MINITRACE_MAX_SPANS", "42")
+        .env("CARGO_TARGET_DIR", target_dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
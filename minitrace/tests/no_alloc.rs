@@ -0,0 +1,74 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+// Spans named by a `&'static str` are expected to be recorded without any allocation once the
+// object pools are warmed up. This is verified by a global allocator counting the allocations
+// made by the current thread.
+
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::cell::Cell;
+
+use minitrace::collector::Config;
+use minitrace::collector::ConsoleReporter;
+use minitrace::prelude::*;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.try_with(|n| n.set(n.get() + 1)).ok();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.try_with(|n| n.set(n.get() + 1)).ok();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn count_allocations(f: impl Fn()) -> usize {
+    let root = Span::root_static("root", SpanContext::random());
+    let _g = root.set_local_parent();
+
+    // Warm up the object pools and grow the span queue, so that the amortized growth of the
+    // queue is not counted.
+    for _ in 0..100 {
+        f();
+    }
+
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn local_span_with_static_name() {
+    #[trace]
+    fn traced() {}
+
+    #[trace(target = "my_module")]
+    fn targeted() {}
+
+    let _guard = minitrace::scoped_reporter(ConsoleReporter, Config::default());
+
+    assert_eq!(
+        count_allocations(|| {
+            let _span = LocalSpan::enter_with_local_parent("span");
+        }),
+        0
+    );
+    assert_eq!(count_allocations(traced), 0);
+    assert_eq!(count_allocations(targeted), 0);
+}
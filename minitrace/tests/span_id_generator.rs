@@ -0,0 +1,40 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+// The span id generator can only be installed once per process, so it is tested in its own test
+// binary.
+
+use minitrace::collector::Config;
+use minitrace::collector::FixedSequenceSpanIdGenerator;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+
+#[test]
+fn fixed_sequence_span_id_generator() {
+    minitrace::set_span_id_generator(FixedSequenceSpanIdGenerator::new([1, 2, 3, 4]));
+
+    let (reporter, collected_spans) = TestReporter::new();
+    let _guard = minitrace::scoped_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::new(TraceId(12), SpanId::default()));
+        let child = Span::enter_with_parent("child", &root);
+        let _g = child.set_local_parent();
+        let _local = LocalSpan::enter_with_local_parent("local");
+        let _local_child = LocalSpan::enter_with_local_parent("local-child");
+    }
+
+    minitrace::flush();
+
+    let mut spans = collected_spans
+        .lock()
+        .iter()
+        .map(|span| (span.name.to_string(), span.span_id.0, span.parent_id.0))
+        .collect::<Vec<_>>();
+    spans.sort();
+    assert_eq!(spans, [
+        ("child".to_string(), 2, 1),
+        ("local".to_string(), 3, 2),
+        ("local-child".to_string(), 4, 3),
+        ("root".to_string(), 1, 0),
+    ]);
+}
@@ -8,7 +8,10 @@
 // The following test is designed to confirm that minitrace compiles when it's statically disabled
 // in the executable.
 
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
+use std::time::SystemTime;
 
 use minitrace::collector::Config;
 use minitrace::collector::ConsoleReporter;
@@ -22,31 +25,73 @@ fn main() {
         Config::default()
             .max_spans_per_trace(Some(100))
             .report_interval(Duration::from_millis(10))
-            .report_before_root_finish(true),
+            .report_before_root_finish(true)
+            .max_spans_per_thread(Some(100))
+            .report_on_replace(true),
     );
+    minitrace::collector::replace_reporter(ConsoleReporter, Config::default());
 
     let mut root = Span::root("root", SpanContext::new(TraceId(0), SpanId(0)))
         .with_property(|| ("k1", "v1"))
         .with_properties(|| [("k2", "v2")]);
+    root.add_property(|| ("k3", "v3"))
+        .add_properties(|| [("k4", "v4")]);
+    root.add_link(SpanContext::random());
+
+    let _root = Span::root_at("root", SpanContext::random(), SystemTime::now())
+        .with_begin_time(SystemTime::now());
+    let _continued = Span::enter_with_context("continued", SpanContext::random());
+    let _with_resource = Span::root_with_resource(
+        "root",
+        SpanContext::random(),
+        Arc::new(HashMap::from([("service.name".into(), "test".into())])),
+    );
+    minitrace::set_resource(HashMap::from([("service.name".into(), "test".into())]));
+    let _with_sampling =
+        Span::root_with_sampling("root", SpanContext::random(), SamplingDecision::RecordOnly);
 
     Event::add_to_parent("event", &root, || []);
     Event::add_to_local_parent("event", || []);
-
+    minitrace::install_panic_hook();
+    minitrace::set_span_id_generator(minitrace::collector::SequentialSpanIdGenerator::new());
+
+    assert!(root.downgrade().upgrade().is_none());
+    let (_span, _is_root) = Span::enter_with_local_parent_or_root("span", TraceId(1));
+    let _span = Span::enter_with_local_parent_or_noop("span");
+    let _span = Span::enter_root_or_child("span", || TraceId(1));
+    let _span = Span::enter_root_or_child_random("span");
+    let _span = LocalSpan::enter_with_parent_span("span", &root)
+        .with_property(|| ("k", "v"))
+        .with_properties(|| [("k", "v")]);
     let _g = root.set_local_parent();
 
     Event::add_to_local_parent("event", || []);
+    LocalSpan::event("event")
+        .with_property(|| ("k", "v"))
+        .with_properties(|| [("k", "v")])
+        .record();
+    LocalSpan::event_now("event", || [("k", "v")]);
+    assert_eq!(minitrace::thread_span_count(), 0);
 
     let _span1 = LocalSpan::enter_with_local_parent("span1")
         .with_property(|| ("k", "v"))
         .with_properties(|| [("k", "v")]);
 
-    let _span2 = LocalSpan::enter_with_local_parent("span2");
+    let _span2 = LocalSpan::enter_with_local_parent("span2").with_begin_time(SystemTime::now());
+    let _span2 = minitrace::location_span!("span2");
+    let _ = (
+        minitrace::file_location!(),
+        minitrace::column_location!(),
+        minitrace::func_path!(),
+    );
 
     LocalSpan::add_property(|| ("k", "v"));
     LocalSpan::add_properties(|| [("k", "v")]);
+    LocalSpan::add_link(SpanContext::random());
 
     let local_collector = LocalCollector::start();
     let _ = LocalSpan::enter_with_local_parent("span3");
+    drop(LocalSpan::enter_async_with_local_parent("span4"));
     let local_spans = local_collector.collect();
     assert_eq!(local_spans.to_span_records(SpanContext::random()), vec![]);
 
@@ -54,14 +99,20 @@ fn main() {
     let span4 = Span::enter_with_local_parent("span4");
     let span5 = Span::enter_with_parents("span5", [&root, &span3, &span4]);
 
-    span5.push_child_spans(local_spans);
+    span5.push_child_spans(local_spans.clone());
+    let span5 = span5.adopt_local_spans(local_spans.clone());
+    span5.adopt_local_spans_mut(local_spans);
 
     assert!(SpanContext::current_local_parent().is_none());
     assert!(SpanContext::from_span(&span5).is_none());
+    assert!(SpanContext::current_span_id().is_none());
+    assert!(SpanContext::current_trace_id().is_none());
 
     assert!(root.elapsed().is_none());
 
     root.cancel();
+    root.cancel_if(|_| unreachable!());
+    root.cancel_if_faster_than(Duration::from_secs(1));
 
     minitrace::flush();
 }
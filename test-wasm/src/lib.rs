@@ -0,0 +1,5 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+// This crate is only used to run the wasm integration tests in `tests/` via
+// `wasm-pack test --headless --chrome test-wasm`, keeping the native-only dev-dependencies of
+// `minitrace` out of the wasm build.
@@ -0,0 +1,48 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+#![cfg(target_family = "wasm")]
+
+use minitrace::collector::Config;
+use minitrace::collector::TestReporter;
+use minitrace::collector::WasmConsoleReporter;
+use minitrace::prelude::*;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+fn span_creation_and_flush() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        let _child = LocalSpan::enter_with_local_parent("child");
+    }
+
+    minitrace::flush();
+
+    let mut names = collected_spans
+        .lock()
+        .iter()
+        .map(|span| span.name.to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["child", "root"]);
+}
+
+#[wasm_bindgen_test]
+async fn wasm_console_reporter_flush_async_promise() {
+    minitrace::set_reporter(WasmConsoleReporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        let _child = LocalSpan::enter_with_local_parent("child");
+    }
+
+    wasm_bindgen_futures::JsFuture::from(minitrace::collector::flush_async_promise())
+        .await
+        .unwrap();
+}